@@ -1,14 +1,47 @@
-use crate::models::{Account, AccountsSummary};
+use crate::models::{Account, AccountValuation, AccountsSummary, NetWorthSummary};
 use rusqlite::{params, Connection, OptionalExtension};
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use tauri::AppHandle;
 
+/// Account kinds accepted by `create_account`/`update_account`.
+pub const ACCOUNT_KINDS: [&str; 6] = [
+    "cash",
+    "credit_card",
+    "loan",
+    "asset",
+    "liability",
+    "brokerage",
+];
+
+/// Kinds whose balances count as liabilities in net worth.
+pub const LIABILITY_KINDS: [&str; 3] = ["credit_card", "loan", "liability"];
+
+pub fn is_liability_kind(kind: &str) -> bool {
+    LIABILITY_KINDS.contains(&kind)
+}
+
+/// Validates a requested account kind, defaulting to `cash` when none is given.
+pub fn normalize_account_kind(kind: Option<String>) -> Result<String, String> {
+    let kind = match kind {
+        Some(k) => k.trim().to_lowercase(),
+        None => return Ok("cash".to_string()),
+    };
+    if kind.is_empty() {
+        return Ok("cash".to_string());
+    }
+    if !ACCOUNT_KINDS.contains(&kind.as_str()) {
+        return Err(format!("Unknown account kind: {}", kind));
+    }
+    Ok(kind)
+}
+
 pub fn create_account_db(
     db_path: &PathBuf,
     name: String,
     balance: f64,
     currency: Option<String>,
+    kind: Option<String>,
 ) -> Result<Account, String> {
     let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
@@ -18,6 +51,8 @@ pub fn create_account_db(
         return Err("Account name cannot be empty or whitespace-only".to_string());
     }
 
+    let kind = normalize_account_kind(kind)?;
+
     // Check for duplicates (case-insensitive)
     {
         let mut stmt = conn
@@ -38,8 +73,8 @@ pub fn create_account_db(
     let balance_to_set = balance;
 
     tx.execute(
-        "INSERT INTO accounts (name, balance, currency) VALUES (?1, ?2, ?3)",
-        params![name_trimmed, balance_to_set, currency],
+        "INSERT INTO accounts (name, balance, currency, kind) VALUES (?1, ?2, ?3, ?4)",
+        params![name_trimmed, balance_to_set, currency, kind],
     )
     .map_err(|e| e.to_string())?;
 
@@ -68,7 +103,11 @@ pub fn create_account_db(
         name: name_trimmed,
         balance: balance_to_set,
         currency,
+        kind,
         exchange_rate: 1.0,
+        valuation: None,
+        holdings_value: None,
+        archived: false,
     })
 }
 
@@ -103,7 +142,9 @@ pub fn rename_account_db(db_path: &PathBuf, id: i32, new_name: String) -> Result
     .map_err(|e| e.to_string())?;
 
     let mut stmt = conn
-        .prepare("SELECT id, name, balance, currency FROM accounts WHERE id = ?1")
+        .prepare(
+            "SELECT id, name, balance, currency, COALESCE(kind, 'cash'), COALESCE(archived, 0) FROM accounts WHERE id = ?1",
+        )
         .map_err(|e| e.to_string())?;
 
     let account = stmt
@@ -113,7 +154,11 @@ pub fn rename_account_db(db_path: &PathBuf, id: i32, new_name: String) -> Result
                 name: row.get(1)?,
                 balance: row.get(2)?,
                 currency: row.get(3)?,
+                kind: row.get(4)?,
                 exchange_rate: 1.0,
+                valuation: None,
+                holdings_value: None,
+                archived: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -121,11 +166,13 @@ pub fn rename_account_db(db_path: &PathBuf, id: i32, new_name: String) -> Result
     Ok(account)
 }
 
+/// Updates name, currency and optionally kind. A `None` kind keeps the current one.
 pub fn update_account_db(
     db_path: &PathBuf,
     id: i32,
     name: String,
     currency: Option<String>,
+    kind: Option<String>,
 ) -> Result<Account, String> {
     let name_trimmed = name.trim().to_string();
     if name_trimmed.is_empty() {
@@ -156,8 +203,21 @@ pub fn update_account_db(
     )
     .map_err(|e| e.to_string())?;
 
+    if kind.is_some() {
+        let kind = normalize_account_kind(kind)?;
+        conn.execute(
+            "UPDATE accounts SET kind = ?1 WHERE id = ?2",
+            params![kind, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    // Cached lots carry the account's currency
+    crate::holdings::invalidate_holdings_cache(db_path);
+
     let mut stmt = conn
-        .prepare("SELECT id, name, balance, currency FROM accounts WHERE id = ?1")
+        .prepare(
+            "SELECT id, name, balance, currency, COALESCE(kind, 'cash'), COALESCE(archived, 0) FROM accounts WHERE id = ?1",
+        )
         .map_err(|e| e.to_string())?;
 
     let account = stmt
@@ -167,7 +227,11 @@ pub fn update_account_db(
                 name: row.get(1)?,
                 balance: row.get(2)?,
                 currency: row.get(3)?,
+                kind: row.get(4)?,
                 exchange_rate: 1.0,
+                valuation: None,
+                holdings_value: None,
+                archived: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -175,11 +239,61 @@ pub fn update_account_db(
     Ok(account)
 }
 
-pub fn delete_account_db(db_path: &PathBuf, id: i32) -> Result<(), String> {
+/// Deletes an account and all of its transactions.
+///
+/// Transfers into other accounts would be left pointing at deleted rows, so deletion is refused
+/// while any exist unless `unlink_transfers` is set, in which case the counterparts in the other
+/// accounts are kept as plain transactions.
+pub fn delete_account_db(db_path: &PathBuf, id: i32, unlink_transfers: bool) -> Result<(), String> {
     let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     let tx = conn.transaction().map_err(|e| e.to_string())?;
 
+    // Counterparts living in other accounts that are linked to this account's transactions
+    let linked_count: i64 = tx
+        .query_row(
+            "SELECT COUNT(*) FROM transactions ctr
+             JOIN transactions own ON ctr.linked_tx_id = own.id
+             WHERE own.account_id = ?1 AND ctr.account_id != ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    if linked_count > 0 {
+        if !unlink_transfers {
+            return Err(format!(
+                "Account has {} linked transfer(s); archive it instead or convert the transfers to plain transactions",
+                linked_count
+            ));
+        }
+
+        tx.execute(
+            "DELETE FROM transfers WHERE id IN (SELECT transfer_id FROM transactions WHERE account_id = ?1)",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        // Keep the other side as a regular transaction so its account balance is untouched
+        tx.execute(
+            "UPDATE transactions SET linked_tx_id = NULL, transfer_id = NULL,
+                category = CASE WHEN category = 'Transfer' THEN NULL ELSE category END
+             WHERE account_id != ?1
+               AND linked_tx_id IN (SELECT id FROM transactions WHERE account_id = ?1)",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    // Share transfers from other accounts now arrive without their source
+    tx.execute(
+        "UPDATE transactions SET related_tx_id = NULL
+         WHERE account_id != ?1
+           AND related_tx_id IN (SELECT id FROM transactions WHERE account_id = ?1)",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+
     // Delete all transactions for this account
     tx.execute(
         "DELETE FROM transactions WHERE account_id = ?1",
@@ -187,20 +301,55 @@ pub fn delete_account_db(db_path: &PathBuf, id: i32) -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
+    tx.execute(
+        "DELETE FROM account_valuations WHERE account_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "DELETE FROM scheduled_transactions WHERE account_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+
     // Delete the account
     tx.execute("DELETE FROM accounts WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
 
     tx.commit().map_err(|e| e.to_string())?;
+    crate::holdings::invalidate_holdings_cache(db_path);
+
+    Ok(())
+}
+
+/// Closes or reopens an account. Transactions are left untouched.
+pub fn set_account_archived_db(db_path: &PathBuf, id: i32, archived: bool) -> Result<(), String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let updated = conn
+        .execute(
+            "UPDATE accounts SET archived = ?1 WHERE id = ?2",
+            params![archived, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Account not found".to_string());
+    }
 
     Ok(())
 }
 
 pub fn get_accounts_db(db_path: &PathBuf) -> Result<Vec<Account>, String> {
+    load_accounts(db_path, "USD")
+}
+
+// Accounts without a currency of their own value their holdings in `target`
+fn load_accounts(db_path: &PathBuf, target: &str) -> Result<Vec<Account>, String> {
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     let mut stmt = conn
-        .prepare("SELECT id, name, balance, currency FROM accounts")
+        .prepare("SELECT id, name, balance, currency, COALESCE(kind, 'cash'), COALESCE(archived, 0) FROM accounts")
         .map_err(|e| e.to_string())?;
     let account_iter = stmt
         .query_map([], |row| {
@@ -209,7 +358,11 @@ pub fn get_accounts_db(db_path: &PathBuf) -> Result<Vec<Account>, String> {
                 name: row.get(1)?,
                 balance: row.get(2)?,
                 currency: row.get(3)?,
+                kind: row.get(4)?,
                 exchange_rate: 1.0,
+                valuation: None,
+                holdings_value: None,
+                archived: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -219,11 +372,142 @@ pub fn get_accounts_db(db_path: &PathBuf) -> Result<Vec<Account>, String> {
         accounts.push(account.map_err(|e| e.to_string())?);
     }
 
+    // Asset accounts report their latest manual valuation
+    {
+        let mut stmt = conn
+            .prepare("SELECT value FROM account_valuations WHERE account_id = ?1 ORDER BY date DESC, id DESC LIMIT 1")
+            .map_err(|e| e.to_string())?;
+        for acc in accounts.iter_mut().filter(|a| a.kind == "asset") {
+            acc.valuation = stmt
+                .query_row(params![acc.id], |row| row.get(0))
+                .optional()
+                .map_err(|e| e.to_string())?;
+            if let Some(v) = acc.valuation {
+                acc.balance = v;
+            }
+        }
+    }
+
+    // Brokerage accounts keep cash in `balance` and value shares from the cached quotes,
+    // converted from each quote's currency into the account's, which fails without a rate.
+    // Shares traded before a split are scaled to today's share basis.
+    {
+        let ratios = crate::corporate_actions::load_split_ratios(&conn)?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT t.ticker, t.date, t.shares, sp.price, sp.currency FROM transactions t
+                 LEFT JOIN stock_prices sp ON sp.ticker = t.ticker COLLATE NOCASE
+                 WHERE t.account_id = ?1 AND t.ticker IS NOT NULL AND t.shares IS NOT NULL",
+            )
+            .map_err(|e| e.to_string())?;
+        for acc in accounts.iter_mut().filter(|a| a.kind == "brokerage") {
+            let acc_currency = acc.currency.clone().unwrap_or_else(|| target.to_string());
+            let rows = stmt
+                .query_map(params![acc.id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, f64>(2)?,
+                        row.get::<_, Option<f64>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                    ))
+                })
+                .map_err(|e| e.to_string())?;
+            let mut by_currency: HashMap<String, f64> = HashMap::new();
+            for r in rows {
+                let (ticker, date, shares, price, currency) = r.map_err(|e| e.to_string())?;
+                let factor = crate::corporate_actions::split_factor(&ratios, &ticker, &date, None);
+                let currency = currency
+                    .map(|c| c.trim().to_uppercase())
+                    .filter(|c| !c.is_empty())
+                    .unwrap_or_else(|| acc_currency.clone());
+                *by_currency.entry(currency).or_insert(0.0) +=
+                    shares * factor * price.unwrap_or(0.0);
+            }
+            let mut value = 0.0;
+            for (currency, amount) in by_currency.iter().filter(|(_, a)| a.abs() > 1e-9) {
+                let rate = crate::fx::rate_on(&conn, currency, &acc_currency, None)
+                    .ok_or_else(|| crate::fx::missing_rate(currency, &acc_currency, None))?;
+                value += amount * rate;
+            }
+            acc.holdings_value = Some(value);
+        }
+    }
+
     Ok(accounts)
 }
 
+pub fn set_account_valuation_db(
+    db_path: &PathBuf,
+    account_id: i32,
+    date: String,
+    value: f64,
+) -> Result<AccountValuation, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let kind: Option<String> = conn
+        .query_row(
+            "SELECT COALESCE(kind, 'cash') FROM accounts WHERE id = ?1",
+            params![account_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match kind.as_deref() {
+        None => return Err("Account not found".to_string()),
+        Some("asset") => {}
+        Some(_) => return Err("Only asset accounts hold manual valuations".to_string()),
+    }
+
+    // One valuation per day; a second entry for the same date replaces the first
+    conn.execute(
+        "INSERT OR REPLACE INTO account_valuations (account_id, date, value) VALUES (?1, ?2, ?3)",
+        params![account_id, date, value],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(AccountValuation {
+        id: conn.last_insert_rowid() as i32,
+        account_id,
+        date,
+        value,
+    })
+}
+
+pub fn get_account_valuations_db(
+    db_path: &PathBuf,
+    account_id: i32,
+) -> Result<Vec<AccountValuation>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, account_id, date, value FROM account_valuations WHERE account_id = ?1 ORDER BY date ASC")
+        .map_err(|e| e.to_string())?;
+    let valuations = stmt
+        .query_map(params![account_id], |row| {
+            Ok(AccountValuation {
+                id: row.get(0)?,
+                account_id: row.get(1)?,
+                date: row.get(2)?,
+                value: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(valuations)
+}
+
+pub fn delete_account_valuation_db(db_path: &PathBuf, id: i32) -> Result<(), String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM account_valuations WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 pub fn get_accounts_summary_db(db_path: &PathBuf, target: &str) -> Result<AccountsSummary, String> {
-    let accounts = get_accounts_db(db_path)?;
+    let accounts = load_accounts(db_path, target)?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     // Group transaction amounts by account and currency
@@ -253,15 +537,75 @@ pub fn get_accounts_summary_db(db_path: &PathBuf, target: &str) -> Result<Accoun
     Ok(AccountsSummary { accounts, raw_data })
 }
 
+/// Like `get_accounts_summary_db`, but converts each transaction into its account's currency
+/// at the rate on the transaction date. Amounts without a known rate are left in their own
+/// currency so they still convert at the current rate.
+pub fn get_accounts_summary_at_transaction_dates_db(
+    db_path: &PathBuf,
+    target: &str,
+) -> Result<AccountsSummary, String> {
+    let accounts = load_accounts(db_path, target)?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let account_currency: HashMap<i32, String> = accounts
+        .iter()
+        .map(|a| {
+            (
+                a.id,
+                a.currency.clone().unwrap_or_else(|| target.to_string()),
+            )
+        })
+        .collect();
+
+    let mut stmt = conn
+        .prepare("SELECT account_id, currency, date, SUM(amount) FROM transactions GROUP BY account_id, currency, date")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<f64>>(3)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut sums: HashMap<(i32, String), f64> = HashMap::new();
+    for r in rows {
+        let (acc_id, curr_opt, date, amt_opt) = r.map_err(|e| e.to_string())?;
+        let amt = amt_opt.unwrap_or(0.0);
+        let curr = curr_opt.unwrap_or_else(|| target.to_string());
+        let acc_curr = account_currency
+            .get(&acc_id)
+            .cloned()
+            .unwrap_or_else(|| target.to_string());
+
+        let (curr, amt) = match crate::fx::rate_on(&conn, &curr, &acc_curr, Some(&date)) {
+            Some(rate) => (acc_curr, amt * rate),
+            None => (curr, amt),
+        };
+        *sums.entry((acc_id, curr)).or_insert(0.0) += amt;
+    }
+
+    let raw_data = sums
+        .into_iter()
+        .map(|((acc_id, curr), amt)| (acc_id, curr, amt))
+        .collect();
+
+    Ok(AccountsSummary { accounts, raw_data })
+}
+
 #[tauri::command]
 pub fn create_account(
     app_handle: AppHandle,
     name: String,
     balance: f64,
     currency: Option<String>,
+    kind: Option<String>,
 ) -> Result<Account, String> {
     let db_path = crate::db_init::get_db_path(&app_handle)?;
-    create_account_db(&db_path, name, balance, currency)
+    create_account_db(&db_path, name, balance, currency, kind)
 }
 
 #[tauri::command]
@@ -276,41 +620,118 @@ pub fn update_account(
     id: i32,
     name: String,
     currency: Option<String>,
+    kind: Option<String>,
 ) -> Result<Account, String> {
     let db_path = crate::db_init::get_db_path(&app_handle)?;
-    update_account_db(&db_path, id, name, currency)
+    update_account_db(&db_path, id, name, currency, kind)
+}
+
+#[tauri::command]
+pub fn delete_account(
+    app_handle: AppHandle,
+    id: i32,
+    unlink_transfers: Option<bool>,
+) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    delete_account_db(&db_path, id, unlink_transfers.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn archive_account(app_handle: AppHandle, id: i32) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_account_archived_db(&db_path, id, true)
+}
+
+#[tauri::command]
+pub fn unarchive_account(app_handle: AppHandle, id: i32) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_account_archived_db(&db_path, id, false)
+}
+
+#[tauri::command]
+pub fn set_account_valuation(
+    app_handle: AppHandle,
+    account_id: i32,
+    date: String,
+    value: f64,
+) -> Result<AccountValuation, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_account_valuation_db(&db_path, account_id, date, value)
 }
 
 #[tauri::command]
-pub fn delete_account(app_handle: AppHandle, id: i32) -> Result<(), String> {
+pub fn get_account_valuations(
+    app_handle: AppHandle,
+    account_id: i32,
+) -> Result<Vec<AccountValuation>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_account_valuations_db(&db_path, account_id)
+}
+
+#[tauri::command]
+pub fn delete_account_valuation(app_handle: AppHandle, id: i32) -> Result<(), String> {
     let db_path = crate::db_init::get_db_path(&app_handle)?;
-    delete_account_db(&db_path, id)
+    delete_account_valuation_db(&db_path, id)
 }
 
 #[tauri::command]
 pub async fn get_accounts(
     app_handle: AppHandle,
     target_currency: Option<String>,
+    include_archived: Option<bool>,
 ) -> Result<Vec<Account>, String> {
     let db_path = crate::db_init::get_db_path(&app_handle)?;
     let target = target_currency.unwrap_or_else(|| "USD".to_string());
+    let settings = crate::db_init::read_settings(&app_handle)?;
+    let by_transaction_date =
+        crate::fx::normalize_rate_mode(settings.rate_mode)? == "transaction_date";
+
+    let offline = crate::markets::quote_cache_settings(&app_handle)?.offline;
+    if by_transaction_date && !offline {
+        let currencies = crate::fx::get_used_currencies_db(&db_path)?;
+        crate::fx::refresh_daily_fx_rates(&db_path, currencies).await;
+    }
 
     let db_path_clone = db_path.clone();
     let target_clone = target.clone();
 
     // Use spawn_blocking for DB operations
     let summary = tauri::async_runtime::spawn_blocking(move || {
-        get_accounts_summary_db(&db_path_clone, &target_clone)
+        if by_transaction_date {
+            get_accounts_summary_at_transaction_dates_db(&db_path_clone, &target_clone)
+        } else {
+            get_accounts_summary_db(&db_path_clone, &target_clone)
+        }
     })
     .await
     .map_err(|e| e.to_string())??;
 
-    let accounts = summary.accounts;
-    let raw_data = summary.raw_data;
+    let include_archived = include_archived.unwrap_or(false);
+    let accounts: Vec<Account> = summary
+        .accounts
+        .into_iter()
+        .filter(|a| include_archived || !a.archived)
+        .collect();
+    let raw_data: Vec<(i32, String, f64)> = summary
+        .raw_data
+        .into_iter()
+        .filter(|(acc_id, _, _)| accounts.iter().any(|a| a.id == *acc_id))
+        .collect();
 
     // Load custom rates
     let custom_rates = crate::utils::get_custom_rates_map(&db_path)?;
 
+    // With the ECB source, every currency it publishes is priced from one cached file
+    let fx_source = crate::fx::normalize_fx_source(settings.fx_source)?;
+    let ecb_rates = if fx_source == "ecb" {
+        if !offline {
+            crate::ecb::refresh_ecb_rates(&db_path).await;
+        }
+        crate::ecb::latest_ecb_rates_db(&db_path)?
+    } else {
+        HashMap::new()
+    };
+
     // Determine which rates we need to fetch
     // Each account might have a specific currency preference.
     // If set, we convert all its txs to that currency.
@@ -341,8 +762,14 @@ pub async fn get_accounts(
     // We treat anything not in custom_rates as potentially on Yahoo.
     // We will verify by fetching X->USD for all of them.
     let mut yahoo_currencies = HashSet::new();
+    let mut rates = HashMap::new();
     for c in &all_currencies {
-        if c != "USD" && !custom_rates.contains_key(c) {
+        if c == "USD" || custom_rates.contains_key(c) {
+            continue;
+        }
+        if let Some(r) = ecb_rates.get(c) {
+            rates.insert(format!("{}USD=X", c), *r);
+        } else {
             yahoo_currencies.insert(c.clone());
         }
     }
@@ -376,20 +803,9 @@ pub async fn get_accounts(
         }
     }
 
-    let mut rates = HashMap::new();
     if !tickers_to_fetch.is_empty() {
         let tickers: Vec<String> = tickers_to_fetch.into_iter().collect();
-        let client = reqwest::Client::builder()
-            .build()
-            .map_err(|e| e.to_string())?;
-
-        let quotes = crate::markets::get_stock_quotes_with_client(
-            client,
-            "https://query1.finance.yahoo.com".to_string(),
-            app_handle.clone(),
-            tickers,
-        )
-        .await?;
+        let quotes = crate::markets::get_stock_quotes(app_handle.clone(), tickers).await?;
 
         for q in quotes {
             rates.insert(q.symbol.clone(), q.price);
@@ -405,3 +821,12 @@ pub async fn get_accounts(
     );
     Ok(accounts)
 }
+
+#[tauri::command]
+pub async fn get_net_worth(
+    app_handle: AppHandle,
+    target_currency: Option<String>,
+) -> Result<NetWorthSummary, String> {
+    let accounts = get_accounts(app_handle, target_currency, Some(false)).await?;
+    Ok(crate::utils::calculate_net_worth(&accounts))
+}
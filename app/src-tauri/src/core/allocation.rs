@@ -0,0 +1,497 @@
+use crate::holdings::get_holdings_db;
+use crate::models::{
+    AllocationLine, AllocationTarget, Holding, Portfolio, RebalancePlan, RebalanceTrade,
+    TickerClassification,
+};
+use crate::transfers::round_cents;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Asset classes a ticker can be assigned to and allocation targets are set for.
+pub const ASSET_CLASSES: [&str; 7] = [
+    "stock",
+    "bond",
+    "cash",
+    "real_estate",
+    "commodity",
+    "crypto",
+    "other",
+];
+
+const EPSILON: f64 = 1e-6;
+
+/// The class a ticker falls into before anyone picks one, from the Yahoo quote type or
+/// chart instrument type (both use the same names). Funds count as stocks, which is what
+/// most of them hold; bond funds need classifying by hand.
+pub(crate) fn default_asset_class(quote_type: Option<&str>) -> &'static str {
+    match quote_type.map(|q| q.trim().to_uppercase()).as_deref() {
+        Some("EQUITY") | Some("ETF") | Some("MUTUALFUND") | Some("INDEX") => "stock",
+        Some("CRYPTOCURRENCY") => "crypto",
+        Some("MONEYMARKET") | Some("CURRENCY") => "cash",
+        Some("FUTURE") => "commodity",
+        _ => "other",
+    }
+}
+
+fn classify(conn: &Connection, ticker: &str) -> Result<TickerClassification, String> {
+    let stored: Option<(Option<String>, Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT asset_class, region, sector FROM ticker_classifications WHERE ticker = ?1",
+            params![ticker],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let (asset_class, region, sector) = stored.unwrap_or((None, None, None));
+    Ok(match asset_class {
+        Some(asset_class) => TickerClassification {
+            ticker: ticker.to_string(),
+            asset_class,
+            region,
+            sector,
+            is_default: false,
+        },
+        None => {
+            let quote_type: Option<String> = conn
+                .query_row(
+                    "SELECT quote_type FROM stock_prices WHERE ticker = ?1 COLLATE NOCASE",
+                    params![ticker],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?
+                .flatten();
+            TickerClassification {
+                ticker: ticker.to_string(),
+                asset_class: default_asset_class(quote_type.as_deref()).to_string(),
+                region,
+                sector,
+                is_default: true,
+            }
+        }
+    })
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Every ticker that was ever traded or classified, with its classification.
+pub fn get_ticker_classifications_db(
+    db_path: &PathBuf,
+) -> Result<Vec<TickerClassification>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tickers: BTreeSet<String> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT UPPER(ticker) FROM transactions WHERE ticker IS NOT NULL AND ticker != ''
+                 UNION SELECT ticker FROM ticker_classifications",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    tickers.iter().map(|t| classify(&conn, t)).collect()
+}
+
+/// Sets what a ticker counts as. A `None` asset class falls back to the quote type default;
+/// clearing all three fields removes the classification.
+pub fn set_ticker_classification_db(
+    db_path: &PathBuf,
+    ticker: String,
+    asset_class: Option<String>,
+    region: Option<String>,
+    sector: Option<String>,
+) -> Result<TickerClassification, String> {
+    let ticker = ticker.trim().to_uppercase();
+    if ticker.is_empty() {
+        return Err("Ticker is required".to_string());
+    }
+    let asset_class = non_empty(asset_class).map(|c| c.to_lowercase());
+    if let Some(class) = &asset_class {
+        if !ASSET_CLASSES.contains(&class.as_str()) {
+            return Err(format!("Unknown asset class: {}", class));
+        }
+    }
+    let region = non_empty(region);
+    let sector = non_empty(sector);
+
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    if asset_class.is_none() && region.is_none() && sector.is_none() {
+        conn.execute(
+            "DELETE FROM ticker_classifications WHERE ticker = ?1",
+            params![ticker],
+        )
+        .map_err(|e| e.to_string())?;
+    } else {
+        conn.execute(
+            "INSERT OR REPLACE INTO ticker_classifications (ticker, asset_class, region, sector)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![ticker, asset_class, region, sector],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    classify(&conn, &ticker)
+}
+
+fn load_targets(conn: &Connection, portfolio: &str) -> Result<Vec<AllocationTarget>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT asset_class, target_percent FROM allocation_targets WHERE portfolio = ?1
+             ORDER BY target_percent DESC, asset_class",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![portfolio], |row| {
+            Ok(AllocationTarget {
+                asset_class: row.get(0)?,
+                target_percent: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn portfolio_accounts(conn: &Connection, portfolio: &str) -> Result<Vec<i32>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM accounts WHERE portfolio = ?1 ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![portfolio], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Portfolios that have accounts or targets assigned.
+pub fn get_portfolios_db(db_path: &PathBuf) -> Result<Vec<Portfolio>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let names: Vec<String> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT portfolio FROM accounts WHERE portfolio IS NOT NULL
+                 UNION SELECT portfolio FROM allocation_targets ORDER BY 1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    names
+        .into_iter()
+        .map(|name| {
+            Ok(Portfolio {
+                account_ids: portfolio_accounts(&conn, &name)?,
+                targets: load_targets(&conn, &name)?,
+                name,
+            })
+        })
+        .collect()
+}
+
+/// Puts an account in a portfolio, or takes it out with `None`.
+pub fn set_account_portfolio_db(
+    db_path: &PathBuf,
+    account_id: i32,
+    portfolio: Option<String>,
+) -> Result<(), String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE accounts SET portfolio = ?1 WHERE id = ?2",
+            params![non_empty(portfolio), account_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Account {} not found", account_id));
+    }
+    Ok(())
+}
+
+/// Replaces the targets of a portfolio. They must add up to 100%; an empty list clears them.
+pub fn set_allocation_targets_db(
+    db_path: &PathBuf,
+    portfolio: String,
+    targets: Vec<AllocationTarget>,
+) -> Result<Vec<AllocationTarget>, String> {
+    let portfolio = portfolio.trim().to_string();
+    if portfolio.is_empty() {
+        return Err("Portfolio name is required".to_string());
+    }
+    let mut seen = BTreeSet::new();
+    for target in &targets {
+        if !ASSET_CLASSES.contains(&target.asset_class.as_str()) {
+            return Err(format!("Unknown asset class: {}", target.asset_class));
+        }
+        if !seen.insert(target.asset_class.as_str()) {
+            return Err(format!("Duplicate target for {}", target.asset_class));
+        }
+        if !(0.0..=100.0).contains(&target.target_percent) {
+            return Err(format!(
+                "Target for {} must be between 0 and 100",
+                target.asset_class
+            ));
+        }
+    }
+    let sum: f64 = targets.iter().map(|t| t.target_percent).sum();
+    if !targets.is_empty() && (sum - 100.0).abs() > 0.01 {
+        return Err(format!("Targets add up to {}%, not 100%", sum));
+    }
+
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM allocation_targets WHERE portfolio = ?1",
+        params![portfolio],
+    )
+    .map_err(|e| e.to_string())?;
+    for target in &targets {
+        tx.execute(
+            "INSERT INTO allocation_targets (portfolio, asset_class, target_percent) VALUES (?1, ?2, ?3)",
+            params![portfolio, target.asset_class, target.target_percent],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    load_targets(&conn, &portfolio)
+}
+
+// Value of one share in the base currency, zero when there is no quote
+fn unit_price(holding: &Holding) -> f64 {
+    if holding.shares > 0.0 {
+        holding.market_value / holding.shares
+    } else {
+        0.0
+    }
+}
+
+fn trade(holding: &Holding, asset_class: &str, action: &str, shares: f64) -> RebalanceTrade {
+    let price = unit_price(holding);
+    RebalanceTrade {
+        ticker: holding.ticker.clone(),
+        asset_class: asset_class.to_string(),
+        action: action.to_string(),
+        shares,
+        price: round_cents(price),
+        amount: round_cents(shares * price),
+    }
+}
+
+/// Drift of a portfolio's holdings from its targets and the whole-share trades that bring it
+/// back, investing `contribution` on top. With `contribution_only` nothing is sold and the
+/// contribution goes to the underweight classes in proportion to how far short they are.
+/// Buys go to the largest holding of a class; a class with nothing held is left in cash.
+pub fn rebalance_plan_db(
+    db_path: &PathBuf,
+    portfolio: String,
+    contribution: f64,
+    contribution_only: bool,
+    base_currency: Option<String>,
+) -> Result<RebalancePlan, String> {
+    if !contribution.is_finite() || contribution < 0.0 {
+        return Err("Contribution cannot be negative".to_string());
+    }
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let targets = load_targets(&conn, &portfolio)?;
+    if targets.is_empty() {
+        return Err(format!("Portfolio {} has no allocation targets", portfolio));
+    }
+    let account_ids = portfolio_accounts(&conn, &portfolio)?;
+    let report = get_holdings_db(db_path, Some(account_ids), None, base_currency)?;
+
+    let mut by_class: BTreeMap<String, Vec<&Holding>> = BTreeMap::new();
+    for holding in &report.holdings {
+        let class = classify(&conn, &holding.ticker)?.asset_class;
+        by_class.entry(class).or_default().push(holding);
+    }
+    let mut classes: Vec<(String, f64)> = targets
+        .iter()
+        .map(|t| (t.asset_class.clone(), t.target_percent))
+        .collect();
+    for class in by_class.keys() {
+        if !classes.iter().any(|(c, _)| c == class) {
+            classes.push((class.clone(), 0.0));
+        }
+    }
+
+    let current_total = report.total_value;
+    let total = current_total + contribution;
+    let value_of = |class: &str| -> f64 {
+        by_class
+            .get(class)
+            .map(|hs| hs.iter().map(|h| h.market_value).sum())
+            .unwrap_or(0.0)
+    };
+
+    let mut allocations = Vec::new();
+    let mut deltas = Vec::new();
+    for (class, target_percent) in &classes {
+        let current_value = value_of(class);
+        let target_value = total * target_percent / 100.0;
+        let current_percent = if current_total > 0.0 {
+            current_value / current_total * 100.0
+        } else {
+            0.0
+        };
+        allocations.push(AllocationLine {
+            asset_class: class.clone(),
+            current_value: round_cents(current_value),
+            current_percent,
+            target_percent: *target_percent,
+            target_value: round_cents(target_value),
+            drift: current_percent - target_percent,
+        });
+        deltas.push(target_value - current_value);
+    }
+    if contribution_only {
+        let shortfall: f64 = deltas.iter().filter(|d| **d > 0.0).sum();
+        for delta in deltas.iter_mut() {
+            *delta = if *delta > 0.0 && shortfall > 0.0 {
+                contribution * *delta / shortfall
+            } else {
+                0.0
+            };
+        }
+    }
+
+    // Sales first, spread over the class's holdings by value, so their cash funds the buys
+    let mut cash = contribution;
+    let mut sells = Vec::new();
+    for ((class, _), delta) in classes.iter().zip(&deltas) {
+        if *delta >= 0.0 {
+            continue;
+        }
+        let current_value = value_of(class);
+        for holding in by_class.get(class).into_iter().flatten() {
+            let price = unit_price(holding);
+            if price <= 0.0 {
+                continue;
+            }
+            let amount = -delta * holding.market_value / current_value;
+            let shares = ((amount / price) + EPSILON).floor().min(holding.shares);
+            if shares > 0.0 {
+                let sale = trade(holding, class, "sell", shares);
+                cash += shares * price;
+                sells.push(sale);
+            }
+        }
+    }
+
+    let mut buys: Vec<(RebalanceTrade, f64)> = Vec::new();
+    for ((class, _), delta) in classes.iter().zip(&deltas) {
+        if *delta <= 0.0 {
+            continue;
+        }
+        let largest = by_class
+            .get(class)
+            .into_iter()
+            .flatten()
+            .filter(|h| unit_price(h) > 0.0)
+            .max_by(|a, b| a.market_value.total_cmp(&b.market_value));
+        if let Some(holding) = largest {
+            let price = unit_price(holding);
+            let shares = ((delta / price) + EPSILON).floor();
+            if shares > 0.0 {
+                buys.push((trade(holding, class, "buy", shares), price));
+            }
+        }
+    }
+    // Whole-share sales can raise a little less than planned; trim the buys to fit
+    let mut spent: f64 = buys.iter().map(|(t, p)| t.shares * p).sum();
+    while spent > cash + EPSILON {
+        let Some((buy, price)) = buys
+            .iter_mut()
+            .filter(|(t, _)| t.shares > 0.0)
+            .max_by(|a, b| a.0.amount.total_cmp(&b.0.amount))
+        else {
+            break;
+        };
+        buy.shares -= 1.0;
+        buy.amount = round_cents(buy.shares * *price);
+        spent -= *price;
+    }
+
+    let mut trades = sells;
+    trades.extend(buys.into_iter().map(|(t, _)| t).filter(|t| t.shares > 0.0));
+    Ok(RebalancePlan {
+        portfolio,
+        base_currency: report.base_currency,
+        contribution,
+        total_value: round_cents(total),
+        allocations,
+        trades,
+        cash_left: round_cents(cash - spent),
+    })
+}
+
+#[tauri::command]
+pub fn get_ticker_classifications(
+    app_handle: AppHandle,
+) -> Result<Vec<TickerClassification>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_ticker_classifications_db(&db_path)
+}
+
+#[tauri::command]
+pub fn set_ticker_classification(
+    app_handle: AppHandle,
+    ticker: String,
+    asset_class: Option<String>,
+    region: Option<String>,
+    sector: Option<String>,
+) -> Result<TickerClassification, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_ticker_classification_db(&db_path, ticker, asset_class, region, sector)
+}
+
+#[tauri::command]
+pub fn get_portfolios(app_handle: AppHandle) -> Result<Vec<Portfolio>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_portfolios_db(&db_path)
+}
+
+#[tauri::command]
+pub fn set_account_portfolio(
+    app_handle: AppHandle,
+    account_id: i32,
+    portfolio: Option<String>,
+) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_account_portfolio_db(&db_path, account_id, portfolio)
+}
+
+#[tauri::command]
+pub fn set_allocation_targets(
+    app_handle: AppHandle,
+    portfolio: String,
+    targets: Vec<AllocationTarget>,
+) -> Result<Vec<AllocationTarget>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_allocation_targets_db(&db_path, portfolio, targets)
+}
+
+#[tauri::command]
+pub fn rebalance_plan(
+    app_handle: AppHandle,
+    portfolio: String,
+    contribution: f64,
+    contribution_only: bool,
+    base_currency: Option<String>,
+) -> Result<RebalancePlan, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    rebalance_plan_db(
+        &db_path,
+        portfolio,
+        contribution,
+        contribution_only,
+        base_currency,
+    )
+}
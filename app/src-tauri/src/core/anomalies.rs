@@ -0,0 +1,282 @@
+use crate::models::{Anomaly, AnomalyReport};
+use crate::performance::parse_date;
+use crate::reports::{
+    base_currency, load_flows, month_key, months_between, resolve_range, Flow, ReportArgs,
+};
+use crate::subscriptions::normalize_payee;
+use crate::transfers::round_cents;
+use chrono::{Months, NaiveDate};
+use rusqlite::Connection;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// What made a transaction or month stand out: an amount far above the payee's or the
+/// category's history, a large first payment to a payee, the same charge twice on one day,
+/// or a month of spending in a category well above its recent months.
+pub const ANOMALY_KINDS: [&str; 4] = ["outlier", "new_payee", "duplicate", "category_spike"];
+
+// Earlier charges needed before a payee or category has a usual amount
+const MIN_HISTORY: usize = 4;
+// Robust z-score, in scaled median absolute deviations, above which an amount is an outlier
+const OUTLIER_SCORE: f64 = 3.5;
+// A first payment this many times the median charge is large
+const NEW_PAYEE_MULTIPLE: f64 = 3.0;
+const NEW_PAYEE_MIN_HISTORY: usize = 5;
+// A month is a spike when above both this multiple of the baseline and two deviations
+const SPIKE_MONTHS: u32 = 6;
+const SPIKE_MIN_MONTHS: usize = 3;
+const SPIKE_RATIO: f64 = 1.5;
+const SPIKE_SCORE: f64 = 2.0;
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+// Median and how many scaled deviations `amount` lies above it. The scale never drops
+// under 5% of the median, so identical past charges don't make every cent an outlier.
+fn robust_score(history: &[f64], amount: f64) -> (f64, f64) {
+    let center = median(history);
+    let deviations: Vec<f64> = history.iter().map(|v| (v - center).abs()).collect();
+    let scale = (1.4826 * median(&deviations)).max(0.05 * center.abs());
+    if scale <= 0.0 {
+        return (center, 0.0);
+    }
+    (center, (amount - center) / scale)
+}
+
+fn anomaly(kind: &str, flow: &Flow, amount: f64, baseline: f64, explanation: String) -> Anomaly {
+    Anomaly {
+        kind: kind.to_string(),
+        transaction_id: Some(flow.id),
+        account_id: Some(flow.account_id),
+        date: flow.date.format("%Y-%m-%d").to_string(),
+        payee: Some(flow.payee.clone()),
+        category: Some(flow.category.clone()),
+        amount: round_cents(amount),
+        baseline: Some(round_cents(baseline)),
+        explanation,
+    }
+}
+
+/// Unusual spending between `from` and `to`, each hit with an explanation. Every
+/// transaction is judged against all spending before its day; transfers and investment
+/// transactions are left out unless the report includes transfers.
+pub fn get_anomalies_db(db_path: &PathBuf, args: ReportArgs) -> Result<AnomalyReport, String> {
+    let base = base_currency(&args);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let (from, to) = resolve_range(&conn, &args)?;
+    let history_start = NaiveDate::from_ymd_opt(1900, 1, 1).unwrap_or(from);
+    let flows: Vec<Flow> = load_flows(&conn, &args, &base, history_start, to)?
+        .into_iter()
+        .filter(|f| f.amount < 0.0)
+        .collect();
+
+    let mut anomalies = Vec::new();
+    let mut by_payee: HashMap<String, Vec<f64>> = HashMap::new();
+    let mut by_category: HashMap<String, Vec<f64>> = HashMap::new();
+    let mut all: Vec<f64> = Vec::new();
+    let mut duplicates: BTreeMap<(i32, NaiveDate, String, i64), Vec<&Flow>> = BTreeMap::new();
+
+    // A day's charges are judged together, then join the history
+    let mut start = 0;
+    while start < flows.len() {
+        let day = flows[start].date;
+        let end = start + flows[start..].iter().take_while(|f| f.date == day).count();
+        let batch = &flows[start..end];
+        start = end;
+        if day >= from {
+            for flow in batch {
+                let spent = -flow.amount;
+                let payee = normalize_payee(&flow.payee);
+                duplicates
+                    .entry((
+                        flow.account_id,
+                        day,
+                        payee.clone(),
+                        (spent * 100.0).round() as i64,
+                    ))
+                    .or_default()
+                    .push(flow);
+
+                let payee_history = by_payee.get(&payee).map(Vec::as_slice).unwrap_or(&[]);
+                let category_history = by_category
+                    .get(&flow.category)
+                    .map(Vec::as_slice)
+                    .unwrap_or(&[]);
+                if payee_history.len() >= MIN_HISTORY {
+                    let (usual, score) = robust_score(payee_history, spent);
+                    if score > OUTLIER_SCORE {
+                        anomalies.push(anomaly(
+                            "outlier",
+                            flow,
+                            spent,
+                            usual,
+                            format!(
+                                "{:.2} {} at {} is {:.1} deviations above the usual {:.2} there",
+                                spent, base, flow.payee, score, usual
+                            ),
+                        ));
+                        continue;
+                    }
+                } else if category_history.len() >= MIN_HISTORY {
+                    let (usual, score) = robust_score(category_history, spent);
+                    if score > OUTLIER_SCORE {
+                        anomalies.push(anomaly(
+                            "outlier",
+                            flow,
+                            spent,
+                            usual,
+                            format!(
+                                "{:.2} {} in {} is {:.1} deviations above the usual {:.2} for the category",
+                                spent, base, flow.category, score, usual
+                            ),
+                        ));
+                        continue;
+                    }
+                }
+                if payee_history.is_empty() && all.len() >= NEW_PAYEE_MIN_HISTORY {
+                    let typical = median(&all);
+                    if spent >= NEW_PAYEE_MULTIPLE * typical {
+                        anomalies.push(anomaly(
+                            "new_payee",
+                            flow,
+                            spent,
+                            typical,
+                            format!(
+                                "First payment to {}, {:.2} {}, is {:.1} times the typical {:.2} charge",
+                                flow.payee,
+                                spent,
+                                base,
+                                spent / typical,
+                                typical
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        for flow in batch {
+            by_payee
+                .entry(normalize_payee(&flow.payee))
+                .or_default()
+                .push(-flow.amount);
+            by_category
+                .entry(flow.category.clone())
+                .or_default()
+                .push(-flow.amount);
+            all.push(-flow.amount);
+        }
+    }
+
+    for ((_, day, _, _), charges) in duplicates {
+        if charges.len() < 2 {
+            continue;
+        }
+        let first = charges[0];
+        for flow in &charges[1..] {
+            anomalies.push(anomaly(
+                "duplicate",
+                flow,
+                -flow.amount,
+                -first.amount,
+                format!(
+                    "{:.2} {} charged at {} {} times on {}",
+                    -flow.amount,
+                    base,
+                    flow.payee,
+                    charges.len(),
+                    day.format("%Y-%m-%d")
+                ),
+            ));
+        }
+    }
+
+    // Monthly spending per category, and the month each category first saw spending
+    let mut monthly: HashMap<(String, String), f64> = HashMap::new();
+    let mut first_month: HashMap<String, String> = HashMap::new();
+    for flow in &flows {
+        let month = month_key(flow.date);
+        first_month
+            .entry(flow.category.clone())
+            .or_insert_with(|| month.clone());
+        *monthly.entry((flow.category.clone(), month)).or_default() -= flow.amount;
+    }
+    let mut categories: Vec<&String> = first_month.keys().collect();
+    categories.sort();
+    for key in months_between(from, to) {
+        let month = parse_date(&format!("{}-01", key))?;
+        for category in &categories {
+            let Some(spent) = monthly.get(&((*category).clone(), key.clone())).copied() else {
+                continue;
+            };
+            let earlier: Vec<f64> = (1..=SPIKE_MONTHS)
+                .map(|back| month_key(month - Months::new(back)))
+                .filter(|m| *m >= first_month[*category])
+                .map(|m| {
+                    monthly
+                        .get(&((*category).clone(), m))
+                        .copied()
+                        .unwrap_or(0.0)
+                })
+                .collect();
+            if earlier.len() < SPIKE_MIN_MONTHS {
+                continue;
+            }
+            let average = earlier.iter().sum::<f64>() / earlier.len() as f64;
+            let spread = (earlier.iter().map(|v| (v - average).powi(2)).sum::<f64>()
+                / earlier.len() as f64)
+                .sqrt();
+            if spent > SPIKE_RATIO * average && spent > average + SPIKE_SCORE * spread {
+                anomalies.push(Anomaly {
+                    kind: "category_spike".to_string(),
+                    transaction_id: None,
+                    account_id: None,
+                    date: key.clone(),
+                    payee: None,
+                    category: Some((*category).clone()),
+                    amount: round_cents(spent),
+                    baseline: Some(round_cents(average)),
+                    explanation: format!(
+                        "Spending on {} in {} is {:.2} {}, {:.1} times the {:.2} monthly average of the previous {} months",
+                        category,
+                        key,
+                        spent,
+                        base,
+                        spent / average,
+                        average,
+                        earlier.len()
+                    ),
+                });
+            }
+        }
+    }
+
+    let kind_order = |kind: &str| ANOMALY_KINDS.iter().position(|k| *k == kind);
+    anomalies.sort_by(|a, b| {
+        a.date
+            .cmp(&b.date)
+            .then(kind_order(&a.kind).cmp(&kind_order(&b.kind)))
+            .then(a.transaction_id.cmp(&b.transaction_id))
+    });
+
+    Ok(AnomalyReport {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        base_currency: base,
+        anomalies,
+    })
+}
+
+#[tauri::command]
+pub fn get_anomalies(app_handle: AppHandle, args: ReportArgs) -> Result<AnomalyReport, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_anomalies_db(&db_path, args)
+}
@@ -0,0 +1,385 @@
+use crate::corporate_actions::{load_split_ratios, split_factor};
+use crate::fx::{missing_rate, rate_date, rate_on, report_rate_mode, requested_rate_mode};
+use crate::lots::{build_lots, holding_term};
+use crate::models::{CapitalGainLine, CapitalGainTotals, CapitalGainsReport, UnrealizedGainLine};
+use crate::transfers::round_cents;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Layouts `export_capital_gains` can produce: every disposal as plain CSV, or grouped
+/// into short- and long-term parts with the columns of IRS Form 8949.
+pub const CAPITAL_GAINS_FORMATS: [&str; 2] = ["csv", "form8949"];
+
+fn convert(
+    conn: &Connection,
+    amount: f64,
+    from: &str,
+    to: &str,
+    date: Option<&str>,
+) -> Result<f64, String> {
+    rate_on(conn, from, to, date)
+        .map(|rate| amount * rate)
+        .ok_or_else(|| missing_rate(from, to, date))
+}
+
+fn add_to(totals: &mut CapitalGainTotals, line: &CapitalGainLine) {
+    totals.proceeds = round_cents(totals.proceeds + line.proceeds);
+    totals.cost_basis = round_cents(totals.cost_basis + line.cost_basis);
+    totals.fees = round_cents(totals.fees + line.fees);
+    totals.gain = round_cents(totals.gain + line.gain);
+}
+
+// Last stored close on or before `date`
+fn close_on(conn: &Connection, ticker: &str, date: &str) -> Result<Option<f64>, String> {
+    conn.query_row(
+        "SELECT price FROM daily_stock_prices
+         WHERE UPPER(ticker) = ?1 AND date <= ?2 ORDER BY date DESC LIMIT 1",
+        params![ticker, date],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Disposals in `year` for the given accounts (all when `None`) and the lots still held at
+/// year end, converted to `base_currency` (USD when `None`) under one of `fx::RATE_MODES`.
+pub fn capital_gains_report_db(
+    db_path: &PathBuf,
+    year: i32,
+    account_ids: Option<Vec<i32>>,
+    base_currency: Option<String>,
+    rate_mode: Option<String>,
+) -> Result<CapitalGainsReport, String> {
+    if !(1900..=9999).contains(&year) {
+        return Err(format!("Invalid tax year: {}", year));
+    }
+    let mode = report_rate_mode(rate_mode)?;
+    let base = base_currency
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "USD".to_string());
+    let year_start = format!("{}-01-01", year);
+    let year_end = format!("{}-12-31", year);
+
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let names: HashMap<i32, String> = {
+        let mut stmt = conn
+            .prepare("SELECT id, name FROM accounts")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    let included = |account_id: &i32| {
+        account_ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(account_id))
+    };
+    let name_of = |account_id: i32| names.get(&account_id).cloned().unwrap_or_default();
+
+    let lots = build_lots(&conn, Some(&year_end))?;
+
+    let mut realized = Vec::new();
+    let mut short_term = CapitalGainTotals::default();
+    let mut long_term = CapitalGainTotals::default();
+    let mut unknown_term = CapitalGainTotals::default();
+    let mut total = CapitalGainTotals::default();
+    for gain in lots
+        .realized
+        .into_iter()
+        .filter(|g| included(&g.account_id) && g.disposed_date.as_str() >= year_start.as_str())
+    {
+        let sold = &gain.disposed_date;
+        let acquired = gain.acquired_date.as_deref().unwrap_or(sold);
+        let proceeds = round_cents(convert(
+            &conn,
+            gain.proceeds,
+            &gain.currency,
+            &base,
+            rate_date(&mode, sold),
+        )?);
+        let fees = round_cents(convert(
+            &conn,
+            gain.fees,
+            &gain.currency,
+            &base,
+            rate_date(&mode, sold),
+        )?);
+        let cost_basis = round_cents(convert(
+            &conn,
+            gain.cost_basis,
+            &gain.currency,
+            &base,
+            rate_date(&mode, acquired),
+        )?);
+        let line = CapitalGainLine {
+            account_name: name_of(gain.account_id),
+            account_id: gain.account_id,
+            ticker: gain.ticker,
+            sell_tx_id: gain.sell_tx_id,
+            shares: gain.shares,
+            acquired_date: gain.acquired_date,
+            disposed_date: gain.disposed_date,
+            proceeds,
+            cost_basis,
+            fees,
+            gain: round_cents(proceeds - fees - cost_basis),
+            term: gain.term,
+            currency: gain.currency,
+        };
+        match line.term.as_str() {
+            "long" => add_to(&mut long_term, &line),
+            "short" => add_to(&mut short_term, &line),
+            _ => add_to(&mut unknown_term, &line),
+        }
+        if line.term != "unknown" {
+            add_to(&mut total, &line);
+        }
+        realized.push(line);
+    }
+    realized.sort_by(|a, b| {
+        a.disposed_date
+            .cmp(&b.disposed_date)
+            .then(a.sell_tx_id.cmp(&b.sell_tx_id))
+    });
+
+    // Stored closes are in today's split basis, the lots in the year-end one
+    let ratios = load_split_ratios(&conn)?;
+    let mut unrealized = Vec::new();
+    for lot in lots
+        .open_lots
+        .into_iter()
+        .filter(|l| included(&l.account_id))
+    {
+        let cost_basis = round_cents(convert(
+            &conn,
+            lot.cost_basis,
+            &lot.currency,
+            &base,
+            rate_date(&mode, &lot.acquired_date),
+        )?);
+        let market_value = match close_on(&conn, &lot.ticker, &year_end)? {
+            Some(close) => {
+                let shares_now = lot.shares * split_factor(&ratios, &lot.ticker, &year_end, None);
+                let value = convert(
+                    &conn,
+                    shares_now * close,
+                    &lot.currency,
+                    &base,
+                    rate_date(&mode, &year_end),
+                )?;
+                Some(round_cents(value))
+            }
+            None => None,
+        };
+        unrealized.push(UnrealizedGainLine {
+            account_name: name_of(lot.account_id),
+            account_id: lot.account_id,
+            term: holding_term(&lot.acquired_date, &year_end).to_string(),
+            ticker: lot.ticker,
+            lot_id: lot.lot_id,
+            acquired_date: lot.acquired_date,
+            shares: lot.shares,
+            cost_basis,
+            gain: market_value.map(|v| round_cents(v - cost_basis)),
+            market_value,
+            currency: lot.currency,
+        });
+    }
+
+    Ok(CapitalGainsReport {
+        year,
+        base_currency: base,
+        realized,
+        short_term,
+        long_term,
+        unknown_term,
+        total,
+        unrealized,
+    })
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    fields.join(",") + "\n"
+}
+
+// Form 8949 writes dates as MM/DD/YYYY
+fn form_date(date: Option<&str>) -> String {
+    date.and_then(|d| chrono::NaiveDate::parse_from_str(d.get(..10).unwrap_or(d), "%Y-%m-%d").ok())
+        .map(|d| d.format("%m/%d/%Y").to_string())
+        .unwrap_or_else(|| "VARIOUS".to_string())
+}
+
+fn money(amount: f64) -> String {
+    format!("{:.2}", amount)
+}
+
+/// Every disposal with its amounts in the base currency, one row each.
+pub fn capital_gains_csv(report: &CapitalGainsReport) -> String {
+    let mut out = csv_line(
+        &[
+            "Account",
+            "Ticker",
+            "Shares",
+            "Date Acquired",
+            "Date Sold",
+            "Proceeds",
+            "Cost Basis",
+            "Fees",
+            "Gain",
+            "Term",
+            "Currency",
+        ]
+        .map(String::from),
+    );
+    for line in &report.realized {
+        out += &csv_line(&[
+            line.account_name.clone(),
+            line.ticker.clone(),
+            line.shares.to_string(),
+            line.acquired_date.clone().unwrap_or_default(),
+            line.disposed_date.clone(),
+            money(line.proceeds),
+            money(line.cost_basis),
+            money(line.fees),
+            money(line.gain),
+            line.term.clone(),
+            report.base_currency.clone(),
+        ]);
+    }
+    out
+}
+
+/// Disposals split into Part I (short-term) and Part II (long-term) with the Form 8949
+/// columns. Selling fees are taken off the proceeds, as brokers report them. Fails when a
+/// sale has shares without a recorded purchase, since neither part can hold them.
+pub fn capital_gains_form_8949(report: &CapitalGainsReport) -> Result<String, String> {
+    if let Some(line) = report.realized.iter().find(|l| l.term == "unknown") {
+        return Err(format!(
+            "Sale {} of {} on {} has {} shares without a recorded purchase",
+            line.sell_tx_id, line.ticker, line.disposed_date, line.shares
+        ));
+    }
+
+    let header = [
+        "(a) Description of property",
+        "(b) Date acquired",
+        "(c) Date sold or disposed of",
+        "(d) Proceeds (sales price)",
+        "(e) Cost or other basis",
+        "(f) Code(s)",
+        "(g) Amount of adjustment",
+        "(h) Gain or (loss)",
+    ]
+    .map(String::from);
+
+    let mut out = String::new();
+    for (title, long) in [
+        ("Part I - Short-Term Capital Gains and Losses", false),
+        ("Part II - Long-Term Capital Gains and Losses", true),
+    ] {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out += &csv_line(&[title.to_string()]);
+        out += &csv_line(&header);
+        let lines = report
+            .realized
+            .iter()
+            .filter(|l| (l.term == "long") == long);
+        for line in lines {
+            out += &csv_line(&[
+                format!("{} sh. {}", line.shares, line.ticker),
+                form_date(line.acquired_date.as_deref()),
+                form_date(Some(&line.disposed_date)),
+                money(line.proceeds - line.fees),
+                money(line.cost_basis),
+                String::new(),
+                String::new(),
+                money(line.gain),
+            ]);
+        }
+        let totals = if long {
+            &report.long_term
+        } else {
+            &report.short_term
+        };
+        out += &csv_line(&[
+            "Totals".to_string(),
+            String::new(),
+            String::new(),
+            money(totals.proceeds - totals.fees),
+            money(totals.cost_basis),
+            String::new(),
+            String::new(),
+            money(totals.gain),
+        ]);
+    }
+    Ok(out)
+}
+
+/// Renders the report for `year` in one of `CAPITAL_GAINS_FORMATS`.
+pub fn export_capital_gains_db(
+    db_path: &PathBuf,
+    year: i32,
+    account_ids: Option<Vec<i32>>,
+    base_currency: Option<String>,
+    format: String,
+    rate_mode: Option<String>,
+) -> Result<String, String> {
+    if !CAPITAL_GAINS_FORMATS.contains(&format.as_str()) {
+        return Err(format!("Unknown export format: {}", format));
+    }
+    let report = capital_gains_report_db(db_path, year, account_ids, base_currency, rate_mode)?;
+    Ok(if format == "form8949" {
+        capital_gains_form_8949(&report)?
+    } else {
+        capital_gains_csv(&report)
+    })
+}
+
+#[tauri::command]
+pub fn capital_gains_report(
+    app_handle: AppHandle,
+    year: i32,
+    account_ids: Option<Vec<i32>>,
+    base_currency: Option<String>,
+    rate_mode: Option<String>,
+) -> Result<CapitalGainsReport, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    let rate_mode = requested_rate_mode(&app_handle, rate_mode)?;
+    capital_gains_report_db(&db_path, year, account_ids, base_currency, rate_mode)
+}
+
+#[tauri::command]
+pub fn export_capital_gains(
+    app_handle: AppHandle,
+    year: i32,
+    account_ids: Option<Vec<i32>>,
+    base_currency: Option<String>,
+    format: String,
+    rate_mode: Option<String>,
+) -> Result<String, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    let rate_mode = requested_rate_mode(&app_handle, rate_mode)?;
+    export_capital_gains_db(
+        &db_path,
+        year,
+        account_ids,
+        base_currency,
+        format,
+        rate_mode,
+    )
+}
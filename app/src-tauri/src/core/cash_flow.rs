@@ -0,0 +1,337 @@
+use crate::fx::{missing_rate, rate_date, rate_on, report_rate_mode, requested_rate_mode};
+use crate::models::{CashFlowGraph, CashFlowLink, CashFlowNode};
+use crate::reports::{base_currency, resolve_range, ReportArgs, CASH_ROW_CONDITION};
+use crate::transfers::round_cents;
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Separates the levels of a category name, as in "Food:Groceries".
+pub const CATEGORY_SEPARATOR: char = ':';
+
+/// Income flows from `source` (payees) through `income` categories into the `pool`, which
+/// feeds `expense` categories, `investments` and `savings`. A shortfall is drawn from
+/// `balances`, and money taken out of brokerage accounts comes in as `investments` too.
+pub const CASH_FLOW_NODE_KINDS: [&str; 7] = [
+    "source",
+    "income",
+    "pool",
+    "expense",
+    "investments",
+    "savings",
+    "balances",
+];
+
+const POOL: &str = "pool";
+
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<CashFlowNode>,
+    node_index: HashMap<String, usize>,
+    links: Vec<CashFlowLink>,
+    link_index: HashMap<(String, String), usize>,
+}
+
+impl Graph {
+    fn node(&mut self, id: &str, label: &str, kind: &str) {
+        if !self.node_index.contains_key(id) {
+            self.node_index.insert(id.to_string(), self.nodes.len());
+            self.nodes.push(CashFlowNode {
+                id: id.to_string(),
+                label: label.to_string(),
+                kind: kind.to_string(),
+                value: 0.0,
+            });
+        }
+    }
+
+    fn link(&mut self, source: &str, target: &str, value: f64) {
+        let key = (source.to_string(), target.to_string());
+        match self.link_index.get(&key) {
+            Some(i) => self.links[*i].value += value,
+            None => {
+                self.link_index.insert(key, self.links.len());
+                self.links.push(CashFlowLink {
+                    source: source.to_string(),
+                    target: target.to_string(),
+                    value,
+                });
+            }
+        }
+    }
+
+    // Node ids for each level of `category` down to `depth`, deepest last
+    fn category_path(&mut self, kind: &str, category: &str, depth: usize) -> Vec<String> {
+        let mut path = Vec::new();
+        let mut id = kind.to_string();
+        for part in category
+            .split(CATEGORY_SEPARATOR)
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .take(depth)
+        {
+            id = format!("{}{}{}", id, CATEGORY_SEPARATOR, part);
+            self.node(&id, part, kind);
+            path.push(id.clone());
+        }
+        path
+    }
+
+    fn finish(mut self) -> (Vec<CashFlowNode>, Vec<CashFlowLink>) {
+        self.links.retain(|l| l.value >= 0.005);
+        let mut inflow = vec![0.0; self.nodes.len()];
+        let mut outflow = vec![0.0; self.nodes.len()];
+        for link in &self.links {
+            outflow[self.node_index[&link.source]] += link.value;
+            inflow[self.node_index[&link.target]] += link.value;
+        }
+        for (i, node) in self.nodes.iter_mut().enumerate() {
+            node.value = round_cents(inflow[i].max(outflow[i]));
+        }
+        for link in self.links.iter_mut() {
+            link.value = round_cents(link.value);
+        }
+        // Nodes follow the direction of the flow, sources first
+        let kind_order = |kind: &str| CASH_FLOW_NODE_KINDS.iter().position(|k| *k == kind);
+        let mut nodes: Vec<CashFlowNode> =
+            self.nodes.into_iter().filter(|n| n.value > 0.0).collect();
+        nodes.sort_by_key(|n| kind_order(&n.kind));
+        (nodes, self.links)
+    }
+}
+
+fn sorted_by_value(totals: Vec<(String, f64)>) -> Vec<(String, f64)> {
+    let mut totals = totals;
+    totals.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    totals
+}
+
+/// Sankey nodes and links for the range, with categories shown `depth` levels deep (2 when
+/// `None`). Amounts net per category, so refunds reduce the spending they undo and a
+/// category counts as income or spending by its sign over the range. Transfers between the
+/// selected accounts cancel out; transfers with brokerage accounts outside the selection
+/// are investments and other transfers move savings, which the leftover already counts.
+/// Without `account_ids` every account except brokerage and asset accounts is included, and
+/// dividends and interest paid into brokerage accounts count as income that stays invested.
+/// Share trades are left out.
+pub fn cash_flow_graph_db(
+    db_path: &PathBuf,
+    args: ReportArgs,
+    depth: Option<u32>,
+) -> Result<CashFlowGraph, String> {
+    let depth = depth.unwrap_or(2);
+    if depth == 0 {
+        return Err("Depth must be at least 1".to_string());
+    }
+    let base = base_currency(&args);
+    let mode = report_rate_mode(args.rate_mode.clone())?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let (from, to) = resolve_range(&conn, &args)?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT t.account_id, COALESCE(a.kind, 'cash'), t.date, COALESCE(t.category, ''),
+                    COALESCE(t.payee, ''), t.amount, COALESCE(t.currency, a.currency),
+                    (t.category = 'Transfer' OR t.transfer_id IS NOT NULL),
+                    COALESCE(la.id, pa.id), COALESCE(la.kind, pa.kind, 'cash'),
+                    t.action IS NOT NULL
+             FROM transactions t
+             JOIN accounts a ON a.id = t.account_id
+             LEFT JOIN transactions l ON l.id = t.linked_tx_id
+             LEFT JOIN accounts la ON la.id = l.account_id
+             LEFT JOIN accounts pa ON t.linked_tx_id IS NULL AND pa.name = t.payee
+             WHERE {}
+               AND substr(t.date, 1, 10) >= ?1 AND substr(t.date, 1, 10) <= ?2
+             ORDER BY t.date, t.id",
+            CASH_ROW_CONDITION
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            params![
+                from.format("%Y-%m-%d").to_string(),
+                to.format("%Y-%m-%d").to_string()
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, f64>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<bool>>(7)?,
+                    row.get::<_, Option<i32>>(8)?,
+                    row.get::<_, String>(9)?,
+                    row.get::<_, bool>(10)?,
+                ))
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let in_scope = |account_id: i32, kind: &str| match &args.account_ids {
+        Some(ids) => ids.contains(&account_id),
+        None => kind != "brokerage" && kind != "asset",
+    };
+    // Net per category, and per payee within it keyed case-insensitively
+    let mut categories: BTreeMap<String, f64> = BTreeMap::new();
+    let mut payees: BTreeMap<String, BTreeMap<String, (String, f64)>> = BTreeMap::new();
+    let mut investments = 0.0;
+    for row in rows {
+        let (
+            account_id,
+            kind,
+            day,
+            category,
+            payee,
+            amount,
+            currency,
+            is_transfer,
+            other_id,
+            other_kind,
+            investment_income,
+        ) = row.map_err(|e| e.to_string())?;
+        let invested = !in_scope(account_id, &kind)
+            && investment_income
+            && args.account_ids.is_none()
+            && kind == "brokerage";
+        if !in_scope(account_id, &kind) && !invested {
+            continue;
+        }
+        let currency = currency.unwrap_or_else(|| base.clone());
+        let date = rate_date(&mode, &day);
+        let rate = rate_on(&conn, &currency, &base, date)
+            .ok_or_else(|| missing_rate(&currency, &base, date))?;
+        let amount = amount * rate;
+        if invested {
+            investments += amount;
+        }
+
+        if is_transfer.unwrap_or(false) {
+            let internal = other_id.is_some_and(|id| in_scope(id, &other_kind));
+            if !internal && other_kind == "brokerage" {
+                investments -= amount;
+            }
+            continue;
+        }
+        // A category of nothing but separators has no level to draw
+        let category = if category
+            .split(CATEGORY_SEPARATOR)
+            .all(|part| part.trim().is_empty())
+        {
+            "Uncategorized".to_string()
+        } else {
+            category.trim().to_string()
+        };
+        *categories.entry(category.clone()).or_default() += amount;
+        let payee = if payee.trim().is_empty() {
+            "Unknown".to_string()
+        } else {
+            payee.trim().to_string()
+        };
+        payees
+            .entry(category)
+            .or_default()
+            .entry(payee.to_lowercase())
+            .or_insert_with(|| (payee, 0.0))
+            .1 += amount;
+    }
+
+    let mut graph = Graph::default();
+    let depth = depth as usize;
+    let (income_categories, expense_categories): (Vec<_>, Vec<_>) = categories
+        .into_iter()
+        .filter(|(_, net)| net.abs() >= 0.005)
+        .partition(|(_, net)| *net > 0.0);
+    let income: f64 = income_categories.iter().map(|(_, net)| net).sum();
+    let expense: f64 = -expense_categories.iter().map(|(_, net)| net).sum::<f64>();
+
+    // Payees feed the income categories they paid into, scaled down when the category also
+    // had money going out
+    let mut sources = Vec::new();
+    for (category, net) in sorted_by_value(income_categories) {
+        let path = graph.category_path("income", &category, depth);
+        let paid: Vec<(String, f64)> = payees
+            .remove(&category)
+            .unwrap_or_default()
+            .into_values()
+            .filter(|(_, amount)| *amount > 0.0)
+            .collect();
+        let gross: f64 = paid.iter().map(|(_, amount)| amount).sum();
+        for (payee, amount) in paid {
+            sources.push((payee, path[path.len() - 1].clone(), amount / gross * net));
+        }
+        for pair in path.windows(2).rev() {
+            graph.link(&pair[1], &pair[0], net);
+        }
+        graph.node(POOL, "Budget", "pool");
+        graph.link(&path[0], POOL, net);
+    }
+    for (payee, target, amount) in sources {
+        let id = format!("source{}{}", CATEGORY_SEPARATOR, payee.to_lowercase());
+        graph.node(&id, &payee, "source");
+        graph.link(&id, &target, amount);
+    }
+
+    let savings = income - expense - investments;
+    if investments < 0.0 {
+        graph.node("investments:in", "From investments", "investments");
+        graph.node(POOL, "Budget", "pool");
+        graph.link("investments:in", POOL, -investments);
+    }
+    if savings < 0.0 {
+        graph.node("balances", "From balances", "balances");
+        graph.node(POOL, "Budget", "pool");
+        graph.link("balances", POOL, -savings);
+    }
+    for (category, net) in sorted_by_value(
+        expense_categories
+            .into_iter()
+            .map(|(category, net)| (category, -net))
+            .collect(),
+    ) {
+        graph.node(POOL, "Budget", "pool");
+        let path = graph.category_path("expense", &category, depth);
+        graph.link(POOL, &path[0], net);
+        for pair in path.windows(2) {
+            graph.link(&pair[0], &pair[1], net);
+        }
+    }
+    if investments > 0.0 {
+        graph.node(POOL, "Budget", "pool");
+        graph.node("investments", "Investments", "investments");
+        graph.link(POOL, "investments", investments);
+    }
+    if savings > 0.0 {
+        graph.node(POOL, "Budget", "pool");
+        graph.node("savings", "Savings", "savings");
+        graph.link(POOL, "savings", savings);
+    }
+
+    let (nodes, links) = graph.finish();
+    Ok(CashFlowGraph {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        base_currency: base,
+        depth: depth as u32,
+        nodes,
+        links,
+        income: round_cents(income),
+        expense: round_cents(expense),
+        investments: round_cents(investments),
+        savings: round_cents(savings),
+    })
+}
+
+#[tauri::command]
+pub fn cash_flow_graph(
+    app_handle: AppHandle,
+    mut args: ReportArgs,
+    depth: Option<u32>,
+) -> Result<CashFlowGraph, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    args.rate_mode = requested_rate_mode(&app_handle, args.rate_mode)?;
+    cash_flow_graph_db(&db_path, args, depth)
+}
@@ -0,0 +1,270 @@
+use crate::models::{DividendSuggestion, StockDividend, StockSplit};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Split ratios (new shares per old share) by uppercase ticker, as `(date, ratio)`.
+pub(crate) type SplitRatios = HashMap<String, Vec<(String, f64)>>;
+
+pub(crate) fn load_split_ratios(conn: &Connection) -> Result<SplitRatios, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT UPPER(ticker), date, numerator / denominator FROM stock_splits ORDER BY date",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f64>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut ratios = SplitRatios::new();
+    for row in rows {
+        let (ticker, date, ratio) = row.map_err(|e| e.to_string())?;
+        ratios.entry(ticker).or_default().push((date, ratio));
+    }
+    Ok(ratios)
+}
+
+/// Factor turning shares held on `date` into shares held after the splits up to `until`
+/// (all known splits when `None`). A split dated `date` is already reflected in a trade that day.
+pub(crate) fn split_factor(
+    ratios: &SplitRatios,
+    ticker: &str,
+    date: &str,
+    until: Option<&str>,
+) -> f64 {
+    ratios
+        .get(&ticker.to_uppercase())
+        .map(|splits| {
+            splits
+                .iter()
+                .filter(|(d, _)| d.as_str() > date && until.is_none_or(|u| d.as_str() <= u))
+                .map(|(_, r)| r)
+                .product()
+        })
+        .unwrap_or(1.0)
+}
+
+/// Days either side of a split's date within which another split of the same ticker is
+/// taken to be the same event, as hand-entered and provider dates often differ.
+const SPLIT_MATCH_DAYS: i64 = 5;
+
+/// Date of a stored split of `ticker` close enough to `date` to be the same event.
+pub(crate) fn matching_split(
+    conn: &Connection,
+    ticker: &str,
+    date: &str,
+) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT date FROM stock_splits
+         WHERE ticker = ?1 COLLATE NOCASE AND ABS(julianday(date) - julianday(?2)) <= ?3
+         ORDER BY ABS(julianday(date) - julianday(?2)) LIMIT 1",
+        params![ticker, date, SPLIT_MATCH_DAYS],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Moves the stored closes and dividends of `ticker` before a split onto the post-split
+/// basis, which every valuation assumes they are in.
+pub(crate) fn rescale_before_split(
+    conn: &Connection,
+    ticker: &str,
+    date: &str,
+    numerator: f64,
+    denominator: f64,
+) -> Result<(), String> {
+    let ratio = numerator / denominator;
+    if !(ratio.is_finite() && ratio > 0.0) {
+        return Err("Split ratio must be positive".to_string());
+    }
+    conn.execute(
+        "UPDATE daily_stock_prices
+         SET price = price / ?3, open = open / ?3, high = high / ?3, low = low / ?3,
+             adj_close = adj_close / ?3, volume = volume * ?3
+         WHERE ticker = ?1 COLLATE NOCASE AND date < ?2",
+        params![ticker, date, ratio],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE stock_dividends SET amount = amount / ?3
+         WHERE ticker = ?1 COLLATE NOCASE AND date < ?2",
+        params![ticker, date, ratio],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_stock_dividends_db(
+    db_path: &PathBuf,
+    ticker: Option<String>,
+) -> Result<Vec<StockDividend>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT ticker, date, amount FROM stock_dividends
+             WHERE ?1 IS NULL OR ticker = ?1 COLLATE NOCASE
+             ORDER BY ticker, date",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![ticker], |row| {
+            Ok(StockDividend {
+                ticker: row.get(0)?,
+                date: row.get(1)?,
+                amount: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn get_stock_splits_db(
+    db_path: &PathBuf,
+    ticker: Option<String>,
+) -> Result<Vec<StockSplit>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT ticker, date, numerator, denominator FROM stock_splits
+             WHERE ?1 IS NULL OR ticker = ?1 COLLATE NOCASE
+             ORDER BY ticker, date",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![ticker], |row| {
+            Ok(StockSplit {
+                ticker: row.get(0)?,
+                date: row.get(1)?,
+                numerator: row.get(2)?,
+                denominator: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Dividends paid on shares the account held on the ex-date, for which no cash income
+/// was recorded between a few days before and 45 days after it.
+///
+/// Yahoo reports dividends per share in today's split basis, so the holding is counted in
+/// that basis too.
+pub fn get_dividend_suggestions_db(
+    db_path: &PathBuf,
+    account_id: Option<i32>,
+) -> Result<Vec<DividendSuggestion>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let ratios = load_split_ratios(&conn)?;
+
+    let mut positions: HashMap<(i32, String), Vec<(String, f64)>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT account_id, UPPER(ticker), date, shares FROM transactions
+                 WHERE ticker IS NOT NULL AND shares IS NOT NULL AND shares != 0
+                   AND (?1 IS NULL OR account_id = ?1)",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![account_id], |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, f64>(3)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (account, ticker, date, shares) = row.map_err(|e| e.to_string())?;
+            positions
+                .entry((account, ticker))
+                .or_default()
+                .push((date, shares));
+        }
+    }
+
+    let dividends = get_stock_dividends_db(db_path, None)?;
+    let mut received = conn
+        .prepare(
+            "SELECT COUNT(*) FROM transactions
+             WHERE account_id = ?1 AND UPPER(ticker) = ?2 AND amount > 0
+               AND COALESCE(shares, 0) = 0
+               AND julianday(date) - julianday(?3) BETWEEN -3 AND 45",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut suggestions = Vec::new();
+    for ((account, ticker), trades) in &positions {
+        for dividend in dividends
+            .iter()
+            .filter(|d| d.ticker.eq_ignore_ascii_case(ticker))
+        {
+            let shares: f64 = trades
+                .iter()
+                .filter(|(date, _)| *date < dividend.date)
+                .map(|(date, shares)| shares * split_factor(&ratios, ticker, date, None))
+                .sum();
+            if shares <= 1e-9 {
+                continue;
+            }
+            let matches: i64 = received
+                .query_row(params![account, ticker, dividend.date], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            if matches > 0 {
+                continue;
+            }
+            suggestions.push(DividendSuggestion {
+                account_id: *account,
+                ticker: ticker.clone(),
+                date: dividend.date.clone(),
+                amount_per_share: dividend.amount,
+                shares,
+                amount: crate::transfers::round_cents(shares * dividend.amount),
+            });
+        }
+    }
+    suggestions.sort_by(|a, b| {
+        a.date
+            .cmp(&b.date)
+            .then(a.account_id.cmp(&b.account_id))
+            .then(a.ticker.cmp(&b.ticker))
+    });
+    Ok(suggestions)
+}
+
+#[tauri::command]
+pub fn get_stock_dividends(
+    app_handle: AppHandle,
+    ticker: Option<String>,
+) -> Result<Vec<StockDividend>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_stock_dividends_db(&db_path, ticker)
+}
+
+#[tauri::command]
+pub fn get_stock_splits(
+    app_handle: AppHandle,
+    ticker: Option<String>,
+) -> Result<Vec<StockSplit>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_stock_splits_db(&db_path, ticker)
+}
+
+#[tauri::command]
+pub fn get_dividend_suggestions(
+    app_handle: AppHandle,
+    account_id: Option<i32>,
+) -> Result<Vec<DividendSuggestion>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_dividend_suggestions_db(&db_path, account_id)
+}
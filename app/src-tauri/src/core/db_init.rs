@@ -180,27 +180,233 @@ pub fn init_db(app_handle: &AppHandle) -> Result<(), String> {
         }
     }
 
+    // Ensure we have a cleared state on transactions (uncleared, cleared, reconciled)
+    {
+        let mut stmt = conn
+            .prepare("PRAGMA table_info(transactions)")
+            .map_err(|e| e.to_string())?;
+        let mut has_cleared = false;
+        let col_iter = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| e.to_string())?;
+        for name in col_iter.flatten() {
+            if name == "cleared" {
+                has_cleared = true;
+                break;
+            }
+        }
+        if !has_cleared {
+            match conn.execute(
+                "ALTER TABLE transactions ADD COLUMN cleared TEXT NOT NULL DEFAULT 'uncleared'",
+                [],
+            ) {
+                Ok(_) => {}
+                Err(e) => {
+                    let s = e.to_string();
+                    if !s.contains("duplicate column name") && !s.contains("already exists") {
+                        return Err(s);
+                    }
+                }
+            }
+        }
+    }
+
+    // Ensure transfer legs can carry their persistent transfer id
+    {
+        let mut stmt = conn
+            .prepare("PRAGMA table_info(transactions)")
+            .map_err(|e| e.to_string())?;
+        let mut has_transfer_id = false;
+        let col_iter = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| e.to_string())?;
+        for name in col_iter.flatten() {
+            if name == "transfer_id" {
+                has_transfer_id = true;
+                break;
+            }
+        }
+        if !has_transfer_id {
+            match conn.execute(
+                "ALTER TABLE transactions ADD COLUMN transfer_id INTEGER",
+                [],
+            ) {
+                Ok(_) => {}
+                Err(e) => {
+                    let s = e.to_string();
+                    if !s.contains("duplicate column name") && !s.contains("already exists") {
+                        return Err(s);
+                    }
+                }
+            }
+        }
+    }
+
+    // Ensure investment rows can record their action, a spin-off's share of cost basis and
+    // the row recorded together with them
+    {
+        let mut stmt = conn
+            .prepare("PRAGMA table_info(transactions)")
+            .map_err(|e| e.to_string())?;
+        let columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| e.to_string())?
+            .flatten()
+            .collect();
+        for (name, definition) in [
+            ("action", "TEXT"),
+            ("basis_fraction", "REAL"),
+            ("related_tx_id", "INTEGER"),
+        ] {
+            if columns.iter().any(|c| c == name) {
+                continue;
+            }
+            match conn.execute(
+                &format!(
+                    "ALTER TABLE transactions ADD COLUMN {} {}",
+                    name, definition
+                ),
+                [],
+            ) {
+                // Existing trades were all buys and sells
+                Ok(_) if name == "action" => {
+                    conn.execute(
+                        "UPDATE transactions SET action = CASE WHEN shares > 0 THEN 'buy' ELSE 'sell' END
+                         WHERE ticker IS NOT NULL AND shares IS NOT NULL AND shares != 0",
+                        [],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+                // Investment actions recorded in pairs were linked like transfers
+                Ok(_) if name == "related_tx_id" => {
+                    conn.execute(
+                        "UPDATE transactions SET related_tx_id = linked_tx_id, linked_tx_id = NULL
+                         WHERE action NOT IN ('buy', 'sell') AND linked_tx_id IS NOT NULL
+                           AND transfer_id IS NULL",
+                        [],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    let s = e.to_string();
+                    if !s.contains("duplicate column name") && !s.contains("already exists") {
+                        return Err(s);
+                    }
+                }
+            }
+        }
+    }
+
+    // Ensure we have an archived flag in accounts (closed accounts keep their history)
+    {
+        let mut stmt = conn
+            .prepare("PRAGMA table_info(accounts)")
+            .map_err(|e| e.to_string())?;
+        let mut has_archived = false;
+        let col_iter = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| e.to_string())?;
+        for name in col_iter.flatten() {
+            if name == "archived" {
+                has_archived = true;
+                break;
+            }
+        }
+        if !has_archived {
+            match conn.execute(
+                "ALTER TABLE accounts ADD COLUMN archived INTEGER NOT NULL DEFAULT 0",
+                [],
+            ) {
+                Ok(_) => {}
+                Err(e) => {
+                    let s = e.to_string();
+                    if !s.contains("duplicate column name") && !s.contains("already exists") {
+                        return Err(s);
+                    }
+                }
+            }
+        }
+    }
+
     conn.execute(
         "CREATE TABLE IF NOT EXISTS stock_prices (
             ticker TEXT PRIMARY KEY,
             price REAL NOT NULL,
+            change_percent REAL NOT NULL DEFAULT 0,
+            currency TEXT,
+            quote_type TEXT,
             last_updated TEXT NOT NULL
         )",
         [],
     )
     .map_err(|e| e.to_string())?;
 
+    // Migration: cached quotes keep enough to be served without a request
+    let _ = conn.execute(
+        "ALTER TABLE stock_prices ADD COLUMN change_percent REAL NOT NULL DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE stock_prices ADD COLUMN currency TEXT", []);
+    let _ = conn.execute("ALTER TABLE stock_prices ADD COLUMN quote_type TEXT", []);
+
     conn.execute(
         "CREATE TABLE IF NOT EXISTS daily_stock_prices (
             ticker TEXT NOT NULL,
             date TEXT NOT NULL,
             price REAL NOT NULL,
+            open REAL,
+            high REAL,
+            low REAL,
+            adj_close REAL,
+            volume REAL,
+            PRIMARY KEY (ticker, date)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Migration: daily bars gained OHLCV columns; older rows only have the close
+    for column in ["open", "high", "low", "adj_close", "volume"] {
+        let _ = conn.execute(
+            &format!("ALTER TABLE daily_stock_prices ADD COLUMN {} REAL", column),
+            [],
+        );
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stock_dividends (
+            ticker TEXT NOT NULL,
+            date TEXT NOT NULL,
+            amount REAL NOT NULL,
+            PRIMARY KEY (ticker, date)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stock_splits (
+            ticker TEXT NOT NULL,
+            date TEXT NOT NULL,
+            numerator REAL NOT NULL,
+            denominator REAL NOT NULL,
             PRIMARY KEY (ticker, date)
         )",
         [],
     )
     .map_err(|e| e.to_string())?;
 
+    // Tickers whose dividends and splits were fetched over the full history range
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stock_event_backfills (
+            ticker TEXT PRIMARY KEY,
+            date TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
     conn.execute(
         "CREATE TABLE IF NOT EXISTS rules (
             id INTEGER PRIMARY KEY,
@@ -240,6 +446,177 @@ pub fn init_db(app_handle: &AppHandle) -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS account_valuations (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            value REAL NOT NULL,
+            UNIQUE (account_id, date),
+            FOREIGN KEY(account_id) REFERENCES accounts(id)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reconciliations (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            statement_date TEXT NOT NULL,
+            statement_balance REAL NOT NULL,
+            transaction_count INTEGER NOT NULL,
+            reconciled_at TEXT NOT NULL,
+            FOREIGN KEY(account_id) REFERENCES accounts(id)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS market_data_providers (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            kind TEXT NOT NULL,
+            base_url TEXT NOT NULL,
+            format TEXT NOT NULL DEFAULT 'json',
+            quote_path TEXT,
+            history_path TEXT,
+            search_path TEXT,
+            fx_ticker TEXT,
+            priority INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ticker_providers (
+            ticker TEXT PRIMARY KEY,
+            providers TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS fx_sources (
+            name TEXT PRIMARY KEY,
+            fetched_at TEXT NOT NULL,
+            latest_date TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS daily_fx_rates (
+            currency TEXT NOT NULL,
+            date TEXT NOT NULL,
+            rate REAL NOT NULL,
+            PRIMARY KEY (currency, date)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS custom_exchange_rate_ranges (
+            id INTEGER PRIMARY KEY,
+            currency TEXT NOT NULL,
+            rate REAL NOT NULL,
+            valid_from TEXT,
+            valid_to TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transfers (
+            id INTEGER PRIMARY KEY,
+            from_tx_id INTEGER NOT NULL,
+            to_tx_id INTEGER NOT NULL,
+            fee REAL NOT NULL DEFAULT 0,
+            exchange_rate REAL NOT NULL DEFAULT 1,
+            rate_estimated INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Migration: transfers created before cross-currency support were all at par
+    let _ = conn.execute(
+        "ALTER TABLE transfers ADD COLUMN exchange_rate REAL NOT NULL DEFAULT 1",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE transfers ADD COLUMN rate_estimated INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Cost basis method per account, one of `LOT_METHODS`
+    let _ = conn.execute(
+        "ALTER TABLE accounts ADD COLUMN lot_method TEXT NOT NULL DEFAULT 'fifo'",
+        [],
+    );
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS lot_selections (
+            sell_tx_id INTEGER NOT NULL,
+            lot_tx_id INTEGER NOT NULL,
+            shares REAL NOT NULL,
+            PRIMARY KEY (sell_tx_id, lot_tx_id)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Portfolio an account is rebalanced with, by name
+    let _ = conn.execute("ALTER TABLE accounts ADD COLUMN portfolio TEXT", []);
+
+    // Asset class, region and sector chosen for a ticker, over the quote type default
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ticker_classifications (
+            ticker TEXT PRIMARY KEY,
+            asset_class TEXT,
+            region TEXT,
+            sector TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS allocation_targets (
+            portfolio TEXT NOT NULL,
+            asset_class TEXT NOT NULL,
+            target_percent REAL NOT NULL,
+            PRIMARY KEY (portfolio, asset_class)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Balance below which the forecast flags an account
+    let _ = conn.execute("ALTER TABLE accounts ADD COLUMN balance_threshold REAL", []);
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduled_transactions (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            payee TEXT NOT NULL,
+            category TEXT,
+            amount REAL NOT NULL,
+            frequency TEXT NOT NULL,
+            next_date TEXT NOT NULL,
+            end_date TEXT,
+            notes TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
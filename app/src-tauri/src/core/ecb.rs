@@ -0,0 +1,256 @@
+use crate::models::EcbImport;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+const ECB_DEFAULT_BASE_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref";
+const ECB_DAILY_FILE: &str = "eurofxref-daily.xml";
+const ECB_HISTORY_FILE: &str = "eurofxref-hist.xml";
+
+// The ECB publishes once per working day, so a fetched file stays good for a while
+const ECB_REFRESH_HOURS: i64 = 12;
+
+/// Reference rates published for one day, in units of each currency per EUR.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EcbDay {
+    pub date: String,
+    pub rates: Vec<(String, f64)>,
+}
+
+/// Uses `ECB_BASE_URL` when set, so the download can be pointed at a stand-in.
+pub fn ecb_base_url() -> String {
+    std::env::var("ECB_BASE_URL").unwrap_or_else(|_| ECB_DEFAULT_BASE_URL.to_string())
+}
+
+// Value of `name="..."` (or single quotes) inside a tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    for quote in ['"', '\''] {
+        let needle = format!("{}={}", name, quote);
+        if let Some(start) = tag.find(&needle) {
+            let rest = &tag[start + needle.len()..];
+            return rest.find(quote).map(|end| &rest[..end]);
+        }
+    }
+    None
+}
+
+fn parse_ecb_xml(body: &str) -> Result<Vec<EcbDay>, String> {
+    let mut days: Vec<EcbDay> = Vec::new();
+    for tag in body.split('<').skip(1) {
+        let Some(tag) = tag.strip_prefix("Cube") else {
+            continue;
+        };
+        let tag = tag.split('>').next().unwrap_or_default();
+        if let Some(date) = attribute(tag, "time") {
+            days.push(EcbDay {
+                date: date.to_string(),
+                rates: Vec::new(),
+            });
+        } else if let (Some(currency), Some(rate)) =
+            (attribute(tag, "currency"), attribute(tag, "rate"))
+        {
+            let day = days
+                .last_mut()
+                .ok_or("Rate listed outside of a dated block")?;
+            let rate: f64 = rate
+                .parse()
+                .map_err(|_| format!("Invalid rate for {}: {}", currency, rate))?;
+            day.rates.push((currency.to_string(), rate));
+        }
+    }
+    Ok(days)
+}
+
+fn parse_ecb_date(value: &str) -> Option<String> {
+    ["%Y-%m-%d", "%d %B %Y"]
+        .iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(value, fmt).ok())
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+fn parse_ecb_csv(body: &str) -> Result<Vec<EcbDay>, String> {
+    let mut lines = body.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<String> = lines
+        .next()
+        .ok_or("Empty ECB file")?
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .collect();
+
+    let mut days = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let date = fields
+            .first()
+            .and_then(|d| parse_ecb_date(d))
+            .ok_or_else(|| format!("Invalid date in ECB file: {}", line))?;
+        // Currencies without a quote that day are published as N/A
+        let rates = header
+            .iter()
+            .zip(fields.iter())
+            .skip(1)
+            .filter(|(currency, _)| !currency.is_empty())
+            .filter_map(|(currency, value)| Some((currency.clone(), value.parse::<f64>().ok()?)))
+            .collect();
+        days.push(EcbDay { date, rates });
+    }
+    Ok(days)
+}
+
+/// Parses the ECB daily or historical reference rates, in either the XML or the CSV layout.
+pub fn parse_ecb_rates(body: &str) -> Result<Vec<EcbDay>, String> {
+    if body.trim_start().starts_with('<') {
+        parse_ecb_xml(body)
+    } else {
+        parse_ecb_csv(body)
+    }
+}
+
+/// Stores the days in `daily_fx_rates` as USD per unit, including EUR itself.
+/// Days without a USD quote cannot be pivoted and are skipped.
+pub fn store_ecb_rates_db(db_path: &PathBuf, days: &[EcbDay]) -> Result<EcbImport, String> {
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut stored_days = 0;
+    let mut currencies = HashSet::new();
+    let mut latest_date: Option<String> = None;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO daily_fx_rates (currency, date, rate) VALUES (?1, ?2, ?3)",
+            )
+            .map_err(|e| e.to_string())?;
+        for day in days {
+            let Some(usd_per_eur) = day
+                .rates
+                .iter()
+                .find(|(c, r)| c == "USD" && *r > 0.0)
+                .map(|(_, r)| *r)
+            else {
+                continue;
+            };
+
+            stmt.execute(params!["EUR", day.date, usd_per_eur])
+                .map_err(|e| e.to_string())?;
+            currencies.insert("EUR".to_string());
+            for (currency, per_eur) in &day.rates {
+                if currency == "USD" || *per_eur <= 0.0 {
+                    continue;
+                }
+                stmt.execute(params![currency, day.date, usd_per_eur / per_eur])
+                    .map_err(|e| e.to_string())?;
+                currencies.insert(currency.clone());
+            }
+
+            stored_days += 1;
+            if latest_date.as_deref().is_none_or(|d| day.date.as_str() > d) {
+                latest_date = Some(day.date.clone());
+            }
+        }
+    }
+
+    tx.execute(
+        "INSERT OR REPLACE INTO fx_sources (name, fetched_at, latest_date) VALUES ('ecb', datetime('now'), ?1)",
+        params![latest_date],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let mut currencies: Vec<String> = currencies.into_iter().collect();
+    currencies.sort();
+    Ok(EcbImport {
+        days: stored_days,
+        currencies,
+        latest_date,
+    })
+}
+
+/// Imports a reference-rate file downloaded from the ECB website.
+pub fn import_ecb_rates_file_db(db_path: &PathBuf, file_path: String) -> Result<EcbImport, String> {
+    let body = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+    store_ecb_rates_db(db_path, &parse_ecb_rates(&body)?)
+}
+
+pub async fn fetch_ecb_rates_with_client(
+    db_path: &PathBuf,
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<EcbImport, String> {
+    let res =
+        crate::http::send_with_retry(client.get(url).header("User-Agent", "Mozilla/5.0")).await?;
+    if !res.status().is_success() {
+        return Err(format!("Failed to fetch ECB rates: {}", res.status()));
+    }
+    let body = res.text().await.map_err(|e| e.to_string())?;
+    store_ecb_rates_db(db_path, &parse_ecb_rates(&body)?)
+}
+
+/// Whether the cached ECB rates are older than the refresh interval (or missing).
+pub fn ecb_rates_stale_db(db_path: &PathBuf) -> Result<bool, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let fresh: Option<i64> = conn
+        .query_row(
+            "SELECT 1 FROM fx_sources WHERE name = 'ecb' AND fetched_at > datetime('now', ?1)",
+            params![format!("-{} hours", ECB_REFRESH_HOURS)],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    Ok(fresh.is_none())
+}
+
+/// USD per unit for every currency in the most recent ECB publication that was stored.
+pub fn latest_ecb_rates_db(db_path: &PathBuf) -> Result<HashMap<String, f64>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.currency, r.rate FROM daily_fx_rates r
+             JOIN fx_sources s ON s.name = 'ecb' AND r.date = s.latest_date",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Downloads the daily file when the cache is stale. Failures leave the cache as it is,
+/// so conversions keep working offline with the last published rates.
+pub(crate) async fn refresh_ecb_rates(db_path: &PathBuf) {
+    if !ecb_rates_stale_db(db_path).unwrap_or(true) {
+        return;
+    }
+    let url = format!("{}/{}", ecb_base_url(), ECB_DAILY_FILE);
+    let refresh =
+        async { fetch_ecb_rates_with_client(db_path, &crate::http::client()?, &url).await };
+    if let Err(e) = refresh.await {
+        println!("Failed to refresh ECB rates: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn update_ecb_rates(
+    app_handle: AppHandle,
+    history: Option<bool>,
+) -> Result<EcbImport, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    let file = if history.unwrap_or(false) {
+        ECB_HISTORY_FILE
+    } else {
+        ECB_DAILY_FILE
+    };
+    let url = format!("{}/{}", ecb_base_url(), file);
+    fetch_ecb_rates_with_client(&db_path, &crate::http::client()?, &url).await
+}
+
+#[tauri::command]
+pub fn import_ecb_rates(app_handle: AppHandle, path: String) -> Result<EcbImport, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    import_ecb_rates_file_db(&db_path, path)
+}
@@ -0,0 +1,277 @@
+use crate::fx::{missing_rate, rate_on};
+use crate::models::{AccountForecast, BalanceForecast, ForecastPoint};
+use crate::performance::parse_date;
+use crate::scheduled::{get_scheduled_transactions_db, occurrences};
+use crate::transfers::round_cents;
+use chrono::{Days, Months, NaiveDate};
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+// One-sided z-score of the 90% band around the projected balance
+const BAND_Z: f64 = 1.645;
+
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForecastArgs {
+    /// Number of days projected after `as_of`
+    pub days: u32,
+    /// Months of history averaged for unscheduled spending, 3 when missing
+    pub lookback_months: Option<u32>,
+    /// Accounts to forecast, all open non-asset accounts when missing
+    pub account_ids: Option<Vec<i32>>,
+    /// Day the forecast starts from, today when missing
+    pub as_of: Option<String>,
+    /// Currency of accounts without one of their own, USD when missing
+    pub base_currency: Option<String>,
+}
+
+struct ForecastAccount {
+    id: i32,
+    name: String,
+    currency: String,
+    threshold: Option<f64>,
+}
+
+/// Sets the balance below which the forecast flags an account; `None` clears it.
+pub fn set_balance_threshold_db(
+    db_path: &PathBuf,
+    account_id: i32,
+    threshold: Option<f64>,
+) -> Result<(), String> {
+    if threshold.is_some_and(|t| !t.is_finite()) {
+        return Err("Threshold must be a number".to_string());
+    }
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE accounts SET balance_threshold = ?1 WHERE id = ?2",
+            params![threshold, account_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Account {} not found", account_id));
+    }
+    Ok(())
+}
+
+fn load_accounts(
+    conn: &Connection,
+    account_ids: &Option<Vec<i32>>,
+    base: &str,
+) -> Result<Vec<ForecastAccount>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, COALESCE(kind, 'cash'), currency, COALESCE(archived, 0),
+                    balance_threshold
+             FROM accounts ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, bool>(4)?,
+                row.get::<_, Option<f64>>(5)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut accounts = Vec::new();
+    for row in rows {
+        let (id, name, kind, currency, archived, threshold) = row.map_err(|e| e.to_string())?;
+        // Asset accounts move with valuations, not cash, so they are only forecast on request
+        let wanted = match account_ids {
+            Some(ids) => ids.contains(&id),
+            None => !archived && kind != "asset",
+        };
+        if wanted {
+            accounts.push(ForecastAccount {
+                id,
+                name,
+                currency: currency.unwrap_or_else(|| base.to_string()),
+                threshold,
+            });
+        }
+    }
+    Ok(accounts)
+}
+
+/// Projects each account's balance for `days` days after `as_of`. The projection starts
+/// from the balance on `as_of`, adds scheduled transactions on their dates and takes off
+/// the average daily spending of the lookback months in every category and with every
+/// payee that no scheduled transaction of the account covers. Transfers and investment
+/// transactions don't count as spending. The band widens with the day-to-day spread of
+/// that spending; days ending below the account's threshold are flagged. Amounts in another
+/// currency than the account's convert at the rate of their date and fail without one.
+pub fn forecast_balances_db(
+    db_path: &PathBuf,
+    args: ForecastArgs,
+) -> Result<BalanceForecast, String> {
+    if args.days == 0 || args.days > 3660 {
+        return Err("Days must be between 1 and 3660".to_string());
+    }
+    let lookback_months = args.lookback_months.unwrap_or(3);
+    if lookback_months == 0 || lookback_months > 120 {
+        return Err("Lookback must be between 1 and 120 months".to_string());
+    }
+    let as_of = match &args.as_of {
+        Some(day) => parse_date(day)?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let until = as_of + Days::new(u64::from(args.days));
+    let history_start = as_of - Months::new(lookback_months);
+    let history_days = (as_of - history_start).num_days() as usize;
+
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let base = args
+        .base_currency
+        .as_ref()
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "USD".to_string());
+    let accounts = load_accounts(&conn, &args.account_ids, &base)?;
+    let scheduled = get_scheduled_transactions_db(db_path, None)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT date, amount, currency, COALESCE(category, ''), COALESCE(payee, ''),
+                    (ticker IS NULL OR ticker = '')
+                    AND NOT (category = 'Transfer' OR transfer_id IS NOT NULL)
+             FROM transactions WHERE account_id = ?1 AND substr(date, 1, 10) <= ?2
+             ORDER BY date, id",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut forecasts = Vec::new();
+    for account in accounts {
+        let items: Vec<_> = scheduled
+            .iter()
+            .filter(|s| s.account_id == account.id)
+            .collect();
+        let covered_categories: HashSet<String> = items
+            .iter()
+            .filter_map(|s| s.category.as_ref().map(|c| c.to_lowercase()))
+            .collect();
+        let covered_payees: HashSet<String> =
+            items.iter().map(|s| s.payee.to_lowercase()).collect();
+
+        let rows = stmt
+            .query_map(
+                params![account.id, as_of.format("%Y-%m-%d").to_string()],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, f64>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                        row.get::<_, Option<bool>>(5)?,
+                    ))
+                },
+            )
+            .map_err(|e| e.to_string())?;
+
+        let mut start_balance = 0.0;
+        let mut daily_totals = vec![0.0; history_days];
+        for row in rows {
+            let (day, amount, currency, category, payee, is_spending) =
+                row.map_err(|e| e.to_string())?;
+            // Amounts in another currency count at the rate of their own date
+            let currency = currency.unwrap_or_else(|| account.currency.clone());
+            let rate = rate_on(&conn, &currency, &account.currency, Some(&day))
+                .ok_or_else(|| missing_rate(&currency, &account.currency, Some(&day)))?;
+            let amount = amount * rate;
+            start_balance += amount;
+
+            let date = parse_date(&day)?;
+            if date <= history_start || amount >= 0.0 || !is_spending.unwrap_or(false) {
+                continue;
+            }
+            if covered_categories.contains(&category.trim().to_lowercase())
+                || covered_payees.contains(&payee.trim().to_lowercase())
+            {
+                continue;
+            }
+            let index = (date - history_start).num_days() as usize - 1;
+            daily_totals[index] -= amount;
+        }
+
+        let daily_spending = daily_totals.iter().sum::<f64>() / history_days as f64;
+        let variance = daily_totals
+            .iter()
+            .map(|d| (d - daily_spending).powi(2))
+            .sum::<f64>()
+            / history_days as f64;
+        let spread = variance.sqrt();
+
+        let mut changes: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+        for item in &items {
+            for date in occurrences(item, as_of, until)? {
+                *changes.entry(date).or_default() += item.amount;
+            }
+        }
+
+        let mut points = Vec::with_capacity(args.days as usize);
+        let mut scheduled_total = 0.0;
+        let mut first_below_threshold = None;
+        for t in 1..=args.days {
+            let date = as_of + Days::new(u64::from(t));
+            scheduled_total += changes.get(&date).copied().unwrap_or(0.0);
+            let balance = start_balance + scheduled_total - daily_spending * f64::from(t);
+            let band = BAND_Z * spread * f64::from(t).sqrt();
+            let below_threshold = account.threshold.is_some_and(|limit| balance < limit);
+            let date = date.format("%Y-%m-%d").to_string();
+            if below_threshold && first_below_threshold.is_none() {
+                first_below_threshold = Some(date.clone());
+            }
+            points.push(ForecastPoint {
+                date,
+                balance: round_cents(balance),
+                low: round_cents(balance - band),
+                high: round_cents(balance + band),
+                below_threshold,
+            });
+        }
+
+        forecasts.push(AccountForecast {
+            account_id: account.id,
+            name: account.name,
+            currency: account.currency,
+            start_balance: round_cents(start_balance),
+            threshold: account.threshold,
+            daily_spending: round_cents(daily_spending),
+            first_below_threshold,
+            points,
+        });
+    }
+
+    Ok(BalanceForecast {
+        as_of: as_of.format("%Y-%m-%d").to_string(),
+        days: args.days,
+        lookback_months,
+        accounts: forecasts,
+    })
+}
+
+#[tauri::command]
+pub fn set_balance_threshold(
+    app_handle: AppHandle,
+    account_id: i32,
+    threshold: Option<f64>,
+) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_balance_threshold_db(&db_path, account_id, threshold)
+}
+
+#[tauri::command]
+pub fn forecast_balances(
+    app_handle: AppHandle,
+    args: ForecastArgs,
+) -> Result<BalanceForecast, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    forecast_balances_db(&db_path, args)
+}
@@ -0,0 +1,498 @@
+use crate::models::{CustomExchangeRateRange, PriceUpdateResult};
+use crate::providers::{ProviderRegistry, YahooProvider};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::AppHandle;
+
+/// How reports convert amounts in other currencies: at today's rate or at the rate
+/// in effect on each transaction's date. The choice is kept in the app settings.
+pub const RATE_MODES: [&str; 2] = ["current", "transaction_date"];
+
+pub fn normalize_rate_mode(mode: Option<String>) -> Result<String, String> {
+    let mode = mode.unwrap_or_else(|| "current".to_string());
+    if !RATE_MODES.contains(&mode.as_str()) {
+        return Err(format!("Unknown rate mode: {}", mode));
+    }
+    Ok(mode)
+}
+
+/// Mode a report converts with. Reports look back in time, so without a choice each amount
+/// converts at the rate of its own date.
+pub fn report_rate_mode(mode: Option<String>) -> Result<String, String> {
+    normalize_rate_mode(Some(mode.unwrap_or_else(|| "transaction_date".to_string())))
+}
+
+/// Mode asked for by a report command, falling back to the one saved in the settings.
+pub(crate) fn requested_rate_mode(
+    app_handle: &AppHandle,
+    mode: Option<String>,
+) -> Result<Option<String>, String> {
+    match mode {
+        Some(mode) => Ok(Some(mode)),
+        None => Ok(crate::db_init::read_settings(app_handle)?.rate_mode),
+    }
+}
+
+/// Date whose rate converts an amount dated `date` under `mode`; `None` is today's rate.
+pub(crate) fn rate_date<'a>(mode: &str, date: &'a str) -> Option<&'a str> {
+    (mode == "transaction_date").then_some(date)
+}
+
+pub(crate) fn missing_rate(from: &str, to: &str, date: Option<&str>) -> String {
+    match date {
+        Some(date) => format!("No exchange rate from {} to {} on {}", from, to, date),
+        None => format!("No exchange rate from {} to {}", from, to),
+    }
+}
+
+/// Where current exchange rates come from: per-pair Yahoo quotes, or the single
+/// ECB reference-rate file cached in `daily_fx_rates`.
+pub const FX_SOURCES: [&str; 2] = ["yahoo", "ecb"];
+
+pub fn normalize_fx_source(source: Option<String>) -> Result<String, String> {
+    let source = source.unwrap_or_else(|| "yahoo".to_string());
+    if !FX_SOURCES.contains(&source.as_str()) {
+        return Err(format!("Unknown FX source: {}", source));
+    }
+    Ok(source)
+}
+
+fn custom_range_rate(conn: &Connection, currency: &str, date: &str) -> Option<f64> {
+    conn.query_row(
+        "SELECT rate FROM custom_exchange_rate_ranges
+         WHERE currency = ?1 AND (valid_from IS NULL OR valid_from <= ?2) AND (valid_to IS NULL OR valid_to >= ?2)
+         ORDER BY valid_from DESC LIMIT 1",
+        params![currency, date],
+        |row| row.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+fn custom_rate(conn: &Connection, currency: &str) -> Option<f64> {
+    conn.query_row(
+        "SELECT rate FROM custom_exchange_rates WHERE currency = ?1",
+        params![currency],
+        |row| row.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+// Close of `XXXUSD=X` on or before `date` from the stored history
+fn daily_rate(conn: &Connection, currency: &str, date: &str) -> Option<f64> {
+    conn.query_row(
+        "SELECT rate FROM daily_fx_rates WHERE currency = ?1 AND date <= ?2 ORDER BY date DESC LIMIT 1",
+        params![currency, date],
+        |row| row.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+// Last live quote stored by `get_stock_quotes`
+fn latest_quote(conn: &Connection, currency: &str) -> Option<f64> {
+    conn.query_row(
+        "SELECT price FROM stock_prices WHERE ticker = ?1 COLLATE NOCASE",
+        params![format!("{}USD=X", currency)],
+        |row| row.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+/// Price of one unit of `currency` in USD.
+///
+/// For a past date, custom ranges covering it win, then the daily history up to that date;
+/// there is no rate when neither has one. Today, a later date or no date use the current
+/// custom rate or live quote.
+pub(crate) fn usd_rate(conn: &Connection, currency: &str, date: Option<&str>) -> Option<f64> {
+    if currency == "USD" {
+        return Some(1.0);
+    }
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let rate = match date {
+        Some(date) if date < today.as_str() => {
+            custom_range_rate(conn, currency, date).or_else(|| daily_rate(conn, currency, date))
+        }
+        _ => {
+            let day = date.unwrap_or(&today);
+            custom_range_rate(conn, currency, day)
+                .or_else(|| custom_rate(conn, currency))
+                .or_else(|| latest_quote(conn, currency))
+                .or_else(|| daily_rate(conn, currency, day))
+        }
+    };
+    rate.filter(|r| *r > 0.0)
+}
+
+/// Rate converting `from` into `to`, pivoting through USD.
+pub(crate) fn rate_on(conn: &Connection, from: &str, to: &str, date: Option<&str>) -> Option<f64> {
+    if from == to {
+        return Some(1.0);
+    }
+    Some(usd_rate(conn, from, date)? / usd_rate(conn, to, date)?)
+}
+
+/// Market rate on `date` from the daily `XXXUSD=X` history only, ignoring custom rates.
+pub(crate) fn market_rate_on(conn: &Connection, from: &str, to: &str, date: &str) -> Option<f64> {
+    if from == to {
+        return Some(1.0);
+    }
+    let usd = |c: &str| {
+        if c == "USD" {
+            Some(1.0)
+        } else {
+            daily_rate(conn, c, date).filter(|r| *r > 0.0)
+        }
+    };
+    Some(usd(from)? / usd(to)?)
+}
+
+pub fn get_fx_rate_db(
+    db_path: &PathBuf,
+    from: String,
+    to: String,
+    date: Option<String>,
+) -> Result<f64, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    rate_on(&conn, &from, &to, date.as_deref())
+        .ok_or_else(|| missing_rate(&from, &to, date.as_deref()))
+}
+
+/// Converts an amount at the rate in effect on `date`, or at the current rate without one.
+pub fn convert_amount_db(
+    db_path: &PathBuf,
+    amount: f64,
+    from: String,
+    to: String,
+    date: Option<String>,
+) -> Result<f64, String> {
+    Ok(amount * get_fx_rate_db(db_path, from, to, date)?)
+}
+
+/// Currencies used by accounts or transactions, for backfilling their history.
+pub fn get_used_currencies_db(db_path: &PathBuf) -> Result<Vec<String>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT currency FROM accounts WHERE currency IS NOT NULL
+             UNION SELECT currency FROM transactions WHERE currency IS NOT NULL",
+        )
+        .map_err(|e| e.to_string())?;
+    let currencies = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashSet<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut currencies: Vec<String> = currencies.into_iter().collect();
+    currencies.sort();
+    Ok(currencies)
+}
+
+/// Extends the `daily_fx_rates` history of each currency against USD from the Yahoo chart endpoint.
+pub async fn update_daily_fx_rates_with_client_and_base(
+    db_path: &std::path::Path,
+    client: &reqwest::Client,
+    base_url: &str,
+    currencies: Vec<String>,
+) -> Result<Vec<PriceUpdateResult>, String> {
+    let registry = ProviderRegistry::yahoo(client.clone(), base_url);
+    update_daily_fx_rates_with_registry(db_path, &registry, currencies).await
+}
+
+/// Extends the `daily_fx_rates` history of each currency against USD from the first
+/// provider that has it. USD is the pivot and is left out of the results.
+pub async fn update_daily_fx_rates_with_registry(
+    db_path: &std::path::Path,
+    registry: &ProviderRegistry,
+    currencies: Vec<String>,
+) -> Result<Vec<PriceUpdateResult>, String> {
+    let currencies: Vec<String> = currencies.into_iter().filter(|c| c != "USD").collect();
+    let end_timestamp = chrono::Utc::now().timestamp();
+
+    let mut pending = Vec::new();
+    {
+        let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+        for currency in &currencies {
+            let last_date: Option<String> = conn
+                .query_row(
+                    "SELECT MAX(date) FROM daily_fx_rates WHERE currency = ?1",
+                    params![currency],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?
+                .flatten();
+            let start_timestamp = crate::markets::history_start_timestamp(last_date)?;
+            if start_timestamp < end_timestamp {
+                pending.push((currency.clone(), start_timestamp));
+            }
+        }
+    }
+
+    let registry = Arc::new(registry.clone());
+    let fetched = crate::http::run_bounded(pending.clone(), |(currency, start_timestamp)| {
+        let registry = registry.clone();
+        async move {
+            registry
+                .fx_history(&currency, start_timestamp, end_timestamp)
+                .await
+        }
+    })
+    .await;
+
+    let mut results: Vec<PriceUpdateResult> = currencies
+        .into_iter()
+        .map(|ticker| PriceUpdateResult {
+            ticker,
+            rows: 0,
+            error: None,
+        })
+        .collect();
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO daily_fx_rates (currency, date, rate) VALUES (?1, ?2, ?3)",
+            )
+            .map_err(|e| e.to_string())?;
+        for ((currency, _), outcome) in pending.iter().zip(fetched) {
+            let Some(result) = results.iter_mut().find(|r| &r.ticker == currency) else {
+                continue;
+            };
+            match outcome.and_then(|r| r) {
+                Ok(closes) => {
+                    for (date, rate) in closes.unwrap_or_default() {
+                        stmt.execute(params![currency, date, rate])
+                            .map_err(|e| e.to_string())?;
+                        result.rows += 1;
+                    }
+                }
+                Err(e) => result.error = Some(e),
+            }
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(results)
+}
+
+/// Best-effort refresh used before date-based conversions; stored history is used on failure.
+pub(crate) async fn refresh_daily_fx_rates(db_path: &PathBuf, currencies: Vec<String>) {
+    if currencies.iter().all(|c| c == "USD") {
+        return;
+    }
+    let client = match crate::http::client() {
+        Ok(client) => client,
+        Err(e) => {
+            println!("Failed to refresh exchange rate history: {}", e);
+            return;
+        }
+    };
+    let registry = ProviderRegistry::load(db_path, client.clone())
+        .unwrap_or_else(|_| ProviderRegistry::yahoo(client, &YahooProvider::default_base_url()));
+    match update_daily_fx_rates_with_registry(std::path::Path::new(db_path), &registry, currencies)
+        .await
+    {
+        Ok(results) => {
+            for r in results.iter().filter(|r| r.error.is_some()) {
+                println!(
+                    "Failed to refresh exchange rate history for {}: {}",
+                    r.ticker,
+                    r.error.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        Err(e) => println!("Failed to refresh exchange rate history: {}", e),
+    }
+}
+
+/// Adds a custom rate (USD per unit) valid between two dates. Open ends are unbounded.
+pub fn add_custom_exchange_rate_range_db(
+    db_path: &PathBuf,
+    currency: String,
+    rate: f64,
+    valid_from: Option<String>,
+    valid_to: Option<String>,
+) -> Result<CustomExchangeRateRange, String> {
+    if rate <= 0.0 {
+        return Err("Exchange rate must be positive".to_string());
+    }
+    if let (Some(from), Some(to)) = (&valid_from, &valid_to) {
+        if from > to {
+            return Err("Rate range ends before it starts".to_string());
+        }
+    }
+
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let overlapping: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM custom_exchange_rate_ranges
+             WHERE currency = ?1
+               AND COALESCE(valid_from, '0000-00-00') <= COALESCE(?3, '9999-99-99')
+               AND COALESCE(?2, '0000-00-00') <= COALESCE(valid_to, '9999-99-99')",
+            params![currency, valid_from, valid_to],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if overlapping > 0 {
+        return Err(format!(
+            "Custom rate range overlaps an existing range for {}",
+            currency
+        ));
+    }
+
+    conn.execute(
+        "INSERT INTO custom_exchange_rate_ranges (currency, rate, valid_from, valid_to) VALUES (?1, ?2, ?3, ?4)",
+        params![currency, rate, valid_from, valid_to],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(CustomExchangeRateRange {
+        id: conn.last_insert_rowid() as i32,
+        currency,
+        rate,
+        valid_from,
+        valid_to,
+    })
+}
+
+pub fn get_custom_exchange_rate_ranges_db(
+    db_path: &PathBuf,
+    currency: Option<String>,
+) -> Result<Vec<CustomExchangeRateRange>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, currency, rate, valid_from, valid_to FROM custom_exchange_rate_ranges
+             WHERE ?1 IS NULL OR currency = ?1
+             ORDER BY currency ASC, COALESCE(valid_from, '') ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let ranges = stmt
+        .query_map(params![currency], |row| {
+            Ok(CustomExchangeRateRange {
+                id: row.get(0)?,
+                currency: row.get(1)?,
+                rate: row.get(2)?,
+                valid_from: row.get(3)?,
+                valid_to: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ranges)
+}
+
+pub fn delete_custom_exchange_rate_range_db(db_path: &PathBuf, id: i32) -> Result<(), String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM custom_exchange_rate_ranges WHERE id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_fx_rate(
+    app_handle: AppHandle,
+    from: String,
+    to: String,
+    date: Option<String>,
+) -> Result<f64, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_fx_rate_db(&db_path, from, to, date)
+}
+
+#[tauri::command]
+pub fn convert_amount(
+    app_handle: AppHandle,
+    amount: f64,
+    from: String,
+    to: String,
+    date: Option<String>,
+) -> Result<f64, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    convert_amount_db(&db_path, amount, from, to, date)
+}
+
+#[tauri::command]
+pub async fn update_daily_fx_rates(
+    app_handle: AppHandle,
+    currencies: Option<Vec<String>>,
+) -> Result<Vec<PriceUpdateResult>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    let currencies = match currencies {
+        Some(c) => c,
+        None => get_used_currencies_db(&db_path)?,
+    };
+
+    let registry = ProviderRegistry::for_app(&app_handle)?;
+    update_daily_fx_rates_with_registry(std::path::Path::new(&db_path), &registry, currencies).await
+}
+
+#[tauri::command]
+pub fn add_custom_exchange_rate_range(
+    app_handle: AppHandle,
+    currency: String,
+    rate: f64,
+    valid_from: Option<String>,
+    valid_to: Option<String>,
+) -> Result<CustomExchangeRateRange, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    add_custom_exchange_rate_range_db(&db_path, currency, rate, valid_from, valid_to)
+}
+
+#[tauri::command]
+pub fn get_custom_exchange_rate_ranges(
+    app_handle: AppHandle,
+    currency: Option<String>,
+) -> Result<Vec<CustomExchangeRateRange>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_custom_exchange_rate_ranges_db(&db_path, currency)
+}
+
+#[tauri::command]
+pub fn delete_custom_exchange_rate_range(app_handle: AppHandle, id: i32) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    delete_custom_exchange_rate_range_db(&db_path, id)
+}
+
+#[tauri::command]
+pub fn get_fx_source(app_handle: AppHandle) -> Result<String, String> {
+    let settings = crate::db_init::read_settings(&app_handle)?;
+    normalize_fx_source(settings.fx_source)
+}
+
+#[tauri::command]
+pub fn set_fx_source(app_handle: AppHandle, source: String) -> Result<(), String> {
+    let source = normalize_fx_source(Some(source))?;
+    let mut settings = crate::db_init::read_settings(&app_handle)?;
+    settings.fx_source = Some(source);
+    crate::db_init::write_settings(&app_handle, &settings)
+}
+
+#[tauri::command]
+pub fn get_rate_mode(app_handle: AppHandle) -> Result<String, String> {
+    let settings = crate::db_init::read_settings(&app_handle)?;
+    normalize_rate_mode(settings.rate_mode)
+}
+
+#[tauri::command]
+pub fn set_rate_mode(app_handle: AppHandle, mode: String) -> Result<(), String> {
+    let mode = normalize_rate_mode(Some(mode))?;
+    let mut settings = crate::db_init::read_settings(&app_handle)?;
+    settings.rate_mode = Some(mode);
+    crate::db_init::write_settings(&app_handle, &settings)
+}
@@ -0,0 +1,223 @@
+use crate::corporate_actions::{load_split_ratios, split_factor};
+use crate::fx::rate_on;
+use crate::lots::build_lots;
+use crate::models::{Holding, HoldingsReport, LotReport};
+use crate::transfers::round_cents;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::AppHandle;
+
+// Replaying every investment transaction into lots is the expensive part of a holdings
+// report, so its result is kept per database and report date until a write invalidates it
+type LotCache = HashMap<(PathBuf, Option<String>), Arc<LotReport>>;
+
+fn lot_cache() -> &'static Mutex<LotCache> {
+    static CACHE: OnceLock<Mutex<LotCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Drops the cached positions of a database. Called by everything that writes transactions,
+/// splits or lot settings.
+pub fn invalidate_holdings_cache(db_path: &Path) {
+    if let Ok(mut cache) = lot_cache().lock() {
+        cache.retain(|(path, _), _| path != db_path);
+    }
+}
+
+fn cached_lots(
+    conn: &Connection,
+    db_path: &Path,
+    as_of: &Option<String>,
+) -> Result<Arc<LotReport>, String> {
+    let key = (db_path.to_path_buf(), as_of.clone());
+    if let Some(lots) = lot_cache().lock().ok().and_then(|c| c.get(&key).cloned()) {
+        return Ok(lots);
+    }
+    let lots = Arc::new(build_lots(conn, as_of.as_deref())?);
+    if let Ok(mut cache) = lot_cache().lock() {
+        cache.insert(key, lots.clone());
+    }
+    Ok(lots)
+}
+
+struct Quote {
+    price: f64,
+    change_percent: f64,
+    currency: Option<String>,
+    quote_type: Option<String>,
+}
+
+// Latest close on or before `date` (any date when `None`) and its change on the close before
+fn close_quote(
+    conn: &Connection,
+    ticker: &str,
+    date: Option<&str>,
+) -> Result<Option<Quote>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT price FROM daily_stock_prices
+             WHERE UPPER(ticker) = ?1 AND (?2 IS NULL OR date <= ?2)
+             ORDER BY date DESC LIMIT 2",
+        )
+        .map_err(|e| e.to_string())?;
+    let closes = stmt
+        .query_map(params![ticker, date], |row| row.get::<_, f64>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(closes.first().map(|price| Quote {
+        price: *price,
+        change_percent: match closes.get(1) {
+            Some(previous) if *previous > 0.0 => (price / previous - 1.0) * 100.0,
+            _ => 0.0,
+        },
+        currency: None,
+        quote_type: None,
+    }))
+}
+
+// The cached live quote for today, the stored closes for a past date
+fn quote_for(
+    conn: &Connection,
+    ticker: &str,
+    as_of: Option<&str>,
+) -> Result<Option<Quote>, String> {
+    let cached: Option<Quote> = conn
+        .query_row(
+            "SELECT price, change_percent, currency, quote_type FROM stock_prices
+             WHERE ticker = ?1 COLLATE NOCASE",
+            params![ticker],
+            |row| {
+                Ok(Quote {
+                    price: row.get(0)?,
+                    change_percent: row.get(1)?,
+                    currency: row.get(2)?,
+                    quote_type: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if as_of.is_none() && cached.is_some() {
+        return Ok(cached);
+    }
+    Ok(close_quote(conn, ticker, as_of)?.map(|quote| Quote {
+        currency: cached.as_ref().and_then(|c| c.currency.clone()),
+        quote_type: cached.and_then(|c| c.quote_type),
+        ..quote
+    }))
+}
+
+/// Current holdings of the given accounts (all when `None`), one per ticker, valued at the
+/// cached quotes or, with `as_of`, at the closes of that date. Cost basis follows each
+/// account's lot method.
+pub fn get_holdings_db(
+    db_path: &PathBuf,
+    account_ids: Option<Vec<i32>>,
+    as_of: Option<String>,
+    base_currency: Option<String>,
+) -> Result<HoldingsReport, String> {
+    let base = base_currency
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "USD".to_string());
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let lots = cached_lots(&conn, db_path, &as_of)?;
+    let value_date = as_of
+        .clone()
+        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let rate = |from: &str, date: &str| {
+        rate_on(&conn, from, &base, Some(date))
+            .ok_or_else(|| format!("No exchange rate from {} to {} on {}", from, base, date))
+    };
+
+    let mut by_ticker: BTreeMap<&str, Vec<&crate::models::TaxLot>> = BTreeMap::new();
+    for lot in lots.open_lots.iter().filter(|l| {
+        account_ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&l.account_id))
+    }) {
+        by_ticker.entry(&lot.ticker).or_default().push(lot);
+    }
+
+    // Stored prices are in today's split basis, the lots in that of `as_of`
+    let ratios = load_split_ratios(&conn)?;
+    let mut holdings = Vec::new();
+    for (ticker, lots) in by_ticker {
+        let shares: f64 = lots.iter().map(|l| l.shares).sum();
+        if shares <= 1e-4 {
+            continue;
+        }
+        let mut cost_basis = 0.0;
+        for lot in &lots {
+            cost_basis += lot.cost_basis * rate(&lot.currency, &lot.acquired_date)?;
+        }
+        let mut account_ids: Vec<i32> = lots.iter().map(|l| l.account_id).collect();
+        account_ids.sort();
+        account_ids.dedup();
+
+        let quote = quote_for(&conn, ticker, as_of.as_deref())?;
+        let currency = quote
+            .as_ref()
+            .and_then(|q| q.currency.clone())
+            .unwrap_or_else(|| lots[0].currency.clone());
+        let price = quote.as_ref().map(|q| q.price).unwrap_or(0.0);
+        let change_percent = quote.as_ref().map(|q| q.change_percent).unwrap_or(0.0);
+        let priced_shares = match &as_of {
+            Some(date) => shares * split_factor(&ratios, ticker, date, None),
+            None => shares,
+        };
+        let market_value = priced_shares * price * rate(&currency, &value_date)?;
+        // A drop of 100% or more leaves no previous close to compare against
+        let previous = 1.0 + change_percent / 100.0;
+        let day_change = if previous > 0.0 {
+            market_value - market_value / previous
+        } else {
+            0.0
+        };
+
+        holdings.push(Holding {
+            ticker: ticker.to_string(),
+            account_ids,
+            shares,
+            currency,
+            price,
+            cost_basis: round_cents(cost_basis),
+            market_value: round_cents(market_value),
+            day_change: round_cents(day_change),
+            day_change_percent: change_percent,
+            unrealized_gain: round_cents(market_value - cost_basis),
+            unrealized_gain_percent: if cost_basis > 0.0 {
+                (market_value - cost_basis) / cost_basis * 100.0
+            } else {
+                0.0
+            },
+            quote_type: quote.and_then(|q| q.quote_type),
+        });
+    }
+    holdings.sort_by(|a, b| b.market_value.total_cmp(&a.market_value));
+
+    let total = |f: fn(&Holding) -> f64| round_cents(holdings.iter().map(f).sum());
+    Ok(HoldingsReport {
+        base_currency: base,
+        as_of,
+        total_value: total(|h| h.market_value),
+        total_cost_basis: total(|h| h.cost_basis),
+        total_day_change: total(|h| h.day_change),
+        total_unrealized_gain: total(|h| h.unrealized_gain),
+        holdings,
+    })
+}
+
+#[tauri::command]
+pub fn get_holdings(
+    app_handle: AppHandle,
+    account_ids: Option<Vec<i32>>,
+    as_of: Option<String>,
+    base_currency: Option<String>,
+) -> Result<HoldingsReport, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_holdings_db(&db_path, account_ids, as_of, base_currency)
+}
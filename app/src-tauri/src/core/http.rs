@@ -0,0 +1,97 @@
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound on market requests in flight for one batch.
+pub const MAX_CONCURRENT_REQUESTS: usize = 6;
+
+const MAX_RETRIES: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_millis(200);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Client shared by all market data requests, so connections are pooled across calls; a
+/// slow or unreachable host fails instead of hanging.
+pub fn client() -> Result<reqwest::Client, String> {
+    if let Some(client) = CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    Ok(CLIENT.get_or_init(|| client).clone())
+}
+
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// Exponential backoff, stretched to a server's Retry-After (in seconds) when it sends one
+fn backoff(attempt: u32, retry_after: Option<&reqwest::header::HeaderValue>) -> Duration {
+    let exponential = BASE_BACKOFF * 2u32.pow(attempt);
+    let requested = retry_after
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_default();
+    exponential.max(requested).min(MAX_BACKOFF)
+}
+
+/// Sends the request, retrying timeouts, connection failures, 429 and 5xx responses with
+/// exponential backoff. Once retries run out the last response is returned as is, so the
+/// caller reports its status like any other.
+pub async fn send_with_retry(
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, String> {
+    let mut attempt = 0;
+    loop {
+        let current = request.try_clone().ok_or("Request cannot be retried")?;
+        let delay = match current.send().await {
+            Ok(resp) if attempt < MAX_RETRIES && is_retryable(resp.status()) => {
+                backoff(attempt, resp.headers().get(reqwest::header::RETRY_AFTER))
+            }
+            Ok(resp) => return Ok(resp),
+            Err(e) if attempt < MAX_RETRIES && (e.is_timeout() || e.is_connect()) => {
+                backoff(attempt, None)
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Runs `task` for every item with at most [`MAX_CONCURRENT_REQUESTS`] running at once.
+/// Results keep the order of `items`; a task that panicked yields an error.
+pub async fn run_bounded<T, R, F, Fut>(items: Vec<T>, task: F) -> Vec<Result<R, String>>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = R> + Send + 'static,
+    R: Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
+    let handles: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            let semaphore = semaphore.clone();
+            let future = task(item);
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                future.await
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await.map_err(|e| e.to_string()));
+    }
+    results
+}
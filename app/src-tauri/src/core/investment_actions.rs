@@ -0,0 +1,477 @@
+use crate::models::Transaction;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Everything a brokerage row can record. Buys and sells go through
+/// `create_investment_transaction_db`; the rest through `record_investment_action_db`.
+pub const INVESTMENT_ACTIONS: [&str; 11] = [
+    "buy",
+    "sell",
+    "dividend",
+    "drip",
+    "interest",
+    "return_of_capital",
+    "withholding_tax",
+    "split",
+    "spin_off",
+    "transfer_in",
+    "transfer_out",
+];
+
+/// Arguments for a non-trade investment action. Which fields are required depends on the action:
+///
+/// - `dividend`, `return_of_capital`: `ticker`, `amount`
+/// - `drip`: `ticker`, `amount` and the `shares` it bought (`price_per_share` defaults to amount / shares)
+/// - `interest`, `withholding_tax`: `amount`, optionally `ticker`
+/// - `split`: `ticker`, `numerator`, `denominator`
+/// - `spin_off`: parent `ticker`, `new_ticker`, `shares` received and `basis_fraction`, the part
+///   of the parent's cost basis that moves to the new shares
+/// - `transfer_in`, `transfer_out`: `ticker`, `shares`; a transfer out with `to_account_id`
+///   also records the matching transfer in, and a transfer in from outside may carry its cost
+///   basis per share in `price_per_share`
+///
+/// Amounts are positive; withholding tax and any `fee` are taken from cash.
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InvestmentActionArgs {
+    pub account_id: i32,
+    pub date: String,
+    pub action: String,
+    pub ticker: Option<String>,
+    pub amount: Option<f64>,
+    pub shares: Option<f64>,
+    pub price_per_share: Option<f64>,
+    pub fee: Option<f64>,
+    pub numerator: Option<f64>,
+    pub denominator: Option<f64>,
+    pub new_ticker: Option<String>,
+    pub basis_fraction: Option<f64>,
+    pub to_account_id: Option<i32>,
+    pub notes: Option<String>,
+    pub currency: Option<String>,
+}
+
+fn required<T>(value: Option<T>, field: &str, action: &str) -> Result<T, String> {
+    value.ok_or_else(|| format!("{} requires {}", action, field))
+}
+
+fn positive(value: Option<f64>, field: &str, action: &str) -> Result<f64, String> {
+    let value = required(value, field, action)?;
+    if value <= 0.0 || !value.is_finite() {
+        return Err(format!("{} must be positive", field));
+    }
+    Ok(value)
+}
+
+fn ticker_for(value: Option<String>, field: &str, action: &str) -> Result<String, String> {
+    let ticker = required(value, field, action)?.trim().to_uppercase();
+    if ticker.is_empty() {
+        return Err(format!("{} requires {}", action, field));
+    }
+    Ok(ticker)
+}
+
+fn ensure_account(conn: &Connection, account_id: i32) -> Result<(), String> {
+    conn.query_row(
+        "SELECT id FROM accounts WHERE id = ?1",
+        params![account_id],
+        |row| row.get::<_, i32>(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .map(|_| ())
+    .ok_or_else(|| format!("Account {} not found", account_id))
+}
+
+// One row to insert; rules may still rename payee, notes and category
+struct ActionRow {
+    account_id: i32,
+    payee: &'static str,
+    category: &'static str,
+    notes: String,
+    amount: f64,
+    ticker: Option<String>,
+    shares: Option<f64>,
+    price_per_share: Option<f64>,
+    fee: Option<f64>,
+    action: &'static str,
+    basis_fraction: Option<f64>,
+}
+
+fn insert_row(
+    tx: &rusqlite::Transaction,
+    row: ActionRow,
+    date: &str,
+    currency: &Option<String>,
+    rules: &[crate::models::Rule],
+) -> Result<Transaction, String> {
+    let mut transaction = Transaction {
+        id: 0,
+        account_id: row.account_id,
+        date: date.to_string(),
+        payee: row.payee.to_string(),
+        notes: Some(row.notes),
+        category: Some(row.category.to_string()),
+        amount: row.amount,
+        ticker: row.ticker,
+        shares: row.shares,
+        price_per_share: row.price_per_share,
+        fee: row.fee,
+        currency: currency.clone(),
+        cleared: crate::models::default_cleared_state(),
+        transfer_id: None,
+        action: Some(row.action.to_string()),
+    };
+    crate::rules::apply_rules_to_transaction(&mut transaction, rules);
+
+    tx.execute(
+        "INSERT INTO transactions (account_id, date, payee, notes, category, amount, ticker, shares, price_per_share, fee, currency, action, basis_fraction)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            transaction.account_id,
+            transaction.date,
+            transaction.payee,
+            transaction.notes,
+            transaction.category,
+            transaction.amount,
+            transaction.ticker,
+            transaction.shares,
+            transaction.price_per_share,
+            transaction.fee,
+            transaction.currency,
+            transaction.action,
+            row.basis_fraction
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    transaction.id = tx.last_insert_rowid() as i32;
+
+    if transaction.amount != 0.0 {
+        tx.execute(
+            "UPDATE accounts SET balance = balance + ?1 WHERE id = ?2",
+            params![transaction.amount, transaction.account_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(transaction)
+}
+
+// Rows recorded together point at each other. Unlike transfer counterparts, editing or
+// deleting one leaves the other as it is.
+fn link_rows(tx: &rusqlite::Transaction, a: i32, b: i32) -> Result<(), String> {
+    tx.execute(
+        "UPDATE transactions SET related_tx_id = ?1 WHERE id = ?2",
+        params![b, a],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE transactions SET related_tx_id = ?1 WHERE id = ?2",
+        params![a, b],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Records a dividend, interest, tax, split, spin-off or share transfer, returning the rows
+/// written (two for DRIPs, spin-offs and transfers between accounts).
+pub fn record_investment_action_db(
+    db_path: &PathBuf,
+    args: InvestmentActionArgs,
+) -> Result<Vec<Transaction>, String> {
+    let action = INVESTMENT_ACTIONS
+        .into_iter()
+        .find(|a| *a == args.action)
+        .ok_or_else(|| format!("Unknown investment action: {}", args.action))?;
+    if action == "buy" || action == "sell" {
+        return Err("Buys and sells are recorded as investment transactions".to_string());
+    }
+    let fee = args.fee.unwrap_or(0.0);
+    if fee < 0.0 {
+        return Err("fee cannot be negative".to_string());
+    }
+    let fee_field = (fee != 0.0).then_some(fee);
+
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    ensure_account(&conn, args.account_id)?;
+    let rules = crate::rules::get_rules_db(db_path).unwrap_or_default();
+
+    let cash_row =
+        |payee, category, action, ticker: Option<String>, amount: f64, notes| ActionRow {
+            account_id: args.account_id,
+            payee,
+            category,
+            notes,
+            amount: amount - fee,
+            ticker,
+            shares: None,
+            price_per_share: None,
+            fee: fee_field,
+            action,
+            basis_fraction: None,
+        };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut rows = Vec::new();
+    match action {
+        "dividend" | "return_of_capital" => {
+            let ticker = ticker_for(args.ticker, "ticker", action)?;
+            let amount = positive(args.amount, "amount", action)?;
+            let (payee, category) = if action == "dividend" {
+                ("Dividend", "Dividend")
+            } else {
+                ("Return of Capital", "Investment")
+            };
+            let notes = args
+                .notes
+                .unwrap_or_else(|| format!("{} from {}", payee, ticker));
+            rows.push(insert_row(
+                &tx,
+                cash_row(payee, category, action, Some(ticker), amount, notes),
+                &args.date,
+                &args.currency,
+                &rules,
+            )?);
+        }
+        "interest" | "withholding_tax" => {
+            let ticker = args
+                .ticker
+                .map(|t| t.trim().to_uppercase())
+                .filter(|t| !t.is_empty());
+            let amount = positive(args.amount, "amount", action)?;
+            let (payee, category, signed) = if action == "interest" {
+                ("Interest", "Interest", amount)
+            } else {
+                ("Withholding Tax", "Taxes", -amount)
+            };
+            let notes = args.notes.unwrap_or_else(|| match &ticker {
+                Some(t) => format!("{} on {}", payee, t),
+                None => payee.to_string(),
+            });
+            rows.push(insert_row(
+                &tx,
+                cash_row(payee, category, action, ticker, signed, notes),
+                &args.date,
+                &args.currency,
+                &rules,
+            )?);
+        }
+        "drip" => {
+            let ticker = ticker_for(args.ticker, "ticker", action)?;
+            let amount = positive(args.amount, "amount", action)?;
+            let shares = positive(args.shares, "shares", action)?;
+            let price = args.price_per_share.unwrap_or(amount / shares);
+            // The dividend is income like any other; the reinvestment buys the shares with it
+            let income = insert_row(
+                &tx,
+                cash_row(
+                    "Dividend",
+                    "Dividend",
+                    "dividend",
+                    Some(ticker.clone()),
+                    amount,
+                    format!("Dividend from {}", ticker),
+                ),
+                &args.date,
+                &args.currency,
+                &rules,
+            )?;
+            let reinvested = insert_row(
+                &tx,
+                ActionRow {
+                    account_id: args.account_id,
+                    payee: "Dividend Reinvestment",
+                    category: "Investment",
+                    notes: args
+                        .notes
+                        .unwrap_or_else(|| format!("Reinvested {} shares of {}", shares, ticker)),
+                    amount: -amount,
+                    ticker: Some(ticker),
+                    shares: Some(shares),
+                    price_per_share: Some(price),
+                    fee: None,
+                    action: "drip",
+                    basis_fraction: None,
+                },
+                &args.date,
+                &args.currency,
+                &rules,
+            )?;
+            link_rows(&tx, income.id, reinvested.id)?;
+            rows.push(income);
+            rows.push(reinvested);
+        }
+        "split" => {
+            let ticker = ticker_for(args.ticker, "ticker", action)?;
+            let numerator = positive(args.numerator, "numerator", action)?;
+            let denominator = positive(args.denominator, "denominator", action)?;
+            // Share counts follow the split table, which also holds splits read from price history
+            if let Some(known) = crate::corporate_actions::matching_split(&tx, &ticker, &args.date)?
+            {
+                return Err(format!(
+                    "A split of {} is already recorded on {}",
+                    ticker, known
+                ));
+            }
+            crate::corporate_actions::rescale_before_split(
+                &tx,
+                &ticker,
+                &args.date,
+                numerator,
+                denominator,
+            )?;
+            tx.execute(
+                "INSERT OR REPLACE INTO stock_splits (ticker, date, numerator, denominator) VALUES (?1, ?2, ?3, ?4)",
+                params![ticker, args.date, numerator, denominator],
+            )
+            .map_err(|e| e.to_string())?;
+            let notes = args
+                .notes
+                .unwrap_or_else(|| format!("{}:{} split of {}", numerator, denominator, ticker));
+            rows.push(insert_row(
+                &tx,
+                cash_row("Split", "Investment", action, Some(ticker), 0.0, notes),
+                &args.date,
+                &args.currency,
+                &rules,
+            )?);
+        }
+        "spin_off" => {
+            let parent = ticker_for(args.ticker, "ticker", action)?;
+            let child = ticker_for(args.new_ticker, "new_ticker", action)?;
+            let shares = positive(args.shares, "shares", action)?;
+            let fraction = required(args.basis_fraction, "basis_fraction", action)?;
+            if !(0.0..1.0).contains(&fraction) {
+                return Err("basis_fraction must be at least 0 and below 1".to_string());
+            }
+            let from = insert_row(
+                &tx,
+                ActionRow {
+                    basis_fraction: Some(fraction),
+                    ..cash_row(
+                        "Spin-off",
+                        "Investment",
+                        action,
+                        Some(parent.clone()),
+                        0.0,
+                        format!("Spun off {} from {}", child, parent),
+                    )
+                },
+                &args.date,
+                &args.currency,
+                &rules,
+            )?;
+            let received = insert_row(
+                &tx,
+                ActionRow {
+                    account_id: args.account_id,
+                    payee: "Spin-off",
+                    category: "Investment",
+                    notes: args.notes.unwrap_or_else(|| {
+                        format!("Received {} shares of {} from {}", shares, child, parent)
+                    }),
+                    amount: 0.0,
+                    ticker: Some(child),
+                    shares: Some(shares),
+                    price_per_share: None,
+                    fee: None,
+                    action: "spin_off",
+                    basis_fraction: Some(fraction),
+                },
+                &args.date,
+                &args.currency,
+                &rules,
+            )?;
+            link_rows(&tx, from.id, received.id)?;
+            rows.push(from);
+            rows.push(received);
+        }
+        "transfer_in" | "transfer_out" => {
+            let ticker = ticker_for(args.ticker, "ticker", action)?;
+            let shares = positive(args.shares, "shares", action)?;
+            if action == "transfer_in" && args.to_account_id.is_some() {
+                return Err("Record transfers between accounts as a transfer out".to_string());
+            }
+            let (payee, signed) = if action == "transfer_in" {
+                ("Shares In", shares)
+            } else {
+                ("Shares Out", -shares)
+            };
+            let notes = args.notes.clone().unwrap_or_else(|| {
+                format!(
+                    "Transferred {} shares of {} {}",
+                    shares,
+                    ticker,
+                    if action == "transfer_in" { "in" } else { "out" }
+                )
+            });
+            let first = insert_row(
+                &tx,
+                ActionRow {
+                    shares: Some(signed),
+                    price_per_share: if action == "transfer_in" {
+                        args.price_per_share
+                    } else {
+                        None
+                    },
+                    ..cash_row(
+                        payee,
+                        "Investment",
+                        action,
+                        Some(ticker.clone()),
+                        0.0,
+                        notes,
+                    )
+                },
+                &args.date,
+                &args.currency,
+                &rules,
+            )?;
+            rows.push(first);
+
+            if let Some(to_account_id) = args.to_account_id {
+                if to_account_id == args.account_id {
+                    return Err("Shares must move to a different account".to_string());
+                }
+                ensure_account(&tx, to_account_id)?;
+                let second = insert_row(
+                    &tx,
+                    ActionRow {
+                        account_id: to_account_id,
+                        payee: "Shares In",
+                        category: "Investment",
+                        notes: args.notes.unwrap_or_else(|| {
+                            format!("Transferred {} shares of {} in", shares, ticker)
+                        }),
+                        amount: 0.0,
+                        ticker: Some(ticker),
+                        shares: Some(shares),
+                        price_per_share: None,
+                        fee: None,
+                        action: "transfer_in",
+                        basis_fraction: None,
+                    },
+                    &args.date,
+                    &args.currency,
+                    &rules,
+                )?;
+                link_rows(&tx, rows[0].id, second.id)?;
+                rows.push(second);
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    crate::holdings::invalidate_holdings_cache(db_path);
+    Ok(rows)
+}
+
+#[tauri::command]
+pub fn record_investment_action(
+    app_handle: AppHandle,
+    args: InvestmentActionArgs,
+) -> Result<Vec<Transaction>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    record_investment_action_db(&db_path, args)
+}
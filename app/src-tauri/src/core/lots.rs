@@ -0,0 +1,555 @@
+use crate::corporate_actions::{load_split_ratios, split_factor};
+use crate::models::{LotReport, LotSelection, RealizedGain, TaxLot};
+use chrono::{Months, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Cost basis methods an account can use. Sales under `specific_id` without a lot
+/// selection fall back to FIFO.
+pub const LOT_METHODS: [&str; 4] = ["fifo", "lifo", "average", "specific_id"];
+
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone)]
+struct Lot {
+    id: i32,
+    date: String,
+    shares: f64,
+    cost: f64,
+    currency: String,
+}
+
+impl Lot {
+    // Splits off `shares` of this lot along with their share of the cost
+    fn take(&mut self, shares: f64) -> Lot {
+        let shares = shares.min(self.shares);
+        let cost = if self.shares > EPSILON {
+            self.cost * shares / self.shares
+        } else {
+            0.0
+        };
+        self.shares -= shares;
+        self.cost -= cost;
+        Lot {
+            shares,
+            cost,
+            ..self.clone()
+        }
+    }
+}
+
+struct LotRow {
+    id: i32,
+    account_id: i32,
+    date: String,
+    ticker: String,
+    shares: Option<f64>,
+    amount: f64,
+    price_per_share: Option<f64>,
+    fee: f64,
+    action: Option<String>,
+    related_tx_id: Option<i32>,
+    basis_fraction: Option<f64>,
+    currency: String,
+}
+
+/// Held for more than a year counts as long-term.
+pub(crate) fn holding_term(acquired: &str, disposed: &str) -> &'static str {
+    let parse = |d: &str| NaiveDate::parse_from_str(d.get(..10).unwrap_or(d), "%Y-%m-%d").ok();
+    match (parse(acquired), parse(disposed)) {
+        (Some(a), Some(d)) if a.checked_add_months(Months::new(12)).is_some_and(|y| d > y) => {
+            "long"
+        }
+        _ => "short",
+    }
+}
+
+// Removes `shares` from the lots in the order the method picks them, returning the pieces taken
+// and whatever could not be covered
+fn take_shares(
+    lots: &mut Vec<Lot>,
+    shares: f64,
+    method: &str,
+    selections: &[(i32, f64)],
+) -> (Vec<Lot>, f64) {
+    let mut remaining = shares;
+    let mut taken = Vec::new();
+
+    if method == "average" {
+        let total_shares: f64 = lots.iter().map(|l| l.shares).sum();
+        let total_cost: f64 = lots.iter().map(|l| l.cost).sum();
+        if total_shares > EPSILON {
+            for lot in lots.iter_mut() {
+                lot.cost = total_cost * lot.shares / total_shares;
+            }
+        }
+    }
+    if method == "specific_id" {
+        for (lot_id, wanted) in selections {
+            if let Some(lot) = lots.iter_mut().find(|l| l.id == *lot_id) {
+                let piece = lot.take(wanted.min(remaining));
+                remaining -= piece.shares;
+                taken.push(piece);
+            }
+        }
+    }
+
+    let order: Vec<usize> = if method == "lifo" {
+        (0..lots.len()).rev().collect()
+    } else {
+        (0..lots.len()).collect()
+    };
+    for i in order {
+        if remaining <= EPSILON {
+            break;
+        }
+        let piece = lots[i].take(remaining);
+        remaining -= piece.shares;
+        taken.push(piece);
+    }
+
+    lots.retain(|l| l.shares > EPSILON);
+    taken.retain(|l| l.shares > EPSILON);
+    (taken, remaining.max(0.0))
+}
+
+fn load_lot_rows(conn: &Connection, as_of: Option<&str>) -> Result<Vec<LotRow>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.account_id, t.date, UPPER(t.ticker), t.shares, t.amount, t.price_per_share,
+                    COALESCE(t.fee, 0), t.action, t.related_tx_id, t.basis_fraction,
+                    COALESCE(t.currency, a.currency, 'USD')
+             FROM transactions t JOIN accounts a ON a.id = t.account_id
+             WHERE t.ticker IS NOT NULL AND t.ticker != '' AND (?1 IS NULL OR t.date <= ?1)
+             ORDER BY t.date, t.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![as_of], |row| {
+            Ok(LotRow {
+                id: row.get(0)?,
+                account_id: row.get(1)?,
+                date: row.get(2)?,
+                ticker: row.get(3)?,
+                shares: row.get(4)?,
+                amount: row.get(5)?,
+                price_per_share: row.get(6)?,
+                fee: row.get(7)?,
+                action: row.get(8)?,
+                related_tx_id: row.get(9)?,
+                basis_fraction: row.get(10)?,
+                currency: row.get(11)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+fn load_lot_methods(conn: &Connection) -> Result<HashMap<i32, String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, COALESCE(lot_method, 'fifo') FROM accounts")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+fn load_selections(conn: &Connection) -> Result<HashMap<i32, Vec<(i32, f64)>>, String> {
+    let mut stmt = conn
+        .prepare("SELECT sell_tx_id, lot_tx_id, shares FROM lot_selections ORDER BY rowid")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, i32>(1)?,
+                row.get::<_, f64>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut selections: HashMap<i32, Vec<(i32, f64)>> = HashMap::new();
+    for row in rows {
+        let (sell, lot, shares) = row.map_err(|e| e.to_string())?;
+        selections.entry(sell).or_default().push((lot, shares));
+    }
+    Ok(selections)
+}
+
+/// Replays every investment transaction up to `as_of` into lots for all accounts.
+///
+/// Share counts are kept in the split basis of `as_of` (today when `None`); realized
+/// shares are reported in the basis of the sale. Buys and DRIPs open lots at their cash
+/// cost including fees, sales close them by the account's method (shares sold beyond the
+/// open lots are realized without a lot, cost basis or term), transfers between
+/// accounts carry lots over with their acquisition dates, spin-offs move `basis_fraction`
+/// of every parent lot to the new ticker, and return of capital lowers the basis of the
+/// open lots (never below zero).
+pub(crate) fn build_lots(conn: &Connection, as_of: Option<&str>) -> Result<LotReport, String> {
+    let ratios = load_split_ratios(conn)?;
+    let methods = load_lot_methods(conn)?;
+    let selections = load_selections(conn)?;
+    let rows = load_lot_rows(conn, as_of)?;
+
+    let mut holdings: HashMap<(i32, String), Vec<Lot>> = HashMap::new();
+    let mut in_transit: HashMap<i32, Vec<Lot>> = HashMap::new();
+    let mut spin_off_parents: HashMap<i32, String> = HashMap::new();
+    let mut realized = Vec::new();
+
+    for row in &rows {
+        let raw_shares = row.shares.unwrap_or(0.0);
+        let factor = split_factor(&ratios, &row.ticker, &row.date, as_of);
+        let shares = raw_shares * factor;
+        let action = match row.action.as_deref() {
+            Some(a) => a,
+            None if raw_shares > 0.0 => "buy",
+            None if raw_shares < 0.0 => "sell",
+            None => continue,
+        };
+        let method = methods
+            .get(&row.account_id)
+            .map(|m| m.as_str())
+            .unwrap_or("fifo");
+        let key = (row.account_id, row.ticker.clone());
+        let new_lot = |shares: f64, cost: f64| Lot {
+            id: row.id,
+            date: row.date.clone(),
+            shares,
+            cost,
+            currency: row.currency.clone(),
+        };
+
+        match action {
+            "buy" | "drip" if shares > EPSILON => {
+                let cost = if row.amount < 0.0 {
+                    -row.amount
+                } else {
+                    raw_shares * row.price_per_share.unwrap_or(0.0) + row.fee
+                };
+                holdings.entry(key).or_default().push(new_lot(shares, cost));
+            }
+            "sell" if shares < -EPSILON => {
+                let sold = -shares;
+                let proceeds = row
+                    .price_per_share
+                    .map(|p| p * -raw_shares)
+                    .unwrap_or(row.amount + row.fee);
+                let chosen: Vec<(i32, f64)> = selections
+                    .get(&row.id)
+                    .map(|s| s.iter().map(|(id, n)| (*id, n * factor)).collect())
+                    .unwrap_or_default();
+                let lots = holdings.entry(key).or_default();
+                let (taken, uncovered) = take_shares(lots, sold, method, &chosen);
+
+                let pieces = taken
+                    .into_iter()
+                    .map(|lot| (Some(lot.id), Some(lot.date), lot.shares, lot.cost))
+                    .chain((uncovered > EPSILON).then_some((None, None, uncovered, 0.0)));
+                for (lot_id, acquired_date, piece_shares, cost) in pieces {
+                    let part = piece_shares / sold;
+                    let fees = row.fee * part;
+                    let piece_proceeds = proceeds * part;
+                    realized.push(RealizedGain {
+                        account_id: row.account_id,
+                        ticker: row.ticker.clone(),
+                        sell_tx_id: row.id,
+                        term: acquired_date
+                            .as_deref()
+                            .map(|d| holding_term(d, &row.date))
+                            .unwrap_or("unknown")
+                            .to_string(),
+                        lot_id,
+                        acquired_date,
+                        disposed_date: row.date.clone(),
+                        shares: piece_shares / factor,
+                        proceeds: piece_proceeds,
+                        cost_basis: cost,
+                        fees,
+                        gain: piece_proceeds - fees - cost,
+                        currency: row.currency.clone(),
+                    });
+                }
+            }
+            "transfer_out" if shares < -EPSILON => {
+                let lots = holdings.entry(key).or_default();
+                let (taken, _) = take_shares(lots, -shares, method, &[]);
+                if let Some(to) = row.related_tx_id {
+                    in_transit.insert(to, taken);
+                }
+            }
+            "transfer_in" if shares > EPSILON => {
+                let lots = match in_transit.remove(&row.id) {
+                    Some(moved) => moved,
+                    None => {
+                        let cost = raw_shares * row.price_per_share.unwrap_or(0.0);
+                        vec![new_lot(shares, cost)]
+                    }
+                };
+                let held = holdings.entry(key).or_default();
+                held.extend(lots);
+                held.sort_by(|a, b| a.date.cmp(&b.date).then(a.id.cmp(&b.id)));
+            }
+            "spin_off" if shares <= EPSILON => {
+                spin_off_parents.insert(row.id, row.ticker.clone());
+            }
+            "spin_off" => {
+                let fraction = row.basis_fraction.unwrap_or(0.0);
+                let parent = row
+                    .related_tx_id
+                    .and_then(|id| spin_off_parents.get(&id))
+                    .cloned();
+                let parent_lots = parent
+                    .and_then(|t| holdings.get_mut(&(row.account_id, t)))
+                    .filter(|lots| !lots.is_empty());
+                let children = match parent_lots {
+                    Some(lots) => {
+                        let total: f64 = lots.iter().map(|l| l.shares).sum();
+                        lots.iter_mut()
+                            .map(|lot| {
+                                let moved = lot.cost * fraction;
+                                lot.cost -= moved;
+                                Lot {
+                                    shares: shares * lot.shares / total,
+                                    cost: moved,
+                                    currency: row.currency.clone(),
+                                    ..lot.clone()
+                                }
+                            })
+                            .collect()
+                    }
+                    None => vec![new_lot(shares, 0.0)],
+                };
+                holdings.entry(key).or_default().extend(children);
+            }
+            "return_of_capital" => {
+                if let Some(lots) = holdings.get_mut(&key) {
+                    let total: f64 = lots.iter().map(|l| l.shares).sum();
+                    if total > EPSILON {
+                        let returned = row.amount + row.fee;
+                        for lot in lots.iter_mut() {
+                            lot.cost = (lot.cost - returned * lot.shares / total).max(0.0);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut open_lots: Vec<TaxLot> = holdings
+        .into_iter()
+        .flat_map(|((account_id, ticker), lots)| {
+            lots.into_iter()
+                .filter(|l| l.shares > EPSILON)
+                .map(move |l| TaxLot {
+                    account_id,
+                    ticker: ticker.clone(),
+                    lot_id: l.id,
+                    acquired_date: l.date,
+                    shares: l.shares,
+                    cost_basis: l.cost,
+                    cost_per_share: l.cost / l.shares,
+                    currency: l.currency,
+                })
+        })
+        .collect();
+    open_lots.sort_by(|a, b| {
+        a.account_id
+            .cmp(&b.account_id)
+            .then(a.ticker.cmp(&b.ticker))
+            .then(a.acquired_date.cmp(&b.acquired_date))
+            .then(a.lot_id.cmp(&b.lot_id))
+    });
+
+    Ok(LotReport {
+        open_lots,
+        realized,
+    })
+}
+
+/// Open lots and realized gains, optionally limited to some accounts and to
+/// transactions up to `as_of`.
+pub fn get_tax_lots_db(
+    db_path: &PathBuf,
+    account_ids: Option<Vec<i32>>,
+    as_of: Option<String>,
+) -> Result<LotReport, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut report = build_lots(&conn, as_of.as_deref())?;
+    if let Some(ids) = account_ids {
+        report.open_lots.retain(|l| ids.contains(&l.account_id));
+        report.realized.retain(|r| ids.contains(&r.account_id));
+    }
+    Ok(report)
+}
+
+pub fn get_lot_method_db(db_path: &PathBuf, account_id: i32) -> Result<String, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT COALESCE(lot_method, 'fifo') FROM accounts WHERE id = ?1",
+        params![account_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Account {} not found", account_id))
+}
+
+pub fn set_lot_method_db(db_path: &PathBuf, account_id: i32, method: String) -> Result<(), String> {
+    let method = method.trim().to_lowercase();
+    if !LOT_METHODS.contains(&method.as_str()) {
+        return Err(format!("Unknown lot method: {}", method));
+    }
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE accounts SET lot_method = ?1 WHERE id = ?2",
+            params![method, account_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Account {} not found", account_id));
+    }
+    crate::holdings::invalidate_holdings_cache(db_path);
+    Ok(())
+}
+
+pub fn get_lot_selection_db(
+    db_path: &PathBuf,
+    sell_tx_id: i32,
+) -> Result<Vec<LotSelection>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    Ok(load_selections(&conn)?
+        .remove(&sell_tx_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(lot_id, shares)| LotSelection { lot_id, shares })
+        .collect())
+}
+
+/// Picks the lots a sale closes under specific identification. An empty list clears the
+/// selection. Shares are in the split basis of the sale and may not exceed the shares sold.
+pub fn set_lot_selection_db(
+    db_path: &PathBuf,
+    sell_tx_id: i32,
+    selections: Vec<LotSelection>,
+) -> Result<(), String> {
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let sale: Option<(Option<f64>, i32, String, String)> = conn
+        .query_row(
+            "SELECT shares, account_id, UPPER(ticker), date FROM transactions
+             WHERE id = ?1 AND ticker IS NOT NULL AND ticker != ''",
+            params![sell_tx_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let (sold, account_id, ticker, date) = match sale {
+        Some((Some(s), account_id, ticker, date)) if s < 0.0 => (-s, account_id, ticker, date),
+        _ => return Err(format!("Transaction {} is not a sale", sell_tx_id)),
+    };
+    if selections.iter().any(|s| s.shares <= 0.0) {
+        return Err("Selected shares must be positive".to_string());
+    }
+    let selected: f64 = selections.iter().map(|s| s.shares).sum();
+    if selected > sold + EPSILON {
+        return Err(format!(
+            "Selected {} shares but the sale was {}",
+            selected, sold
+        ));
+    }
+
+    // Only lots of the sold ticker the account held when it sold: those the sale closed
+    // and those still open after it, leaving out lots bought later on the sale date
+    if !selections.is_empty() {
+        let report = build_lots(&conn, Some(&date))?;
+        let held: HashSet<i32> = report
+            .open_lots
+            .iter()
+            .filter(|l| l.account_id == account_id && l.ticker == ticker)
+            .filter(|l| (l.acquired_date.as_str(), l.lot_id) < (date.as_str(), sell_tx_id))
+            .map(|l| l.lot_id)
+            .chain(
+                report
+                    .realized
+                    .iter()
+                    .filter(|r| r.sell_tx_id == sell_tx_id)
+                    .filter_map(|r| r.lot_id),
+            )
+            .collect();
+        if let Some(s) = selections.iter().find(|s| !held.contains(&s.lot_id)) {
+            return Err(format!(
+                "Lot {} is not a {} lot held in the account before the sale",
+                s.lot_id, ticker
+            ));
+        }
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM lot_selections WHERE sell_tx_id = ?1",
+        params![sell_tx_id],
+    )
+    .map_err(|e| e.to_string())?;
+    for selection in &selections {
+        tx.execute(
+            "INSERT OR REPLACE INTO lot_selections (sell_tx_id, lot_tx_id, shares) VALUES (?1, ?2, ?3)",
+            params![sell_tx_id, selection.lot_id, selection.shares],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    crate::holdings::invalidate_holdings_cache(db_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_tax_lots(
+    app_handle: AppHandle,
+    account_ids: Option<Vec<i32>>,
+    as_of: Option<String>,
+) -> Result<LotReport, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_tax_lots_db(&db_path, account_ids, as_of)
+}
+
+#[tauri::command]
+pub fn get_lot_method(app_handle: AppHandle, account_id: i32) -> Result<String, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_lot_method_db(&db_path, account_id)
+}
+
+#[tauri::command]
+pub fn set_lot_method(
+    app_handle: AppHandle,
+    account_id: i32,
+    method: String,
+) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_lot_method_db(&db_path, account_id, method)
+}
+
+#[tauri::command]
+pub fn get_lot_selection(
+    app_handle: AppHandle,
+    sell_tx_id: i32,
+) -> Result<Vec<LotSelection>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_lot_selection_db(&db_path, sell_tx_id)
+}
+
+#[tauri::command]
+pub fn set_lot_selection(
+    app_handle: AppHandle,
+    sell_tx_id: i32,
+    selections: Vec<LotSelection>,
+) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_lot_selection_db(&db_path, sell_tx_id, selections)
+}
@@ -1,25 +1,88 @@
-use crate::models::{DailyPrice, YahooChartResponse, YahooQuote, YahooSearchQuote};
-use chrono::{NaiveDate, TimeZone, Utc};
+use crate::models::{
+    DailyBar, DailyPrice, PriceUpdateResult, QuoteCacheSettings, YahooQuote, YahooSearchQuote,
+};
+use crate::providers::{MarketDataProvider, ProviderRegistry, YahooProvider};
+use chrono::{NaiveDate, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Arc;
 
 pub async fn search_ticker_with_client(
     client: reqwest::Client,
     base_url: String,
     query: String,
 ) -> Result<Vec<YahooSearchQuote>, String> {
-    let url = format!("{}/v1/finance/search?q={}", base_url, query);
-    let res = client
-        .get(&url)
-        .header("User-Agent", "Mozilla/5.0")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    YahooProvider::new(client, &base_url).search(&query).await
+}
 
-    let text = res.text().await.map_err(|e| e.to_string())?;
-    let response: crate::models::YahooSearchResponse =
-        serde_json::from_str(&text).map_err(|e| e.to_string())?;
+// Quotes younger than this are served from stock_prices unless configured otherwise
+const DEFAULT_QUOTE_TTL_SECONDS: u64 = 15 * 60;
+
+pub fn quote_cache_settings(app_handle: &tauri::AppHandle) -> Result<QuoteCacheSettings, String> {
+    let settings = crate::db_init::read_settings(app_handle)?;
+    Ok(QuoteCacheSettings {
+        ttl_seconds: settings
+            .quote_ttl_seconds
+            .unwrap_or(DEFAULT_QUOTE_TTL_SECONDS),
+        offline: settings.offline_mode.unwrap_or(false),
+    })
+}
+
+#[tauri::command]
+pub fn get_quote_cache_settings(
+    app_handle: tauri::AppHandle,
+) -> Result<QuoteCacheSettings, String> {
+    quote_cache_settings(&app_handle)
+}
+
+#[tauri::command]
+pub fn set_quote_cache_settings(
+    app_handle: tauri::AppHandle,
+    ttl_seconds: Option<u64>,
+    offline: Option<bool>,
+) -> Result<QuoteCacheSettings, String> {
+    let mut settings = crate::db_init::read_settings(&app_handle)?;
+    if let Some(ttl) = ttl_seconds {
+        settings.quote_ttl_seconds = Some(ttl);
+    }
+    if let Some(offline) = offline {
+        settings.offline_mode = Some(offline);
+    }
+    crate::db_init::write_settings(&app_handle, &settings)?;
+    quote_cache_settings(&app_handle)
+}
 
-    Ok(response.quotes)
+/// Offline search over tickers the database already knows from quotes or transactions.
+pub fn search_known_tickers_db(
+    db_path: &std::path::Path,
+    query: &str,
+) -> Result<Vec<YahooSearchQuote>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT ticker, MAX(currency) FROM (
+                 SELECT ticker, currency FROM stock_prices
+                 UNION ALL
+                 SELECT UPPER(ticker), NULL FROM transactions WHERE ticker IS NOT NULL AND ticker != ''
+             )
+             WHERE ticker LIKE '%' || ?1 || '%'
+             GROUP BY ticker COLLATE NOCASE
+             ORDER BY ticker",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![query.trim()], |row| {
+            Ok(YahooSearchQuote {
+                symbol: row.get(0)?,
+                shortname: None,
+                longname: None,
+                exchange: None,
+                type_disp: None,
+                currency: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 // Search helper that enriches results with currency info using get_stock_quotes
@@ -28,13 +91,15 @@ pub async fn search_ticker(
     app_handle: tauri::AppHandle,
     query: String,
 ) -> Result<Vec<YahooSearchQuote>, String> {
+    if quote_cache_settings(&app_handle)?.offline {
+        let db_path = crate::db_init::get_db_path(&app_handle)?;
+        return search_known_tickers_db(&db_path, &query);
+    }
+
     // 1. Get initial search results
-    let mut quotes = search_ticker_with_client(
-        reqwest::Client::new(),
-        "https://query1.finance.yahoo.com".to_string(),
-        query,
-    )
-    .await?;
+    let mut quotes = ProviderRegistry::for_app(&app_handle)?
+        .search(&query)
+        .await?;
 
     if quotes.is_empty() {
         return Ok(quotes);
@@ -61,15 +126,10 @@ pub async fn get_stock_quotes(
     app_handle: tauri::AppHandle,
     tickers: Vec<String>,
 ) -> Result<Vec<YahooQuote>, String> {
-    get_stock_quotes_with_client(
-        reqwest::Client::builder()
-            .build()
-            .map_err(|e| e.to_string())?,
-        "https://query1.finance.yahoo.com".to_string(),
-        app_handle,
-        tickers,
-    )
-    .await
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    let settings = quote_cache_settings(&app_handle)?;
+    let registry = Arc::new(ProviderRegistry::for_app(&app_handle)?);
+    get_stock_quotes_with_policy(registry, &db_path, tickers, &settings).await
 }
 
 pub async fn get_stock_quotes_with_client(
@@ -78,124 +138,9 @@ pub async fn get_stock_quotes_with_client(
     app_handle: tauri::AppHandle,
     tickers: Vec<String>,
 ) -> Result<Vec<YahooQuote>, String> {
-    if tickers.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    let mut tasks = Vec::new();
-
-    for ticker in tickers.clone() {
-        let client = client.clone();
-        let base_url = base_url.clone();
-        tasks.push(tokio::spawn(async move {
-            let url = format!("{}/v8/finance/chart/{}?interval=1d&range=1d", base_url, ticker);
-            let res = client.get(&url)
-                .header("User-Agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-                .send()
-                .await;
-
-            match res {
-                Ok(resp) => {
-                    if resp.status().is_success() {
-                        let text_res = resp.text().await;
-                        match text_res {
-                            Ok(text) => {
-                                let json: Result<YahooChartResponse, _> = serde_json::from_str(&text);
-                                match json {
-                                    Ok(data) => {
-                                        if let Some(results) = data.chart.result {
-                                            if let Some(item) = results.first() {
-                                                let price = item.meta.regular_market_price.unwrap_or(0.0);
-                                                let prev = item.meta.chart_previous_close
-                                                    .or(item.meta.previous_close)
-                                                    .unwrap_or(price);
-
-                                                let change_percent = if prev != 0.0 {
-                                                    ((price - prev) / prev) * 100.0
-                                                } else {
-                                                    0.0
-                                                };
-                                                return Some(YahooQuote {
-                                                    symbol: item.meta.symbol.clone(),
-                                                    price,
-                                                    change_percent,
-                                                    currency: item.meta.currency.clone(),
-                                                    quote_type: item.meta.instrument_type.clone(),
-                                                });
-                                            }
-                                        }
-                                    },
-                                    Err(e) => {
-                                        println!("Failed to parse JSON for {}: {}", ticker, e);
-                                    }
-                                }
-                            },
-                            Err(e) => println!("Failed to get text for {}: {}", ticker, e),
-                        }
-                    } else {
-                        println!("Request failed for {}: {}", ticker, resp.status());
-                    }
-                },
-                Err(e) => {
-                    println!("Request error for {}: {}", ticker, e);
-                }
-            }
-            None
-        }));
-    }
-
-    let mut quotes = Vec::new();
-    for task in tasks {
-        if let Ok(Some(quote)) = task.await {
-            quotes.push(quote);
-        }
-    }
-
-    // Update DB with new quotes
     let db_path = crate::db_init::get_db_path(&app_handle)?;
-    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-    {
-        let mut stmt = tx.prepare("INSERT OR REPLACE INTO stock_prices (ticker, price, last_updated) VALUES (?1, ?2, datetime('now'))").map_err(|e| e.to_string())?;
-        for quote in &quotes {
-            stmt.execute(params![quote.symbol, quote.price])
-                .map_err(|e| e.to_string())?;
-        }
-    }
-    tx.commit().map_err(|e| e.to_string())?;
-
-    // If we missed some tickers, try to fetch from DB
-    let found_symbols: Vec<String> = quotes.iter().map(|q| q.symbol.clone()).collect();
-    let missing_tickers: Vec<String> = tickers
-        .into_iter()
-        .filter(|t| !found_symbols.iter().any(|s| s.eq_ignore_ascii_case(t)))
-        .collect();
-
-    if !missing_tickers.is_empty() {
-        let conn = Connection::open(crate::db_init::get_db_path(&app_handle)?)
-            .map_err(|e| e.to_string())?;
-        let mut stmt = conn
-            .prepare("SELECT ticker, price FROM stock_prices WHERE ticker = ?1 COLLATE NOCASE")
-            .map_err(|e| e.to_string())?;
-
-        for ticker in missing_tickers {
-            let res: Result<(String, f64), _> =
-                stmt.query_row(params![ticker], |row| Ok((row.get(0)?, row.get(1)?)));
-
-            if let Ok((symbol, price)) = res {
-                quotes.push(YahooQuote {
-                    symbol,
-                    price,
-                    change_percent: 0.0,
-                    currency: None,
-                    quote_type: None,
-                });
-            }
-        }
-    }
-
-    Ok(quotes)
+    let registry = Arc::new(ProviderRegistry::yahoo(client, &base_url));
+    get_stock_quotes_with_registry(registry, &db_path, tickers).await
 }
 
 pub async fn get_stock_quotes_with_client_and_db(
@@ -203,247 +148,313 @@ pub async fn get_stock_quotes_with_client_and_db(
     base_url: String,
     db_path: &std::path::Path,
     tickers: Vec<String>,
+) -> Result<Vec<YahooQuote>, String> {
+    let registry = Arc::new(ProviderRegistry::yahoo(client, &base_url));
+    get_stock_quotes_with_registry(registry, db_path, tickers).await
+}
+
+/// Fetches each ticker from its provider chain, stores the prices, and falls back to the
+/// last stored price for tickers no provider could quote.
+pub async fn get_stock_quotes_with_registry(
+    registry: Arc<ProviderRegistry>,
+    db_path: &std::path::Path,
+    tickers: Vec<String>,
+) -> Result<Vec<YahooQuote>, String> {
+    get_stock_quotes_with_policy(registry, db_path, tickers, &QuoteCacheSettings::default()).await
+}
+
+fn cached_quote(conn: &Connection, ticker: &str) -> Result<Option<(YahooQuote, f64)>, String> {
+    conn.query_row(
+        "SELECT ticker, price, change_percent, currency, quote_type, last_updated,
+                (julianday('now') - julianday(last_updated)) * 86400
+         FROM stock_prices WHERE ticker = ?1 COLLATE NOCASE",
+        params![ticker],
+        |row| {
+            Ok((
+                YahooQuote {
+                    symbol: row.get(0)?,
+                    price: row.get(1)?,
+                    change_percent: row.get(2)?,
+                    currency: row.get(3)?,
+                    quote_type: row.get(4)?,
+                    last_updated: row.get(5)?,
+                },
+                row.get::<_, Option<f64>>(6)?.unwrap_or(f64::MAX),
+            ))
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Like [`get_stock_quotes_with_registry`], but serves stored quotes younger than the TTL
+/// without a request, and never touches the network in offline mode.
+pub async fn get_stock_quotes_with_policy(
+    registry: Arc<ProviderRegistry>,
+    db_path: &std::path::Path,
+    tickers: Vec<String>,
+    settings: &QuoteCacheSettings,
 ) -> Result<Vec<YahooQuote>, String> {
     if tickers.is_empty() {
         return Ok(Vec::new());
     }
 
-    let mut tasks = Vec::new();
-
-    for ticker in tickers.clone() {
-        let client = client.clone();
-        let base_url = base_url.clone();
-        tasks.push(tokio::spawn(async move {
-            let url = format!("{}/v8/finance/chart/{}?interval=1d&range=1d", base_url.trim_end_matches('/'), ticker);
-            let res = client.get(&url)
-                .header("User-Agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-                .send()
-                .await;
-
-            match res {
-                Ok(resp) => {
-                    if resp.status().is_success() {
-                        let text_res = resp.text().await;
-                        match text_res {
-                            Ok(text) => {
-                                let json: Result<YahooChartResponse, _> = serde_json::from_str(&text);
-                                match json {
-                                    Ok(data) => {
-                                        if let Some(results) = data.chart.result {
-                                            if let Some(item) = results.first() {
-                                                let price = item.meta.regular_market_price.unwrap_or(0.0);
-                                                let prev = item.meta.chart_previous_close
-                                                    .or(item.meta.previous_close)
-                                                    .unwrap_or(price);
-
-                                                let change_percent = if prev != 0.0 {
-                                                    ((price - prev) / prev) * 100.0
-                                                } else {
-                                                    0.0
-                                                };
-                                                return Some(YahooQuote {
-                                                    symbol: item.meta.symbol.clone(),
-                                                    price,
-                                                    change_percent,
-                                                    currency: item.meta.currency.clone(),
-                                                    quote_type: item.meta.instrument_type.clone(),
-                                                });
-                                            }
-                                        }
-                                    },
-                                    Err(e) => {
-                                        println!("Failed to parse JSON for {}: {}", ticker, e);
-                                    }
-                                }
-                            },
-                            Err(e) => println!("Failed to get text for {}: {}", ticker, e),
-                        }
-                    } else {
-                        println!("Request failed for {}: {}", ticker, resp.status());
-                    }
-                },
-                Err(e) => {
-                    println!("Request error for {}: {}", ticker, e);
+    let mut quotes = Vec::new();
+    let mut stale = Vec::new();
+    {
+        let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+        for ticker in tickers {
+            match cached_quote(&conn, &ticker)? {
+                Some((quote, age)) if settings.offline || age < settings.ttl_seconds as f64 => {
+                    quotes.push(quote)
                 }
+                cached => stale.push((ticker, cached.map(|(quote, _)| quote))),
             }
-            None
-        }));
-    }
-
-    let mut quotes = Vec::new();
-    for task in tasks {
-        if let Ok(Some(quote)) = task.await {
-            quotes.push(quote);
         }
     }
+    if settings.offline || stale.is_empty() {
+        quotes.extend(stale.into_iter().filter_map(|(_, cached)| cached));
+        return Ok(quotes);
+    }
+
+    let stale_tickers: Vec<String> = stale.iter().map(|(ticker, _)| ticker.clone()).collect();
+    let mut fetched: Vec<YahooQuote> = crate::http::run_bounded(stale_tickers, |ticker| {
+        let registry = registry.clone();
+        async move { registry.quote(&ticker).await }
+    })
+    .await
+    .into_iter()
+    .filter_map(|quote| quote.ok().flatten())
+    .collect();
 
     // Update DB with new quotes
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
     let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
-
     {
-        let mut stmt = tx.prepare("INSERT OR REPLACE INTO stock_prices (ticker, price, last_updated) VALUES (?1, ?2, datetime('now'))").map_err(|e| e.to_string())?;
-        for quote in &quotes {
-            stmt.execute(params![quote.symbol, quote.price])
-                .map_err(|e| e.to_string())?;
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO stock_prices
+                 (ticker, price, change_percent, currency, quote_type, last_updated)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(|e| e.to_string())?;
+        for quote in &mut fetched {
+            stmt.execute(params![
+                quote.symbol,
+                quote.price,
+                quote.change_percent,
+                quote.currency,
+                quote.quote_type,
+                now
+            ])
+            .map_err(|e| e.to_string())?;
+            quote.last_updated = Some(now.clone());
         }
     }
     tx.commit().map_err(|e| e.to_string())?;
 
-    // If we missed some tickers, try to fetch from DB
-    let found_symbols: Vec<String> = quotes.iter().map(|q| q.symbol.clone()).collect();
-    let missing_tickers: Vec<String> = tickers
-        .into_iter()
-        .filter(|t| !found_symbols.iter().any(|s| s.eq_ignore_ascii_case(t)))
-        .collect();
-
-    if !missing_tickers.is_empty() {
-        let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-        let mut stmt = conn
-            .prepare("SELECT ticker, price FROM stock_prices WHERE ticker = ?1 COLLATE NOCASE")
-            .map_err(|e| e.to_string())?;
-
-        for ticker in missing_tickers {
-            let res: Result<(String, f64), _> =
-                stmt.query_row(params![ticker], |row| Ok((row.get(0)?, row.get(1)?)));
-
-            if let Ok((symbol, price)) = res {
-                quotes.push(YahooQuote {
-                    symbol,
-                    price,
-                    change_percent: 0.0,
-                    currency: None,
-                    quote_type: None,
-                });
-            }
+    // Tickers no provider could quote keep their last stored price
+    for (ticker, cached) in stale {
+        if !fetched
+            .iter()
+            .any(|q| q.symbol.eq_ignore_ascii_case(&ticker))
+        {
+            quotes.extend(cached);
         }
     }
+    quotes.extend(fetched);
 
     Ok(quotes)
 }
 
+/// First timestamp to request when extending a daily history that ends on `last_date`.
+pub(crate) fn history_start_timestamp(last_date: Option<String>) -> Result<i64, String> {
+    if let Some(date_str) = last_date {
+        // Parse date and add 1 day
+        let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d").map_err(|e| e.to_string())?;
+        let next_day = date.succ_opt().ok_or("Invalid date")?;
+        let datetime = next_day.and_hms_opt(0, 0, 0).unwrap();
+        Ok(datetime.and_utc().timestamp())
+    } else {
+        // Default to 10 years ago
+        Ok(Utc::now().timestamp() - 10 * 365 * 24 * 60 * 60)
+    }
+}
+
 pub async fn update_daily_stock_prices_with_client_and_base(
     db_path: &std::path::Path,
     client: &reqwest::Client,
     base_url: &str,
     tickers: Vec<String>,
-) -> Result<(), String> {
-    if tickers.is_empty() {
-        return Ok(());
-    }
+) -> Result<Vec<PriceUpdateResult>, String> {
+    let registry = ProviderRegistry::yahoo(client.clone(), base_url);
+    update_daily_stock_prices_with_registry(db_path, &registry, tickers).await
+}
 
-    for ticker in tickers {
-        // 1. Get last date from DB
-        let last_date_str: Option<String> = {
-            let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
-            conn.query_row(
-                "SELECT MAX(date) FROM daily_stock_prices WHERE ticker = ?1",
-                params![ticker],
-                |row| row.get(0),
-            )
-            .optional()
-            .map_err(|e| e.to_string())?
-            .flatten()
-        };
-
-        let start_timestamp = if let Some(date_str) = last_date_str {
-            // Parse date and add 1 day
-            let date =
-                NaiveDate::parse_from_str(&date_str, "%Y-%m-%d").map_err(|e| e.to_string())?;
-            let next_day = date.succ_opt().ok_or("Invalid date")?;
-            let datetime = next_day.and_hms_opt(0, 0, 0).unwrap();
-            datetime.and_utc().timestamp()
-        } else {
-            // Default to 10 years ago
-            Utc::now().timestamp() - 10 * 365 * 24 * 60 * 60
-        };
-
-        let end_timestamp = Utc::now().timestamp();
-
-        if start_timestamp >= end_timestamp {
-            continue;
-        }
+/// Extends the daily history of each ticker, fetching a few tickers at a time. A ticker
+/// that fails is reported in its own result and does not stop the others.
+pub async fn update_daily_stock_prices_with_registry(
+    db_path: &std::path::Path,
+    registry: &ProviderRegistry,
+    tickers: Vec<String>,
+) -> Result<Vec<PriceUpdateResult>, String> {
+    let end_timestamp = Utc::now().timestamp();
 
-        // 2. Fetch from Yahoo
-        let url = format!(
-            "{}/v8/finance/chart/{}?period1={}&period2={}&interval=1d",
-            base_url, ticker, start_timestamp, end_timestamp
-        );
-
-        let res = client
-            .get(&url)
-            .header("User-Agent", "Mozilla/5.0")
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+    // 1. Find where each stored history ends. A ticker whose dividends and splits were never
+    //    fetched over the full range starts over, so past events are backfilled.
+    let mut pending = Vec::new();
+    {
+        let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+        for ticker in &tickers {
+            let last_date: Option<String> = conn
+                .query_row(
+                    "SELECT MAX(date) FROM daily_stock_prices WHERE ticker = ?1",
+                    params![ticker],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?
+                .flatten();
+            let backfilled: bool = conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM stock_event_backfills WHERE ticker = ?1 COLLATE NOCASE)",
+                    params![ticker],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            let start_timestamp = history_start_timestamp(last_date.filter(|_| backfilled))?;
+            if start_timestamp < end_timestamp {
+                pending.push((ticker.clone(), start_timestamp, !backfilled));
+            }
+        }
+    }
 
-        if !res.status().is_success() {
-            println!("Failed to fetch history for {}: {}", ticker, res.status());
-            continue;
+    // 2. Fetch from the tickers' providers
+    let registry = Arc::new(registry.clone());
+    let requests: Vec<(String, i64)> = pending
+        .iter()
+        .map(|(ticker, start_timestamp, _)| (ticker.clone(), *start_timestamp))
+        .collect();
+    let fetched = crate::http::run_bounded(requests, |(ticker, start_timestamp)| {
+        let registry = registry.clone();
+        async move {
+            registry
+                .price_history(&ticker, start_timestamp, end_timestamp)
+                .await
         }
+    })
+    .await;
 
-        let text = res.text().await.map_err(|e| e.to_string())?;
-        let json: crate::models::YahooChartResponse =
-            serde_json::from_str(&text).map_err(|e| e.to_string())?;
-
-        // 3. Insert into DB
-        if let Some(result) = json.chart.result {
-            if let Some(data) = result.first() {
-                if let (Some(timestamps), Some(indicators)) = (&data.timestamp, &data.indicators) {
-                    if let Some(quotes) = &indicators.quote {
-                        if let Some(quote) = quotes.first() {
-                            if let Some(closes) = &quote.close {
-                                let mut conn =
-                                    Connection::open(db_path).map_err(|e| e.to_string())?;
-                                let tx = conn.transaction().map_err(|e| e.to_string())?;
-                                {
-                                    let mut stmt = tx.prepare(
-                                        "INSERT OR REPLACE INTO daily_stock_prices (ticker, date, price) VALUES (?1, ?2, ?3)"
-                                    )
-                                    .map_err(|e: rusqlite::Error| e.to_string())?;
-
-                                    for (i, ts) in timestamps.iter().enumerate() {
-                                        if let Some(price) = closes.get(i).and_then(|p| *p) {
-                                            let date_str = Utc
-                                                .timestamp_opt(*ts, 0)
-                                                .unwrap()
-                                                .format("%Y-%m-%d")
-                                                .to_string();
-                                            stmt.execute(params![ticker, date_str, price])
-                                                .map_err(|e| e.to_string())?;
-                                        }
-                                    }
-                                }
-                                tx.commit().map_err(|e: rusqlite::Error| e.to_string())?;
-                            }
-                        }
+    // 3. Insert into DB
+    let mut results: Vec<PriceUpdateResult> = tickers
+        .into_iter()
+        .map(|ticker| PriceUpdateResult {
+            ticker,
+            rows: 0,
+            error: None,
+        })
+        .collect();
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    {
+        let mut bar_stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO daily_stock_prices
+                 (ticker, date, price, open, high, low, adj_close, volume)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )
+            .map_err(|e| e.to_string())?;
+        let mut dividend_stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO stock_dividends (ticker, date, amount) VALUES (?1, ?2, ?3)",
+            )
+            .map_err(|e| e.to_string())?;
+        let mut split_stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO stock_splits (ticker, date, numerator, denominator)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )
+            .map_err(|e| e.to_string())?;
+        let mut backfill_stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO stock_event_backfills (ticker, date) VALUES (?1, date('now'))",
+            )
+            .map_err(|e| e.to_string())?;
+        for ((ticker, _, backfill), outcome) in pending.iter().zip(fetched) {
+            let Some(result) = results.iter_mut().find(|r| &r.ticker == ticker) else {
+                continue;
+            };
+            let history = match outcome.and_then(|r| r) {
+                Ok(history) => history.unwrap_or_default(),
+                Err(e) => {
+                    result.error = Some(e);
+                    continue;
+                }
+            };
+            // Splits go first: closes stored before a new split are moved onto its basis,
+            // while the fetched bars already are. A full refetch replaces the stored closes.
+            for (date, numerator, denominator) in &history.splits {
+                if let Some(known) = crate::corporate_actions::matching_split(&tx, ticker, date)? {
+                    if &known != date {
+                        // Already recorded by hand under a nearby date
+                        continue;
                     }
+                } else if !backfill {
+                    crate::corporate_actions::rescale_before_split(
+                        &tx,
+                        ticker,
+                        date,
+                        *numerator,
+                        *denominator,
+                    )?;
                 }
+                split_stmt
+                    .execute(params![ticker, date, numerator, denominator])
+                    .map_err(|e| e.to_string())?;
+            }
+            for bar in &history.bars {
+                bar_stmt
+                    .execute(params![
+                        ticker,
+                        bar.date,
+                        bar.close,
+                        bar.open,
+                        bar.high,
+                        bar.low,
+                        bar.adj_close,
+                        bar.volume
+                    ])
+                    .map_err(|e| e.to_string())?;
+                result.rows += 1;
+            }
+            for (date, amount) in &history.dividends {
+                dividend_stmt
+                    .execute(params![ticker, date, amount])
+                    .map_err(|e| e.to_string())?;
+            }
+            if *backfill {
+                backfill_stmt
+                    .execute(params![ticker])
+                    .map_err(|e| e.to_string())?;
             }
         }
     }
+    tx.commit().map_err(|e| e.to_string())?;
+    crate::holdings::invalidate_holdings_cache(db_path);
 
-    Ok(())
+    Ok(results)
 }
 
 #[tauri::command]
 pub async fn update_daily_stock_prices(
     app_handle: tauri::AppHandle,
     tickers: Vec<String>,
-) -> Result<(), String> {
-    // Allow overriding base URL via env var for testing
-    let base_url = std::env::var("YAHOO_BASE_URL")
-        .unwrap_or_else(|_| "https://query1.finance.yahoo.com".to_string());
+) -> Result<Vec<PriceUpdateResult>, String> {
     let db_path = crate::db_init::get_db_path(&app_handle)?;
-
-    let client = reqwest::Client::new();
-    update_daily_stock_prices_with_client_and_base(
-        std::path::Path::new(&db_path),
-        &client,
-        &base_url,
-        tickers,
-    )
-    .await
+    let registry = ProviderRegistry::for_app(&app_handle)?;
+    update_daily_stock_prices_with_registry(std::path::Path::new(&db_path), &registry, tickers)
+        .await
 }
 
 pub fn get_daily_stock_prices_from_path(
@@ -470,6 +481,48 @@ pub fn get_daily_stock_prices_from_path(
     Ok(prices)
 }
 
+/// Full daily bars; days stored before OHLCV was kept only have a close.
+pub fn get_daily_stock_bars_db(
+    db_path: &std::path::Path,
+    ticker: String,
+) -> Result<Vec<DailyBar>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT date, open, high, low, price, adj_close, volume FROM daily_stock_prices
+             WHERE ticker = ?1 ORDER BY date ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let bars = stmt
+        .query_map(params![ticker], |row| {
+            Ok(DailyBar {
+                date: row.get(0)?,
+                open: row.get(1)?,
+                high: row.get(2)?,
+                low: row.get(3)?,
+                close: row.get(4)?,
+                adj_close: row.get(5)?,
+                volume: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(bars)
+}
+
+#[tauri::command]
+pub fn get_daily_stock_bars(
+    app_handle: tauri::AppHandle,
+    ticker: String,
+) -> Result<Vec<DailyBar>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_daily_stock_bars_db(&db_path, ticker)
+}
+
 #[tauri::command]
 pub fn get_daily_stock_prices(
     app_handle: tauri::AppHandle,
@@ -489,17 +542,7 @@ pub async fn check_currency_availability(
     }
 
     let ticker = format!("{}USD=X", currency);
-    let client = reqwest::Client::builder()
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    let quotes = get_stock_quotes_with_client(
-        client,
-        "https://query1.finance.yahoo.com".to_string(),
-        app_handle,
-        vec![ticker],
-    )
-    .await?;
+    let quotes = get_stock_quotes(app_handle, vec![ticker]).await?;
 
     Ok(!quotes.is_empty())
 }
@@ -1,9 +1,29 @@
 pub mod accounts;
+pub mod allocation;
+pub mod anomalies;
+pub mod capital_gains;
+pub mod cash_flow;
+pub mod corporate_actions;
 pub mod db_init;
+pub mod ecb;
+pub mod forecast;
+pub mod fx;
+pub mod holdings;
+pub mod http;
+pub mod investment_actions;
+pub mod lots;
 pub mod markets;
 pub mod models;
+pub mod net_worth;
+pub mod performance;
+pub mod providers;
+pub mod reconciliation;
+pub mod reports;
 pub mod rules;
+pub mod scheduled;
+pub mod subscriptions;
 pub mod transactions;
+pub mod transfers;
 pub mod utils;
 
 #[cfg(test)]
@@ -10,6 +10,9 @@ pub struct YahooQuote {
     pub currency: Option<String>,
     #[serde(rename = "quoteType")]
     pub quote_type: Option<String>,
+    /// When the price was fetched (UTC, `YYYY-MM-DD HH:MM:SS`).
+    #[serde(default, rename = "lastUpdated")]
+    pub last_updated: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -28,12 +31,49 @@ pub struct YahooChartMeta {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct YahooChartQuote {
+    #[serde(default)]
+    pub open: Option<Vec<Option<f64>>>,
+    #[serde(default)]
+    pub high: Option<Vec<Option<f64>>>,
+    #[serde(default)]
+    pub low: Option<Vec<Option<f64>>>,
     pub close: Option<Vec<Option<f64>>>,
+    #[serde(default)]
+    pub volume: Option<Vec<Option<f64>>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct YahooChartAdjClose {
+    pub adjclose: Option<Vec<Option<f64>>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct YahooChartIndicators {
     pub quote: Option<Vec<YahooChartQuote>>,
+    #[serde(default)]
+    pub adjclose: Option<Vec<YahooChartAdjClose>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct YahooDividendEvent {
+    pub amount: f64,
+    pub date: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct YahooSplitEvent {
+    pub date: i64,
+    pub numerator: f64,
+    pub denominator: f64,
+}
+
+/// Chart events keyed by timestamp, as Yahoo returns them.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct YahooChartEvents {
+    #[serde(default)]
+    pub dividends: Option<std::collections::HashMap<String, YahooDividendEvent>>,
+    #[serde(default)]
+    pub splits: Option<std::collections::HashMap<String, YahooSplitEvent>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -41,6 +81,8 @@ pub struct YahooChartResult {
     pub meta: YahooChartMeta,
     pub timestamp: Option<Vec<i64>>,
     pub indicators: Option<YahooChartIndicators>,
+    #[serde(default)]
+    pub events: Option<YahooChartEvents>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -69,20 +111,92 @@ pub struct YahooSearchResponse {
     pub quotes: Vec<YahooSearchQuote>,
 }
 
+/// A configured market data source. `kind` is `yahoo` or `http`; paths and `format`
+/// only apply to the generic HTTP provider.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MarketDataProviderConfig {
+    #[serde(default)]
+    pub id: i32,
+    pub name: String,
+    pub kind: String,
+    pub base_url: String,
+    #[serde(default = "default_provider_format")]
+    pub format: String,
+    #[serde(default)]
+    pub quote_path: Option<String>,
+    #[serde(default)]
+    pub history_path: Option<String>,
+    #[serde(default)]
+    pub search_path: Option<String>,
+    /// Symbol template for USD rates, e.g. `{currency}USD`
+    #[serde(default)]
+    pub fx_ticker: Option<String>,
+    /// Lower values are tried first
+    #[serde(default)]
+    pub priority: i32,
+}
+
+pub fn default_provider_format() -> String {
+    "json".to_string()
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Account {
     pub id: i32,
     pub name: String,
     pub balance: f64,
     pub currency: Option<String>,
+    #[serde(default = "default_account_kind")]
+    pub kind: String, // cash, credit_card, loan, asset, liability, brokerage
     #[serde(default = "default_exchange_rate")]
     pub exchange_rate: f64,
+    /// Latest manual valuation, only set for `asset` accounts
+    #[serde(default)]
+    pub valuation: Option<f64>,
+    /// Market value of held shares in the account's currency, only set for `brokerage`
+    /// accounts. `balance` stays the cash side of the account.
+    #[serde(default)]
+    pub holdings_value: Option<f64>,
+    /// Archived accounts are hidden from pickers and net worth but keep their history
+    #[serde(default)]
+    pub archived: bool,
 }
 
 pub fn default_exchange_rate() -> f64 {
     1.0
 }
 
+pub fn default_account_kind() -> String {
+    "cash".to_string()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountValuation {
+    pub id: i32,
+    pub account_id: i32,
+    pub date: String,
+    pub value: f64,
+}
+
+/// Custom rate (USD per unit) for a currency between two dates; open ends are unbounded
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CustomExchangeRateRange {
+    pub id: i32,
+    pub currency: String,
+    pub rate: f64,
+    pub valid_from: Option<String>,
+    pub valid_to: Option<String>,
+}
+
+/// Net worth split into what is owned and what is owed, in the target currency.
+/// `liabilities` is reported as a positive amount owed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NetWorthSummary {
+    pub assets: f64,
+    pub liabilities: f64,
+    pub net_worth: f64,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Transaction {
     pub id: i32,
@@ -97,13 +211,82 @@ pub struct Transaction {
     pub price_per_share: Option<f64>,
     pub fee: Option<f64>,
     pub currency: Option<String>,
+    #[serde(default = "default_cleared_state")]
+    pub cleared: String, // uncleared, cleared, reconciled
+    /// Set on both legs of a transfer between accounts
+    #[serde(default)]
+    pub transfer_id: Option<i32>,
+    /// Investment action for brokerage rows, one of `INVESTMENT_ACTIONS`
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+pub fn default_cleared_state() -> String {
+    "uncleared".to_string()
+}
+
+/// Both legs of a transfer between two accounts
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Transfer {
+    pub id: i32,
+    pub fee: f64,
+    /// Units of the destination currency received per unit of the source currency
+    #[serde(default = "default_exchange_rate")]
+    pub exchange_rate: f64,
+    /// Set when no rate was known and the legs were recorded at par
+    #[serde(default)]
+    pub rate_estimated: bool,
+    pub from: Transaction,
+    pub to: Transaction,
+}
+
+/// Realized exchange result of a cross-currency transfer, in the destination currency
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransferFxResult {
+    pub transfer_id: i32,
+    pub date: String,
+    pub from_currency: String,
+    pub to_currency: String,
+    pub amount_from: f64,
+    pub amount_to: f64,
+    pub effective_rate: f64,
+    /// Market rate on the transfer date, if a quote was available
+    pub market_rate: Option<f64>,
+    /// Amount received minus what the market rate would have given. Not computed while the
+    /// transfer's rate is only estimated.
+    pub fx_gain_loss: Option<f64>,
+    /// Set when the legs were recorded at par because no rate was known
+    #[serde(default)]
+    pub rate_estimated: bool,
+}
+
+/// Outcome of comparing a bank statement against the cleared transactions of an account
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReconciliationStatus {
+    pub account_id: i32,
+    pub statement_date: String,
+    pub statement_balance: f64,
+    pub cleared_balance: f64,
+    pub difference: f64,
+    pub cleared_count: i64,
+}
+
+/// A finished reconciliation kept as history
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Reconciliation {
+    pub id: i32,
+    pub account_id: i32,
+    pub statement_date: String,
+    pub statement_balance: f64,
+    pub transaction_count: i64,
+    pub reconciled_at: String,
 }
 
 /// A single condition within a rule
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct RuleCondition {
     pub field: String,
-    pub operator: String, // equals, contains, starts_with, ends_with, greater_than, less_than
+    pub operator: String, // one of `rules::RULE_OPERATORS`
     pub value: String,
     #[serde(default)]
     pub negated: bool, // NOT operator
@@ -143,6 +326,580 @@ pub struct AccountsSummary {
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct AppSettings {
     pub db_path: Option<String>,
+    #[serde(default)]
+    pub fx_source: Option<String>,
+    /// One of `fx::RATE_MODES`, `current` when missing
+    #[serde(default)]
+    pub rate_mode: Option<String>,
+    #[serde(default)]
+    pub quote_ttl_seconds: Option<u64>,
+    #[serde(default)]
+    pub offline_mode: Option<bool>,
+}
+
+/// A TTL of zero always refetches; offline serves only what is stored.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct QuoteCacheSettings {
+    pub ttl_seconds: u64,
+    pub offline: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EcbImport {
+    pub days: usize,
+    pub currencies: Vec<String>,
+    pub latest_date: Option<String>,
+}
+
+/// Outcome of a batch history update for one ticker (or currency).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PriceUpdateResult {
+    pub ticker: String,
+    pub rows: usize,
+    pub error: Option<String>,
+}
+
+/// One trading day. `close` is always present; the rest depend on the provider.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DailyBar {
+    pub date: String,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub close: f64,
+    pub adj_close: Option<f64>,
+    pub volume: Option<f64>,
+}
+
+/// Cash dividend per share, keyed by ex-date.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StockDividend {
+    pub ticker: String,
+    pub date: String,
+    pub amount: f64,
+}
+
+/// `numerator` new shares for every `denominator` old ones, effective on `date`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StockSplit {
+    pub ticker: String,
+    pub date: String,
+    pub numerator: f64,
+    pub denominator: f64,
+}
+
+/// A dividend the account should have received but that has no matching transaction.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DividendSuggestion {
+    pub account_id: i32,
+    pub ticker: String,
+    pub date: String,
+    pub amount_per_share: f64,
+    pub shares: f64,
+    pub amount: f64,
+}
+
+/// One ticker held across the requested accounts. `price` is in the ticker's own
+/// `currency`; the amounts are in the report's base currency, with cost converted at the
+/// rates on the acquisition dates.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Holding {
+    pub ticker: String,
+    pub account_ids: Vec<i32>,
+    pub shares: f64,
+    pub currency: String,
+    pub price: f64,
+    pub cost_basis: f64,
+    pub market_value: f64,
+    pub day_change: f64,
+    pub day_change_percent: f64,
+    pub unrealized_gain: f64,
+    pub unrealized_gain_percent: f64,
+    pub quote_type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HoldingsReport {
+    pub base_currency: String,
+    pub as_of: Option<String>,
+    pub holdings: Vec<Holding>,
+    pub total_value: f64,
+    pub total_cost_basis: f64,
+    pub total_day_change: f64,
+    pub total_unrealized_gain: f64,
+}
+
+/// How a ticker counts towards allocations. `is_default` is set when no class was chosen
+/// and `asset_class` comes from the quote type.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TickerClassification {
+    pub ticker: String,
+    pub asset_class: String,
+    pub region: Option<String>,
+    pub sector: Option<String>,
+    pub is_default: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AllocationTarget {
+    pub asset_class: String,
+    pub target_percent: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Portfolio {
+    pub name: String,
+    pub account_ids: Vec<i32>,
+    pub targets: Vec<AllocationTarget>,
+}
+
+/// Where an asset class stands against its target. `drift` is in percentage points of the
+/// current value, positive when overweight.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AllocationLine {
+    pub asset_class: String,
+    pub current_value: f64,
+    pub current_percent: f64,
+    pub target_percent: f64,
+    pub target_value: f64,
+    pub drift: f64,
+}
+
+/// A whole-share trade towards the targets. `price` and `amount` are in the plan's base
+/// currency.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RebalanceTrade {
+    pub ticker: String,
+    pub asset_class: String,
+    pub action: String,
+    pub shares: f64,
+    pub price: f64,
+    pub amount: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RebalancePlan {
+    pub portfolio: String,
+    pub base_currency: String,
+    pub contribution: f64,
+    pub total_value: f64,
+    pub allocations: Vec<AllocationLine>,
+    pub trades: Vec<RebalanceTrade>,
+    pub cash_left: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetWorthAccount {
+    pub account_id: i32,
+    pub name: String,
+    pub kind: String,
+    pub currency: String,
+    pub is_liability: bool,
+}
+
+/// Net worth at the end of `date`. `balances` follows the order of the series' accounts and
+/// holds each one's value in the base currency, negative for amounts owed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetWorthPoint {
+    pub date: String,
+    pub assets: f64,
+    pub liabilities: f64,
+    pub net_worth: f64,
+    pub balances: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetWorthSeries {
+    pub from: String,
+    pub to: String,
+    pub interval: String,
+    pub base_currency: String,
+    pub accounts: Vec<NetWorthAccount>,
+    pub points: Vec<NetWorthPoint>,
+}
+
+/// Change from an earlier month. Percentages are `None` when the earlier figure was zero.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PeriodChange {
+    pub compared_to: String,
+    pub income: f64,
+    pub expense: f64,
+    pub net: f64,
+    pub income_percent: Option<f64>,
+    pub expense_percent: Option<f64>,
+}
+
+/// Income and spending of one `YYYY-MM` month. Expenses are positive amounts and the
+/// savings rate is the net as a percentage of income.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IncomeExpenseMonth {
+    pub month: String,
+    pub income: f64,
+    pub expense: f64,
+    pub net: f64,
+    pub savings_rate: Option<f64>,
+    pub month_over_month: Option<PeriodChange>,
+    pub year_over_year: Option<PeriodChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IncomeExpenseReport {
+    pub from: String,
+    pub to: String,
+    pub base_currency: String,
+    pub months: Vec<IncomeExpenseMonth>,
+    pub income: f64,
+    pub expense: f64,
+    pub net: f64,
+    pub savings_rate: Option<f64>,
+}
+
+/// Spending in one category, with `monthly` following the report's months.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryTrend {
+    pub category: String,
+    pub monthly: Vec<f64>,
+    pub total: f64,
+    pub average: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryTrendReport {
+    pub from: String,
+    pub to: String,
+    pub base_currency: String,
+    pub months: Vec<String>,
+    pub categories: Vec<CategoryTrend>,
+}
+
+/// Spending at one payee; `share` is its percentage of all spending in the range.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PayeeTotal {
+    pub payee: String,
+    pub total: f64,
+    pub count: i32,
+    pub share: f64,
+}
+
+/// A bill or paycheck that repeats at `frequency` from `next_date`, until `end_date` if set.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScheduledTransaction {
+    pub id: i32,
+    pub account_id: i32,
+    pub payee: String,
+    pub category: Option<String>,
+    pub amount: f64,
+    pub frequency: String,
+    pub next_date: String,
+    pub end_date: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Projected end-of-day balance; `low` and `high` bound the 90% confidence band.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ForecastPoint {
+    pub date: String,
+    pub balance: f64,
+    pub low: f64,
+    pub high: f64,
+    pub below_threshold: bool,
+}
+
+/// Forecast of one account in its own currency. `daily_spending` is the average unscheduled
+/// spending per day over the lookback months.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountForecast {
+    pub account_id: i32,
+    pub name: String,
+    pub currency: String,
+    pub start_balance: f64,
+    pub threshold: Option<f64>,
+    pub daily_spending: f64,
+    pub first_below_threshold: Option<String>,
+    pub points: Vec<ForecastPoint>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BalanceForecast {
+    pub as_of: String,
+    pub days: u32,
+    pub lookback_months: u32,
+    pub accounts: Vec<AccountForecast>,
+}
+
+/// A charge that differs from the one before it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PriceChange {
+    pub date: String,
+    pub old_amount: f64,
+    pub new_amount: f64,
+    pub percent: f64,
+}
+
+/// Charges to one payee repeating at a regular cadence. Amounts are positive and in
+/// `currency`; `status` is one of `SUBSCRIPTION_STATUSES`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Subscription {
+    pub payee: String,
+    pub normalized_payee: String,
+    pub account_id: i32,
+    pub category: Option<String>,
+    pub currency: String,
+    pub cadence: String,
+    pub charges: i32,
+    pub first_charge: String,
+    pub last_charge: String,
+    pub last_amount: f64,
+    pub average_amount: f64,
+    pub next_charge: String,
+    pub annualized_cost: f64,
+    pub price_changes: Vec<PriceChange>,
+    pub status: String,
+}
+
+/// An unusual transaction or month of spending. `kind` is one of `ANOMALY_KINDS`;
+/// `date` is the month (`YYYY-MM`) for category spikes, which have no transaction.
+/// Amounts are in the report's base currency and `baseline` is what they were compared with.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Anomaly {
+    pub kind: String,
+    pub transaction_id: Option<i32>,
+    pub account_id: Option<i32>,
+    pub date: String,
+    pub payee: Option<String>,
+    pub category: Option<String>,
+    pub amount: f64,
+    pub baseline: Option<f64>,
+    pub explanation: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AnomalyReport {
+    pub from: String,
+    pub to: String,
+    pub base_currency: String,
+    pub anomalies: Vec<Anomaly>,
+}
+
+/// A node of the cash-flow graph. `kind` is one of `CASH_FLOW_NODE_KINDS` and `value` the
+/// larger of what flows in and out of it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CashFlowNode {
+    pub id: String,
+    pub label: String,
+    pub kind: String,
+    pub value: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CashFlowLink {
+    pub source: String,
+    pub target: String,
+    pub value: f64,
+}
+
+/// Sankey data for a range. `investments` is the net moved into brokerage accounts and
+/// `savings` what is left of income after spending and investing; either is negative when
+/// money came the other way.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CashFlowGraph {
+    pub from: String,
+    pub to: String,
+    pub base_currency: String,
+    pub depth: u32,
+    pub nodes: Vec<CashFlowNode>,
+    pub links: Vec<CashFlowLink>,
+    pub income: f64,
+    pub expense: f64,
+    pub investments: f64,
+    pub savings: f64,
+}
+
+/// A field a rule changed on a transaction; `rule_id` is the rule whose value was kept.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RuleFieldChange {
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub rule_id: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionRuleDiff {
+    pub transaction_id: i32,
+    pub account_id: i32,
+    pub date: String,
+    pub payee: String,
+    pub changes: Vec<RuleFieldChange>,
+}
+
+/// Outcome of running rules over existing transactions. `skipped_reconciled` counts
+/// reconciled transactions the rules would have changed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RuleApplication {
+    pub dry_run: bool,
+    pub examined: i32,
+    pub skipped_reconciled: i32,
+    pub transactions: Vec<TransactionRuleDiff>,
+}
+
+/// Shares acquired together and still held, in the split basis of the report date.
+/// `lot_id` is the acquiring transaction; lots moved between accounts or split off
+/// from a parent keep the original one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TaxLot {
+    pub account_id: i32,
+    pub ticker: String,
+    pub lot_id: i32,
+    pub acquired_date: String,
+    pub shares: f64,
+    pub cost_basis: f64,
+    pub cost_per_share: f64,
+    pub currency: String,
+}
+
+/// The part of one sale matched against one lot. `term` is "short", "long", or
+/// "unknown" for shares sold beyond what the account held on record.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RealizedGain {
+    pub account_id: i32,
+    pub ticker: String,
+    pub sell_tx_id: i32,
+    pub lot_id: Option<i32>,
+    pub acquired_date: Option<String>,
+    pub disposed_date: String,
+    pub shares: f64,
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    pub fees: f64,
+    pub gain: f64,
+    pub term: String,
+    pub currency: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LotReport {
+    pub open_lots: Vec<TaxLot>,
+    pub realized: Vec<RealizedGain>,
+}
+
+/// One disposal in a capital gains report, in the report's base currency. Proceeds and fees
+/// use the rate on the sale date, the cost basis the rate on the acquisition date.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CapitalGainLine {
+    pub account_id: i32,
+    pub account_name: String,
+    pub ticker: String,
+    pub sell_tx_id: i32,
+    pub shares: f64,
+    pub acquired_date: Option<String>,
+    pub disposed_date: String,
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    pub fees: f64,
+    pub gain: f64,
+    pub term: String,
+    pub currency: String,
+}
+
+/// A lot still held at the end of the tax year, valued at the last close on or before it.
+/// Value and gain are missing when no price is stored for the ticker.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnrealizedGainLine {
+    pub account_id: i32,
+    pub account_name: String,
+    pub ticker: String,
+    pub lot_id: i32,
+    pub acquired_date: String,
+    pub shares: f64,
+    pub cost_basis: f64,
+    pub market_value: Option<f64>,
+    pub gain: Option<f64>,
+    pub term: String,
+    pub currency: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CapitalGainTotals {
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    pub fees: f64,
+    pub gain: f64,
+}
+
+/// Realized gains for a tax year plus the unrealized position at its end. Shares sold
+/// without a recorded purchase have term `unknown` and no cost basis; they are totalled in
+/// `unknown_term` only, not in the short-term, long-term or overall totals.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CapitalGainsReport {
+    pub year: i32,
+    pub base_currency: String,
+    pub realized: Vec<CapitalGainLine>,
+    pub short_term: CapitalGainTotals,
+    pub long_term: CapitalGainTotals,
+    pub unknown_term: CapitalGainTotals,
+    pub total: CapitalGainTotals,
+    pub unrealized: Vec<UnrealizedGainLine>,
+}
+
+/// Returns over a period. `time_weighted_return` chains daily returns so contributions
+/// do not distort it; `money_weighted_return` is the annual internal rate of return of the
+/// cash flows (XIRR). Returns are fractions (0.05 is 5%); the annualized TWR is only given
+/// for periods of a year or more.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PerformanceMetrics {
+    pub start_value: f64,
+    pub end_value: f64,
+    pub net_contributions: f64,
+    pub income: f64,
+    pub gain: f64,
+    pub time_weighted_return: Option<f64>,
+    pub annualized_return: Option<f64>,
+    pub money_weighted_return: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HoldingPerformance {
+    pub account_id: i32,
+    pub ticker: String,
+    pub metrics: PerformanceMetrics,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountPerformance {
+    pub account_id: i32,
+    pub account_name: String,
+    pub metrics: PerformanceMetrics,
+}
+
+/// Price return of the benchmark over the same dates, and how far the portfolio's
+/// time-weighted return was above it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchmarkPerformance {
+    pub ticker: String,
+    pub total_return: Option<f64>,
+    pub annualized_return: Option<f64>,
+    pub excess_return: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PerformanceReport {
+    pub period: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub base_currency: String,
+    pub portfolio: PerformanceMetrics,
+    pub accounts: Vec<AccountPerformance>,
+    pub holdings: Vec<HoldingPerformance>,
+    pub benchmark: Option<BenchmarkPerformance>,
+}
+
+/// Shares of a lot picked for a sale under specific identification, in the split
+/// basis of the sale date.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LotSelection {
+    pub lot_id: i32,
+    pub shares: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
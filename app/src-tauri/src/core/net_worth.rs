@@ -0,0 +1,318 @@
+use crate::accounts::is_liability_kind;
+use crate::corporate_actions::{load_split_ratios, split_factor};
+use crate::fx::{missing_rate, rate_date, rate_on, report_rate_mode, requested_rate_mode};
+use crate::models::{NetWorthAccount, NetWorthPoint, NetWorthSeries};
+use crate::performance::{load_prices, load_quote_currencies, parse_date, FxCache, TradePrices};
+use crate::transfers::round_cents;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use rusqlite::Connection;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Spacing of the points in a net worth series. Weekly points fall on Sundays and monthly
+/// ones on month ends, with a last point on the end date.
+pub const NET_WORTH_INTERVALS: [&str; 3] = ["daily", "weekly", "monthly"];
+
+struct AccountHistory {
+    account: NetWorthAccount,
+    // Changes to the cash balance in the account currency, by date
+    cash: Vec<(NaiveDate, f64)>,
+    valuations: Vec<(NaiveDate, f64)>,
+    // Share changes per (ticker, quote currency), in today's split basis
+    positions: BTreeMap<(String, String), Vec<(NaiveDate, f64)>>,
+}
+
+// Running total over changes sorted by date, moved forward one point at a time
+#[derive(Default, Clone)]
+struct Cursor {
+    next: usize,
+    total: f64,
+    latest: Option<f64>,
+}
+
+impl Cursor {
+    // Sum of the changes up to and including `date`, and the last of them
+    fn advance(&mut self, changes: &[(NaiveDate, f64)], date: NaiveDate) -> f64 {
+        while self.next < changes.len() && changes[self.next].0 <= date {
+            self.total += changes[self.next].1;
+            self.latest = Some(changes[self.next].1);
+            self.next += 1;
+        }
+        self.total
+    }
+}
+
+fn sample_dates(from: NaiveDate, to: NaiveDate, interval: &str) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    let mut date = match interval {
+        "daily" => from,
+        "weekly" => from + Days::new(u64::from(6 - from.weekday().num_days_from_monday())),
+        _ => (from.with_day(1).unwrap_or(from) + Months::new(1)) - Days::new(1),
+    };
+    while date < to {
+        dates.push(date);
+        date = match interval {
+            "daily" => date + Days::new(1),
+            "weekly" => date + Days::new(7),
+            _ => (date + Days::new(1) + Months::new(1)) - Days::new(1),
+        };
+    }
+    dates.push(to);
+    dates
+}
+
+fn load_accounts(
+    conn: &Connection,
+    account_ids: &Option<Vec<i32>>,
+    base: &str,
+) -> Result<Vec<AccountHistory>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, COALESCE(kind, 'cash'), currency, COALESCE(archived, 0)
+             FROM accounts ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, bool>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut accounts = Vec::new();
+    for row in rows {
+        let (id, name, kind, currency, archived) = row.map_err(|e| e.to_string())?;
+        // Archived accounts are left out of net worth unless asked for by id
+        let wanted = match account_ids {
+            Some(ids) => ids.contains(&id),
+            None => !archived,
+        };
+        if !wanted {
+            continue;
+        }
+        accounts.push(AccountHistory {
+            account: NetWorthAccount {
+                account_id: id,
+                name,
+                is_liability: is_liability_kind(&kind),
+                kind,
+                currency: currency.unwrap_or_else(|| base.to_string()),
+            },
+            cash: Vec::new(),
+            valuations: Vec::new(),
+            positions: BTreeMap::new(),
+        });
+    }
+    Ok(accounts)
+}
+
+/// Net worth from `from` (the first transaction when `None`) to `to` (today when `None`) at
+/// the given interval, per account and split into assets and liabilities, in `base_currency`
+/// (USD when `None`). Balances come from the transactions, asset accounts from their latest
+/// valuation and brokerage holdings from the stored closes in their quote currency; closes,
+/// valuations and exchange rates carry forward over days without one, and an amount with no
+/// rate at all is an error. `rate_mode` is one of `fx::RATE_MODES`, each day's own rate when
+/// `None`.
+pub fn net_worth_series_db(
+    db_path: &PathBuf,
+    from: Option<String>,
+    to: Option<String>,
+    interval: String,
+    account_ids: Option<Vec<i32>>,
+    base_currency: Option<String>,
+    rate_mode: Option<String>,
+) -> Result<NetWorthSeries, String> {
+    if !NET_WORTH_INTERVALS.contains(&interval.as_str()) {
+        return Err(format!("Unknown interval: {}", interval));
+    }
+    let mode = report_rate_mode(rate_mode)?;
+    let base = base_currency
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "USD".to_string());
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut accounts = load_accounts(&conn, &account_ids, &base)?;
+    let index: HashMap<i32, usize> = accounts
+        .iter()
+        .enumerate()
+        .map(|(i, a)| (a.account.account_id, i))
+        .collect();
+
+    let ratios = load_split_ratios(&conn)?;
+    let quotes = load_quote_currencies(&conn)?;
+    let mut trade_prices = TradePrices::new();
+    let mut first_date: Option<NaiveDate> = None;
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT account_id, date, amount, currency, UPPER(ticker), shares, price_per_share
+                 FROM transactions ORDER BY date, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<f64>>(5)?,
+                    row.get::<_, Option<f64>>(6)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (account_id, day, amount, currency, ticker, shares, price) =
+                row.map_err(|e| e.to_string())?;
+            let Some(history) = index.get(&account_id).map(|i| &mut accounts[*i]) else {
+                continue;
+            };
+            let date = parse_date(&day)?;
+            first_date = Some(first_date.map_or(date, |d| d.min(date)));
+
+            // Amounts in another currency count at the rate of their own date
+            let currency = currency.unwrap_or_else(|| history.account.currency.clone());
+            let on = rate_date(&mode, &day);
+            let rate = rate_on(&conn, &currency, &history.account.currency, on)
+                .ok_or_else(|| missing_rate(&currency, &history.account.currency, on))?;
+            history.cash.push((date, amount * rate));
+
+            if let (Some(ticker), Some(shares)) = (ticker.filter(|t| !t.is_empty()), shares) {
+                let factor = split_factor(&ratios, &ticker, &day, None);
+                let prices = trade_prices.entry(ticker.clone()).or_default();
+                if let Some(price) = price.filter(|p| *p > 0.0) {
+                    prices.insert(date, price / factor);
+                }
+                // Closes are in the quote currency, which trades may not be in
+                let currency = quotes.get(&ticker).cloned().unwrap_or(currency);
+                history
+                    .positions
+                    .entry((ticker, currency))
+                    .or_default()
+                    .push((date, shares * factor));
+            }
+        }
+    }
+    {
+        let mut stmt = conn
+            .prepare("SELECT account_id, date, value FROM account_valuations ORDER BY date, id")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (account_id, day, value) = row.map_err(|e| e.to_string())?;
+            if let Some(i) = index.get(&account_id) {
+                let date = parse_date(&day)?;
+                first_date = Some(first_date.map_or(date, |d| d.min(date)));
+                accounts[*i].valuations.push((date, value));
+            }
+        }
+    }
+    let prices = load_prices(&conn, trade_prices)?;
+
+    let to = match &to {
+        Some(day) => parse_date(day)?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let from = match &from {
+        Some(day) => parse_date(day)?,
+        None => first_date.unwrap_or(to).min(to),
+    };
+    if from > to {
+        return Err("Start date is after end date".to_string());
+    }
+
+    let mut fx = FxCache::new(&conn, &base, &mode);
+    let mut cash_cursors = vec![Cursor::default(); accounts.len()];
+    let mut valuation_cursors = vec![Cursor::default(); accounts.len()];
+    let mut position_cursors: Vec<Vec<Cursor>> = accounts
+        .iter()
+        .map(|h| vec![Cursor::default(); h.positions.len()])
+        .collect();
+    let mut points = Vec::new();
+    for date in sample_dates(from, to, &interval) {
+        let mut point = NetWorthPoint {
+            date: date.format("%Y-%m-%d").to_string(),
+            assets: 0.0,
+            liabilities: 0.0,
+            net_worth: 0.0,
+            balances: Vec::with_capacity(accounts.len()),
+        };
+        for (i, history) in accounts.iter().enumerate() {
+            let cash = cash_cursors[i].advance(&history.cash, date);
+            valuation_cursors[i].advance(&history.valuations, date);
+            let cash = match valuation_cursors[i].latest {
+                Some(value) if history.account.kind == "asset" => value,
+                _ => cash,
+            };
+            let mut value = if cash.abs() > 1e-9 {
+                cash * fx.rate(&history.account.currency, date)?
+            } else {
+                0.0
+            };
+            let cursors = position_cursors[i].iter_mut();
+            for (((ticker, currency), changes), cursor) in history.positions.iter().zip(cursors) {
+                let shares = cursor.advance(changes, date);
+                if shares.abs() > 1e-9 {
+                    value += shares * prices.on(ticker, date) * fx.rate(currency, date)?;
+                }
+            }
+            if history.account.is_liability {
+                point.liabilities -= value;
+            } else {
+                point.assets += value;
+            }
+            point.balances.push(round_cents(value));
+        }
+        point.assets = round_cents(point.assets);
+        point.liabilities = round_cents(point.liabilities);
+        point.net_worth = round_cents(point.assets - point.liabilities);
+        points.push(point);
+    }
+
+    Ok(NetWorthSeries {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        interval,
+        base_currency: base,
+        accounts: accounts.into_iter().map(|h| h.account).collect(),
+        points,
+    })
+}
+
+#[tauri::command]
+pub fn net_worth_series(
+    app_handle: AppHandle,
+    from: Option<String>,
+    to: Option<String>,
+    interval: String,
+    account_ids: Option<Vec<i32>>,
+    base_currency: Option<String>,
+    rate_mode: Option<String>,
+) -> Result<NetWorthSeries, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    let rate_mode = requested_rate_mode(&app_handle, rate_mode)?;
+    net_worth_series_db(
+        &db_path,
+        from,
+        to,
+        interval,
+        account_ids,
+        base_currency,
+        rate_mode,
+    )
+}
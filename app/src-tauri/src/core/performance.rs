@@ -0,0 +1,601 @@
+use crate::corporate_actions::{load_split_ratios, split_factor};
+use crate::fx::{missing_rate, rate_date, rate_on, report_rate_mode, requested_rate_mode};
+use crate::models::{
+    AccountPerformance, BenchmarkPerformance, HoldingPerformance, PerformanceMetrics,
+    PerformanceReport,
+};
+use crate::transfers::round_cents;
+use chrono::{Datelike, Months, NaiveDate};
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Periods a performance report can cover, ending on the report date.
+pub const PERFORMANCE_PERIODS: [&str; 5] = ["ytd", "1y", "3y", "5y", "inception"];
+
+const EPSILON: f64 = 1e-9;
+
+// Trade prices per ticker, the fallback for days without a stored close
+pub(crate) type TradePrices = HashMap<String, BTreeMap<NaiveDate, f64>>;
+
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceArgs {
+    pub period: String,
+    pub account_ids: Option<Vec<i32>>,
+    pub benchmark: Option<String>,
+    pub base_currency: Option<String>,
+    /// Report date, today when missing
+    pub as_of: Option<String>,
+    /// One of `fx::RATE_MODES`; amounts convert at their own date's rate when missing
+    pub rate_mode: Option<String>,
+}
+
+// Something that changes a holding on one day. A priced flow moves shares in or out at
+// the market value of `(ticker, shares)` that day, for shares that arrive without cash.
+#[derive(Default)]
+struct Event {
+    date: NaiveDate,
+    shares: f64,
+    cash_flow: f64,
+    income: f64,
+    priced_flow: Option<(String, f64)>,
+}
+
+struct Holding {
+    account_id: i32,
+    ticker: String,
+    // Currency of the holding's cash flows; its closes are in the quote currency
+    currency: String,
+    events: Vec<Event>,
+}
+
+// Day by day values of a holding or a group of them, index 0 being the baseline day
+#[derive(Clone)]
+struct Series {
+    values: Vec<f64>,
+    inflows: Vec<f64>,
+    outflows: Vec<f64>,
+    income: Vec<f64>,
+}
+
+impl Series {
+    fn zero(days: usize) -> Self {
+        Series {
+            values: vec![0.0; days],
+            inflows: vec![0.0; days],
+            outflows: vec![0.0; days],
+            income: vec![0.0; days],
+        }
+    }
+
+    fn add(&mut self, other: &Series) {
+        for d in 0..self.values.len() {
+            self.values[d] += other.values[d];
+            self.inflows[d] += other.inflows[d];
+            self.outflows[d] += other.outflows[d];
+            self.income[d] += other.income[d];
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.values.iter().all(|v| v.abs() < EPSILON)
+            && self.inflows.iter().all(|v| v.abs() < EPSILON)
+            && self.outflows.iter().all(|v| v.abs() < EPSILON)
+            && self.income.iter().all(|v| v.abs() < EPSILON)
+    }
+}
+
+pub(crate) fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.get(..10).unwrap_or(value), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date: {}", value))
+}
+
+fn annualize(growth: f64, days: i64) -> Option<f64> {
+    (days >= 365 && growth > 0.0).then(|| growth.powf(365.0 / days as f64) - 1.0)
+}
+
+/// Annual rate at which the dated cash flows (days from the first, negative for money put
+/// in) have a net present value of zero, found by bisection. `None` without both signs.
+pub(crate) fn xirr(flows: &[(i64, f64)]) -> Option<f64> {
+    if !flows.iter().any(|(_, f)| *f > EPSILON) || !flows.iter().any(|(_, f)| *f < -EPSILON) {
+        return None;
+    }
+    let npv = |rate: f64| -> f64 {
+        flows
+            .iter()
+            .map(|(day, f)| f / (1.0 + rate).powf(*day as f64 / 365.0))
+            .sum()
+    };
+    let mut lo = -0.9999;
+    let mut hi = 1.0;
+    let low_sign = npv(lo).signum();
+    while npv(hi).signum() == low_sign {
+        hi *= 2.0;
+        if hi > 1e6 {
+            return None;
+        }
+    }
+    for _ in 0..200 {
+        let mid = (lo + hi) / 2.0;
+        if npv(mid).signum() == low_sign {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some((lo + hi) / 2.0)
+}
+
+// Flows happen at the trade price during the day, so each day's gain is measured against
+// the previous close, or against the money put in when nothing was held yet
+fn metrics(series: &Series) -> PerformanceMetrics {
+    let days = series.values.len();
+    let last = days - 1;
+
+    let mut growth = 1.0;
+    let mut measured = false;
+    for d in 1..days {
+        let flows = series.inflows[d] + series.outflows[d];
+        let gain = series.values[d] + series.income[d] - flows - series.values[d - 1];
+        let base = if series.values[d - 1] > EPSILON {
+            series.values[d - 1]
+        } else {
+            series.inflows[d]
+        };
+        if base > EPSILON {
+            growth *= 1.0 + gain / base;
+            measured = true;
+        }
+    }
+
+    let mut flows = vec![(0, -series.values[0])];
+    for d in 1..days {
+        flows.push((
+            d as i64,
+            series.income[d] - series.inflows[d] - series.outflows[d],
+        ));
+    }
+    flows.push((last as i64, series.values[last]));
+
+    let contributions: f64 =
+        series.inflows[1..].iter().sum::<f64>() + series.outflows[1..].iter().sum::<f64>();
+    let income: f64 = series.income[1..].iter().sum();
+    PerformanceMetrics {
+        start_value: round_cents(series.values[0]),
+        end_value: round_cents(series.values[last]),
+        net_contributions: round_cents(contributions),
+        income: round_cents(income),
+        gain: round_cents(series.values[last] - series.values[0] - contributions + income),
+        time_weighted_return: measured.then_some(growth - 1.0),
+        annualized_return: if measured {
+            annualize(growth, last as i64)
+        } else {
+            None
+        },
+        money_weighted_return: xirr(&flows),
+    }
+}
+
+// Closes carried forward, with trade prices filling days before the stored history
+pub(crate) struct Prices(HashMap<String, Vec<(NaiveDate, f64)>>);
+
+impl Prices {
+    pub(crate) fn on(&self, ticker: &str, date: NaiveDate) -> f64 {
+        let Some(series) = self.0.get(ticker) else {
+            return 0.0;
+        };
+        let i = series.partition_point(|(d, _)| *d <= date);
+        if i > 0 {
+            series[i - 1].1
+        } else {
+            series.first().map(|(_, p)| *p).unwrap_or(0.0)
+        }
+    }
+}
+
+pub(crate) fn load_prices(conn: &Connection, trade_prices: TradePrices) -> Result<Prices, String> {
+    let mut prices = HashMap::new();
+    let mut stmt = conn
+        .prepare("SELECT date, price FROM daily_stock_prices WHERE UPPER(ticker) = ?1")
+        .map_err(|e| e.to_string())?;
+    for (ticker, mut series) in trade_prices {
+        let rows = stmt
+            .query_map(params![ticker], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (date, price) = row.map_err(|e| e.to_string())?;
+            if let Ok(date) = parse_date(&date) {
+                series.insert(date, price);
+            }
+        }
+        prices.insert(ticker, series.into_iter().collect());
+    }
+    Ok(Prices(prices))
+}
+
+// Quote currency per ticker, for tickers whose quote has one
+pub(crate) fn load_quote_currencies(conn: &Connection) -> Result<HashMap<String, String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT UPPER(ticker), UPPER(TRIM(currency)) FROM stock_prices
+             WHERE currency IS NOT NULL AND TRIM(currency) != ''",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+pub(crate) struct FxCache<'a> {
+    conn: &'a Connection,
+    base: String,
+    mode: String,
+    rates: HashMap<(String, NaiveDate), f64>,
+}
+
+impl<'a> FxCache<'a> {
+    pub(crate) fn new(conn: &'a Connection, base: &str, mode: &str) -> Self {
+        FxCache {
+            conn,
+            base: base.to_string(),
+            mode: mode.to_string(),
+            rates: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn rate(&mut self, currency: &str, date: NaiveDate) -> Result<f64, String> {
+        if currency == self.base {
+            return Ok(1.0);
+        }
+        if let Some(rate) = self.rates.get(&(currency.to_string(), date)) {
+            return Ok(*rate);
+        }
+        let day = date.format("%Y-%m-%d").to_string();
+        let day = rate_date(&self.mode, &day);
+        let rate = rate_on(self.conn, currency, &self.base, day)
+            .ok_or_else(|| missing_rate(currency, &self.base, day))?;
+        self.rates.insert((currency.to_string(), date), rate);
+        Ok(rate)
+    }
+}
+
+fn load_holdings(
+    conn: &Connection,
+    account_ids: &Option<Vec<i32>>,
+    end: NaiveDate,
+) -> Result<(Vec<Holding>, TradePrices), String> {
+    struct Row {
+        id: i32,
+        account_id: i32,
+        date: NaiveDate,
+        ticker: String,
+        shares: f64,
+        amount: f64,
+        price: Option<f64>,
+        action: Option<String>,
+        related: Option<i32>,
+        currency: String,
+    }
+
+    let ratios = load_split_ratios(conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.account_id, t.date, UPPER(t.ticker), COALESCE(t.shares, 0), t.amount,
+                    t.price_per_share, t.action, t.related_tx_id, COALESCE(t.currency, a.currency, 'USD')
+             FROM transactions t JOIN accounts a ON a.id = t.account_id
+             WHERE t.ticker IS NOT NULL AND t.ticker != '' AND t.date <= ?1
+             ORDER BY t.date, t.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let end_day = end.format("%Y-%m-%d").to_string();
+    let mapped = stmt
+        .query_map(params![end_day], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, i32>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, f64>(5)?,
+                row.get::<_, Option<f64>>(6)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, Option<i32>>(8)?,
+                row.get::<_, String>(9)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut rows = Vec::new();
+    for row in mapped {
+        let (id, account_id, date, ticker, shares, amount, price, action, related, currency) =
+            row.map_err(|e| e.to_string())?;
+        if account_ids
+            .as_ref()
+            .is_some_and(|ids| !ids.contains(&account_id))
+        {
+            continue;
+        }
+        let factor = split_factor(&ratios, &ticker, &date, None);
+        rows.push(Row {
+            id,
+            account_id,
+            date: parse_date(&date)?,
+            ticker,
+            // Stored closes are in today's split basis, so share counts are too
+            shares: shares * factor,
+            amount,
+            price: price.filter(|p| *p > 0.0).map(|p| p / factor),
+            action,
+            related,
+            currency,
+        });
+    }
+
+    let by_id: HashMap<i32, usize> = rows.iter().enumerate().map(|(i, r)| (r.id, i)).collect();
+    let mut holdings: BTreeMap<(i32, String), Holding> = BTreeMap::new();
+    let mut trade_prices = TradePrices::new();
+
+    for row in &rows {
+        trade_prices.entry(row.ticker.clone()).or_default();
+        if let Some(price) = row.price {
+            trade_prices
+                .entry(row.ticker.clone())
+                .or_default()
+                .insert(row.date, price);
+        }
+        let action = match row.action.as_deref() {
+            Some(a) => a,
+            None if row.shares > 0.0 => "buy",
+            None if row.shares < 0.0 => "sell",
+            None => continue,
+        };
+        let event = match action {
+            "buy" | "sell" | "drip" => Event {
+                shares: row.shares,
+                cash_flow: -row.amount,
+                ..Default::default()
+            },
+            "dividend" | "interest" | "return_of_capital" | "withholding_tax" => Event {
+                income: row.amount,
+                ..Default::default()
+            },
+            "transfer_in" | "transfer_out" => Event {
+                shares: row.shares,
+                priced_flow: Some((row.ticker.clone(), row.shares)),
+                ..Default::default()
+            },
+            // The parent gives up the value the new shares arrive with
+            "spin_off" if row.shares.abs() < EPSILON => {
+                let Some(child) = row.related.and_then(|id| by_id.get(&id)).map(|i| &rows[*i])
+                else {
+                    continue;
+                };
+                Event {
+                    priced_flow: Some((child.ticker.clone(), -child.shares)),
+                    ..Default::default()
+                }
+            }
+            "spin_off" => Event {
+                shares: row.shares,
+                priced_flow: Some((row.ticker.clone(), row.shares)),
+                ..Default::default()
+            },
+            _ => continue,
+        };
+        holdings
+            .entry((row.account_id, row.ticker.clone()))
+            .or_insert_with(|| Holding {
+                account_id: row.account_id,
+                ticker: row.ticker.clone(),
+                currency: row.currency.clone(),
+                events: Vec::new(),
+            })
+            .events
+            .push(Event {
+                date: row.date,
+                ..event
+            });
+    }
+    Ok((holdings.into_values().collect(), trade_prices))
+}
+
+fn holding_series(
+    holding: &Holding,
+    baseline: NaiveDate,
+    days: usize,
+    prices: &Prices,
+    quotes: &HashMap<String, String>,
+    fx: &mut FxCache,
+) -> Result<Series, String> {
+    let quote_currency = |ticker: &str| quotes.get(ticker).unwrap_or(&holding.currency).clone();
+    let mut series = Series::zero(days);
+    let mut shares: f64 = 0.0;
+    let mut next = 0;
+    for d in 0..days {
+        let date = baseline + chrono::Days::new(d as u64);
+        let due = next < holding.events.len() && holding.events[next].date <= date;
+        let rate = if due {
+            fx.rate(&holding.currency, date)?
+        } else {
+            1.0
+        };
+        while next < holding.events.len() && holding.events[next].date <= date {
+            let event = &holding.events[next];
+            next += 1;
+            shares += event.shares;
+            if d == 0 {
+                // Everything up to the baseline is already in the starting value
+                continue;
+            }
+            let priced = match &event.priced_flow {
+                Some((ticker, n)) => {
+                    n * prices.on(ticker, date) * fx.rate(&quote_currency(ticker), date)?
+                }
+                None => 0.0,
+            };
+            let flow = event.cash_flow * rate + priced;
+            if flow >= 0.0 {
+                series.inflows[d] += flow;
+            } else {
+                series.outflows[d] += flow;
+            }
+            series.income[d] += event.income * rate;
+        }
+        if shares.abs() > EPSILON {
+            series.values[d] = shares
+                * prices.on(&holding.ticker, date)
+                * fx.rate(&quote_currency(&holding.ticker), date)?;
+        }
+    }
+    Ok(series)
+}
+
+fn period_baseline(period: &str, end: NaiveDate) -> Result<Option<NaiveDate>, String> {
+    let years_back = |n: u32| end.checked_sub_months(Months::new(12 * n));
+    Ok(match period {
+        "ytd" => NaiveDate::from_ymd_opt(end.year() - 1, 12, 31),
+        "1y" => years_back(1),
+        "3y" => years_back(3),
+        "5y" => years_back(5),
+        "inception" => None,
+        _ => return Err(format!("Unknown performance period: {}", period)),
+    })
+}
+
+fn benchmark_performance(
+    conn: &Connection,
+    ticker: &str,
+    baseline: NaiveDate,
+    end: NaiveDate,
+    portfolio_return: Option<f64>,
+) -> Result<BenchmarkPerformance, String> {
+    let ticker = ticker.trim().to_uppercase();
+    let mut trade_prices = HashMap::new();
+    trade_prices.insert(ticker.clone(), BTreeMap::new());
+    let prices = load_prices(conn, trade_prices)?;
+    let start = prices.on(&ticker, baseline);
+    let last = prices.on(&ticker, end);
+    let total_return = (start > 0.0 && last > 0.0).then(|| last / start - 1.0);
+    Ok(BenchmarkPerformance {
+        ticker,
+        total_return,
+        annualized_return: total_return
+            .and_then(|r| annualize(1.0 + r, (end - baseline).num_days())),
+        excess_return: portfolio_return.zip(total_return).map(|(p, b)| p - b),
+    })
+}
+
+/// Time- and money-weighted returns of the securities in the given accounts (all when
+/// `None`) per holding, per account and for the whole portfolio, in `base_currency`.
+///
+/// Values are taken at the close of `start_date` and `end_date`. Buys and sales are
+/// contributions and withdrawals, dividends and interest are income, and shares moved
+/// in or out without cash (transfers, spin-offs) count at their market value that day.
+pub fn get_performance_db(
+    db_path: &PathBuf,
+    args: PerformanceArgs,
+) -> Result<PerformanceReport, String> {
+    let period = args.period.trim().to_lowercase();
+    let end = match &args.as_of {
+        Some(date) => parse_date(date)?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let base = args
+        .base_currency
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "USD".to_string());
+    let requested = period_baseline(&period, end)?;
+    let mode = report_rate_mode(args.rate_mode)?;
+
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let (holdings, trade_prices) = load_holdings(&conn, &args.account_ids, end)?;
+    let prices = load_prices(&conn, trade_prices)?;
+    let quotes = load_quote_currencies(&conn)?;
+
+    // Nothing happens before the first transaction, so the period starts no earlier
+    let first = holdings
+        .iter()
+        .filter_map(|h| h.events.first().map(|e| e.date))
+        .min()
+        .unwrap_or(end);
+    let earliest = first.pred_opt().unwrap_or(first);
+    let baseline = requested.map_or(earliest, |d| d.max(earliest)).min(end);
+    let days = (end - baseline).num_days() as usize + 1;
+
+    let mut fx = FxCache::new(&conn, &base, &mode);
+    let mut portfolio = Series::zero(days);
+    let mut by_account: BTreeMap<i32, Series> = BTreeMap::new();
+    let mut holding_results = Vec::new();
+    for holding in &holdings {
+        let series = holding_series(holding, baseline, days, &prices, &quotes, &mut fx)?;
+        if series.is_empty() {
+            continue;
+        }
+        portfolio.add(&series);
+        by_account
+            .entry(holding.account_id)
+            .or_insert_with(|| Series::zero(days))
+            .add(&series);
+        holding_results.push(HoldingPerformance {
+            account_id: holding.account_id,
+            ticker: holding.ticker.clone(),
+            metrics: metrics(&series),
+        });
+    }
+
+    let names: HashMap<i32, String> = {
+        let mut stmt = conn
+            .prepare("SELECT id, name FROM accounts")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    let accounts = by_account
+        .iter()
+        .map(|(account_id, series)| AccountPerformance {
+            account_id: *account_id,
+            account_name: names.get(account_id).cloned().unwrap_or_default(),
+            metrics: metrics(series),
+        })
+        .collect();
+
+    let portfolio = metrics(&portfolio);
+    let benchmark = match args.benchmark.filter(|b| !b.trim().is_empty()) {
+        Some(ticker) => Some(benchmark_performance(
+            &conn,
+            &ticker,
+            baseline,
+            end,
+            portfolio.time_weighted_return,
+        )?),
+        None => None,
+    };
+
+    Ok(PerformanceReport {
+        period,
+        start_date: baseline.format("%Y-%m-%d").to_string(),
+        end_date: end.format("%Y-%m-%d").to_string(),
+        base_currency: base,
+        portfolio,
+        accounts,
+        holdings: holding_results,
+        benchmark,
+    })
+}
+
+#[tauri::command]
+pub fn get_performance(
+    app_handle: AppHandle,
+    mut args: PerformanceArgs,
+) -> Result<PerformanceReport, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    args.rate_mode = requested_rate_mode(&app_handle, args.rate_mode)?;
+    get_performance_db(&db_path, args)
+}
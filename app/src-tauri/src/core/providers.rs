@@ -0,0 +1,867 @@
+use crate::http::send_with_retry;
+use crate::models::{
+    DailyBar, MarketDataProviderConfig, YahooChartResponse, YahooQuote, YahooSearchQuote,
+    YahooSearchResponse,
+};
+use chrono::{TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use tauri::AppHandle;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Daily closes as `(YYYY-MM-DD, close)`, oldest first
+pub type DailyCloses = Vec<(String, f64)>;
+
+/// Daily bars plus the dividends `(date, amount)` and splits `(date, numerator, denominator)`
+/// reported over the same period.
+#[derive(Debug, Clone, Default)]
+pub struct PriceHistory {
+    pub bars: Vec<DailyBar>,
+    pub dividends: Vec<(String, f64)>,
+    pub splits: Vec<(String, f64, f64)>,
+}
+
+impl PriceHistory {
+    pub fn closes(&self) -> DailyCloses {
+        self.bars
+            .iter()
+            .map(|b| (b.date.clone(), b.close))
+            .collect()
+    }
+}
+
+pub const PROVIDER_KINDS: [&str; 2] = ["yahoo", "http"];
+pub const PROVIDER_FORMATS: [&str; 2] = ["json", "csv"];
+
+const YAHOO_DEFAULT_BASE_URL: &str = "https://query1.finance.yahoo.com";
+
+/// A source of market data. Methods return `Ok(None)` when the provider has no data for the
+/// symbol, so callers can fall through to the next provider.
+pub trait MarketDataProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn search<'a>(&'a self, query: &'a str)
+        -> BoxFuture<'a, Result<Vec<YahooSearchQuote>, String>>;
+
+    fn quote<'a>(&'a self, ticker: &'a str) -> BoxFuture<'a, Result<Option<YahooQuote>, String>>;
+
+    /// Daily bars and corporate events between two unix timestamps.
+    fn price_history<'a>(
+        &'a self,
+        ticker: &'a str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> BoxFuture<'a, Result<Option<PriceHistory>, String>>;
+
+    /// Daily closes between two unix timestamps.
+    fn history<'a>(
+        &'a self,
+        ticker: &'a str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> BoxFuture<'a, Result<Option<DailyCloses>, String>> {
+        Box::pin(async move {
+            Ok(self
+                .price_history(ticker, start_timestamp, end_timestamp)
+                .await?
+                .map(|h| h.closes()))
+        })
+    }
+
+    /// Symbol under which the price of one unit of `currency` in USD is listed.
+    fn fx_ticker(&self, currency: &str) -> String {
+        format!("{}USD=X", currency)
+    }
+}
+
+/// Yahoo Finance chart and search endpoints.
+pub struct YahooProvider {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl YahooProvider {
+    pub fn new(client: reqwest::Client, base_url: &str) -> Self {
+        YahooProvider {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Uses `YAHOO_BASE_URL` when set, so every Yahoo request can be pointed at a stand-in.
+    pub fn default_base_url() -> String {
+        std::env::var("YAHOO_BASE_URL").unwrap_or_else(|_| YAHOO_DEFAULT_BASE_URL.to_string())
+    }
+}
+
+impl MarketDataProvider for YahooProvider {
+    fn name(&self) -> &str {
+        "yahoo"
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<YahooSearchQuote>, String>> {
+        Box::pin(async move {
+            let url = format!("{}/v1/finance/search", self.base_url);
+            let res = send_with_retry(
+                self.client
+                    .get(&url)
+                    .query(&[("q", query)])
+                    .header("User-Agent", "Mozilla/5.0"),
+            )
+            .await?;
+
+            let text = res.text().await.map_err(|e| e.to_string())?;
+            let response: YahooSearchResponse =
+                serde_json::from_str(&text).map_err(|e| e.to_string())?;
+
+            Ok(response.quotes)
+        })
+    }
+
+    fn quote<'a>(&'a self, ticker: &'a str) -> BoxFuture<'a, Result<Option<YahooQuote>, String>> {
+        Box::pin(async move {
+            let url = format!(
+                "{}/v8/finance/chart/{}?interval=1d&range=1d",
+                self.base_url, ticker
+            );
+            let resp = send_with_retry(self.client.get(&url)
+                .header("User-Agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"))
+                .await?;
+
+            if !resp.status().is_success() {
+                return Err(format!("Request failed for {}: {}", ticker, resp.status()));
+            }
+
+            let text = resp.text().await.map_err(|e| e.to_string())?;
+            let data: YahooChartResponse = serde_json::from_str(&text)
+                .map_err(|e| format!("Failed to parse JSON for {}: {}", ticker, e))?;
+
+            let Some(item) = data.chart.result.as_ref().and_then(|r| r.first()) else {
+                return Ok(None);
+            };
+            let price = item.meta.regular_market_price.unwrap_or(0.0);
+            let prev = item
+                .meta
+                .chart_previous_close
+                .or(item.meta.previous_close)
+                .unwrap_or(price);
+
+            let change_percent = if prev != 0.0 {
+                ((price - prev) / prev) * 100.0
+            } else {
+                0.0
+            };
+            Ok(Some(YahooQuote {
+                symbol: item.meta.symbol.clone(),
+                price,
+                change_percent,
+                currency: item.meta.currency.clone(),
+                quote_type: item.meta.instrument_type.clone(),
+                last_updated: None,
+            }))
+        })
+    }
+
+    fn price_history<'a>(
+        &'a self,
+        ticker: &'a str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> BoxFuture<'a, Result<Option<PriceHistory>, String>> {
+        Box::pin(async move {
+            let url = format!(
+                "{}/v8/finance/chart/{}?period1={}&period2={}&interval=1d&events=div%7Csplit",
+                self.base_url, ticker, start_timestamp, end_timestamp
+            );
+
+            let res =
+                send_with_retry(self.client.get(&url).header("User-Agent", "Mozilla/5.0")).await?;
+
+            if res.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            if !res.status().is_success() {
+                return Err(format!(
+                    "Failed to fetch history for {}: {}",
+                    ticker,
+                    res.status()
+                ));
+            }
+
+            let text = res.text().await.map_err(|e| e.to_string())?;
+            let json: YahooChartResponse =
+                serde_json::from_str(&text).map_err(|e| e.to_string())?;
+
+            let mut history = PriceHistory::default();
+            let Some(data) = json.chart.result.as_ref().and_then(|r| r.first()) else {
+                return Ok(Some(history));
+            };
+
+            if let (Some(timestamps), Some(indicators)) = (&data.timestamp, &data.indicators) {
+                let quote = indicators.quote.as_ref().and_then(|q| q.first());
+                let adjclose = indicators
+                    .adjclose
+                    .as_ref()
+                    .and_then(|a| a.first())
+                    .and_then(|a| a.adjclose.as_ref());
+                let at = |series: Option<&Vec<Option<f64>>>, i: usize| {
+                    series.and_then(|s| s.get(i).copied().flatten())
+                };
+                if let Some(quote) = quote {
+                    for (i, ts) in timestamps.iter().enumerate() {
+                        if let Some(close) = at(quote.close.as_ref(), i) {
+                            history.bars.push(DailyBar {
+                                date: timestamp_to_date(*ts),
+                                open: at(quote.open.as_ref(), i),
+                                high: at(quote.high.as_ref(), i),
+                                low: at(quote.low.as_ref(), i),
+                                close,
+                                adj_close: at(adjclose, i),
+                                volume: at(quote.volume.as_ref(), i),
+                            });
+                        }
+                    }
+                }
+            }
+
+            if let Some(events) = &data.events {
+                for d in events.dividends.iter().flat_map(|d| d.values()) {
+                    history
+                        .dividends
+                        .push((timestamp_to_date(d.date), d.amount));
+                }
+                for s in events.splits.iter().flat_map(|s| s.values()) {
+                    if s.numerator > 0.0 && s.denominator > 0.0 {
+                        history.splits.push((
+                            timestamp_to_date(s.date),
+                            s.numerator,
+                            s.denominator,
+                        ));
+                    }
+                }
+                history.dividends.sort_by(|a, b| a.0.cmp(&b.0));
+                history.splits.sort_by(|a, b| a.0.cmp(&b.0));
+            }
+
+            Ok(Some(history))
+        })
+    }
+}
+
+/// Generic provider reading JSON or CSV documents over HTTP.
+///
+/// Paths are templates appended to the base URL; `{ticker}`, `{query}`, `{from}` and `{to}`
+/// (as `YYYY-MM-DD`) are substituted. JSON quotes are objects with `symbol`, `price`, and
+/// optionally `currency` and `change_percent`; histories are arrays of `{date, close}` with optional `open`, `high`, `low`,
+/// `adj_close` and `volume`; search
+/// results are arrays of objects with `symbol` and optionally `name`. CSV documents use the
+/// same names as header columns.
+pub struct HttpProvider {
+    client: reqwest::Client,
+    config: MarketDataProviderConfig,
+}
+
+impl HttpProvider {
+    pub fn new(client: reqwest::Client, config: MarketDataProviderConfig) -> Self {
+        HttpProvider { client, config }
+    }
+
+    fn url(&self, path: &str, vars: &[(&str, &str)]) -> String {
+        let mut path = path.to_string();
+        for (key, value) in vars {
+            path = path.replace(&format!("{{{}}}", key), value);
+        }
+        format!("{}{}", self.config.base_url.trim_end_matches('/'), path)
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Option<String>, String> {
+        let resp = send_with_retry(self.client.get(url)).await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(format!("Request to {} failed: {}", url, resp.status()));
+        }
+        resp.text().await.map(Some).map_err(|e| e.to_string())
+    }
+
+    // Rows of the document as field maps, whichever the configured format
+    fn records(&self, body: &str) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
+        if self.config.format == "csv" {
+            return Ok(parse_csv(body));
+        }
+        let value: serde_json::Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+        let items = match value {
+            serde_json::Value::Array(items) => items,
+            other => vec![other],
+        };
+        Ok(items
+            .into_iter()
+            .filter_map(|item| match item {
+                serde_json::Value::Object(map) => Some(map.into_iter().collect()),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+fn field_str(record: &HashMap<String, serde_json::Value>, key: &str) -> Option<String> {
+    match record.get(key)? {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn field_f64(record: &HashMap<String, serde_json::Value>, key: &str) -> Option<f64> {
+    match record.get(key)? {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// Minimal CSV reader: a header row naming the columns, comma separated, optional quotes
+fn parse_csv(body: &str) -> Vec<HashMap<String, serde_json::Value>> {
+    let mut lines = body.lines().filter(|l| !l.trim().is_empty());
+    let split = |line: &str| -> Vec<String> {
+        line.split(',')
+            .map(|f| f.trim().trim_matches('"').to_string())
+            .collect()
+    };
+    let Some(header) = lines.next() else {
+        return Vec::new();
+    };
+    let columns: Vec<String> = split(header)
+        .into_iter()
+        .map(|c| c.to_lowercase())
+        .collect();
+
+    lines
+        .map(|line| {
+            columns
+                .iter()
+                .cloned()
+                .zip(split(line).into_iter().map(serde_json::Value::String))
+                .collect()
+        })
+        .collect()
+}
+
+fn timestamp_to_date(ts: i64) -> String {
+    Utc.timestamp_opt(ts, 0)
+        .single()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+impl MarketDataProvider for HttpProvider {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<YahooSearchQuote>, String>> {
+        Box::pin(async move {
+            let Some(path) = &self.config.search_path else {
+                return Ok(Vec::new());
+            };
+            let Some(body) = self.fetch(&self.url(path, &[("query", query)])).await? else {
+                return Ok(Vec::new());
+            };
+            Ok(self
+                .records(&body)?
+                .iter()
+                .filter_map(|r| {
+                    Some(YahooSearchQuote {
+                        symbol: field_str(r, "symbol")?,
+                        shortname: field_str(r, "name").or_else(|| field_str(r, "shortname")),
+                        longname: field_str(r, "longname"),
+                        exchange: field_str(r, "exchange"),
+                        type_disp: field_str(r, "type"),
+                        currency: field_str(r, "currency"),
+                    })
+                })
+                .collect())
+        })
+    }
+
+    fn quote<'a>(&'a self, ticker: &'a str) -> BoxFuture<'a, Result<Option<YahooQuote>, String>> {
+        Box::pin(async move {
+            let Some(path) = &self.config.quote_path else {
+                return Ok(None);
+            };
+            let Some(body) = self.fetch(&self.url(path, &[("ticker", ticker)])).await? else {
+                return Ok(None);
+            };
+            let records = self.records(&body)?;
+            let Some(record) = records.first() else {
+                return Ok(None);
+            };
+            let Some(price) = field_f64(record, "price").or_else(|| field_f64(record, "close"))
+            else {
+                return Ok(None);
+            };
+            Ok(Some(YahooQuote {
+                symbol: field_str(record, "symbol").unwrap_or_else(|| ticker.to_string()),
+                price,
+                change_percent: field_f64(record, "change_percent").unwrap_or(0.0),
+                currency: field_str(record, "currency"),
+                quote_type: field_str(record, "type"),
+                last_updated: None,
+            }))
+        })
+    }
+
+    fn price_history<'a>(
+        &'a self,
+        ticker: &'a str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> BoxFuture<'a, Result<Option<PriceHistory>, String>> {
+        Box::pin(async move {
+            let Some(path) = &self.config.history_path else {
+                return Ok(None);
+            };
+            let from = timestamp_to_date(start_timestamp);
+            let to = timestamp_to_date(end_timestamp);
+            let url = self.url(path, &[("ticker", ticker), ("from", &from), ("to", &to)]);
+            let Some(body) = self.fetch(&url).await? else {
+                return Ok(None);
+            };
+            let mut bars: Vec<DailyBar> = self
+                .records(&body)?
+                .iter()
+                .filter_map(|r| {
+                    Some(DailyBar {
+                        date: field_str(r, "date")?,
+                        open: field_f64(r, "open"),
+                        high: field_f64(r, "high"),
+                        low: field_f64(r, "low"),
+                        close: field_f64(r, "close").or_else(|| field_f64(r, "price"))?,
+                        adj_close: field_f64(r, "adj_close"),
+                        volume: field_f64(r, "volume"),
+                    })
+                })
+                .filter(|b| b.date >= from && b.date <= to)
+                .collect();
+            bars.sort_by(|a, b| a.date.cmp(&b.date));
+            Ok(Some(PriceHistory {
+                bars,
+                ..Default::default()
+            }))
+        })
+    }
+
+    fn fx_ticker(&self, currency: &str) -> String {
+        match &self.config.fx_ticker {
+            Some(template) => template.replace("{currency}", currency),
+            None => format!("{}USD=X", currency),
+        }
+    }
+}
+
+/// Configured providers in fallback order, with per-ticker preferences.
+#[derive(Clone)]
+pub struct ProviderRegistry {
+    providers: Vec<Arc<dyn MarketDataProvider>>,
+    ticker_providers: HashMap<String, Vec<String>>,
+}
+
+impl ProviderRegistry {
+    pub fn new(providers: Vec<Arc<dyn MarketDataProvider>>) -> Self {
+        ProviderRegistry {
+            providers,
+            ticker_providers: HashMap::new(),
+        }
+    }
+
+    /// Only Yahoo, at the given base URL.
+    pub fn yahoo(client: reqwest::Client, base_url: &str) -> Self {
+        Self::new(vec![Arc::new(YahooProvider::new(client, base_url))])
+    }
+
+    pub fn set_ticker_providers(&mut self, ticker: &str, providers: Vec<String>) {
+        self.ticker_providers
+            .insert(ticker.to_uppercase(), providers);
+    }
+
+    /// Providers to try for `ticker`: its preferred providers in their order, then the
+    /// remaining ones by priority.
+    pub fn chain_for(&self, ticker: &str) -> Vec<Arc<dyn MarketDataProvider>> {
+        let preferred = self
+            .ticker_providers
+            .get(&ticker.to_uppercase())
+            .cloned()
+            .unwrap_or_default();
+        let mut chain: Vec<Arc<dyn MarketDataProvider>> = preferred
+            .iter()
+            .filter_map(|name| self.providers.iter().find(|p| p.name() == name).cloned())
+            .collect();
+        for p in &self.providers {
+            if !preferred.iter().any(|name| name == p.name()) {
+                chain.push(p.clone());
+            }
+        }
+        chain
+    }
+
+    pub fn providers(&self) -> &[Arc<dyn MarketDataProvider>] {
+        &self.providers
+    }
+
+    /// Builds the registry from the providers saved in the database. Yahoo is always
+    /// available; a saved `yahoo` entry only changes its base URL and position.
+    pub fn load(db_path: &PathBuf, client: reqwest::Client) -> Result<Self, String> {
+        let configs = get_market_data_providers_db(db_path)?;
+
+        let mut providers: Vec<Arc<dyn MarketDataProvider>> = Vec::new();
+        for config in &configs {
+            if config.kind == "yahoo" {
+                providers.push(Arc::new(YahooProvider::new(
+                    client.clone(),
+                    &config.base_url,
+                )));
+            } else {
+                providers.push(Arc::new(HttpProvider::new(client.clone(), config.clone())));
+            }
+        }
+        if !configs.iter().any(|c| c.kind == "yahoo") {
+            providers.push(Arc::new(YahooProvider::new(
+                client,
+                &YahooProvider::default_base_url(),
+            )));
+        }
+
+        let mut registry = Self::new(providers);
+        let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT ticker, providers FROM ticker_providers")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for r in rows {
+            let (ticker, providers) = r.map_err(|e| e.to_string())?;
+            let providers: Vec<String> = serde_json::from_str(&providers).unwrap_or_default();
+            registry.set_ticker_providers(&ticker, providers);
+        }
+
+        Ok(registry)
+    }
+
+    /// Registry for the app database, falling back to plain Yahoo if it cannot be read.
+    pub fn for_app(app_handle: &AppHandle) -> Result<Self, String> {
+        let client = crate::http::client()?;
+        Ok(crate::db_init::get_db_path(app_handle)
+            .and_then(|db_path| Self::load(&db_path, client.clone()))
+            .unwrap_or_else(|_| Self::yahoo(client, &YahooProvider::default_base_url())))
+    }
+
+    /// First non-empty search result along the default order.
+    pub async fn search(&self, query: &str) -> Result<Vec<YahooSearchQuote>, String> {
+        let mut last_err = None;
+        for provider in &self.providers {
+            match provider.search(query).await {
+                Ok(results) if !results.is_empty() => return Ok(results),
+                Ok(_) => {}
+                Err(e) => last_err = Some(e),
+            }
+        }
+        match last_err {
+            Some(e) => Err(e),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn quote(&self, ticker: &str) -> Option<YahooQuote> {
+        for provider in self.chain_for(ticker) {
+            match provider.quote(ticker).await {
+                Ok(Some(quote)) => return Some(quote),
+                Ok(None) => {}
+                Err(e) => println!("{} quote for {} failed: {}", provider.name(), ticker, e),
+            }
+        }
+        None
+    }
+
+    pub async fn history(
+        &self,
+        ticker: &str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> Result<Option<DailyCloses>, String> {
+        let mut last_err = None;
+        for provider in self.chain_for(ticker) {
+            match provider
+                .history(ticker, start_timestamp, end_timestamp)
+                .await
+            {
+                Ok(Some(closes)) if !closes.is_empty() => return Ok(Some(closes)),
+                Ok(_) => {}
+                Err(e) => last_err = Some(e),
+            }
+        }
+        match last_err {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    /// Bars and events from the first provider in the ticker's chain that has any bars.
+    pub async fn price_history(
+        &self,
+        ticker: &str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> Result<Option<PriceHistory>, String> {
+        let mut last_err = None;
+        for provider in self.chain_for(ticker) {
+            match provider
+                .price_history(ticker, start_timestamp, end_timestamp)
+                .await
+            {
+                Ok(Some(history)) if !history.bars.is_empty() => return Ok(Some(history)),
+                Ok(_) => {}
+                Err(e) => last_err = Some(e),
+            }
+        }
+        match last_err {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    /// Daily USD prices of one unit of `currency`, from the first provider that has them.
+    pub async fn fx_history(
+        &self,
+        currency: &str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> Result<Option<DailyCloses>, String> {
+        let mut last_err = None;
+        for provider in self.chain_for(&format!("{}USD=X", currency)) {
+            let ticker = provider.fx_ticker(currency);
+            match provider
+                .history(&ticker, start_timestamp, end_timestamp)
+                .await
+            {
+                Ok(Some(closes)) if !closes.is_empty() => return Ok(Some(closes)),
+                Ok(_) => {}
+                Err(e) => last_err = Some(e),
+            }
+        }
+        match last_err {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+}
+
+fn validate_provider(config: &MarketDataProviderConfig) -> Result<(), String> {
+    if config.name.trim().is_empty() {
+        return Err("Provider name cannot be empty".to_string());
+    }
+    if !PROVIDER_KINDS.contains(&config.kind.as_str()) {
+        return Err(format!("Unknown provider kind: {}", config.kind));
+    }
+    if config.kind == "yahoo" && config.name != "yahoo" {
+        return Err("The Yahoo provider must be named \"yahoo\"".to_string());
+    }
+    if config.kind == "http" && config.name == "yahoo" {
+        return Err("The name \"yahoo\" is reserved".to_string());
+    }
+    if !PROVIDER_FORMATS.contains(&config.format.as_str()) {
+        return Err(format!("Unknown provider format: {}", config.format));
+    }
+    if !config.base_url.starts_with("http://") && !config.base_url.starts_with("https://") {
+        return Err("Provider base URL must start with http:// or https://".to_string());
+    }
+    Ok(())
+}
+
+pub fn get_market_data_providers_db(
+    db_path: &PathBuf,
+) -> Result<Vec<MarketDataProviderConfig>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT id, name, kind, base_url, format, quote_path, history_path, search_path, fx_ticker, priority FROM market_data_providers ORDER BY priority ASC, id ASC")
+        .map_err(|e| e.to_string())?;
+    let providers = stmt
+        .query_map([], |row| {
+            Ok(MarketDataProviderConfig {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                kind: row.get(2)?,
+                base_url: row.get(3)?,
+                format: row.get(4)?,
+                quote_path: row.get(5)?,
+                history_path: row.get(6)?,
+                search_path: row.get(7)?,
+                fx_ticker: row.get(8)?,
+                priority: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(providers)
+}
+
+/// Creates or replaces the provider with the same name.
+pub fn save_market_data_provider_db(
+    db_path: &PathBuf,
+    config: MarketDataProviderConfig,
+) -> Result<MarketDataProviderConfig, String> {
+    validate_provider(&config)?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO market_data_providers (name, kind, base_url, format, quote_path, history_path, search_path, fx_ticker, priority)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(name) DO UPDATE SET kind = excluded.kind, base_url = excluded.base_url, format = excluded.format,
+             quote_path = excluded.quote_path, history_path = excluded.history_path, search_path = excluded.search_path,
+             fx_ticker = excluded.fx_ticker, priority = excluded.priority",
+        params![
+            config.name,
+            config.kind,
+            config.base_url,
+            config.format,
+            config.quote_path,
+            config.history_path,
+            config.search_path,
+            config.fx_ticker,
+            config.priority
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let id: i32 = conn
+        .query_row(
+            "SELECT id FROM market_data_providers WHERE name = ?1",
+            params![config.name],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(MarketDataProviderConfig { id, ..config })
+}
+
+pub fn delete_market_data_provider_db(db_path: &PathBuf, name: String) -> Result<(), String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM market_data_providers WHERE name = ?1",
+        params![name],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Sets the providers tried first for a ticker, in order. An empty list clears the preference.
+pub fn set_ticker_providers_db(
+    db_path: &PathBuf,
+    ticker: String,
+    providers: Vec<String>,
+) -> Result<(), String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let ticker = ticker.to_uppercase();
+    if providers.is_empty() {
+        conn.execute(
+            "DELETE FROM ticker_providers WHERE ticker = ?1",
+            params![ticker],
+        )
+        .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    for name in &providers {
+        let known = name == "yahoo"
+            || conn
+                .query_row(
+                    "SELECT 1 FROM market_data_providers WHERE name = ?1",
+                    params![name],
+                    |_| Ok(()),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?
+                .is_some();
+        if !known {
+            return Err(format!("Unknown provider: {}", name));
+        }
+    }
+
+    let json = serde_json::to_string(&providers).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO ticker_providers (ticker, providers) VALUES (?1, ?2)",
+        params![ticker, json],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_ticker_providers_db(db_path: &PathBuf, ticker: String) -> Result<Vec<String>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT providers FROM ticker_providers WHERE ticker = ?1",
+            params![ticker.to_uppercase()],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match json {
+        Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[tauri::command]
+pub fn get_market_data_providers(
+    app_handle: AppHandle,
+) -> Result<Vec<MarketDataProviderConfig>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_market_data_providers_db(&db_path)
+}
+
+#[tauri::command]
+pub fn save_market_data_provider(
+    app_handle: AppHandle,
+    provider: MarketDataProviderConfig,
+) -> Result<MarketDataProviderConfig, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    save_market_data_provider_db(&db_path, provider)
+}
+
+#[tauri::command]
+pub fn delete_market_data_provider(app_handle: AppHandle, name: String) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    delete_market_data_provider_db(&db_path, name)
+}
+
+#[tauri::command]
+pub fn set_ticker_providers(
+    app_handle: AppHandle,
+    ticker: String,
+    providers: Vec<String>,
+) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_ticker_providers_db(&db_path, ticker, providers)
+}
+
+#[tauri::command]
+pub fn get_ticker_providers(app_handle: AppHandle, ticker: String) -> Result<Vec<String>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_ticker_providers_db(&db_path, ticker)
+}
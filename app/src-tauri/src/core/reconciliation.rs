@@ -0,0 +1,211 @@
+use crate::models::{Reconciliation, ReconciliationStatus};
+use rusqlite::{params, Connection};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+pub const CLEARED_STATES: [&str; 3] = ["uncleared", "cleared", "reconciled"];
+
+// Differences below half a cent are treated as balanced
+const BALANCE_TOLERANCE: f64 = 0.005;
+
+/// Marks a transaction as uncleared or cleared.
+///
+/// `reconciled` can only be reached by finishing a reconciliation, and leaving it needs
+/// `override_reconciled` so locked history is not changed by accident.
+pub fn set_transaction_cleared_db(
+    db_path: &PathBuf,
+    id: i32,
+    state: String,
+    override_reconciled: bool,
+) -> Result<(), String> {
+    if !CLEARED_STATES.contains(&state.as_str()) {
+        return Err(format!("Unknown cleared state: {}", state));
+    }
+    if state == "reconciled" {
+        return Err("Transactions are reconciled by finishing a reconciliation".to_string());
+    }
+
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let current: String = conn
+        .query_row(
+            "SELECT COALESCE(cleared, 'uncleared') FROM transactions WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if current == "reconciled" && !override_reconciled {
+        return Err(
+            "Transaction is reconciled; an explicit override is required to change it".to_string(),
+        );
+    }
+
+    conn.execute(
+        "UPDATE transactions SET cleared = ?1 WHERE id = ?2",
+        params![state, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn reconciliation_status(
+    conn: &Connection,
+    account_id: i32,
+    statement_date: &str,
+    statement_balance: f64,
+) -> Result<ReconciliationStatus, String> {
+    // Previously reconciled rows are part of the running cleared balance
+    let (cleared_balance, cleared_count): (Option<f64>, i64) = conn
+        .query_row(
+            "SELECT SUM(amount), COUNT(*) FROM transactions
+             WHERE account_id = ?1 AND date <= ?2 AND cleared IN ('cleared', 'reconciled')",
+            params![account_id, statement_date],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let cleared_balance = cleared_balance.unwrap_or(0.0);
+
+    Ok(ReconciliationStatus {
+        account_id,
+        statement_date: statement_date.to_string(),
+        statement_balance,
+        cleared_balance,
+        difference: statement_balance - cleared_balance,
+        cleared_count,
+    })
+}
+
+/// Compares a statement balance with the sum of cleared transactions up to the statement date.
+pub fn start_reconciliation_db(
+    db_path: &PathBuf,
+    account_id: i32,
+    statement_date: String,
+    statement_balance: f64,
+) -> Result<ReconciliationStatus, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    reconciliation_status(&conn, account_id, &statement_date, statement_balance)
+}
+
+/// Locks the cleared transactions of a balanced statement and records it in the history.
+pub fn finish_reconciliation_db(
+    db_path: &PathBuf,
+    account_id: i32,
+    statement_date: String,
+    statement_balance: f64,
+) -> Result<Reconciliation, String> {
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let status = reconciliation_status(&conn, account_id, &statement_date, statement_balance)?;
+    if status.difference.abs() > BALANCE_TOLERANCE {
+        return Err(format!(
+            "Statement does not balance: difference of {:.2}",
+            status.difference
+        ));
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let transaction_count = tx
+        .execute(
+            "UPDATE transactions SET cleared = 'reconciled'
+             WHERE account_id = ?1 AND date <= ?2 AND cleared = 'cleared'",
+            params![account_id, statement_date],
+        )
+        .map_err(|e| e.to_string())? as i64;
+
+    tx.execute(
+        "INSERT INTO reconciliations (account_id, statement_date, statement_balance, transaction_count, reconciled_at) VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+        params![account_id, statement_date, statement_balance, transaction_count],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let id = tx.last_insert_rowid() as i32;
+    let reconciled_at: String = tx
+        .query_row(
+            "SELECT reconciled_at FROM reconciliations WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(Reconciliation {
+        id,
+        account_id,
+        statement_date,
+        statement_balance,
+        transaction_count,
+        reconciled_at,
+    })
+}
+
+pub fn get_reconciliations_db(
+    db_path: &PathBuf,
+    account_id: i32,
+) -> Result<Vec<Reconciliation>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, account_id, statement_date, statement_balance, transaction_count, reconciled_at FROM reconciliations WHERE account_id = ?1 ORDER BY statement_date DESC, id DESC")
+        .map_err(|e| e.to_string())?;
+    let history = stmt
+        .query_map(params![account_id], |row| {
+            Ok(Reconciliation {
+                id: row.get(0)?,
+                account_id: row.get(1)?,
+                statement_date: row.get(2)?,
+                statement_balance: row.get(3)?,
+                transaction_count: row.get(4)?,
+                reconciled_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(history)
+}
+
+#[tauri::command]
+pub fn set_transaction_cleared(
+    app_handle: AppHandle,
+    id: i32,
+    state: String,
+    override_reconciled: Option<bool>,
+) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_transaction_cleared_db(&db_path, id, state, override_reconciled.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn start_reconciliation(
+    app_handle: AppHandle,
+    account_id: i32,
+    statement_date: String,
+    statement_balance: f64,
+) -> Result<ReconciliationStatus, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    start_reconciliation_db(&db_path, account_id, statement_date, statement_balance)
+}
+
+#[tauri::command]
+pub fn finish_reconciliation(
+    app_handle: AppHandle,
+    account_id: i32,
+    statement_date: String,
+    statement_balance: f64,
+) -> Result<Reconciliation, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    finish_reconciliation_db(&db_path, account_id, statement_date, statement_balance)
+}
+
+#[tauri::command]
+pub fn get_reconciliations(
+    app_handle: AppHandle,
+    account_id: i32,
+) -> Result<Vec<Reconciliation>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_reconciliations_db(&db_path, account_id)
+}
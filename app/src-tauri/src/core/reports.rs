@@ -0,0 +1,382 @@
+use crate::fx::{missing_rate, rate_date, rate_on, report_rate_mode, requested_rate_mode};
+use crate::models::{
+    CategoryTrend, CategoryTrendReport, IncomeExpenseMonth, IncomeExpenseReport, PayeeTotal,
+    PeriodChange,
+};
+use crate::performance::parse_date;
+use crate::transfers::round_cents;
+use chrono::{Datelike, Months, NaiveDate};
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Filters shared by the income, spending and payee reports.
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportArgs {
+    /// First day included, the first transaction when missing
+    pub from: Option<String>,
+    /// Last day included, today when missing
+    pub to: Option<String>,
+    pub account_ids: Option<Vec<i32>>,
+    pub base_currency: Option<String>,
+    /// Count transfers between accounts as income and spending
+    #[serde(default)]
+    pub include_transfers: bool,
+    /// One of `fx::RATE_MODES`; amounts convert at their own date's rate when missing
+    pub rate_mode: Option<String>,
+}
+
+/// A cash transaction converted to the report's base currency. Share trades are left out,
+/// as the dashboard does; dividends, interest and withholding tax count.
+pub(crate) struct Flow {
+    pub id: i32,
+    pub account_id: i32,
+    pub date: NaiveDate,
+    pub category: String,
+    pub payee: String,
+    pub amount: f64,
+}
+
+pub(crate) fn base_currency(args: &ReportArgs) -> String {
+    args.base_currency
+        .as_ref()
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "USD".to_string())
+}
+
+pub(crate) fn month_key(date: NaiveDate) -> String {
+    date.format("%Y-%m").to_string()
+}
+
+fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// `YYYY-MM` keys of every month from `from` to `to`.
+pub(crate) fn months_between(from: NaiveDate, to: NaiveDate) -> Vec<String> {
+    let mut months = Vec::new();
+    let mut month = month_start(from);
+    while month <= to {
+        months.push(month_key(month));
+        month = month + Months::new(1);
+    }
+    months
+}
+
+/// The report range, defaulting to the first transaction through today.
+pub(crate) fn resolve_range(
+    conn: &Connection,
+    args: &ReportArgs,
+) -> Result<(NaiveDate, NaiveDate), String> {
+    let to = match &args.to {
+        Some(day) => parse_date(day)?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let from = match &args.from {
+        Some(day) => parse_date(day)?,
+        None => {
+            let first: Option<String> = conn
+                .query_row("SELECT MIN(date) FROM transactions", [], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            match first {
+                Some(day) => parse_date(&day)?.min(to),
+                None => to,
+            }
+        }
+    };
+    if from > to {
+        return Err("Start date is after end date".to_string());
+    }
+    Ok((from, to))
+}
+
+/// SQL condition on `transactions t` keeping cash rows: those without a security, and
+/// investment actions that earn or cost cash rather than trade shares. A return of capital
+/// gives back part of the cost basis, so it is not income.
+pub(crate) const CASH_ROW_CONDITION: &str = "((t.ticker IS NULL OR t.ticker = '')
+    OR t.action IN ('dividend', 'interest', 'withholding_tax'))";
+
+/// Cash transactions of the selected accounts between `from` and `to`, in `base`.
+pub(crate) fn load_flows(
+    conn: &Connection,
+    args: &ReportArgs,
+    base: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<Flow>, String> {
+    let mode = report_rate_mode(args.rate_mode.clone())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT t.id, t.account_id, t.date, COALESCE(t.category, ''), COALESCE(t.payee, ''), t.amount,
+                    COALESCE(t.currency, a.currency),
+                    (t.category = 'Transfer' OR t.transfer_id IS NOT NULL)
+             FROM transactions t JOIN accounts a ON a.id = t.account_id
+             WHERE {}
+               AND substr(t.date, 1, 10) >= ?1 AND substr(t.date, 1, 10) <= ?2
+             ORDER BY t.date, t.id",
+            CASH_ROW_CONDITION
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            params![
+                from.format("%Y-%m-%d").to_string(),
+                to.format("%Y-%m-%d").to_string()
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, i32>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, f64>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<bool>>(7)?,
+                ))
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut flows = Vec::new();
+    for row in rows {
+        let (id, account_id, day, category, payee, amount, currency, is_transfer) =
+            row.map_err(|e| e.to_string())?;
+        if args
+            .account_ids
+            .as_ref()
+            .is_some_and(|ids| !ids.contains(&account_id))
+        {
+            continue;
+        }
+        if is_transfer.unwrap_or(false) && !args.include_transfers {
+            continue;
+        }
+        let currency = currency.unwrap_or_else(|| base.to_string());
+        let date = rate_date(&mode, &day);
+        let rate = rate_on(conn, &currency, base, date)
+            .ok_or_else(|| missing_rate(&currency, base, date))?;
+        flows.push(Flow {
+            id,
+            account_id,
+            date: parse_date(&day)?,
+            category: if category.trim().is_empty() {
+                "Uncategorized".to_string()
+            } else {
+                category
+            },
+            payee: payee.trim().to_string(),
+            amount: amount * rate,
+        });
+    }
+    Ok(flows)
+}
+
+fn percent_change(current: f64, previous: f64) -> Option<f64> {
+    (previous.abs() > 1e-9).then(|| (current - previous) / previous.abs() * 100.0)
+}
+
+fn savings_rate(income: f64, net: f64) -> Option<f64> {
+    (income > 0.0).then(|| net / income * 100.0)
+}
+
+/// Monthly income, spending, net and savings rate, each month compared with the one before
+/// and the same month a year earlier. Comparisons reach back before `from` when needed.
+pub fn income_expense_report_db(
+    db_path: &PathBuf,
+    args: ReportArgs,
+) -> Result<IncomeExpenseReport, String> {
+    let base = base_currency(&args);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let (from, to) = resolve_range(&conn, &args)?;
+    let history_start = month_start(from) - Months::new(12);
+
+    // Earlier months count whole; the first month of the report only from `from` on
+    let first_month = month_key(from);
+    let mut totals: HashMap<String, (f64, f64)> = HashMap::new();
+    for flow in load_flows(&conn, &args, &base, history_start, to)? {
+        let month = month_key(flow.date);
+        if flow.date < from && month == first_month {
+            continue;
+        }
+        let entry = totals.entry(month).or_default();
+        if flow.amount > 0.0 {
+            entry.0 += flow.amount;
+        } else {
+            entry.1 -= flow.amount;
+        }
+    }
+
+    let change = |current: (f64, f64), month: NaiveDate| -> PeriodChange {
+        let key = month_key(month);
+        let (income, expense) = totals.get(&key).copied().unwrap_or_default();
+        PeriodChange {
+            compared_to: key,
+            income: round_cents(current.0 - income),
+            expense: round_cents(current.1 - expense),
+            net: round_cents((current.0 - current.1) - (income - expense)),
+            income_percent: percent_change(current.0, income),
+            expense_percent: percent_change(current.1, expense),
+        }
+    };
+
+    let mut months = Vec::new();
+    let (mut income, mut expense) = (0.0, 0.0);
+    for key in months_between(from, to) {
+        let month = parse_date(&format!("{}-01", key))?;
+        let current = totals.get(&key).copied().unwrap_or_default();
+        income += current.0;
+        expense += current.1;
+        let net = current.0 - current.1;
+        months.push(IncomeExpenseMonth {
+            income: round_cents(current.0),
+            expense: round_cents(current.1),
+            net: round_cents(net),
+            savings_rate: savings_rate(current.0, net),
+            month_over_month: Some(change(current, month - Months::new(1))),
+            year_over_year: Some(change(current, month - Months::new(12))),
+            month: key,
+        });
+    }
+
+    Ok(IncomeExpenseReport {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        base_currency: base,
+        months,
+        income: round_cents(income),
+        expense: round_cents(expense),
+        net: round_cents(income - expense),
+        savings_rate: savings_rate(income, income - expense),
+    })
+}
+
+/// Spending per category and month, largest category first.
+pub fn category_trends_db(
+    db_path: &PathBuf,
+    args: ReportArgs,
+) -> Result<CategoryTrendReport, String> {
+    let base = base_currency(&args);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let (from, to) = resolve_range(&conn, &args)?;
+    let months = months_between(from, to);
+    let index: HashMap<&str, usize> = months
+        .iter()
+        .enumerate()
+        .map(|(i, m)| (m.as_str(), i))
+        .collect();
+
+    let mut by_category: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for flow in load_flows(&conn, &args, &base, from, to)? {
+        if flow.amount >= 0.0 {
+            continue;
+        }
+        let Some(i) = index.get(month_key(flow.date).as_str()) else {
+            continue;
+        };
+        by_category
+            .entry(flow.category)
+            .or_insert_with(|| vec![0.0; months.len()])[*i] -= flow.amount;
+    }
+
+    let mut categories: Vec<CategoryTrend> = by_category
+        .into_iter()
+        .map(|(category, monthly)| {
+            let total: f64 = monthly.iter().sum();
+            CategoryTrend {
+                category,
+                monthly: monthly.into_iter().map(round_cents).collect(),
+                total: round_cents(total),
+                average: round_cents(total / months.len() as f64),
+            }
+        })
+        .collect();
+    categories.sort_by(|a, b| b.total.total_cmp(&a.total));
+
+    Ok(CategoryTrendReport {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        base_currency: base,
+        months,
+        categories,
+    })
+}
+
+/// Payees with the most spending, `limit` of them (10 when `None`). Payees differing only
+/// in case or surrounding spaces count as one.
+pub fn top_payees_db(
+    db_path: &PathBuf,
+    args: ReportArgs,
+    limit: Option<usize>,
+) -> Result<Vec<PayeeTotal>, String> {
+    let base = base_currency(&args);
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let (from, to) = resolve_range(&conn, &args)?;
+
+    let mut payees: HashMap<String, PayeeTotal> = HashMap::new();
+    let mut spending = 0.0;
+    for flow in load_flows(&conn, &args, &base, from, to)? {
+        if flow.amount >= 0.0 {
+            continue;
+        }
+        spending -= flow.amount;
+        let payee = if flow.payee.is_empty() {
+            "Unknown".to_string()
+        } else {
+            flow.payee
+        };
+        let entry = payees
+            .entry(payee.to_lowercase())
+            .or_insert_with(|| PayeeTotal {
+                payee,
+                total: 0.0,
+                count: 0,
+                share: 0.0,
+            });
+        entry.total -= flow.amount;
+        entry.count += 1;
+    }
+
+    let mut payees: Vec<PayeeTotal> = payees.into_values().collect();
+    payees.sort_by(|a, b| b.total.total_cmp(&a.total).then(a.payee.cmp(&b.payee)));
+    payees.truncate(limit.unwrap_or(10));
+    for payee in payees.iter_mut() {
+        payee.share = payee.total / spending * 100.0;
+        payee.total = round_cents(payee.total);
+    }
+    Ok(payees)
+}
+
+#[tauri::command]
+pub fn income_expense_report(
+    app_handle: AppHandle,
+    mut args: ReportArgs,
+) -> Result<IncomeExpenseReport, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    args.rate_mode = requested_rate_mode(&app_handle, args.rate_mode)?;
+    income_expense_report_db(&db_path, args)
+}
+
+#[tauri::command]
+pub fn category_trends(
+    app_handle: AppHandle,
+    mut args: ReportArgs,
+) -> Result<CategoryTrendReport, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    args.rate_mode = requested_rate_mode(&app_handle, args.rate_mode)?;
+    category_trends_db(&db_path, args)
+}
+
+#[tauri::command]
+pub fn top_payees(
+    app_handle: AppHandle,
+    mut args: ReportArgs,
+    limit: Option<usize>,
+) -> Result<Vec<PayeeTotal>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    args.rate_mode = requested_rate_mode(&app_handle, args.rate_mode)?;
+    top_payees_db(&db_path, args, limit)
+}
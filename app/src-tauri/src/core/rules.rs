@@ -1,17 +1,110 @@
-use crate::models::{Rule, RuleAction, RuleCondition, Transaction};
+use crate::models::{
+    Rule, RuleAction, RuleApplication, RuleCondition, RuleFieldChange, Transaction,
+    TransactionRuleDiff,
+};
+use crate::performance::parse_date;
+use crate::transactions::{transaction_from_row, TRANSACTION_COLUMNS};
+use chrono::{Datelike, Days, NaiveDate};
+use regex::{Regex, RegexBuilder};
 use rusqlite::{params, Connection};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, OnceLock};
 use tauri::AppHandle;
 
+/// Transaction fields a condition can test. `account` is the account id and `price` the
+/// price per share.
+pub const RULE_FIELDS: [&str; 11] = [
+    "payee", "notes", "category", "amount", "date", "ticker", "shares", "price", "fee", "account",
+    "currency",
+];
+
+/// Transaction fields rule actions can set, in the order changes are reported.
+const ACTION_FIELDS: [&str; 3] = ["payee", "notes", "category"];
+
+/// Fields compared as numbers by `equals`, `in`, `greater_than`, `less_than` and `between`.
+pub const NUMERIC_RULE_FIELDS: [&str; 5] = ["amount", "shares", "price", "fee", "account"];
+
+/// Condition operators. Text comparisons ignore case. `in` takes a comma-separated list,
+/// `between` an inclusive "low,high" pair, `sign` one of positive, negative or zero,
+/// `within_days` a number of days back from today and `day_of_month` a list of days.
+pub const RULE_OPERATORS: [&str; 18] = [
+    "equals",
+    "not_equals",
+    "contains",
+    "not_contains",
+    "starts_with",
+    "ends_with",
+    "regex",
+    "in",
+    "is_empty",
+    "is_not_empty",
+    "greater_than",
+    "less_than",
+    "between",
+    "sign",
+    "before",
+    "after",
+    "within_days",
+    "day_of_month",
+];
+
 /// Evaluates all rules against a transaction and applies the actions of matching rules.
 /// Rules are applied in reverse order of the slice (assuming input is priority DESC,
 /// we apply lowest priority first so highest priority wins).
 pub fn apply_rules_to_transaction(transaction: &mut Transaction, rules: &[Rule]) {
+    apply_rules_with_changes(transaction, rules);
+}
+
+/// Same as `apply_rules_to_transaction`, returning the fields that ended up different and
+/// the rule that set each of them.
+pub fn apply_rules_with_changes(
+    transaction: &mut Transaction,
+    rules: &[Rule],
+) -> Vec<RuleFieldChange> {
+    let before = [
+        Some(transaction.payee.clone()),
+        transaction.notes.clone(),
+        transaction.category.clone(),
+    ];
+    let mut setters: [Option<i32>; 3] = [None; 3];
     for rule in rules.iter().rev() {
         if matches_rule(transaction, rule) {
             apply_rule_actions(transaction, rule);
+            for (i, field) in ACTION_FIELDS.iter().enumerate() {
+                if rule_sets_field(rule, field) {
+                    setters[i] = Some(rule.id);
+                }
+            }
+        }
+    }
+    let after = [
+        Some(transaction.payee.clone()),
+        transaction.notes.clone(),
+        transaction.category.clone(),
+    ];
+
+    let mut changes = Vec::new();
+    for (i, field) in ACTION_FIELDS.iter().enumerate() {
+        if let (true, Some(rule_id)) = (before[i] != after[i], setters[i]) {
+            changes.push(RuleFieldChange {
+                field: field.to_string(),
+                old_value: before[i].clone(),
+                new_value: after[i].clone(),
+                rule_id,
+            });
         }
     }
+    changes
+}
+
+fn rule_sets_field(rule: &Rule, field: &str) -> bool {
+    if rule.actions.is_empty() {
+        rule.action_field == field && !rule.action_value.is_empty()
+    } else {
+        rule.actions.iter().any(|a| a.field == field)
+    }
 }
 
 fn matches_rule(transaction: &Transaction, rule: &Rule) -> bool {
@@ -26,9 +119,13 @@ fn matches_rule(transaction: &Transaction, rule: &Rule) -> bool {
     };
 
     if logic == "or" {
-        conditions.iter().any(|c| matches_condition(transaction, c))
+        conditions
+            .iter()
+            .any(|c| matches_condition(transaction, rule.id, c))
     } else {
-        conditions.iter().all(|c| matches_condition(transaction, c))
+        conditions
+            .iter()
+            .all(|c| matches_condition(transaction, rule.id, c))
     }
 }
 
@@ -38,6 +135,7 @@ fn matches_legacy(transaction: &Transaction, field: &str, pattern: &str) -> bool
 }
 
 fn get_transaction_field(transaction: &Transaction, field: &str) -> String {
+    let number = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
     match field {
         "payee" => transaction.payee.clone(),
         "notes" => transaction.notes.clone().unwrap_or_default(),
@@ -45,29 +143,145 @@ fn get_transaction_field(transaction: &Transaction, field: &str) -> String {
         "amount" => transaction.amount.to_string(),
         "date" => transaction.date.clone(),
         "ticker" => transaction.ticker.clone().unwrap_or_default(),
+        "shares" => number(transaction.shares),
+        "price" => number(transaction.price_per_share),
+        "fee" => number(transaction.fee),
+        "account" => transaction.account_id.to_string(),
+        "currency" => transaction.currency.clone().unwrap_or_default(),
         _ => String::new(),
     }
 }
 
-fn matches_condition(transaction: &Transaction, condition: &RuleCondition) -> bool {
-    let val = get_transaction_field(transaction, &condition.field);
+fn parse_number(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn parse_day(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value.get(..10).unwrap_or(value), "%Y-%m-%d").ok()
+}
+
+// Items of an `in`, `between` or `day_of_month` value
+fn list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn range_bounds(value: &str) -> Option<(&str, &str)> {
+    let bounds: Vec<&str> = list(value).collect();
+    match bounds[..] {
+        [low, high] => Some((low, high)),
+        _ => None,
+    }
+}
+
+type RegexCache = HashMap<(i32, String), Option<Regex>>;
+
+// Compiled regex conditions of the rules last loaded by `get_rules_db`, by rule id and pattern
+fn regex_cache() -> MutexGuard<'static, RegexCache> {
+    static CACHE: OnceLock<Mutex<RegexCache>> = OnceLock::new();
+    CACHE
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+// Patterns are compiled as stored, surrounding whitespace included
+fn build_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+// Keeps the cache to the patterns of `rules`, compiling only those new since the last load
+fn cache_rule_regexes(rules: &[Rule]) {
+    let wanted: HashSet<(i32, &str)> = rules
+        .iter()
+        .flat_map(|rule| {
+            rule.conditions
+                .iter()
+                .filter(|condition| condition.operator == "regex")
+                .map(|condition| (rule.id, condition.value.as_str()))
+        })
+        .collect();
+    let mut cache = regex_cache();
+    cache.retain(|(id, pattern), _| wanted.contains(&(*id, pattern.as_str())));
+    for (id, pattern) in wanted {
+        cache
+            .entry((id, pattern.to_string()))
+            .or_insert_with(|| build_regex(pattern).ok());
+    }
+}
+
+// Case-insensitive match; patterns of rules not loaded from the database are compiled here
+fn regex_matches(rule_id: i32, pattern: &str, value: &str) -> bool {
+    if let Some(compiled) = regex_cache().get(&(rule_id, pattern.to_string())) {
+        return compiled.as_ref().is_some_and(|re| re.is_match(value));
+    }
+    build_regex(pattern).is_ok_and(|re| re.is_match(value))
+}
+
+// Compares `value` with `pattern` as dates for the date field and numbers otherwise
+fn compare(field: &str, value: &str, pattern: &str) -> Option<Ordering> {
+    if field == "date" {
+        Some(parse_day(value)?.cmp(&parse_day(pattern)?))
+    } else {
+        parse_number(value)?.partial_cmp(&parse_number(pattern)?)
+    }
+}
+
+fn matches_condition(transaction: &Transaction, rule_id: i32, condition: &RuleCondition) -> bool {
+    let field = condition.field.as_str();
+    let val = get_transaction_field(transaction, field);
     let pattern = &condition.value;
+    let numeric = NUMERIC_RULE_FIELDS.contains(&field);
+    let same = |value: &str, pattern: &str| {
+        if numeric {
+            matches!(compare(field, value, pattern), Some(Ordering::Equal))
+        } else {
+            value.trim().to_lowercase() == pattern.trim().to_lowercase()
+        }
+    };
 
     let matched = match condition.operator.as_str() {
-        "equals" => val.to_lowercase() == pattern.to_lowercase(),
+        "equals" => same(&val, pattern),
+        "not_equals" => !same(&val, pattern),
         "contains" => val.to_lowercase().contains(&pattern.to_lowercase()),
+        "not_contains" => !val.to_lowercase().contains(&pattern.to_lowercase()),
         "starts_with" => val.to_lowercase().starts_with(&pattern.to_lowercase()),
         "ends_with" => val.to_lowercase().ends_with(&pattern.to_lowercase()),
-        "greater_than" => {
-            let v = val.parse::<f64>().unwrap_or(0.0);
-            let p = pattern.parse::<f64>().unwrap_or(0.0);
-            v > p
-        }
-        "less_than" => {
-            let v = val.parse::<f64>().unwrap_or(0.0);
-            let p = pattern.parse::<f64>().unwrap_or(0.0);
-            v < p
+        "regex" => regex_matches(rule_id, pattern, &val),
+        "in" => list(pattern).any(|item| same(&val, item)),
+        "is_empty" => val.trim().is_empty(),
+        "is_not_empty" => !val.trim().is_empty(),
+        "greater_than" | "after" => {
+            matches!(compare(field, &val, pattern), Some(Ordering::Greater))
         }
+        "less_than" | "before" => matches!(compare(field, &val, pattern), Some(Ordering::Less)),
+        "between" => range_bounds(pattern).is_some_and(|(low, high)| {
+            matches!(
+                compare(field, &val, low),
+                Some(Ordering::Greater | Ordering::Equal)
+            ) && matches!(
+                compare(field, &val, high),
+                Some(Ordering::Less | Ordering::Equal)
+            )
+        }),
+        "sign" => parse_number(&val).is_some_and(|v| match pattern.trim() {
+            "positive" => v > 0.0,
+            "negative" => v < 0.0,
+            "zero" => v == 0.0,
+            _ => false,
+        }),
+        "within_days" => match (parse_day(&val), pattern.trim().parse::<u64>()) {
+            (Some(date), Ok(days)) => {
+                let today = chrono::Local::now().date_naive();
+                date <= today
+                    && today
+                        .checked_sub_days(Days::new(days))
+                        .is_none_or(|s| date >= s)
+            }
+            _ => false,
+        },
+        "day_of_month" => parse_day(&val)
+            .is_some_and(|date| list(pattern).any(|d| d.parse::<u32>() == Ok(date.day()))),
         _ => false,
     };
 
@@ -78,6 +292,93 @@ fn matches_condition(transaction: &Transaction, condition: &RuleCondition) -> bo
     }
 }
 
+/// Checks that a rule's conditions can match: known fields and operators, operators that
+/// suit the field, and values that parse as the numbers, dates or pattern they stand for.
+pub fn validate_rule_conditions(logic: &str, conditions: &[RuleCondition]) -> Result<(), String> {
+    if logic != "and" && logic != "or" {
+        return Err(format!("Unknown rule logic: {}", logic));
+    }
+    for condition in conditions {
+        let field = condition.field.as_str();
+        let operator = condition.operator.as_str();
+        let value = condition.value.trim();
+        if !RULE_FIELDS.contains(&field) {
+            return Err(format!("Unknown rule field: {}", field));
+        }
+        if !RULE_OPERATORS.contains(&operator) {
+            return Err(format!("Unknown rule operator: {}", operator));
+        }
+        let numeric = NUMERIC_RULE_FIELDS.contains(&field);
+        let is_date = field == "date";
+        let check_value = |item: &str| -> Result<(), String> {
+            if is_date && parse_day(item).is_none() {
+                Err(format!("Invalid date for {}: {}", field, item))
+            } else if numeric && parse_number(item).is_none() {
+                Err(format!("Invalid number for {}: {}", field, item))
+            } else {
+                Ok(())
+            }
+        };
+        match operator {
+            "equals" | "not_equals" if numeric => check_value(value)?,
+            "in" => {
+                if list(value).next().is_none() {
+                    return Err(format!("{} needs at least one value", operator));
+                }
+                if numeric {
+                    list(value).try_for_each(check_value)?;
+                }
+            }
+            "regex" => {
+                build_regex(&condition.value)
+                    .map_err(|e| format!("Invalid regex {}: {}", condition.value, e))?;
+            }
+            "greater_than" | "less_than" | "between" if !numeric && !is_date => {
+                return Err(format!("{} needs a number or date field", operator));
+            }
+            "greater_than" | "less_than" => check_value(value)?,
+            "between" => {
+                let (low, high) = range_bounds(value)
+                    .ok_or_else(|| format!("between needs two values, got {}", value))?;
+                check_value(low)?;
+                check_value(high)?;
+                if compare(field, low, high) == Some(Ordering::Greater) {
+                    return Err(format!("between {} and {} is an empty range", low, high));
+                }
+            }
+            "sign" => {
+                if !numeric {
+                    return Err("sign needs a number field".to_string());
+                }
+                if !["positive", "negative", "zero"].contains(&value) {
+                    return Err(format!("Unknown sign: {}", value));
+                }
+            }
+            "before" | "after" | "within_days" | "day_of_month" if !is_date => {
+                return Err(format!("{} needs the date field", operator));
+            }
+            "before" | "after" => check_value(value)?,
+            "within_days" => {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid number of days: {}", value))?;
+            }
+            "day_of_month" => {
+                if list(value).next().is_none() {
+                    return Err("day_of_month needs at least one day".to_string());
+                }
+                for day in list(value) {
+                    if !day.parse::<u32>().is_ok_and(|d| (1..=31).contains(&d)) {
+                        return Err(format!("Invalid day of month: {}", day));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 fn apply_rule_actions(transaction: &mut Transaction, rule: &Rule) {
     if !rule.actions.is_empty() {
         for action in &rule.actions {
@@ -141,6 +442,7 @@ pub fn get_rules_db(db_path: &PathBuf) -> Result<Vec<Rule>, String> {
     for rule in rule_iter {
         rules.push(rule.map_err(|e| e.to_string())?);
     }
+    cache_rule_regexes(&rules);
     Ok(rules)
 }
 
@@ -157,6 +459,7 @@ pub struct CreateRuleDbParams {
 }
 
 pub fn create_rule_db(db_path: &PathBuf, params: CreateRuleDbParams) -> Result<i32, String> {
+    validate_rule_conditions(&params.logic, &params.conditions)?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     let conditions_json = serde_json::to_string(&params.conditions).map_err(|e| e.to_string())?;
@@ -195,6 +498,7 @@ pub struct UpdateRuleDbParams {
 }
 
 pub fn update_rule_db(db_path: &PathBuf, params: UpdateRuleDbParams) -> Result<(), String> {
+    validate_rule_conditions(&params.logic, &params.conditions)?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     let conditions_json = serde_json::to_string(&params.conditions).map_err(|e| e.to_string())?;
@@ -248,6 +552,129 @@ pub fn update_rules_order_db(db_path: &PathBuf, rule_ids: Vec<i32>) -> Result<()
     Ok(())
 }
 
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyRulesArgs {
+    /// First day included, no lower bound when missing
+    pub from: Option<String>,
+    /// Last day included, no upper bound when missing
+    pub to: Option<String>,
+    pub account_ids: Option<Vec<i32>>,
+    /// Rules to run, all of them when missing. Priorities still decide between them.
+    pub rule_ids: Option<Vec<i32>>,
+    /// Report the changes without saving them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Runs rules over existing transactions and reports, per transaction, each field that
+/// changes and the rule that set it. All changes are saved in one database transaction,
+/// or none on a dry run. Transfer legs are skipped as on creation, and reconciled
+/// transactions are counted but left untouched.
+pub fn apply_rules_db(db_path: &PathBuf, args: ApplyRulesArgs) -> Result<RuleApplication, String> {
+    let from = args.from.as_deref().map(parse_date).transpose()?;
+    let to = args.to.as_deref().map(parse_date).transpose()?;
+    let mut rules = get_rules_db(db_path)?;
+    if let Some(ids) = &args.rule_ids {
+        for id in ids {
+            if !rules.iter().any(|r| r.id == *id) {
+                return Err(format!("Rule {} not found", id));
+            }
+        }
+        rules.retain(|r| ids.contains(&r.id));
+    }
+
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let currencies: HashMap<i32, Option<String>> = {
+        let mut stmt = tx
+            .prepare("SELECT id, currency FROM accounts")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())?
+    };
+    let transactions: Vec<Transaction> = {
+        let mut stmt = tx
+            .prepare(&format!(
+                "SELECT {} FROM transactions
+                 WHERE (?1 IS NULL OR substr(date, 1, 10) >= ?1)
+                   AND (?2 IS NULL OR substr(date, 1, 10) <= ?2)
+                 ORDER BY date, id",
+                TRANSACTION_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![
+                    from.map(|d| d.format("%Y-%m-%d").to_string()),
+                    to.map(|d| d.format("%Y-%m-%d").to_string())
+                ],
+                transaction_from_row,
+            )
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    let mut examined = 0;
+    let mut skipped_reconciled = 0;
+    let mut diffs = Vec::new();
+    for mut transaction in transactions {
+        if args
+            .account_ids
+            .as_ref()
+            .is_some_and(|ids| !ids.contains(&transaction.account_id))
+            || transaction.transfer_id.is_some()
+            || transaction.category.as_deref() == Some("Transfer")
+        {
+            continue;
+        }
+        examined += 1;
+        if transaction.currency.is_none() {
+            transaction.currency = currencies.get(&transaction.account_id).cloned().flatten();
+        }
+        let original_payee = transaction.payee.clone();
+        let changes = apply_rules_with_changes(&mut transaction, &rules);
+        if changes.is_empty() {
+            continue;
+        }
+        if transaction.cleared == "reconciled" {
+            skipped_reconciled += 1;
+            continue;
+        }
+        if !args.dry_run {
+            tx.execute(
+                "UPDATE transactions SET payee = ?1, notes = ?2, category = ?3 WHERE id = ?4",
+                params![
+                    transaction.payee,
+                    transaction.notes,
+                    transaction.category,
+                    transaction.id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        diffs.push(TransactionRuleDiff {
+            transaction_id: transaction.id,
+            account_id: transaction.account_id,
+            date: transaction.date,
+            payee: original_payee,
+            changes,
+        });
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(RuleApplication {
+        dry_run: args.dry_run,
+        examined,
+        skipped_reconciled,
+        transactions: diffs,
+    })
+}
+
 #[tauri::command]
 pub fn get_rules(app_handle: AppHandle) -> Result<Vec<Rule>, String> {
     let db_path = crate::db_init::get_db_path(&app_handle)?;
@@ -323,3 +750,9 @@ pub fn update_rules_order(app_handle: AppHandle, rule_ids: Vec<i32>) -> Result<(
     let db_path = crate::db_init::get_db_path(&app_handle)?;
     update_rules_order_db(&db_path, rule_ids)
 }
+
+#[tauri::command]
+pub fn apply_rules(app_handle: AppHandle, args: ApplyRulesArgs) -> Result<RuleApplication, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    apply_rules_db(&db_path, args)
+}
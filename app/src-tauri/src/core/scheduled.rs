@@ -0,0 +1,279 @@
+use crate::models::ScheduledTransaction;
+use crate::performance::parse_date;
+use chrono::{Days, Months, NaiveDate};
+use rusqlite::{params, Connection};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// How often a scheduled transaction repeats. Monthly, quarterly and yearly ones keep the
+/// day of `next_date`, falling back to the month end in shorter months.
+pub const SCHEDULE_FREQUENCIES: [&str; 7] = [
+    "once",
+    "daily",
+    "weekly",
+    "biweekly",
+    "monthly",
+    "quarterly",
+    "yearly",
+];
+
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTransactionArgs {
+    pub account_id: i32,
+    pub payee: String,
+    pub category: Option<String>,
+    pub amount: f64,
+    pub frequency: String,
+    pub next_date: String,
+    pub end_date: Option<String>,
+    pub notes: Option<String>,
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+// The `index`-th occurrence counted from `start`, `None` past the first for one-off items
+fn nth_occurrence(start: NaiveDate, frequency: &str, index: u32) -> Option<NaiveDate> {
+    match frequency {
+        "once" => (index == 0).then_some(start),
+        "daily" => start.checked_add_days(Days::new(u64::from(index))),
+        "weekly" => start.checked_add_days(Days::new(7 * u64::from(index))),
+        "biweekly" => start.checked_add_days(Days::new(14 * u64::from(index))),
+        "monthly" => start.checked_add_months(Months::new(index)),
+        "quarterly" => start.checked_add_months(Months::new(3 * index)),
+        "yearly" => start.checked_add_months(Months::new(12 * index)),
+        _ => None,
+    }
+}
+
+/// Dates the item falls on after `after` and up to `until`.
+pub(crate) fn occurrences(
+    item: &ScheduledTransaction,
+    after: NaiveDate,
+    until: NaiveDate,
+) -> Result<Vec<NaiveDate>, String> {
+    let start = parse_date(&item.next_date)?;
+    let until = match &item.end_date {
+        Some(end) => parse_date(end)?.min(until),
+        None => until,
+    };
+    let mut dates = Vec::new();
+    let mut index = 0;
+    while let Some(date) = nth_occurrence(start, &item.frequency, index) {
+        if date > until {
+            break;
+        }
+        if date > after {
+            dates.push(date);
+        }
+        index += 1;
+    }
+    Ok(dates)
+}
+
+fn validate(
+    conn: &Connection,
+    args: ScheduledTransactionArgs,
+) -> Result<ScheduledTransactionArgs, String> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM accounts WHERE id = ?1)",
+            params![args.account_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!("Account {} not found", args.account_id));
+    }
+    let payee = args.payee.trim().to_string();
+    if payee.is_empty() {
+        return Err("Payee is required".to_string());
+    }
+    if !args.amount.is_finite() {
+        return Err("Amount must be a number".to_string());
+    }
+    let frequency = args.frequency.trim().to_lowercase();
+    if !SCHEDULE_FREQUENCIES.contains(&frequency.as_str()) {
+        return Err(format!("Unknown frequency: {}", args.frequency));
+    }
+    let next_date = parse_date(args.next_date.trim())?;
+    let end_date = non_empty(args.end_date);
+    if let Some(end) = &end_date {
+        if parse_date(end)? < next_date {
+            return Err("End date is before the next date".to_string());
+        }
+    }
+    Ok(ScheduledTransactionArgs {
+        account_id: args.account_id,
+        payee,
+        category: non_empty(args.category),
+        amount: args.amount,
+        frequency,
+        next_date: next_date.format("%Y-%m-%d").to_string(),
+        end_date,
+        notes: non_empty(args.notes),
+    })
+}
+
+fn into_scheduled(id: i32, args: ScheduledTransactionArgs) -> ScheduledTransaction {
+    ScheduledTransaction {
+        id,
+        account_id: args.account_id,
+        payee: args.payee,
+        category: args.category,
+        amount: args.amount,
+        frequency: args.frequency,
+        next_date: args.next_date,
+        end_date: args.end_date,
+        notes: args.notes,
+    }
+}
+
+pub fn create_scheduled_transaction_db(
+    db_path: &PathBuf,
+    args: ScheduledTransactionArgs,
+) -> Result<ScheduledTransaction, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let args = validate(&conn, args)?;
+    conn.execute(
+        "INSERT INTO scheduled_transactions
+            (account_id, payee, category, amount, frequency, next_date, end_date, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            args.account_id,
+            args.payee,
+            args.category,
+            args.amount,
+            args.frequency,
+            args.next_date,
+            args.end_date,
+            args.notes
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(into_scheduled(conn.last_insert_rowid() as i32, args))
+}
+
+/// Scheduled transactions of the given accounts (all when `None`), soonest first.
+pub fn get_scheduled_transactions_db(
+    db_path: &PathBuf,
+    account_ids: Option<Vec<i32>>,
+) -> Result<Vec<ScheduledTransaction>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, account_id, payee, category, amount, frequency, next_date, end_date, notes
+             FROM scheduled_transactions ORDER BY next_date, id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ScheduledTransaction {
+                id: row.get(0)?,
+                account_id: row.get(1)?,
+                payee: row.get(2)?,
+                category: row.get(3)?,
+                amount: row.get(4)?,
+                frequency: row.get(5)?,
+                next_date: row.get(6)?,
+                end_date: row.get(7)?,
+                notes: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut items = Vec::new();
+    for row in rows {
+        let item = row.map_err(|e| e.to_string())?;
+        if account_ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&item.account_id))
+        {
+            items.push(item);
+        }
+    }
+    Ok(items)
+}
+
+pub fn update_scheduled_transaction_db(
+    db_path: &PathBuf,
+    id: i32,
+    args: ScheduledTransactionArgs,
+) -> Result<ScheduledTransaction, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let args = validate(&conn, args)?;
+    let updated = conn
+        .execute(
+            "UPDATE scheduled_transactions
+             SET account_id = ?1, payee = ?2, category = ?3, amount = ?4, frequency = ?5,
+                 next_date = ?6, end_date = ?7, notes = ?8
+             WHERE id = ?9",
+            params![
+                args.account_id,
+                args.payee,
+                args.category,
+                args.amount,
+                args.frequency,
+                args.next_date,
+                args.end_date,
+                args.notes,
+                id
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Scheduled transaction not found".to_string());
+    }
+    Ok(into_scheduled(id, args))
+}
+
+pub fn delete_scheduled_transaction_db(db_path: &PathBuf, id: i32) -> Result<(), String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let deleted = conn
+        .execute(
+            "DELETE FROM scheduled_transactions WHERE id = ?1",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err("Scheduled transaction not found".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn create_scheduled_transaction(
+    app_handle: AppHandle,
+    args: ScheduledTransactionArgs,
+) -> Result<ScheduledTransaction, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    create_scheduled_transaction_db(&db_path, args)
+}
+
+#[tauri::command]
+pub fn get_scheduled_transactions(
+    app_handle: AppHandle,
+    account_ids: Option<Vec<i32>>,
+) -> Result<Vec<ScheduledTransaction>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_scheduled_transactions_db(&db_path, account_ids)
+}
+
+#[tauri::command]
+pub fn update_scheduled_transaction(
+    app_handle: AppHandle,
+    id: i32,
+    args: ScheduledTransactionArgs,
+) -> Result<ScheduledTransaction, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    update_scheduled_transaction_db(&db_path, id, args)
+}
+
+#[tauri::command]
+pub fn delete_scheduled_transaction(app_handle: AppHandle, id: i32) -> Result<(), String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    delete_scheduled_transaction_db(&db_path, id)
+}
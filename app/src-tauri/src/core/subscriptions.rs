@@ -0,0 +1,250 @@
+use crate::models::{PriceChange, Subscription};
+use crate::performance::parse_date;
+use crate::transfers::round_cents;
+use chrono::{Days, Months, NaiveDate};
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// `changed` subscriptions had their last charge at a different amount than the one before;
+/// `stopped` ones missed their expected charge by more than a grace period.
+pub const SUBSCRIPTION_STATUSES: [&str; 3] = ["active", "changed", "stopped"];
+
+struct Cadence {
+    name: &'static str,
+    // Days allowed between two charges
+    gap: RangeInclusive<i64>,
+    min_charges: usize,
+    per_year: f64,
+    // Days past the expected charge before a subscription counts as stopped
+    grace: u64,
+}
+
+const CADENCES: [Cadence; 3] = [
+    Cadence {
+        name: "weekly",
+        gap: 5..=9,
+        min_charges: 3,
+        per_year: 52.0,
+        grace: 3,
+    },
+    Cadence {
+        name: "monthly",
+        gap: 26..=35,
+        min_charges: 3,
+        per_year: 12.0,
+        grace: 7,
+    },
+    Cadence {
+        name: "yearly",
+        gap: 350..=380,
+        min_charges: 2,
+        per_year: 1.0,
+        grace: 30,
+    },
+];
+
+impl Cadence {
+    fn next(&self, date: NaiveDate) -> NaiveDate {
+        match self.name {
+            "weekly" => date + Days::new(7),
+            "monthly" => date + Months::new(1),
+            _ => date + Months::new(12),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionArgs {
+    pub account_ids: Option<Vec<i32>>,
+    /// Day charges are judged against, today when missing
+    pub as_of: Option<String>,
+}
+
+struct Charge {
+    account_id: i32,
+    date: NaiveDate,
+    payee: String,
+    category: Option<String>,
+    amount: f64,
+}
+
+/// Lowercased payee without punctuation or tokens containing digits, so that
+/// "NETFLIX.COM 8841" and "Netflix.com" match.
+pub(crate) fn normalize_payee(payee: &str) -> String {
+    let lower = payee.to_lowercase();
+    let normalized = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty() && !token.chars().any(|c| c.is_ascii_digit()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if normalized.is_empty() {
+        lower.trim().to_string()
+    } else {
+        normalized
+    }
+}
+
+// Share of the run's median a charge may differ by and still belong to the run
+const AMOUNT_TOLERANCE: f64 = 0.2;
+
+// Whether a charge is within the tolerance of the median of the run so far
+fn similar(run: &[Charge], amount: f64) -> bool {
+    let mut amounts: Vec<f64> = run.iter().map(|c| c.amount).collect();
+    amounts.sort_by(f64::total_cmp);
+    let mid = amounts.len() / 2;
+    let median = if amounts.len().is_multiple_of(2) {
+        (amounts[mid - 1] + amounts[mid]) / 2.0
+    } else {
+        amounts[mid]
+    };
+    (amount - median).abs() <= median * AMOUNT_TOLERANCE
+}
+
+// Latest run of charges at one cadence, counting back from the last charge
+fn detect(charges: &[Charge]) -> Option<(&'static Cadence, &[Charge])> {
+    let n = charges.len();
+    if n < 2 {
+        return None;
+    }
+    let last_gap = (charges[n - 1].date - charges[n - 2].date).num_days();
+    let cadence = CADENCES.iter().find(|c| c.gap.contains(&last_gap))?;
+    let mut start = n - 1;
+    while start > 0 {
+        let gap = (charges[start].date - charges[start - 1].date).num_days();
+        if !cadence.gap.contains(&gap) || !similar(&charges[start..], charges[start - 1].amount) {
+            break;
+        }
+        start -= 1;
+    }
+    let run = &charges[start..];
+    (run.len() >= cadence.min_charges).then_some((cadence, run))
+}
+
+/// Finds recurring charges: spending at the same normalized payee and currency, at similar
+/// amounts, repeating weekly, monthly or yearly. Only the latest unbroken run of charges is
+/// reported, with the next expected charge, the yearly cost at the last amount and every
+/// change of amount along the way. Transfers and investment transactions are ignored.
+pub fn detect_subscriptions_db(
+    db_path: &PathBuf,
+    args: SubscriptionArgs,
+) -> Result<Vec<Subscription>, String> {
+    let as_of = match &args.as_of {
+        Some(day) => parse_date(day)?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.account_id, t.date, COALESCE(t.payee, ''), t.category, t.amount,
+                    COALESCE(t.currency, a.currency, 'USD')
+             FROM transactions t JOIN accounts a ON a.id = t.account_id
+             WHERE t.amount < 0
+               AND (t.ticker IS NULL OR t.ticker = '')
+               AND COALESCE(t.category, '') != 'Transfer' AND t.transfer_id IS NULL
+               AND substr(t.date, 1, 10) <= ?1
+             ORDER BY t.date, t.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([as_of.format("%Y-%m-%d").to_string()], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut groups: BTreeMap<(String, String), Vec<Charge>> = BTreeMap::new();
+    for row in rows {
+        let (account_id, day, payee, category, amount, currency) =
+            row.map_err(|e| e.to_string())?;
+        if args
+            .account_ids
+            .as_ref()
+            .is_some_and(|ids| !ids.contains(&account_id))
+        {
+            continue;
+        }
+        let key = normalize_payee(&payee);
+        if key.is_empty() {
+            continue;
+        }
+        groups.entry((key, currency)).or_default().push(Charge {
+            account_id,
+            date: parse_date(&day)?,
+            payee: payee.trim().to_string(),
+            category: category.filter(|c| !c.trim().is_empty()),
+            amount: -amount,
+        });
+    }
+
+    let mut subscriptions = Vec::new();
+    for ((normalized_payee, currency), charges) in groups {
+        let Some((cadence, run)) = detect(&charges) else {
+            continue;
+        };
+        let mut price_changes = Vec::new();
+        for pair in run.windows(2) {
+            let (old, new) = (pair[0].amount, pair[1].amount);
+            if (new - old).abs() >= 0.005 {
+                price_changes.push(PriceChange {
+                    date: pair[1].date.format("%Y-%m-%d").to_string(),
+                    old_amount: round_cents(old),
+                    new_amount: round_cents(new),
+                    percent: (new - old) / old * 100.0,
+                });
+            }
+        }
+        let last = &run[run.len() - 1];
+        let next_charge = cadence.next(last.date);
+        let status = if as_of > next_charge + Days::new(cadence.grace) {
+            "stopped"
+        } else if (last.amount - run[run.len() - 2].amount).abs() >= 0.005 {
+            "changed"
+        } else {
+            "active"
+        };
+        subscriptions.push(Subscription {
+            payee: last.payee.clone(),
+            normalized_payee,
+            account_id: last.account_id,
+            category: last.category.clone(),
+            currency,
+            cadence: cadence.name.to_string(),
+            charges: run.len() as i32,
+            first_charge: run[0].date.format("%Y-%m-%d").to_string(),
+            last_charge: last.date.format("%Y-%m-%d").to_string(),
+            last_amount: round_cents(last.amount),
+            average_amount: round_cents(
+                run.iter().map(|c| c.amount).sum::<f64>() / run.len() as f64,
+            ),
+            next_charge: next_charge.format("%Y-%m-%d").to_string(),
+            annualized_cost: round_cents(last.amount * cadence.per_year),
+            price_changes,
+            status: status.to_string(),
+        });
+    }
+    subscriptions.sort_by(|a, b| {
+        b.annualized_cost
+            .total_cmp(&a.annualized_cost)
+            .then(a.payee.cmp(&b.payee))
+    });
+    Ok(subscriptions)
+}
+
+#[tauri::command]
+pub fn detect_subscriptions(
+    app_handle: AppHandle,
+    args: SubscriptionArgs,
+) -> Result<Vec<Subscription>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    detect_subscriptions_db(&db_path, args)
+}
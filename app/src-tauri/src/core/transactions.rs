@@ -3,6 +3,57 @@ use rusqlite::{params, Connection, OptionalExtension};
 use std::path::PathBuf;
 use tauri::AppHandle;
 
+/// Columns read by `transaction_from_row`, in order
+pub(crate) const TRANSACTION_COLUMNS: &str = "id, account_id, date, payee, notes, category, amount, ticker, shares, price_per_share, fee, currency, COALESCE(cleared, 'uncleared'), transfer_id, action";
+
+pub(crate) fn transaction_from_row(row: &rusqlite::Row) -> rusqlite::Result<Transaction> {
+    Ok(Transaction {
+        id: row.get(0)?,
+        account_id: row.get(1)?,
+        date: row.get(2)?,
+        payee: row.get(3)?,
+        notes: row.get(4)?,
+        category: row.get(5)?,
+        amount: row.get(6)?,
+        ticker: row.get(7)?,
+        shares: row.get(8)?,
+        price_per_share: row.get(9)?,
+        fee: row.get(10)?,
+        currency: row.get(11)?,
+        cleared: row.get(12)?,
+        transfer_id: row.get(13)?,
+        action: row.get(14)?,
+    })
+}
+
+pub(crate) fn get_transaction_by_id(conn: &Connection, id: i32) -> Result<Transaction, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM transactions WHERE id = ?1",
+            TRANSACTION_COLUMNS
+        ),
+        params![id],
+        transaction_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Currency of the account, which rules see for transactions without their own
+pub(crate) fn account_currency(
+    conn: &Connection,
+    account_id: i32,
+) -> Result<Option<String>, String> {
+    Ok(conn
+        .query_row(
+            "SELECT currency FROM accounts WHERE id = ?1",
+            params![account_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .flatten())
+}
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateTransactionArgs {
@@ -25,8 +76,10 @@ pub fn create_transaction_db(
 ) -> Result<Transaction, String> {
     let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
-    // Apply rules before starting transaction
+    // Apply rules before starting transaction. Rules see the account's currency when the
+    // transaction doesn't have its own.
     let rules = crate::rules::get_rules_db(db_path).unwrap_or_default();
+    let account_currency = account_currency(&conn, args.account_id)?;
     let mut temp_tx = Transaction {
         id: 0,
         account_id: args.account_id,
@@ -39,7 +92,10 @@ pub fn create_transaction_db(
         shares: args.shares,
         price_per_share: args.price_per_share,
         fee: args.fee,
-        currency: args.currency.clone(),
+        currency: args.currency.clone().or(account_currency),
+        cleared: crate::models::default_cleared_state(),
+        transfer_id: None,
+        action: None,
     };
     crate::rules::apply_rules_to_transaction(&mut temp_tx, &rules);
 
@@ -49,15 +105,20 @@ pub fn create_transaction_db(
 
     let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    // Check if payee matches another account for Transfer detection
-    let target_account_info: Option<i32> = tx
-        .query_row(
-            "SELECT id FROM accounts WHERE name = ?1 AND id != ?2",
+    // A payee naming another account only makes a transfer when the caller marks the row as
+    // one, so merchants that share an account's name don't produce phantom counterparts.
+    // Explicit transfers should go through `transfers::create_transfer_db`.
+    let target_account_info: Option<i32> = if args.category.as_deref() == Some("Transfer") {
+        tx.query_row(
+            "SELECT id FROM accounts WHERE name = ?1 AND id != ?2 AND COALESCE(archived, 0) = 0",
             params![final_payee, args.account_id],
             |row| row.get(0),
         )
         .optional()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())?
+    } else {
+        None
+    };
 
     let final_category = if target_account_info.is_some() {
         Some("Transfer".to_string())
@@ -78,44 +139,79 @@ pub fn create_transaction_db(
     )
     .map_err(|e| e.to_string())?;
 
+    let mut transfer_id = None;
+    let mut currency = args.currency;
     if let Some(target_id) = target_account_info {
-        // Get source account name for the target transaction's payee
-        let source_name: String = tx
+        // Get source account name and currency for the target transaction
+        let (source_name, source_currency): (String, Option<String>) = tx
             .query_row(
-                "SELECT name FROM accounts WHERE id = ?1",
+                "SELECT name, currency FROM accounts WHERE id = ?1",
                 params![args.account_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        let target_currency: Option<String> = tx
+            .query_row(
+                "SELECT currency FROM accounts WHERE id = ?1",
+                params![target_id],
                 |row| row.get(0),
             )
             .map_err(|e| e.to_string())?;
 
-        // Insert target transaction
-        tx.execute(
-            "INSERT INTO transactions (account_id, date, payee, notes, category, amount) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![target_id, args.date, source_name, args.notes, "Transfer", -args.amount],
-        ).map_err(|e| e.to_string())?;
+        // Each leg is recorded in its own account currency, converted at the market rate. Without
+        // a known rate the legs are recorded at par and the transfer is flagged for correction.
+        currency = currency.or(source_currency);
+        let known_rate = match (&currency, &target_currency) {
+            (Some(from), Some(to)) if from != to => {
+                crate::fx::rate_on(&tx, from, to, Some(&args.date))
+            }
+            _ => Some(1.0),
+        };
+        let rate = known_rate.unwrap_or(1.0);
+        let target_amount = if rate == 1.0 {
+            -args.amount
+        } else {
+            crate::transfers::round_cents(-args.amount * rate)
+        };
 
-        // Capture inserted target transaction id and link both transactions for future sync
-        let target_tx_id = tx.last_insert_rowid() as i32;
         tx.execute(
-            "UPDATE transactions SET linked_tx_id = ?1 WHERE id = ?2",
-            params![target_tx_id, id],
+            "UPDATE transactions SET currency = ?1 WHERE id = ?2",
+            params![currency, id],
         )
         .map_err(|e| e.to_string())?;
+
+        // Insert target transaction
         tx.execute(
-            "UPDATE transactions SET linked_tx_id = ?1 WHERE id = ?2",
-            params![id, target_tx_id],
-        )
-        .map_err(|e| e.to_string())?;
+            "INSERT INTO transactions (account_id, date, payee, notes, category, amount, currency) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![target_id, args.date, source_name, args.notes, "Transfer", target_amount, target_currency],
+        ).map_err(|e| e.to_string())?;
+
+        // Capture inserted target transaction id and link both transactions for future sync.
+        // The stored rate is always destination per source, whichever leg was entered.
+        let target_tx_id = tx.last_insert_rowid() as i32;
+        transfer_id = Some(if args.amount <= 0.0 {
+            crate::transfers::record_transfer(&tx, id, target_tx_id, 0.0, rate)?
+        } else {
+            crate::transfers::record_transfer(&tx, target_tx_id, id, 0.0, 1.0 / rate)?
+        });
+        if known_rate.is_none() {
+            tx.execute(
+                "UPDATE transfers SET rate_estimated = 1 WHERE id = ?1",
+                params![transfer_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
 
         // Update target account balance
         tx.execute(
             "UPDATE accounts SET balance = balance + ?1 WHERE id = ?2",
-            params![-args.amount, target_id],
+            params![target_amount, target_id],
         )
         .map_err(|e| e.to_string())?;
     }
 
     tx.commit().map_err(|e| e.to_string())?;
+    crate::holdings::invalidate_holdings_cache(db_path);
 
     Ok(Transaction {
         id,
@@ -129,31 +225,24 @@ pub fn create_transaction_db(
         shares: args.shares,
         price_per_share: args.price_per_share,
         fee: args.fee,
-        currency: args.currency,
+        currency,
+        cleared: crate::models::default_cleared_state(),
+        transfer_id,
+        action: None,
     })
 }
 
 pub fn get_transactions_db(db_path: &PathBuf, account_id: i32) -> Result<Vec<Transaction>, String> {
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
-    let mut stmt = conn.prepare("SELECT id, account_id, date, payee, notes, category, amount, ticker, shares, price_per_share, fee, currency FROM transactions WHERE account_id = ?1 ORDER BY date DESC, id DESC").map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM transactions WHERE account_id = ?1 ORDER BY date DESC, id DESC",
+            TRANSACTION_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
     let transaction_iter = stmt
-        .query_map(params![account_id], |row| {
-            Ok(Transaction {
-                id: row.get(0)?,
-                account_id: row.get(1)?,
-                date: row.get(2)?,
-                payee: row.get(3)?,
-                notes: row.get(4)?,
-                category: row.get(5)?,
-                amount: row.get(6)?,
-                ticker: row.get(7)?,
-                shares: row.get(8)?,
-                price_per_share: row.get(9)?,
-                fee: row.get(10)?,
-                currency: row.get(11)?,
-            })
-        })
+        .query_map(params![account_id], transaction_from_row)
         .map_err(|e| e.to_string())?;
 
     let mut transactions = Vec::new();
@@ -167,24 +256,14 @@ pub fn get_transactions_db(db_path: &PathBuf, account_id: i32) -> Result<Vec<Tra
 pub fn get_all_transactions_db(db_path: &PathBuf) -> Result<Vec<Transaction>, String> {
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
-    let mut stmt = conn.prepare("SELECT id, account_id, date, payee, notes, category, amount, ticker, shares, price_per_share, fee, currency FROM transactions ORDER BY date DESC, id DESC").map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM transactions ORDER BY date DESC, id DESC",
+            TRANSACTION_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
     let transaction_iter = stmt
-        .query_map([], |row| {
-            Ok(Transaction {
-                id: row.get(0)?,
-                account_id: row.get(1)?,
-                date: row.get(2)?,
-                payee: row.get(3)?,
-                notes: row.get(4)?,
-                category: row.get(5)?,
-                amount: row.get(6)?,
-                ticker: row.get(7)?,
-                shares: row.get(8)?,
-                price_per_share: row.get(9)?,
-                fee: row.get(10)?,
-                currency: row.get(11)?,
-            })
-        })
+        .query_map([], transaction_from_row)
         .map_err(|e| e.to_string())?;
 
     let mut transactions = Vec::new();
@@ -263,6 +342,7 @@ pub fn create_investment_transaction_db(
     // Apply rules
     let rules = crate::rules::get_rules_db(db_path).unwrap_or_default();
     let is_buy_local = is_buy; // avoid move issues
+    let investment_action = if is_buy { "buy" } else { "sell" };
     let mut temp_tx = Transaction {
         id: 0,
         account_id,
@@ -289,6 +369,9 @@ pub fn create_investment_transaction_db(
         price_per_share: Some(price_per_share),
         fee: Some(fee),
         currency: currency.clone(),
+        cleared: crate::models::default_cleared_state(),
+        transfer_id: None,
+        action: Some(investment_action.to_string()),
     };
     crate::rules::apply_rules_to_transaction(&mut temp_tx, &rules);
 
@@ -308,7 +391,7 @@ pub fn create_investment_transaction_db(
     let investment_shares = if is_buy { shares } else { -shares };
 
     tx.execute(
-        "INSERT INTO transactions (account_id, date, payee, notes, category, amount, ticker, shares, price_per_share, fee, currency) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        "INSERT INTO transactions (account_id, date, payee, notes, category, amount, ticker, shares, price_per_share, fee, currency, action) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
         params![
             account_id,
             date,
@@ -320,7 +403,8 @@ pub fn create_investment_transaction_db(
             investment_shares,
             price_per_share,
             fee,
-            currency
+            currency,
+            investment_action
         ],
     ).map_err(|e| e.to_string())?;
 
@@ -333,6 +417,7 @@ pub fn create_investment_transaction_db(
     .map_err(|e| e.to_string())?;
 
     tx.commit().map_err(|e| e.to_string())?;
+    crate::holdings::invalidate_holdings_cache(db_path);
 
     Ok(Transaction {
         id,
@@ -347,6 +432,9 @@ pub fn create_investment_transaction_db(
         price_per_share: Some(price_per_share),
         fee: Some(fee),
         currency,
+        cleared: crate::models::default_cleared_state(),
+        transfer_id: None,
+        action: Some(investment_action.to_string()),
     })
 }
 
@@ -361,6 +449,22 @@ pub struct UpdateTransactionArgs {
     pub category: Option<String>,
     pub amount: f64,
     pub currency: Option<String>,
+    /// Required to edit a transaction locked by a finished reconciliation
+    #[serde(default)]
+    pub override_reconciled: bool,
+}
+
+/// Rejects changes to reconciled transactions unless the caller explicitly overrides the lock.
+pub(crate) fn ensure_not_reconciled(
+    cleared: &str,
+    override_reconciled: bool,
+) -> Result<(), String> {
+    if cleared == "reconciled" && !override_reconciled {
+        return Err(
+            "Transaction is reconciled; an explicit override is required to change it".to_string(),
+        );
+    }
+    Ok(())
 }
 
 pub fn update_transaction_db(
@@ -376,20 +480,50 @@ pub fn update_transaction_db(
         category,
         amount,
         currency,
+        override_reconciled,
     } = args;
 
     let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let rules = crate::rules::get_rules_db(db_path).unwrap_or_default();
 
     let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    // Get old amount and account
-    let (old_amount, old_account_id): (f64, i32) = tx
+    // Get old amount, account, cleared state, transfer id and investment action
+    let (old_amount, old_account_id, cleared, transfer_id, action): (
+        f64,
+        i32,
+        String,
+        Option<i32>,
+        Option<String>,
+    ) = tx
         .query_row(
-            "SELECT amount, account_id FROM transactions WHERE id = ?1",
+            "SELECT amount, account_id, COALESCE(cleared, 'uncleared'), transfer_id, action FROM transactions WHERE id = ?1",
             params![id],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
         )
         .map_err(|e| e.to_string())?;
+    ensure_not_reconciled(&cleared, override_reconciled)?;
+
+    // Rules run on the edited values like they do on new transactions; transfer legs are
+    // left alone as on creation
+    let (payee, notes, category) =
+        if transfer_id.is_none() && category.as_deref() != Some("Transfer") {
+            let mut edited = get_transaction_by_id(&tx, id)?;
+            edited.account_id = account_id;
+            edited.date = date.clone();
+            edited.payee = payee;
+            edited.notes = notes;
+            edited.category = category;
+            edited.amount = amount;
+            edited.currency = match &currency {
+                Some(currency) => Some(currency.clone()),
+                None => account_currency(&tx, account_id)?,
+            };
+            crate::rules::apply_rules_to_transaction(&mut edited, &rules);
+            (edited.payee, edited.notes, edited.category)
+        } else {
+            (payee, notes, category)
+        };
 
     // Update transaction including account_id to support moving between accounts
     tx.execute(
@@ -422,7 +556,7 @@ pub fn update_transaction_db(
     }
 
     // Try to find and update corresponding transfer transaction if any
-    let mut counterpart_id_opt: Option<i32> = tx
+    let counterpart_id_opt: Option<i32> = tx
         .query_row(
             "SELECT linked_tx_id FROM transactions WHERE id = ?1",
             params![id],
@@ -432,46 +566,21 @@ pub fn update_transaction_db(
         .map_err(|e| e.to_string())?
         .flatten();
 
-    if counterpart_id_opt.is_none() {
-        if let Some(ref n) = notes {
-            // fallback: find by exact notes match
-            if let Some((found_id, _found_amount, _found_acc)) = tx
-                .query_row(
-                    "SELECT id, amount, account_id FROM transactions WHERE notes = ?1 AND category = 'Transfer' AND id != ?2 LIMIT 1",
-                    params![n, id],
-                    |row| Ok((row.get::<_, i32>(0)?, row.get::<_, f64>(1)?, row.get::<_, i32>(2)?)),
-                )
-                .optional()
-                .map_err(|e| e.to_string())?
-            {
-                counterpart_id_opt = Some(found_id);
-                // set linkage for future operations
-                tx.execute(
-                    "UPDATE transactions SET linked_tx_id = ?1 WHERE id = ?2",
-                    params![found_id, id],
-                )
-                .map_err(|e| e.to_string())?;
-                tx.execute(
-                    "UPDATE transactions SET linked_tx_id = ?1 WHERE id = ?2",
-                    params![id, found_id],
-                )
-                .map_err(|e| e.to_string())?;
-            }
-        }
-    }
-
     if let Some(counterpart_id) = counterpart_id_opt {
         // Get old amount and account for counterpart
-        if let Some((old_ctr_amount, ctr_account_id)) = tx
+        if let Some((old_ctr_amount, ctr_account_id, ctr_cleared)) = tx
             .query_row(
-                "SELECT amount, account_id FROM transactions WHERE id = ?1",
+                "SELECT amount, account_id, COALESCE(cleared, 'uncleared') FROM transactions WHERE id = ?1",
                 params![counterpart_id],
-                |row| Ok((row.get::<_, f64>(0)?, row.get::<_, i32>(1)?)),
+                |row| Ok((row.get::<_, f64>(0)?, row.get::<_, i32>(1)?, row.get::<_, String>(2)?)),
             )
             .optional()
             .map_err(|e| e.to_string())?
         {
-            let new_ctr_amount = -amount;
+            ensure_not_reconciled(&ctr_cleared, override_reconciled)?;
+            // The counterpart stays in its own currency at the rate recorded for the transfer
+            let new_ctr_amount =
+                crate::transfers::counterpart_amount(&tx, transfer_id, id, amount)?;
             let ctr_diff = new_ctr_amount - old_ctr_amount;
 
             // Determine payee for counterpart (source account name)
@@ -484,8 +593,8 @@ pub fn update_transaction_db(
                 .map_err(|e| e.to_string())?;
 
             tx.execute(
-                "UPDATE transactions SET date = ?1, payee = ?2, notes = ?3, category = ?4, amount = ?5, currency = ?6 WHERE id = ?7",
-                params![date, source_name, notes, "Transfer", new_ctr_amount, currency, counterpart_id],
+                "UPDATE transactions SET date = ?1, payee = ?2, notes = ?3, category = ?4, amount = ?5 WHERE id = ?6",
+                params![date, source_name, notes, "Transfer", new_ctr_amount, counterpart_id],
             )
             .map_err(|e| e.to_string())?;
 
@@ -500,6 +609,7 @@ pub fn update_transaction_db(
     }
 
     tx.commit().map_err(|e| e.to_string())?;
+    crate::holdings::invalidate_holdings_cache(db_path);
 
     Ok(Transaction {
         id,
@@ -514,6 +624,9 @@ pub fn update_transaction_db(
         price_per_share: None,
         fee: None,
         currency,
+        cleared,
+        transfer_id,
+        action,
     })
 }
 
@@ -530,6 +643,9 @@ pub struct UpdateInvestmentTransactionArgs {
     pub is_buy: bool,
     pub notes: Option<String>,
     pub currency: Option<String>,
+    /// Required to edit a transaction locked by a finished reconciliation
+    #[serde(default)]
+    pub override_reconciled: bool,
 }
 
 pub fn update_investment_transaction_db(
@@ -547,20 +663,22 @@ pub fn update_investment_transaction_db(
         is_buy,
         notes,
         currency,
+        override_reconciled,
     } = args;
 
     let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    // Get old amount and account
-    let (old_amount, old_account_id): (f64, i32) = tx
+    // Get old amount, account, cleared state and transfer id
+    let (old_amount, old_account_id, cleared, transfer_id): (f64, i32, String, Option<i32>) = tx
         .query_row(
-            "SELECT amount, account_id FROM transactions WHERE id = ?1",
+            "SELECT amount, account_id, COALESCE(cleared, 'uncleared'), transfer_id FROM transactions WHERE id = ?1",
             params![id],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
         )
         .map_err(|e| e.to_string())?;
+    ensure_not_reconciled(&cleared, override_reconciled)?;
 
     let total_price = shares * price_per_share;
 
@@ -596,8 +714,9 @@ pub fn update_investment_transaction_db(
             shares = ?8,
             price_per_share = ?9,
             fee = ?10,
-            currency = ?11
-         WHERE id = ?12",
+            currency = ?11,
+            action = ?12
+         WHERE id = ?13",
         params![
             account_id,
             date,
@@ -610,6 +729,7 @@ pub fn update_investment_transaction_db(
             price_per_share,
             fee,
             currency,
+            if is_buy { "buy" } else { "sell" },
             id
         ],
     )
@@ -640,6 +760,7 @@ pub fn update_investment_transaction_db(
     }
 
     tx.commit().map_err(|e| e.to_string())?;
+    crate::holdings::invalidate_holdings_cache(db_path);
 
     Ok(Transaction {
         id,
@@ -658,26 +779,78 @@ pub fn update_investment_transaction_db(
         price_per_share: Some(price_per_share),
         fee: Some(fee),
         currency,
+        cleared,
+        transfer_id,
+        action: Some(if is_buy { "buy" } else { "sell" }.to_string()),
     })
 }
 
-pub fn delete_transaction_db(db_path: &PathBuf, id: i32) -> Result<(), String> {
+pub fn delete_transaction_db(
+    db_path: &PathBuf,
+    id: i32,
+    override_reconciled: bool,
+) -> Result<(), String> {
     let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    // Get amount, account_id, notes and linked_tx_id (if any)
-    let (amount, account_id, notes, linked): (f64, i32, Option<String>, Option<i32>) = tx
+    // Get amount, account_id, linked_tx_id and transfer id (if any) and cleared state
+    let (amount, account_id, linked, transfer_id, cleared): (
+        f64,
+        i32,
+        Option<i32>,
+        Option<i32>,
+        String,
+    ) = tx
         .query_row(
-            "SELECT amount, account_id, notes, linked_tx_id FROM transactions WHERE id = ?1",
+            "SELECT amount, account_id, linked_tx_id, transfer_id, COALESCE(cleared, 'uncleared') FROM transactions WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    ensure_not_reconciled(&cleared, override_reconciled)?;
+
+    // A recorded split also goes from the split table, so share counts and stored closes
+    // revert with it
+    let split: Option<(String, String, f64, f64)> = tx
+        .query_row(
+            "SELECT s.ticker, s.date, s.numerator, s.denominator FROM stock_splits s
+             JOIN transactions t ON UPPER(t.ticker) = s.ticker AND t.date = s.date
+             WHERE t.id = ?1 AND t.action = 'split'",
             params![id],
             |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
         )
+        .optional()
         .map_err(|e| e.to_string())?;
+    if let Some((ticker, date, numerator, denominator)) = split {
+        crate::corporate_actions::rescale_before_split(
+            &tx,
+            &ticker,
+            &date,
+            denominator,
+            numerator,
+        )?;
+        tx.execute(
+            "DELETE FROM stock_splits WHERE ticker = ?1 AND date = ?2",
+            params![ticker, date],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.execute(
+        "DELETE FROM lot_selections WHERE sell_tx_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
 
-    // Delete the requested transaction
+    // Delete the requested transaction; a row recorded with it stays on its own
     tx.execute("DELETE FROM transactions WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE transactions SET related_tx_id = NULL WHERE related_tx_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
 
     tx.execute(
         "UPDATE accounts SET balance = balance - ?1 WHERE id = ?2",
@@ -687,15 +860,16 @@ pub fn delete_transaction_db(db_path: &PathBuf, id: i32) -> Result<(), String> {
 
     // If there's a linked counterpart, delete it and update its account balance
     if let Some(linked_id) = linked {
-        if let Some((ctr_amount, ctr_account_id)) = tx
+        if let Some((ctr_amount, ctr_account_id, ctr_cleared)) = tx
             .query_row(
-                "SELECT amount, account_id FROM transactions WHERE id = ?1",
+                "SELECT amount, account_id, COALESCE(cleared, 'uncleared') FROM transactions WHERE id = ?1",
                 params![linked_id],
-                |row| Ok((row.get::<_, f64>(0)?, row.get::<_, i32>(1)?)),
+                |row| Ok((row.get::<_, f64>(0)?, row.get::<_, i32>(1)?, row.get::<_, String>(2)?)),
             )
             .optional()
             .map_err(|e| e.to_string())?
         {
+            ensure_not_reconciled(&ctr_cleared, override_reconciled)?;
             tx.execute("DELETE FROM transactions WHERE id = ?1", params![linked_id])
                 .map_err(|e| e.to_string())?;
 
@@ -705,29 +879,15 @@ pub fn delete_transaction_db(db_path: &PathBuf, id: i32) -> Result<(), String> {
             )
             .map_err(|e| e.to_string())?;
         }
-    } else if let Some(ref n) = notes {
-        // fallback: try to find counterpart by notes
-        if let Some((found_id, ctr_amount, ctr_account_id)) = tx
-            .query_row(
-                "SELECT id, amount, account_id FROM transactions WHERE notes = ?1 AND category = 'Transfer' LIMIT 1",
-                params![n],
-                |row| Ok((row.get::<_, i32>(0)?, row.get::<_, f64>(1)?, row.get::<_, i32>(2)?)),
-            )
-            .optional()
-            .map_err(|e| e.to_string())?
-        {
-            tx.execute("DELETE FROM transactions WHERE id = ?1", params![found_id])
-                .map_err(|e| e.to_string())?;
+    }
 
-            tx.execute(
-                "UPDATE accounts SET balance = balance - ?1 WHERE id = ?2",
-                params![ctr_amount, ctr_account_id],
-            )
+    if let Some(transfer_id) = transfer_id {
+        tx.execute("DELETE FROM transfers WHERE id = ?1", params![transfer_id])
             .map_err(|e| e.to_string())?;
-        }
     }
 
     tx.commit().map_err(|e| e.to_string())?;
+    crate::holdings::invalidate_holdings_cache(db_path);
 
     Ok(())
 }
@@ -784,9 +944,13 @@ pub fn update_investment_transaction(
 }
 
 #[tauri::command]
-pub fn delete_transaction(app_handle: AppHandle, id: i32) -> Result<(), String> {
+pub fn delete_transaction(
+    app_handle: AppHandle,
+    id: i32,
+    override_reconciled: Option<bool>,
+) -> Result<(), String> {
     let db_path = crate::db_init::get_db_path(&app_handle)?;
-    delete_transaction_db(&db_path, id)
+    delete_transaction_db(&db_path, id, override_reconciled.unwrap_or(false))
 }
 
 #[tauri::command]
@@ -0,0 +1,411 @@
+use crate::models::{Transfer, TransferFxResult};
+use crate::transactions::get_transaction_by_id;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Records a transfer between two already inserted legs and links them to each other.
+/// `exchange_rate` is the destination amount per unit of source amount, fee excluded.
+/// Returns the persistent transfer id.
+pub(crate) fn record_transfer(
+    tx: &rusqlite::Transaction,
+    from_tx_id: i32,
+    to_tx_id: i32,
+    fee: f64,
+    exchange_rate: f64,
+) -> Result<i32, String> {
+    tx.execute(
+        "INSERT INTO transfers (from_tx_id, to_tx_id, fee, exchange_rate) VALUES (?1, ?2, ?3, ?4)",
+        params![from_tx_id, to_tx_id, fee, exchange_rate],
+    )
+    .map_err(|e| e.to_string())?;
+    let transfer_id = tx.last_insert_rowid() as i32;
+
+    tx.execute(
+        "UPDATE transactions SET linked_tx_id = ?1, transfer_id = ?2 WHERE id = ?3",
+        params![to_tx_id, transfer_id, from_tx_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE transactions SET linked_tx_id = ?1, transfer_id = ?2 WHERE id = ?3",
+        params![from_tx_id, transfer_id, to_tx_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(transfer_id)
+}
+
+fn load_transfer(conn: &Connection, transfer_id: i32) -> Result<Transfer, String> {
+    let (from_tx_id, to_tx_id, fee, exchange_rate, rate_estimated): (i32, i32, f64, f64, bool) =
+        conn.query_row(
+            "SELECT from_tx_id, to_tx_id, fee, exchange_rate, rate_estimated FROM transfers WHERE id = ?1",
+            params![transfer_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(Transfer {
+        id: transfer_id,
+        fee,
+        exchange_rate,
+        rate_estimated,
+        from: get_transaction_by_id(conn, from_tx_id)?,
+        to: get_transaction_by_id(conn, to_tx_id)?,
+    })
+}
+
+pub(crate) fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// Amount the other leg of a transfer should have after one leg changed to `amount`.
+/// Legs linked before transfer records existed are mirrored one to one.
+pub(crate) fn counterpart_amount(
+    conn: &Connection,
+    transfer_id: Option<i32>,
+    edited_tx_id: i32,
+    amount: f64,
+) -> Result<f64, String> {
+    let Some(transfer_id) = transfer_id else {
+        return Ok(-amount);
+    };
+    let (from_tx_id, fee, exchange_rate): (i32, f64, f64) = conn
+        .query_row(
+            "SELECT from_tx_id, fee, exchange_rate FROM transfers WHERE id = ?1",
+            params![transfer_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    if edited_tx_id == from_tx_id {
+        Ok(round_cents((-amount - fee) * exchange_rate))
+    } else {
+        Ok(round_cents(-(amount / exchange_rate + fee)))
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTransferArgs {
+    pub from_account_id: i32,
+    pub to_account_id: i32,
+    /// Amount leaving the source account, in its currency
+    pub amount_from: f64,
+    /// Amount arriving in the destination account, in its currency. Defaults to `amount_from`.
+    pub amount_to: Option<f64>,
+    pub date: String,
+    /// Charged to the source account on top of `amount_from`
+    pub fee: Option<f64>,
+    pub notes: Option<String>,
+}
+
+/// Creates both legs of a transfer, each in its own account currency.
+pub fn create_transfer_db(db_path: &PathBuf, args: CreateTransferArgs) -> Result<Transfer, String> {
+    let CreateTransferArgs {
+        from_account_id,
+        to_account_id,
+        amount_from,
+        amount_to,
+        date,
+        fee,
+        notes,
+    } = args;
+    let amount_to = amount_to.unwrap_or(amount_from);
+    let fee = fee.unwrap_or(0.0);
+
+    if from_account_id == to_account_id {
+        return Err("Cannot transfer to the same account".to_string());
+    }
+    if amount_from <= 0.0 || amount_to <= 0.0 {
+        return Err("Transfer amounts must be positive".to_string());
+    }
+    if fee < 0.0 {
+        return Err("Transfer fee cannot be negative".to_string());
+    }
+
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let account = |id: i32| -> Result<(String, Option<String>), String> {
+        tx.query_row(
+            "SELECT name, currency FROM accounts WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account {} not found", id))
+    };
+    let (from_name, from_currency) = account(from_account_id)?;
+    let (to_name, to_currency) = account(to_account_id)?;
+
+    let from_amount = -(amount_from + fee);
+    tx.execute(
+        "INSERT INTO transactions (account_id, date, payee, notes, category, amount, fee, currency) VALUES (?1, ?2, ?3, ?4, 'Transfer', ?5, ?6, ?7)",
+        params![
+            from_account_id,
+            date,
+            to_name,
+            notes,
+            from_amount,
+            if fee > 0.0 { Some(fee) } else { None },
+            from_currency
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let from_tx_id = tx.last_insert_rowid() as i32;
+
+    tx.execute(
+        "INSERT INTO transactions (account_id, date, payee, notes, category, amount, currency) VALUES (?1, ?2, ?3, ?4, 'Transfer', ?5, ?6)",
+        params![to_account_id, date, from_name, notes, amount_to, to_currency],
+    )
+    .map_err(|e| e.to_string())?;
+    let to_tx_id = tx.last_insert_rowid() as i32;
+
+    tx.execute(
+        "UPDATE accounts SET balance = balance + ?1 WHERE id = ?2",
+        params![from_amount, from_account_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE accounts SET balance = balance + ?1 WHERE id = ?2",
+        params![amount_to, to_account_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let transfer_id = record_transfer(&tx, from_tx_id, to_tx_id, fee, amount_to / amount_from)?;
+    let transfer = load_transfer(&tx, transfer_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(transfer)
+}
+
+/// Turns two existing transactions in different accounts into a linked transfer.
+/// The outgoing (negative) transaction becomes the source leg.
+pub fn link_transfer_db(db_path: &PathBuf, tx_a: i32, tx_b: i32) -> Result<Transfer, String> {
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let a = get_transaction_by_id(&tx, tx_a)?;
+    let b = get_transaction_by_id(&tx, tx_b)?;
+
+    if a.account_id == b.account_id {
+        return Err("Both transactions belong to the same account".to_string());
+    }
+    if a.transfer_id.is_some() || b.transfer_id.is_some() {
+        return Err("Transaction is already part of a transfer".to_string());
+    }
+    let (from, to) = if a.amount < 0.0 && b.amount > 0.0 {
+        (a, b)
+    } else if b.amount < 0.0 && a.amount > 0.0 {
+        (b, a)
+    } else {
+        return Err("A transfer needs one outgoing and one incoming transaction".to_string());
+    };
+
+    tx.execute(
+        "UPDATE transactions SET category = 'Transfer' WHERE id IN (?1, ?2)",
+        params![from.id, to.id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // The rate actually obtained, after taking out any fee charged on the source leg
+    let fee = from.fee.unwrap_or(0.0);
+    let sent = if -from.amount - fee > 0.0 {
+        -from.amount - fee
+    } else {
+        -from.amount
+    };
+    let transfer_id = record_transfer(&tx, from.id, to.id, fee, to.amount / sent)?;
+    let transfer = load_transfer(&tx, transfer_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(transfer)
+}
+
+pub fn get_transfer_db(db_path: &PathBuf, id: i32) -> Result<Transfer, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    load_transfer(&conn, id)
+}
+
+/// Replaces the rate of a transfer, typically one recorded at par while no rate was known.
+/// The destination leg is recomputed from the source leg and its account balance follows.
+pub fn set_transfer_rate_db(
+    db_path: &PathBuf,
+    id: i32,
+    exchange_rate: f64,
+) -> Result<Transfer, String> {
+    if !(exchange_rate.is_finite() && exchange_rate > 0.0) {
+        return Err("Exchange rate must be positive".to_string());
+    }
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let transfer = load_transfer(&tx, id)?;
+    crate::transactions::ensure_not_reconciled(&transfer.to.cleared, false)?;
+
+    tx.execute(
+        "UPDATE transfers SET exchange_rate = ?1, rate_estimated = 0 WHERE id = ?2",
+        params![exchange_rate, id],
+    )
+    .map_err(|e| e.to_string())?;
+    let amount_to = counterpart_amount(&tx, Some(id), transfer.from.id, transfer.from.amount)?;
+    tx.execute(
+        "UPDATE transactions SET amount = ?1 WHERE id = ?2",
+        params![amount_to, transfer.to.id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE accounts SET balance = balance + ?1 WHERE id = ?2",
+        params![amount_to - transfer.to.amount, transfer.to.account_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let transfer = load_transfer(&tx, id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(transfer)
+}
+
+/// Effective versus market exchange rate for every cross-currency transfer in the date range.
+/// Market rates come from the `daily_fx_rates` history; transfers on dates without a quote,
+/// or whose rate was only estimated, are reported without a gain or loss.
+pub fn get_transfer_fx_report_db(
+    db_path: &PathBuf,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<TransferFxResult>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tr.id, tf.date, tr.fee, tr.exchange_rate, tf.amount, tt.amount,
+                    COALESCE(tf.currency, af.currency), COALESCE(tt.currency, at.currency),
+                    tr.rate_estimated
+             FROM transfers tr
+             JOIN transactions tf ON tf.id = tr.from_tx_id
+             JOIN transactions tt ON tt.id = tr.to_tx_id
+             JOIN accounts af ON af.id = tf.account_id
+             JOIN accounts at ON at.id = tt.account_id
+             WHERE (?1 IS NULL OR tf.date >= ?1) AND (?2 IS NULL OR tf.date <= ?2)
+             ORDER BY tf.date ASC, tr.id ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    #[allow(clippy::type_complexity)]
+    let rows: Vec<(
+        i32,
+        String,
+        f64,
+        f64,
+        f64,
+        f64,
+        Option<String>,
+        Option<String>,
+        bool,
+    )> = stmt
+        .query_map(params![start_date, end_date], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+                row.get(7)?,
+                row.get(8)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for (
+        transfer_id,
+        date,
+        fee,
+        effective_rate,
+        from_amount,
+        to_amount,
+        from_cur,
+        to_cur,
+        rate_estimated,
+    ) in rows
+    {
+        let (Some(from_currency), Some(to_currency)) = (from_cur, to_cur) else {
+            continue;
+        };
+        if from_currency == to_currency {
+            continue;
+        }
+        let amount_from = -from_amount - fee;
+        let market_rate = crate::fx::market_rate_on(&conn, &from_currency, &to_currency, &date);
+        let fx_gain_loss = market_rate
+            .filter(|_| !rate_estimated)
+            .map(|r| round_cents(to_amount - amount_from * r));
+
+        results.push(TransferFxResult {
+            transfer_id,
+            date,
+            from_currency,
+            to_currency,
+            amount_from,
+            amount_to: to_amount,
+            effective_rate,
+            market_rate,
+            fx_gain_loss,
+            rate_estimated,
+        });
+    }
+
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn create_transfer(
+    app_handle: AppHandle,
+    args: CreateTransferArgs,
+) -> Result<Transfer, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    create_transfer_db(&db_path, args)
+}
+
+#[tauri::command]
+pub fn link_transfer(app_handle: AppHandle, tx_a: i32, tx_b: i32) -> Result<Transfer, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    link_transfer_db(&db_path, tx_a, tx_b)
+}
+
+#[tauri::command]
+pub fn get_transfer(app_handle: AppHandle, id: i32) -> Result<Transfer, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    get_transfer_db(&db_path, id)
+}
+
+#[tauri::command]
+pub fn set_transfer_rate(
+    app_handle: AppHandle,
+    id: i32,
+    exchange_rate: f64,
+) -> Result<Transfer, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+    set_transfer_rate_db(&db_path, id, exchange_rate)
+}
+
+#[tauri::command]
+pub async fn get_transfer_fx_report(
+    app_handle: AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<TransferFxResult>, String> {
+    let db_path = crate::db_init::get_db_path(&app_handle)?;
+
+    // Make sure the exchange rate history covers the transfer dates before comparing
+    let currencies = crate::fx::get_used_currencies_db(&db_path)?;
+    crate::fx::refresh_daily_fx_rates(&db_path, currencies).await;
+
+    get_transfer_fx_report_db(&db_path, start_date, end_date)
+}
@@ -1,4 +1,4 @@
-use crate::models::Account;
+use crate::models::{Account, NetWorthSummary};
 use rusqlite::Connection;
 use std::collections::HashMap;
 use tauri::AppHandle;
@@ -19,6 +19,25 @@ pub fn get_custom_rates_map(db_path: &std::path::PathBuf) -> Result<HashMap<Stri
         let (c, rate) = r.map_err(|e| e.to_string())?;
         map.insert(c, rate);
     }
+
+    // A range covering today wins over the undated rate, as in `fx::usd_rate`
+    let mut stmt = conn
+        .prepare(
+            "SELECT currency, rate FROM custom_exchange_rate_ranges
+             WHERE (valid_from IS NULL OR valid_from <= date('now', 'localtime'))
+               AND (valid_to IS NULL OR valid_to >= date('now', 'localtime'))
+             ORDER BY valid_from IS NULL DESC, valid_from ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for r in rows {
+        let (c, rate) = r.map_err(|e| e.to_string())?;
+        map.insert(c, rate);
+    }
     Ok(map)
 }
 
@@ -86,7 +105,10 @@ pub fn calculate_account_balances(
     }
 
     for acc in &mut accounts {
-        if let Some(sum) = sums.get(&acc.id) {
+        if let Some(v) = acc.valuation {
+            // Manual valuations of asset accounts take precedence over transaction sums
+            acc.balance = v;
+        } else if let Some(sum) = sums.get(&acc.id) {
             acc.balance = *sum;
         }
 
@@ -100,6 +122,24 @@ pub fn calculate_account_balances(
     accounts
 }
 
+/// Splits converted account balances into assets and liabilities by their sign, so an
+/// overpaid card counts as an asset and an overdrawn account as a liability. Brokerage
+/// holdings are added on top of the cash balance; archived accounts are skipped.
+pub fn calculate_net_worth(accounts: &[Account]) -> NetWorthSummary {
+    let mut summary = NetWorthSummary::default();
+    for acc in accounts.iter().filter(|a| !a.archived) {
+        let value = (acc.balance + acc.holdings_value.unwrap_or(0.0)) * acc.exchange_rate;
+        if value < 0.0 {
+            // Amounts owed are negative balances
+            summary.liabilities -= value;
+        } else {
+            summary.assets += value;
+        }
+    }
+    summary.net_worth = summary.assets - summary.liabilities;
+    summary
+}
+
 // Custom exchange rate DB helpers moved here (used by tauri commands)
 use rusqlite::params;
 use std::path::PathBuf;
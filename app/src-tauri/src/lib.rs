@@ -1,14 +1,30 @@
 mod core;
-pub use crate::core::{accounts, db_init, markets, models, rules, transactions, utils};
+pub use crate::core::{
+    accounts, allocation, anomalies, capital_gains, cash_flow, corporate_actions, db_init, ecb,
+    forecast, fx, holdings, http, investment_actions, lots, markets, models, net_worth,
+    performance, providers, reconciliation, reports, rules, scheduled, subscriptions, transactions,
+    transfers, utils,
+};
 
 pub use crate::models::{
-    Account, AppSettings, DailyPrice, Rule, Transaction, YahooChartResponse, YahooQuote,
-    YahooSearchQuote, YahooSearchResponse,
+    Account, AccountForecast, AccountPerformance, AccountValuation, AllocationLine,
+    AllocationTarget, Anomaly, AnomalyReport, AppSettings, BalanceForecast, BenchmarkPerformance,
+    CapitalGainLine, CapitalGainTotals, CapitalGainsReport, CashFlowGraph, CashFlowLink,
+    CashFlowNode, CategoryTrend, CategoryTrendReport, CustomExchangeRateRange, DailyBar,
+    DailyPrice, DividendSuggestion, EcbImport, ForecastPoint, Holding, HoldingPerformance,
+    HoldingsReport, IncomeExpenseMonth, IncomeExpenseReport, LotReport, LotSelection,
+    MarketDataProviderConfig, NetWorthAccount, NetWorthPoint, NetWorthSeries, NetWorthSummary,
+    PayeeTotal, PerformanceMetrics, PerformanceReport, PeriodChange, Portfolio, PriceChange,
+    PriceUpdateResult, QuoteCacheSettings, RealizedGain, RebalancePlan, RebalanceTrade, Rule,
+    RuleApplication, RuleFieldChange, ScheduledTransaction, StockDividend, StockSplit,
+    Subscription, TaxLot, TickerClassification, Transaction, TransactionRuleDiff, Transfer,
+    TransferFxResult, UnrealizedGainLine, YahooChartResponse, YahooQuote, YahooSearchQuote,
+    YahooSearchResponse,
 };
 
 // Re-export utility helpers used by tests
 pub use crate::utils::{
-    calculate_account_balances, get_custom_exchange_rate_db,
+    calculate_account_balances, calculate_net_worth, get_custom_exchange_rate_db,
     get_system_theme as get_system_theme_fn, set_custom_exchange_rate_db,
 };
 
@@ -32,19 +48,111 @@ pub use crate::transactions::{
 
 // Re-export accounts helpers used by tests
 pub use crate::accounts::{
-    create_account_db, delete_account_db, get_accounts_db, get_accounts_summary_db,
-    rename_account_db, update_account_db,
+    create_account_db, delete_account_db, delete_account_valuation_db, get_account_valuations_db,
+    get_accounts_db, get_accounts_summary_at_transaction_dates_db, get_accounts_summary_db,
+    rename_account_db, set_account_archived_db, set_account_valuation_db, update_account_db,
+};
+
+// Re-export exchange rate helpers used by tests
+pub use crate::fx::{
+    add_custom_exchange_rate_range_db, convert_amount_db, delete_custom_exchange_rate_range_db,
+    get_custom_exchange_rate_ranges_db, get_fx_rate_db, get_used_currencies_db,
+    update_daily_fx_rates_with_client_and_base, update_daily_fx_rates_with_registry,
+};
+
+// Re-export ECB reference-rate helpers used by tests
+pub use crate::ecb::{
+    ecb_rates_stale_db, fetch_ecb_rates_with_client, import_ecb_rates_file_db, latest_ecb_rates_db,
+    parse_ecb_rates, store_ecb_rates_db, EcbDay,
+};
+
+// Re-export transfer helpers used by tests
+pub use crate::transfers::{
+    create_transfer_db, get_transfer_db, get_transfer_fx_report_db, link_transfer_db,
+    set_transfer_rate_db, CreateTransferArgs,
+};
+
+// Re-export reconciliation helpers used by tests
+pub use crate::reconciliation::{
+    finish_reconciliation_db, get_reconciliations_db, set_transaction_cleared_db,
+    start_reconciliation_db,
 };
 
 // Re-export rules helpers used by tests
 pub use crate::rules::{
-    create_rule_db, delete_rule_db, get_rules_db, update_rule_db, update_rules_order_db,
+    apply_rules_db, create_rule_db, delete_rule_db, get_rules_db, update_rule_db,
+    update_rules_order_db, ApplyRulesArgs,
 };
 
 // Re-export markets helpers used by tests
 pub use crate::markets::{
-    get_daily_stock_prices_from_path, get_stock_quotes_with_client_and_db,
+    get_daily_stock_bars_db, get_daily_stock_prices_from_path, get_stock_quotes_with_client_and_db,
+    get_stock_quotes_with_policy, get_stock_quotes_with_registry, search_known_tickers_db,
     search_ticker_with_client, update_daily_stock_prices_with_client_and_base,
+    update_daily_stock_prices_with_registry,
+};
+
+// Re-export dividend and split helpers used by tests
+pub use crate::corporate_actions::{
+    get_dividend_suggestions_db, get_stock_dividends_db, get_stock_splits_db,
+};
+
+// Re-export investment action helpers used by tests
+pub use crate::investment_actions::{
+    record_investment_action_db, InvestmentActionArgs, INVESTMENT_ACTIONS,
+};
+
+// Re-export tax lot helpers used by tests
+pub use crate::lots::{
+    get_lot_method_db, get_lot_selection_db, get_tax_lots_db, set_lot_method_db,
+    set_lot_selection_db, LOT_METHODS,
+};
+
+// Re-export capital gains helpers used by tests
+pub use crate::capital_gains::{
+    capital_gains_csv, capital_gains_form_8949, capital_gains_report_db, export_capital_gains_db,
+    CAPITAL_GAINS_FORMATS,
+};
+
+// Re-export holdings helpers used by tests
+pub use crate::holdings::{get_holdings_db, invalidate_holdings_cache};
+
+// Re-export allocation helpers used by tests
+pub use crate::allocation::{
+    get_portfolios_db, get_ticker_classifications_db, rebalance_plan_db, set_account_portfolio_db,
+    set_allocation_targets_db, set_ticker_classification_db, ASSET_CLASSES,
+};
+
+// Re-export net worth helpers used by tests
+pub use crate::net_worth::{net_worth_series_db, NET_WORTH_INTERVALS};
+
+// Re-export report helpers used by tests
+pub use crate::reports::{category_trends_db, income_expense_report_db, top_payees_db, ReportArgs};
+
+// Re-export forecast helpers used by tests
+pub use crate::forecast::{forecast_balances_db, set_balance_threshold_db, ForecastArgs};
+pub use crate::scheduled::{
+    create_scheduled_transaction_db, delete_scheduled_transaction_db,
+    get_scheduled_transactions_db, update_scheduled_transaction_db, ScheduledTransactionArgs,
+};
+
+// Re-export subscription helpers used by tests
+pub use crate::subscriptions::{detect_subscriptions_db, SubscriptionArgs};
+
+// Re-export anomaly helpers used by tests
+pub use crate::anomalies::get_anomalies_db;
+
+// Re-export cash-flow graph helpers used by tests
+pub use crate::cash_flow::cash_flow_graph_db;
+
+// Re-export performance helpers used by tests
+pub use crate::performance::{get_performance_db, PerformanceArgs, PERFORMANCE_PERIODS};
+
+// Re-export market data providers used by tests
+pub use crate::providers::{
+    delete_market_data_provider_db, get_market_data_providers_db, get_ticker_providers_db,
+    save_market_data_provider_db, set_ticker_providers_db, HttpProvider, MarketDataProvider,
+    ProviderRegistry, YahooProvider,
 };
 
 // Test-only helpers
@@ -92,7 +200,13 @@ pub fn run() {
             accounts::rename_account,
             accounts::update_account,
             accounts::delete_account,
+            accounts::archive_account,
+            accounts::unarchive_account,
             accounts::get_accounts,
+            accounts::get_net_worth,
+            accounts::set_account_valuation,
+            accounts::get_account_valuations,
+            accounts::delete_account_valuation,
             transactions::create_transaction,
             transactions::get_transactions,
             transactions::get_all_transactions,
@@ -102,22 +216,84 @@ pub fn run() {
             transactions::delete_transaction,
             transactions::get_payees,
             transactions::get_categories,
+            transfers::create_transfer,
+            transfers::link_transfer,
+            transfers::get_transfer,
+            transfers::get_transfer_fx_report,
+            transfers::set_transfer_rate,
+            reconciliation::set_transaction_cleared,
+            reconciliation::start_reconciliation,
+            reconciliation::finish_reconciliation,
+            reconciliation::get_reconciliations,
             markets::search_ticker,
             markets::get_stock_quotes,
             markets::update_daily_stock_prices,
             markets::get_daily_stock_prices,
+            markets::get_daily_stock_bars,
+            corporate_actions::get_stock_dividends,
+            corporate_actions::get_stock_splits,
+            corporate_actions::get_dividend_suggestions,
+            investment_actions::record_investment_action,
+            lots::get_tax_lots,
+            lots::get_lot_method,
+            lots::set_lot_method,
+            lots::get_lot_selection,
+            lots::set_lot_selection,
+            capital_gains::capital_gains_report,
+            capital_gains::export_capital_gains,
+            performance::get_performance,
+            holdings::get_holdings,
+            net_worth::net_worth_series,
+            reports::income_expense_report,
+            reports::category_trends,
+            reports::top_payees,
+            scheduled::create_scheduled_transaction,
+            scheduled::get_scheduled_transactions,
+            scheduled::update_scheduled_transaction,
+            scheduled::delete_scheduled_transaction,
+            forecast::set_balance_threshold,
+            forecast::forecast_balances,
+            subscriptions::detect_subscriptions,
+            anomalies::get_anomalies,
+            cash_flow::cash_flow_graph,
+            allocation::get_ticker_classifications,
+            allocation::set_ticker_classification,
+            allocation::get_portfolios,
+            allocation::set_account_portfolio,
+            allocation::set_allocation_targets,
+            allocation::rebalance_plan,
             markets::check_currency_availability,
+            markets::get_quote_cache_settings,
+            markets::set_quote_cache_settings,
+            providers::get_market_data_providers,
+            providers::save_market_data_provider,
+            providers::delete_market_data_provider,
+            providers::set_ticker_providers,
+            providers::get_ticker_providers,
             db_init::set_db_path,
             db_init::reset_db_path,
             db_init::get_db_path_command,
             utils::get_system_theme,
             utils::set_custom_exchange_rate,
             utils::get_custom_exchange_rate,
+            fx::get_fx_rate,
+            fx::convert_amount,
+            fx::update_daily_fx_rates,
+            fx::add_custom_exchange_rate_range,
+            fx::get_custom_exchange_rate_ranges,
+            fx::delete_custom_exchange_rate_range,
+            fx::get_fx_source,
+            fx::set_fx_source,
+            fx::get_rate_mode,
+            fx::set_rate_mode,
+            ecb::update_ecb_rates,
+            ecb::import_ecb_rates,
             rules::get_rules,
             rules::create_rule,
             rules::update_rule,
             rules::delete_rule,
             rules::update_rules_order,
+            rules::apply_rules,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,261 @@
+use super::common::setup_db;
+use crate::{calculate_net_worth, Account};
+use rusqlite::{params, Connection};
+
+#[test]
+fn test_create_account_with_kind() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(
+        &db_path,
+        "Mortgage".to_string(),
+        -200000.0,
+        None,
+        Some("loan".to_string()),
+    )
+    .unwrap();
+    assert_eq!(acc.kind, "loan");
+
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    let a = accounts.iter().find(|a| a.id == acc.id).unwrap();
+    assert_eq!(a.kind, "loan");
+}
+
+#[test]
+fn test_create_account_defaults_to_cash_kind() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Wallet".to_string(), 0.0, None, None).unwrap();
+    assert_eq!(acc.kind, "cash");
+}
+
+#[test]
+fn test_create_account_unknown_kind_should_error() {
+    let (_dir, db_path) = setup_db();
+    let res = crate::create_account_db(
+        &db_path,
+        "Odd".to_string(),
+        0.0,
+        None,
+        Some("piggy_bank".to_string()),
+    );
+    assert!(res.is_err());
+    assert!(crate::get_accounts_db(&db_path).unwrap().is_empty());
+}
+
+#[test]
+fn test_asset_valuation_overrides_balance() {
+    let (_dir, db_path) = setup_db();
+    let house = crate::create_account_db(
+        &db_path,
+        "House".to_string(),
+        250000.0,
+        None,
+        Some("asset".to_string()),
+    )
+    .unwrap();
+
+    crate::set_account_valuation_db(&db_path, house.id, "2024-01-01".to_string(), 260000.0)
+        .unwrap();
+    crate::set_account_valuation_db(&db_path, house.id, "2024-06-01".to_string(), 275000.0)
+        .unwrap();
+
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    let a = accounts.iter().find(|a| a.id == house.id).unwrap();
+    assert_eq!(a.valuation, Some(275000.0));
+    assert_eq!(a.balance, 275000.0);
+
+    let history = crate::get_account_valuations_db(&db_path, house.id).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].date, "2024-01-01");
+}
+
+#[test]
+fn test_valuation_same_date_replaces_previous() {
+    let (_dir, db_path) = setup_db();
+    let car = crate::create_account_db(
+        &db_path,
+        "Car".to_string(),
+        0.0,
+        None,
+        Some("asset".to_string()),
+    )
+    .unwrap();
+
+    crate::set_account_valuation_db(&db_path, car.id, "2024-01-01".to_string(), 20000.0).unwrap();
+    crate::set_account_valuation_db(&db_path, car.id, "2024-01-01".to_string(), 18000.0).unwrap();
+
+    let history = crate::get_account_valuations_db(&db_path, car.id).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].value, 18000.0);
+}
+
+#[test]
+fn test_valuation_on_cash_account_should_error() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let res = crate::set_account_valuation_db(&db_path, acc.id, "2024-01-01".to_string(), 10.0);
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_brokerage_holdings_value_separate_from_cash() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(
+        &db_path,
+        "Broker".to_string(),
+        1000.0,
+        None,
+        Some("brokerage".to_string()),
+    )
+    .unwrap();
+
+    crate::create_investment_transaction_db(
+        &db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id: acc.id,
+            date: "2024-01-02".to_string(),
+            ticker: "AAPL".to_string(),
+            shares: 2.0,
+            price_per_share: 100.0,
+            fee: 0.0,
+            is_buy: true,
+            currency: None,
+        },
+    )
+    .unwrap();
+
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO stock_prices (ticker, price, last_updated) VALUES (?1, ?2, datetime('now'))",
+        params!["AAPL", 150.0],
+    )
+    .unwrap();
+
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    let a = accounts.iter().find(|a| a.id == acc.id).unwrap();
+    assert_eq!(a.balance, 800.0);
+    assert_eq!(a.holdings_value, Some(300.0));
+}
+
+#[test]
+fn test_net_worth_counts_liability_kinds() {
+    let make = |id: i32, kind: &str, balance: f64| Account {
+        id,
+        name: format!("Acc{}", id),
+        balance,
+        currency: None,
+        kind: kind.to_string(),
+        exchange_rate: 1.0,
+        valuation: None,
+        holdings_value: None,
+        archived: false,
+    };
+    let mut broker = make(4, "brokerage", 100.0);
+    broker.holdings_value = Some(900.0);
+    let accounts = vec![
+        make(1, "cash", 5000.0),
+        make(2, "credit_card", -1200.0),
+        make(3, "loan", -10000.0),
+        broker,
+    ];
+
+    let summary = calculate_net_worth(&accounts);
+    assert_eq!(summary.assets, 6000.0);
+    assert_eq!(summary.liabilities, 11200.0);
+    assert_eq!(summary.net_worth, -5200.0);
+
+    // An overpaid card is money owed back, an overdrawn account money owed
+    let accounts = vec![make(1, "credit_card", 50.0), make(2, "cash", -20.0)];
+    let summary = calculate_net_worth(&accounts);
+    assert_eq!(summary.assets, 50.0);
+    assert_eq!(summary.liabilities, 20.0);
+    assert_eq!(summary.net_worth, 30.0);
+}
+
+#[test]
+fn test_holdings_converted_from_quote_currency() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(
+        &db_path,
+        "Depot".to_string(),
+        1000.0,
+        Some("EUR".to_string()),
+        Some("brokerage".to_string()),
+    )
+    .unwrap();
+    crate::create_investment_transaction_db(
+        &db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id: acc.id,
+            date: "2024-01-02".to_string(),
+            ticker: "AAPL".to_string(),
+            shares: 2.0,
+            price_per_share: 100.0,
+            fee: 0.0,
+            is_buy: true,
+            currency: None,
+        },
+    )
+    .unwrap();
+    crate::set_custom_exchange_rate_db(&db_path, "EUR".to_string(), 1.25).unwrap();
+
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO stock_prices (ticker, price, currency, last_updated) VALUES (?1, ?2, 'USD', datetime('now'))",
+        params!["AAPL", 150.0],
+    )
+    .unwrap();
+
+    // 300 USD of shares are worth 240 EUR
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    let a = accounts.iter().find(|a| a.id == acc.id).unwrap();
+    assert_eq!(a.balance, 800.0);
+    assert!((a.holdings_value.unwrap() - 240.0).abs() < 1e-9);
+
+    let summary = crate::get_accounts_summary_db(&db_path, "EUR").unwrap();
+    let custom_rates = crate::utils::get_custom_rates_map(&db_path).unwrap();
+    let accounts = crate::calculate_account_balances(
+        summary.accounts,
+        summary.raw_data,
+        "EUR",
+        &std::collections::HashMap::new(),
+        &custom_rates,
+    );
+    let net_worth = calculate_net_worth(&accounts);
+    assert!((net_worth.net_worth - 1040.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_holdings_without_rate_are_an_error() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(
+        &db_path,
+        "Depot".to_string(),
+        1000.0,
+        Some("EUR".to_string()),
+        Some("brokerage".to_string()),
+    )
+    .unwrap();
+    crate::create_investment_transaction_db(
+        &db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id: acc.id,
+            date: "2024-01-02".to_string(),
+            ticker: "VOD".to_string(),
+            shares: 2.0,
+            price_per_share: 100.0,
+            fee: 0.0,
+            is_buy: true,
+            currency: None,
+        },
+    )
+    .unwrap();
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO stock_prices (ticker, price, currency, last_updated) VALUES (?1, ?2, 'GBP', datetime('now'))",
+        params!["VOD", 70.0],
+    )
+    .unwrap();
+
+    let err = crate::get_accounts_db(&db_path).unwrap_err();
+    assert_eq!(err, "No exchange rate from GBP to EUR");
+}
@@ -0,0 +1,81 @@
+use super::common::setup_db;
+
+#[test]
+fn test_archive_account_keeps_transactions() {
+    let (_dir, db_path) = setup_db();
+    let acc =
+        crate::create_account_db(&db_path, "Old Bank".to_string(), 100.0, None, None).unwrap();
+
+    crate::set_account_archived_db(&db_path, acc.id, true).unwrap();
+
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    let a = accounts.iter().find(|a| a.id == acc.id).unwrap();
+    assert!(a.archived);
+
+    // History is preserved for past-period reports
+    let all = crate::get_all_transactions_db(&db_path).unwrap();
+    assert_eq!(all.iter().filter(|t| t.account_id == acc.id).count(), 1);
+}
+
+#[test]
+fn test_unarchive_account() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Reopened".to_string(), 0.0, None, None).unwrap();
+
+    crate::set_account_archived_db(&db_path, acc.id, true).unwrap();
+    crate::set_account_archived_db(&db_path, acc.id, false).unwrap();
+
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    assert!(!accounts.iter().find(|a| a.id == acc.id).unwrap().archived);
+}
+
+#[test]
+fn test_archive_missing_account_should_error() {
+    let (_dir, db_path) = setup_db();
+    assert!(crate::set_account_archived_db(&db_path, -1, true).is_err());
+}
+
+#[test]
+fn test_archived_account_excluded_from_net_worth() {
+    let (_dir, db_path) = setup_db();
+    let open = crate::create_account_db(&db_path, "Open".to_string(), 100.0, None, None).unwrap();
+    let closed =
+        crate::create_account_db(&db_path, "Closed".to_string(), 50.0, None, None).unwrap();
+    crate::set_account_archived_db(&db_path, closed.id, true).unwrap();
+
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    let summary = crate::calculate_net_worth(&accounts);
+    assert_eq!(summary.net_worth, 100.0);
+    assert!(accounts.iter().any(|a| a.id == open.id));
+}
+
+#[test]
+fn test_payee_matching_archived_account_is_not_a_transfer() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Main".to_string(), 100.0, None, None).unwrap();
+    let closed = crate::create_account_db(&db_path, "Closed".to_string(), 0.0, None, None).unwrap();
+    crate::set_account_archived_db(&db_path, closed.id, true).unwrap();
+
+    let tx = crate::create_transaction_db(
+        &db_path,
+        crate::CreateTransactionArgs {
+            account_id: acc.id,
+            date: "2024-01-01".to_string(),
+            payee: "Closed".to_string(),
+            notes: None,
+            category: None,
+            amount: -10.0,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap();
+
+    assert_ne!(tx.category.as_deref(), Some("Transfer"));
+    assert!(crate::get_transactions_db(&db_path, closed.id)
+        .unwrap()
+        .is_empty());
+}
@@ -3,8 +3,9 @@ use super::common::setup_db;
 #[test]
 fn test_delete_account() {
     let (_dir, db_path) = setup_db();
-    let account = crate::create_account_db(&db_path, "ToDelete".to_string(), 100.0, None).unwrap();
-    crate::delete_account_db(&db_path, account.id).unwrap();
+    let account =
+        crate::create_account_db(&db_path, "ToDelete".to_string(), 100.0, None, None).unwrap();
+    crate::delete_account_db(&db_path, account.id, false).unwrap();
     let accounts = crate::get_accounts_db(&db_path).unwrap();
     assert!(accounts.is_empty());
 }
@@ -12,7 +13,8 @@ fn test_delete_account() {
 #[test]
 fn test_delete_account_with_transactions() {
     let (_dir, db_path) = setup_db();
-    let account = crate::create_account_db(&db_path, "ToDelete".to_string(), 100.0, None).unwrap();
+    let account =
+        crate::create_account_db(&db_path, "ToDelete".to_string(), 100.0, None, None).unwrap();
     crate::create_transaction_db(
         &db_path,
         crate::CreateTransactionArgs {
@@ -33,7 +35,7 @@ fn test_delete_account_with_transactions() {
     let txs_before = crate::get_transactions_db(&db_path, account.id).unwrap();
     assert!(!txs_before.is_empty());
 
-    crate::delete_account_db(&db_path, account.id).unwrap();
+    crate::delete_account_db(&db_path, account.id, false).unwrap();
 
     let accounts = crate::get_accounts_db(&db_path).unwrap();
     assert!(accounts.iter().all(|a| a.id != account.id));
@@ -41,3 +43,70 @@ fn test_delete_account_with_transactions() {
     let txs_after = crate::get_transactions_db(&db_path, account.id).unwrap();
     assert!(txs_after.is_empty());
 }
+
+fn transfer(db_path: &std::path::PathBuf, from: i32, to_name: &str, amount: f64) {
+    crate::create_transaction_db(
+        db_path,
+        crate::CreateTransactionArgs {
+            account_id: from,
+            date: "2023-01-03".to_string(),
+            payee: to_name.to_string(),
+            notes: None,
+            category: Some("Transfer".to_string()),
+            amount,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_delete_account_with_linked_transfers_is_refused() {
+    let (_dir, db_path) = setup_db();
+    let a = crate::create_account_db(&db_path, "A".to_string(), 100.0, None, None).unwrap();
+    let b = crate::create_account_db(&db_path, "B".to_string(), 0.0, None, None).unwrap();
+    transfer(&db_path, a.id, "B", -40.0);
+
+    let res = crate::delete_account_db(&db_path, a.id, false);
+    assert!(res.is_err());
+
+    // Nothing was removed
+    assert_eq!(crate::get_accounts_db(&db_path).unwrap().len(), 2);
+    assert_eq!(crate::get_transactions_db(&db_path, a.id).unwrap().len(), 2);
+    assert_eq!(crate::get_transactions_db(&db_path, b.id).unwrap().len(), 1);
+}
+
+#[test]
+fn test_delete_account_unlinking_transfers_keeps_counterparts() {
+    let (_dir, db_path) = setup_db();
+    let a = crate::create_account_db(&db_path, "A".to_string(), 100.0, None, None).unwrap();
+    let b = crate::create_account_db(&db_path, "B".to_string(), 0.0, None, None).unwrap();
+    transfer(&db_path, a.id, "B", -40.0);
+
+    crate::delete_account_db(&db_path, a.id, true).unwrap();
+
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    assert!(accounts.iter().all(|acc| acc.id != a.id));
+    let b_after = accounts.iter().find(|acc| acc.id == b.id).unwrap();
+    assert_eq!(b_after.balance, 40.0);
+
+    let b_txs = crate::get_transactions_db(&db_path, b.id).unwrap();
+    assert_eq!(b_txs.len(), 1);
+    assert_eq!(b_txs[0].amount, 40.0);
+    assert_eq!(b_txs[0].category, None);
+
+    // The surviving row no longer points at a deleted transaction
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let linked: Option<i32> = conn
+        .query_row(
+            "SELECT linked_tx_id FROM transactions WHERE id = ?1",
+            [b_txs[0].id],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert!(linked.is_none());
+}
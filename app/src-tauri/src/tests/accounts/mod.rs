@@ -1,5 +1,9 @@
 pub use super::common;
 
+pub mod account_kinds;
+pub mod archive_account;
 pub mod create_account;
 pub mod delete_account;
+pub mod net_worth_series;
 pub mod rename_account;
+pub mod update_account;
@@ -0,0 +1,302 @@
+use super::common::{account_of_kind, setup_db};
+use rusqlite::{params, Connection};
+
+fn record(db_path: &std::path::PathBuf, account_id: i32, date: &str, amount: f64) {
+    crate::create_transaction_db(
+        db_path,
+        crate::CreateTransactionArgs {
+            account_id,
+            date: date.to_string(),
+            payee: "Someone".to_string(),
+            notes: None,
+            category: None,
+            amount,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap();
+}
+
+fn series(
+    db_path: &std::path::PathBuf,
+    from: Option<&str>,
+    to: &str,
+    interval: &str,
+) -> crate::NetWorthSeries {
+    crate::net_worth_series_db(
+        db_path,
+        from.map(String::from),
+        Some(to.to_string()),
+        interval.to_string(),
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+}
+
+fn net_worths(series: &crate::NetWorthSeries) -> Vec<(String, f64)> {
+    series
+        .points
+        .iter()
+        .map(|p| (p.date.clone(), p.net_worth))
+        .collect()
+}
+
+fn dates(series: &crate::NetWorthSeries) -> Vec<&str> {
+    series.points.iter().map(|p| p.date.as_str()).collect()
+}
+
+#[test]
+fn test_daily_series_splits_assets_and_liabilities() {
+    let (_dir, db_path) = setup_db();
+    let checking = account_of_kind(&db_path, "Checking", None, "cash");
+    let card = account_of_kind(&db_path, "Card", None, "credit_card");
+    record(&db_path, checking, "2024-01-01", 1000.0);
+    record(&db_path, card, "2024-01-02", -300.0);
+    record(&db_path, checking, "2024-01-03", -200.0);
+
+    // Starts at the first transaction when no start is given
+    let daily = series(&db_path, None, "2024-01-04", "daily");
+    assert_eq!(daily.from, "2024-01-01");
+    assert_eq!(daily.base_currency, "USD");
+    assert_eq!(
+        net_worths(&daily),
+        vec![
+            ("2024-01-01".to_string(), 1000.0),
+            ("2024-01-02".to_string(), 700.0),
+            ("2024-01-03".to_string(), 500.0),
+            ("2024-01-04".to_string(), 500.0),
+        ]
+    );
+    let second = &daily.points[1];
+    assert_eq!(second.assets, 1000.0);
+    assert_eq!(second.liabilities, 300.0);
+    assert_eq!(daily.accounts[1].account_id, card);
+    assert!(daily.accounts[1].is_liability);
+    assert_eq!(second.balances, vec![1000.0, -300.0]);
+
+    // Archived accounts drop out unless asked for
+    crate::set_account_archived_db(&db_path, card, true).unwrap();
+    let active = series(&db_path, None, "2024-01-04", "daily");
+    assert_eq!(active.accounts.len(), 1);
+    assert_eq!(active.points[3].net_worth, 800.0);
+    let only_card = crate::net_worth_series_db(
+        &db_path,
+        None,
+        Some("2024-01-04".to_string()),
+        "daily".to_string(),
+        Some(vec![card]),
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(only_card.from, "2024-01-02");
+    assert_eq!(only_card.points.last().unwrap().net_worth, -300.0);
+}
+
+#[test]
+fn test_weekly_and_monthly_points() {
+    let (_dir, db_path) = setup_db();
+    let checking = account_of_kind(&db_path, "Checking", None, "cash");
+    record(&db_path, checking, "2024-01-01", 100.0);
+    record(&db_path, checking, "2024-02-10", 50.0);
+
+    let weekly = series(&db_path, Some("2024-01-01"), "2024-01-20", "weekly");
+    assert_eq!(
+        dates(&weekly),
+        vec!["2024-01-07", "2024-01-14", "2024-01-20"]
+    );
+
+    let monthly = series(&db_path, Some("2024-01-15"), "2024-03-10", "monthly");
+    assert_eq!(
+        net_worths(&monthly),
+        vec![
+            ("2024-01-31".to_string(), 100.0),
+            ("2024-02-29".to_string(), 150.0),
+            ("2024-03-10".to_string(), 150.0),
+        ]
+    );
+
+    let month_end = series(&db_path, Some("2024-01-01"), "2024-02-29", "monthly");
+    assert_eq!(dates(&month_end), vec!["2024-01-31", "2024-02-29"]);
+
+    for (from, interval) in [("2024-01-01", "hourly"), ("2024-05-01", "daily")] {
+        let bad = crate::net_worth_series_db(
+            &db_path,
+            Some(from.to_string()),
+            Some("2024-03-01".to_string()),
+            interval.to_string(),
+            None,
+            None,
+            None,
+        );
+        assert!(bad.is_err());
+    }
+}
+
+#[test]
+fn test_holdings_carry_closes_forward_across_splits() {
+    let (_dir, db_path) = setup_db();
+    let broker = account_of_kind(&db_path, "Broker", None, "brokerage");
+    record(&db_path, broker, "2024-01-01", 2000.0);
+    crate::create_investment_transaction_db(
+        &db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id: broker,
+            date: "2024-01-02".to_string(),
+            ticker: "VTI".to_string(),
+            shares: 10.0,
+            price_per_share: 100.0,
+            fee: 0.0,
+            is_buy: true,
+            currency: None,
+        },
+    )
+    .unwrap();
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            account_id: broker,
+            date: "2024-01-04".to_string(),
+            action: "split".to_string(),
+            ticker: Some("VTI".to_string()),
+            numerator: Some(2.0),
+            denominator: Some(1.0),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    // Closes are stored in today's split basis; Friday's close covers the weekend
+    let conn = Connection::open(&db_path).unwrap();
+    for (date, price) in [("2024-01-02", 50.0), ("2024-01-05", 55.0)] {
+        conn.execute(
+            "INSERT INTO daily_stock_prices (ticker, date, price) VALUES ('VTI', ?1, ?2)",
+            params![date, price],
+        )
+        .unwrap();
+    }
+
+    let daily = series(&db_path, Some("2024-01-01"), "2024-01-07", "daily");
+    let values: Vec<f64> = daily.points.iter().map(|p| p.net_worth).collect();
+    assert_eq!(
+        values,
+        vec![2000.0, 2000.0, 2000.0, 2000.0, 2100.0, 2100.0, 2100.0]
+    );
+}
+
+#[test]
+fn test_converts_at_historical_rates_and_uses_valuations() {
+    let (_dir, db_path) = setup_db();
+    let euros = account_of_kind(&db_path, "Girokonto", Some("EUR"), "cash");
+    let house = account_of_kind(&db_path, "House", None, "asset");
+    record(&db_path, euros, "2024-01-01", 1000.0);
+    crate::set_account_valuation_db(&db_path, house, "2024-01-03".to_string(), 300000.0).unwrap();
+    let conn = Connection::open(&db_path).unwrap();
+    for (date, rate) in [("2024-01-01", 1.10), ("2024-01-03", 1.20)] {
+        conn.execute(
+            "INSERT INTO daily_fx_rates (currency, date, rate) VALUES ('EUR', ?1, ?2)",
+            params![date, rate],
+        )
+        .unwrap();
+    }
+
+    let usd = series(&db_path, Some("2024-01-01"), "2024-01-04", "daily");
+    let values: Vec<f64> = usd.points.iter().map(|p| p.net_worth).collect();
+    assert_eq!(values, vec![1100.0, 1100.0, 301200.0, 301200.0]);
+    assert_eq!(usd.accounts[0].currency, "EUR");
+
+    let eur = crate::net_worth_series_db(
+        &db_path,
+        Some("2024-01-01".to_string()),
+        Some("2024-01-04".to_string()),
+        "daily".to_string(),
+        Some(vec![euros]),
+        Some("eur".to_string()),
+        None,
+    )
+    .unwrap();
+    assert_eq!(eur.base_currency, "EUR");
+    assert!(eur.points.iter().all(|p| p.net_worth == 1000.0));
+}
+
+#[test]
+fn test_holdings_convert_from_their_quote_currency() {
+    let (_dir, db_path) = setup_db();
+    let depot = account_of_kind(&db_path, "Depot", Some("EUR"), "brokerage");
+    record(&db_path, depot, "2024-01-01", 1000.0);
+    crate::create_investment_transaction_db(
+        &db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id: depot,
+            date: "2024-01-02".to_string(),
+            ticker: "VTI".to_string(),
+            shares: 10.0,
+            price_per_share: 100.0,
+            fee: 0.0,
+            is_buy: true,
+            currency: None,
+        },
+    )
+    .unwrap();
+    // The trade was in euros, but VTI is quoted and closes in dollars
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO stock_prices (ticker, price, currency, last_updated)
+         VALUES ('VTI', 110.0, 'USD', '2024-01-03')",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO daily_stock_prices (ticker, date, price) VALUES ('VTI', '2024-01-03', 110.0)",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO daily_fx_rates (currency, date, rate) VALUES ('EUR', '2024-01-01', 1.10)",
+        [],
+    )
+    .unwrap();
+
+    let eur = crate::net_worth_series_db(
+        &db_path,
+        Some("2024-01-03".to_string()),
+        Some("2024-01-03".to_string()),
+        "daily".to_string(),
+        None,
+        Some("EUR".to_string()),
+        None,
+    )
+    .unwrap();
+    assert_eq!(eur.points[0].net_worth, 1000.0);
+}
+
+#[test]
+fn test_amount_without_rate_is_an_error() {
+    let (_dir, db_path) = setup_db();
+    let checking = account_of_kind(&db_path, "Checking", None, "cash");
+    record(&db_path, checking, "2024-01-01", 1000.0);
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO transactions (account_id, date, payee, amount, currency)
+         VALUES (?1, '2024-01-02', 'Hotel', -50.0, 'GBP')",
+        params![checking],
+    )
+    .unwrap();
+
+    let err = crate::net_worth_series_db(
+        &db_path,
+        None,
+        Some("2024-01-03".to_string()),
+        "daily".to_string(),
+        None,
+        None,
+        None,
+    )
+    .unwrap_err();
+    assert!(err.contains("No exchange rate from GBP to USD"), "{}", err);
+}
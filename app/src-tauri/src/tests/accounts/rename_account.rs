@@ -3,7 +3,8 @@ use super::common::setup_db;
 #[test]
 fn test_rename_account() {
     let (_dir, db_path) = setup_db();
-    let account = crate::create_account_db(&db_path, "Old Name".to_string(), 0.0, None).unwrap();
+    let account =
+        crate::create_account_db(&db_path, "Old Name".to_string(), 0.0, None, None).unwrap();
     let updated = crate::rename_account_db(&db_path, account.id, "New Name".to_string()).unwrap();
     assert_eq!(updated.name, "New Name");
 }
@@ -11,7 +12,7 @@ fn test_rename_account() {
 #[test]
 fn test_rename_account_empty_should_error() {
     let (_dir, db_path) = setup_db();
-    let account = crate::create_account_db(&db_path, "Old".to_string(), 0.0, None).unwrap();
+    let account = crate::create_account_db(&db_path, "Old".to_string(), 0.0, None, None).unwrap();
     let res = crate::rename_account_db(&db_path, account.id, "   ".to_string());
     assert!(res.is_err());
 }
@@ -26,8 +27,8 @@ fn test_rename_account_missing_id_should_error() {
 #[test]
 fn test_rename_account_duplicate_name_should_error() {
     let (_dir, db_path) = setup_db();
-    let _a = crate::create_account_db(&db_path, "A".to_string(), 0.0, None).unwrap();
-    let b = crate::create_account_db(&db_path, "B".to_string(), 0.0, None).unwrap();
+    let _a = crate::create_account_db(&db_path, "A".to_string(), 0.0, None, None).unwrap();
+    let b = crate::create_account_db(&db_path, "B".to_string(), 0.0, None, None).unwrap();
     let res = crate::rename_account_db(&db_path, b.id, "A".to_string());
     assert!(res.is_err());
 }
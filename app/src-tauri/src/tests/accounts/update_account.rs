@@ -3,9 +3,16 @@ use super::common::setup_db;
 #[test]
 fn test_update_account_currency() {
     let (_dir, db_path) = setup_db();
-    let acc = crate::create_account_db(&db_path, "UpdAcct".to_string(), 0.0, None).unwrap();
+    let acc = crate::create_account_db(&db_path, "UpdAcct".to_string(), 0.0, None, None).unwrap();
 
-    let updated = crate::update_account_db(&db_path, acc.id, "UpdAcct".to_string(), Some("EUR".to_string())).unwrap();
+    let updated = crate::update_account_db(
+        &db_path,
+        acc.id,
+        "UpdAcct".to_string(),
+        Some("EUR".to_string()),
+        None,
+    )
+    .unwrap();
     assert_eq!(updated.currency.as_deref(), Some("EUR"));
 
     // Ensure persisted value
@@ -13,3 +20,39 @@ fn test_update_account_currency() {
     let a = accounts.into_iter().find(|a| a.id == acc.id).unwrap();
     assert_eq!(a.currency.as_deref(), Some("EUR"));
 }
+
+#[test]
+fn test_update_account_kind() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Card".to_string(), 0.0, None, None).unwrap();
+    assert_eq!(acc.kind, "cash");
+
+    let updated = crate::update_account_db(
+        &db_path,
+        acc.id,
+        "Card".to_string(),
+        None,
+        Some("credit_card".to_string()),
+    )
+    .unwrap();
+    assert_eq!(updated.kind, "credit_card");
+
+    // Omitting the kind keeps the current one
+    let renamed =
+        crate::update_account_db(&db_path, acc.id, "Visa".to_string(), None, None).unwrap();
+    assert_eq!(renamed.kind, "credit_card");
+}
+
+#[test]
+fn test_update_account_unknown_kind_should_error() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Acc".to_string(), 0.0, None, None).unwrap();
+    let res = crate::update_account_db(
+        &db_path,
+        acc.id,
+        "Acc".to_string(),
+        None,
+        Some("savings_bond".to_string()),
+    );
+    assert!(res.is_err());
+}
@@ -18,7 +18,7 @@ fn test_create_account_and_transaction_via_helpers() {
         "2023-01-01".to_string(),
         acc2.name.clone(),
         Some("XFER".to_string()),
-        None,
+        Some("Transfer".to_string()),
         -30.0,
     )
     .unwrap();
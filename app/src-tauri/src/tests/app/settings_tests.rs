@@ -7,6 +7,7 @@ fn test_write_and_read_settings() {
 
     let s = crate::AppSettings {
         db_path: Some(dir_path.join("db.sqlite").to_string_lossy().to_string()),
+        ..Default::default()
     };
     crate::write_settings_to_dir(&dir_path, &s).unwrap();
 
@@ -20,6 +21,7 @@ fn test_get_db_path_override_creates_parent_dir() {
     let nested = dir.path().join("nested").join("db.sqlite");
     let s = crate::AppSettings {
         db_path: Some(nested.to_string_lossy().to_string()),
+        ..Default::default()
     };
     crate::write_settings_to_dir(dir.path(), &s).unwrap();
 
@@ -62,3 +64,29 @@ fn test_init_db_adds_linked_tx_column() {
         .any(|c| c == "linked_tx_id");
     assert!(has_linked_after);
 }
+
+#[test]
+fn test_rate_mode_is_kept_in_settings() {
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path().to_path_buf();
+
+    // Older settings files have no rate mode and convert at current rates
+    crate::write_settings_to_dir(&dir_path, &crate::AppSettings::default()).unwrap();
+    let s = crate::read_settings_from_dir(&dir_path).unwrap();
+    assert_eq!(
+        crate::fx::normalize_rate_mode(s.rate_mode).unwrap(),
+        "current"
+    );
+
+    let s = crate::AppSettings {
+        rate_mode: Some("transaction_date".to_string()),
+        ..Default::default()
+    };
+    crate::write_settings_to_dir(&dir_path, &s).unwrap();
+    let s = crate::read_settings_from_dir(&dir_path).unwrap();
+    assert_eq!(
+        crate::fx::normalize_rate_mode(s.rate_mode).unwrap(),
+        "transaction_date"
+    );
+    assert!(crate::fx::normalize_rate_mode(Some("yesterday".to_string())).is_err());
+}
@@ -0,0 +1,245 @@
+use super::common::{account, setup_db};
+use rusqlite::{params, Connection};
+
+fn buy(db_path: &std::path::PathBuf, account_id: i32, ticker: &str, shares: f64, price: f64) {
+    crate::create_investment_transaction_db(
+        db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id,
+            date: "2023-01-02".to_string(),
+            ticker: ticker.to_string(),
+            shares,
+            price_per_share: price,
+            fee: 0.0,
+            is_buy: true,
+            currency: None,
+        },
+    )
+    .unwrap();
+}
+
+fn quote(db_path: &std::path::PathBuf, ticker: &str, price: f64, quote_type: &str) {
+    let conn = Connection::open(db_path).unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO stock_prices (ticker, price, change_percent, currency, quote_type, last_updated)
+         VALUES (?1, ?2, 0, 'USD', ?3, '2024-01-01T00:00:00Z')",
+        params![ticker, price, quote_type],
+    )
+    .unwrap();
+}
+
+fn target(asset_class: &str, target_percent: f64) -> crate::AllocationTarget {
+    crate::AllocationTarget {
+        asset_class: asset_class.to_string(),
+        target_percent,
+    }
+}
+
+// 7000 in VTI and 2000 in BND in the portfolio, and an account outside it
+fn sample(db_path: &std::path::PathBuf) {
+    let ira = account(db_path, "IRA", None);
+    let taxable = account(db_path, "Taxable", None);
+    let other = account(db_path, "Play money", None);
+    buy(db_path, ira, "VTI", 70.0, 90.0);
+    buy(db_path, taxable, "BND", 40.0, 50.0);
+    buy(db_path, other, "BTC-USD", 1.0, 20000.0);
+    quote(db_path, "VTI", 100.0, "ETF");
+    quote(db_path, "BND", 50.0, "ETF");
+    quote(db_path, "BTC-USD", 30000.0, "CRYPTOCURRENCY");
+    crate::set_ticker_classification_db(
+        db_path,
+        "bnd".to_string(),
+        Some("bond".to_string()),
+        Some("US".to_string()),
+        None,
+    )
+    .unwrap();
+    for id in [ira, taxable] {
+        crate::set_account_portfolio_db(db_path, id, Some("Retirement".to_string())).unwrap();
+    }
+}
+
+fn plan(db_path: &std::path::PathBuf, contribution: f64, only: bool) -> crate::RebalancePlan {
+    crate::rebalance_plan_db(db_path, "Retirement".to_string(), contribution, only, None).unwrap()
+}
+
+fn trades(plan: &crate::RebalancePlan) -> Vec<(String, String, f64)> {
+    plan.trades
+        .iter()
+        .map(|t| (t.action.clone(), t.ticker.clone(), t.shares))
+        .collect()
+}
+
+#[test]
+fn test_classifications_default_from_quote_type() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+
+    let all = crate::get_ticker_classifications_db(&db_path).unwrap();
+    let tickers: Vec<&str> = all.iter().map(|c| c.ticker.as_str()).collect();
+    assert_eq!(tickers, vec!["BND", "BTC-USD", "VTI"]);
+    assert_eq!(
+        all[0],
+        crate::TickerClassification {
+            ticker: "BND".to_string(),
+            asset_class: "bond".to_string(),
+            region: Some("US".to_string()),
+            sector: None,
+            is_default: false,
+        }
+    );
+    assert_eq!(all[1].asset_class, "crypto");
+    assert!(all[1].is_default);
+    assert_eq!(all[2].asset_class, "stock");
+
+    // Only a sector: the class still follows the quote type
+    let vti = crate::set_ticker_classification_db(
+        &db_path,
+        "VTI".to_string(),
+        None,
+        None,
+        Some("Total market".to_string()),
+    )
+    .unwrap();
+    assert_eq!(vti.asset_class, "stock");
+    assert!(vti.is_default);
+    assert_eq!(vti.sector.as_deref(), Some("Total market"));
+
+    let cleared =
+        crate::set_ticker_classification_db(&db_path, "BND".to_string(), None, None, None).unwrap();
+    assert_eq!(cleared.asset_class, "stock");
+    assert_eq!(cleared.region, None);
+
+    let bad = crate::set_ticker_classification_db(
+        &db_path,
+        "VTI".to_string(),
+        Some("beanie babies".to_string()),
+        None,
+        None,
+    );
+    assert!(bad.unwrap_err().contains("Unknown asset class"));
+}
+
+#[test]
+fn test_targets_are_validated() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+
+    let saved = crate::set_allocation_targets_db(
+        &db_path,
+        "Retirement".to_string(),
+        vec![target("bond", 40.0), target("stock", 60.0)],
+    )
+    .unwrap();
+    assert_eq!(saved, vec![target("stock", 60.0), target("bond", 40.0)]);
+
+    let portfolios = crate::get_portfolios_db(&db_path).unwrap();
+    assert_eq!(portfolios.len(), 1);
+    assert_eq!(portfolios[0].name, "Retirement");
+    assert_eq!(portfolios[0].account_ids.len(), 2);
+    assert_eq!(portfolios[0].targets.len(), 2);
+
+    for targets in [
+        vec![target("stock", 60.0), target("bond", 30.0)],
+        vec![target("stock", 50.0), target("stock", 50.0)],
+        vec![target("stocks", 100.0)],
+        vec![target("stock", 120.0), target("bond", -20.0)],
+    ] {
+        assert!(
+            crate::set_allocation_targets_db(&db_path, "Retirement".to_string(), targets).is_err()
+        );
+    }
+    // A rejected update leaves the saved targets alone
+    assert_eq!(
+        crate::get_portfolios_db(&db_path).unwrap()[0].targets,
+        saved
+    );
+
+    crate::set_allocation_targets_db(&db_path, "Retirement".to_string(), vec![]).unwrap();
+    let missing = crate::rebalance_plan_db(&db_path, "Retirement".to_string(), 0.0, false, None);
+    assert!(missing.unwrap_err().contains("no allocation targets"));
+}
+
+#[test]
+fn test_rebalance_sells_overweight_and_buys_underweight() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+    crate::set_allocation_targets_db(
+        &db_path,
+        "Retirement".to_string(),
+        vec![target("stock", 60.0), target("bond", 40.0)],
+    )
+    .unwrap();
+
+    let full = plan(&db_path, 1000.0, false);
+    assert_eq!(full.total_value, 10000.0);
+    let stock = &full.allocations[0];
+    assert_eq!(stock.asset_class, "stock");
+    assert_eq!(stock.current_value, 7000.0);
+    assert_eq!(stock.target_value, 6000.0);
+    assert!((stock.drift - (700.0 / 9.0 - 60.0)).abs() < 1e-9);
+    assert_eq!(
+        trades(&full),
+        vec![
+            ("sell".to_string(), "VTI".to_string(), 10.0),
+            ("buy".to_string(), "BND".to_string(), 40.0),
+        ]
+    );
+    assert_eq!(full.trades[1].amount, 2000.0);
+    assert_eq!(full.cash_left, 0.0);
+
+    // Without sales the contribution all goes to bonds
+    let only = plan(&db_path, 1000.0, true);
+    assert_eq!(
+        trades(&only),
+        vec![("buy".to_string(), "BND".to_string(), 20.0)]
+    );
+
+    // A larger one is split by how far short each class is
+    let split = plan(&db_path, 3500.0, true);
+    assert_eq!(
+        trades(&split),
+        vec![
+            ("buy".to_string(), "VTI".to_string(), 5.0),
+            ("buy".to_string(), "BND".to_string(), 60.0),
+        ]
+    );
+    assert_eq!(split.cash_left, 0.0);
+
+    assert!(
+        crate::rebalance_plan_db(&db_path, "Retirement".to_string(), -5.0, false, None).is_err()
+    );
+}
+
+#[test]
+fn test_rebalance_keeps_cash_for_classes_without_holdings() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+    crate::set_allocation_targets_db(
+        &db_path,
+        "Retirement".to_string(),
+        vec![
+            target("stock", 50.0),
+            target("bond", 40.0),
+            target("cash", 10.0),
+        ],
+    )
+    .unwrap();
+
+    let full = plan(&db_path, 0.0, false);
+    assert_eq!(
+        trades(&full),
+        vec![
+            ("sell".to_string(), "VTI".to_string(), 25.0),
+            ("buy".to_string(), "BND".to_string(), 32.0),
+        ]
+    );
+    assert_eq!(full.cash_left, 900.0);
+    let cash = full
+        .allocations
+        .iter()
+        .find(|a| a.asset_class == "cash")
+        .unwrap();
+    assert_eq!(cash.current_value, 0.0);
+    assert_eq!(cash.drift, -10.0);
+}
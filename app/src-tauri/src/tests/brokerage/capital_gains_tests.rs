@@ -0,0 +1,157 @@
+use super::common::{account, setup_db, trade};
+use rusqlite::{params, Connection};
+
+fn sample(db_path: &std::path::PathBuf) -> i32 {
+    let acc = account(db_path, "Broker", None);
+    trade(db_path, acc, "2022-01-10", "AAPL", 10.0, 100.0, 0.0);
+    trade(db_path, acc, "2023-03-01", "AAPL", 10.0, 150.0, 0.0);
+    trade(db_path, acc, "2023-06-01", "AAPL", -15.0, 200.0, 3.0);
+    trade(db_path, acc, "2024-02-01", "AAPL", -2.0, 210.0, 0.0);
+    let conn = Connection::open(db_path).unwrap();
+    conn.execute(
+        "INSERT INTO daily_stock_prices (ticker, date, price) VALUES ('AAPL', '2023-12-29', 190.0)",
+        [],
+    )
+    .unwrap();
+    acc
+}
+
+#[test]
+fn test_report_splits_short_and_long_term() {
+    let (_dir, db_path) = setup_db();
+    let acc = sample(&db_path);
+
+    let report = crate::capital_gains_report_db(&db_path, 2023, None, None, None).unwrap();
+    assert_eq!(report.base_currency, "USD");
+    assert_eq!(report.realized.len(), 2);
+    assert_eq!(report.realized[0].account_name, "Broker");
+    assert_eq!(report.realized[0].term, "long");
+    assert_eq!(
+        report.long_term,
+        crate::CapitalGainTotals {
+            proceeds: 2000.0,
+            cost_basis: 1000.0,
+            fees: 2.0,
+            gain: 998.0,
+        }
+    );
+    assert_eq!(report.short_term.gain, 249.0);
+    assert_eq!(report.total.gain, 1247.0);
+
+    // The five shares left at year end, valued at the last close of the year
+    assert_eq!(report.unrealized.len(), 1);
+    let open = &report.unrealized[0];
+    assert_eq!(open.shares, 5.0);
+    assert_eq!(open.cost_basis, 750.0);
+    assert_eq!(open.market_value, Some(950.0));
+    assert_eq!(open.gain, Some(200.0));
+    assert_eq!(open.term, "short");
+
+    let earlier =
+        crate::capital_gains_report_db(&db_path, 2022, Some(vec![acc]), None, None).unwrap();
+    assert!(earlier.realized.is_empty());
+    assert_eq!(earlier.unrealized[0].market_value, None);
+
+    let other =
+        crate::capital_gains_report_db(&db_path, 2023, Some(vec![acc + 1]), None, None).unwrap();
+    assert!(other.realized.is_empty());
+    assert!(other.unrealized.is_empty());
+}
+
+#[test]
+fn test_report_converts_at_transaction_dates() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Depot", Some("EUR"));
+    trade(&db_path, acc, "2022-01-03", "AAPL", 10.0, 100.0, 0.0);
+    trade(&db_path, acc, "2023-02-01", "AAPL", -10.0, 120.0, 0.0);
+
+    let conn = Connection::open(&db_path).unwrap();
+    for (date, rate) in [("2022-01-03", 1.10), ("2023-02-01", 1.20)] {
+        conn.execute(
+            "INSERT INTO daily_fx_rates (currency, date, rate) VALUES ('EUR', ?1, ?2)",
+            params![date, rate],
+        )
+        .unwrap();
+    }
+
+    let report =
+        crate::capital_gains_report_db(&db_path, 2023, None, Some("usd".to_string()), None)
+            .unwrap();
+    let line = &report.realized[0];
+    assert_eq!(line.currency, "EUR");
+    assert_eq!(line.cost_basis, 1100.0);
+    assert_eq!(line.proceeds, 1440.0);
+    assert_eq!(line.gain, 340.0);
+    assert_eq!(line.term, "long");
+
+    let native =
+        crate::capital_gains_report_db(&db_path, 2023, None, Some("EUR".to_string()), None)
+            .unwrap();
+    assert_eq!(native.realized[0].gain, 200.0);
+
+    let missing =
+        crate::capital_gains_report_db(&db_path, 2023, None, Some("GBP".to_string()), None);
+    assert!(missing.unwrap_err().contains("No exchange rate"));
+}
+
+#[test]
+fn test_exports() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+
+    let csv = crate::export_capital_gains_db(&db_path, 2023, None, None, "csv".to_string(), None)
+        .unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("Account,Ticker,Shares"));
+    assert_eq!(
+        lines[1],
+        "Broker,AAPL,10,2022-01-10,2023-06-01,2000.00,1000.00,2.00,998.00,long,USD"
+    );
+
+    let form =
+        crate::export_capital_gains_db(&db_path, 2023, None, None, "form8949".to_string(), None)
+            .unwrap();
+    let short = form.find("Part I -").unwrap();
+    let long = form.find("Part II -").unwrap();
+    assert!(short < long);
+    assert!(form[short..long].contains("5 sh. AAPL,03/01/2023,06/01/2023,999.00,750.00,,,249.00"));
+    assert!(form[long..].contains("10 sh. AAPL,01/10/2022,06/01/2023,1998.00,1000.00,,,998.00"));
+    assert!(form[long..].contains("Totals,,,1998.00,1000.00,,,998.00"));
+
+    assert!(
+        crate::export_capital_gains_db(&db_path, 2023, None, None, "pdf".to_string(), None)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_sales_without_purchases_are_kept_apart() {
+    let (_dir, db_path) = setup_db();
+    let acc = sample(&db_path);
+    // Three more shares sold than were ever bought
+    trade(&db_path, acc, "2023-09-01", "AAPL", -8.0, 220.0, 0.0);
+
+    let report = crate::capital_gains_report_db(&db_path, 2023, None, None, None).unwrap();
+    let unknown: Vec<_> = report
+        .realized
+        .iter()
+        .filter(|l| l.term == "unknown")
+        .collect();
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(unknown[0].shares, 3.0);
+    assert_eq!(unknown[0].cost_basis, 0.0);
+    assert_eq!(report.unknown_term.proceeds, 660.0);
+    // The five shares that were held still count as short-term
+    assert_eq!(report.short_term.gain, 249.0 + 350.0);
+    assert_eq!(report.total.gain, 1247.0 + 350.0);
+
+    let csv = crate::export_capital_gains_db(&db_path, 2023, None, None, "csv".to_string(), None)
+        .unwrap();
+    assert!(csv.contains(",unknown,USD"));
+    let form =
+        crate::export_capital_gains_db(&db_path, 2023, None, None, "form8949".to_string(), None);
+    assert!(form
+        .unwrap_err()
+        .contains("3 shares without a recorded purchase"));
+}
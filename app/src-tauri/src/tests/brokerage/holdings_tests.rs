@@ -0,0 +1,253 @@
+use super::common::{account, setup_db, trade};
+use rusqlite::{params, Connection};
+
+fn quote(db_path: &std::path::PathBuf, ticker: &str, price: f64, change_percent: f64) {
+    let conn = Connection::open(db_path).unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO stock_prices (ticker, price, change_percent, currency, quote_type, last_updated)
+         VALUES (?1, ?2, ?3, 'USD', 'EQUITY', '2024-01-01T00:00:00Z')",
+        params![ticker, price, change_percent],
+    )
+    .unwrap();
+}
+
+fn holdings(db_path: &std::path::PathBuf) -> crate::HoldingsReport {
+    crate::get_holdings_db(db_path, None, None, None).unwrap()
+}
+
+#[test]
+fn test_buys_and_sells_follow_lot_method() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    trade(&db_path, acc, "2023-01-01", "AAPL", 10.0, 150.0, 5.0);
+    trade(&db_path, acc, "2023-03-01", "AAPL", 5.0, 160.0, 5.0);
+    trade(&db_path, acc, "2023-04-01", "AAPL", -2.0, 170.0, 0.0);
+    trade(&db_path, acc, "2023-05-01", "GOOGL", 5.0, 2000.0, 10.0);
+
+    // FIFO sells from the first lot: 8/10 of 1505 plus 805
+    let fifo = holdings(&db_path);
+    let aapl = fifo.holdings.iter().find(|h| h.ticker == "AAPL").unwrap();
+    assert_eq!(aapl.shares, 13.0);
+    assert_eq!(aapl.cost_basis, 2009.0);
+    assert_eq!(aapl.account_ids, vec![acc]);
+
+    // Average cost matches what the frontend computed: 13/15 of 2310
+    crate::set_lot_method_db(&db_path, acc, "average".to_string()).unwrap();
+    let average = holdings(&db_path);
+    let aapl = average
+        .holdings
+        .iter()
+        .find(|h| h.ticker == "AAPL")
+        .unwrap();
+    assert_eq!(aapl.cost_basis, 2002.0);
+    let googl = average
+        .holdings
+        .iter()
+        .find(|h| h.ticker == "GOOGL")
+        .unwrap();
+    assert_eq!(googl.shares, 5.0);
+    assert_eq!(googl.cost_basis, 10010.0);
+
+    trade(&db_path, acc, "2023-06-01", "GOOGL", -5.0, 2100.0, 0.0);
+    let sold = holdings(&db_path);
+    assert_eq!(sold.holdings.len(), 1);
+    assert_eq!(sold.holdings[0].ticker, "AAPL");
+}
+
+#[test]
+fn test_quotes_value_holdings() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    trade(&db_path, acc, "2023-01-01", "AAPL", 10.0, 150.0, 0.0);
+    trade(&db_path, acc, "2023-01-01", "MSFT", 10.0, 500.0, 0.0);
+    quote(&db_path, "AAPL", 200.0, 10.0);
+
+    let report = holdings(&db_path);
+    assert_eq!(report.base_currency, "USD");
+    let aapl = &report.holdings[0];
+    assert_eq!(aapl.ticker, "AAPL");
+    assert_eq!(aapl.price, 200.0);
+    assert_eq!(aapl.market_value, 2000.0);
+    assert_eq!(aapl.unrealized_gain, 500.0);
+    assert!((aapl.unrealized_gain_percent - 100.0 / 3.0).abs() < 1e-9);
+    assert_eq!(aapl.day_change, 181.82);
+    assert_eq!(aapl.quote_type.as_deref(), Some("EQUITY"));
+
+    // No quote yet: valued at zero, a total loss on paper
+    let msft = &report.holdings[1];
+    assert_eq!(msft.price, 0.0);
+    assert_eq!(msft.market_value, 0.0);
+    assert_eq!(msft.unrealized_gain_percent, -100.0);
+
+    assert_eq!(report.total_value, 2000.0);
+    assert_eq!(report.total_cost_basis, 6500.0);
+    assert_eq!(report.total_unrealized_gain, -4500.0);
+    assert_eq!(report.total_day_change, 181.82);
+
+    let other = crate::get_holdings_db(&db_path, Some(vec![acc + 1]), None, None).unwrap();
+    assert!(other.holdings.is_empty());
+    assert_eq!(other.total_value, 0.0);
+}
+
+#[test]
+fn test_total_daily_loss_has_no_day_change() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    trade(&db_path, acc, "2023-01-01", "GONE", 10.0, 5.0, 0.0);
+    quote(&db_path, "GONE", 0.0, -100.0);
+
+    let report = holdings(&db_path);
+    assert_eq!(report.holdings[0].day_change, 0.0);
+    assert_eq!(report.total_day_change, 0.0);
+}
+
+#[test]
+fn test_cache_is_invalidated_by_writes() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    trade(&db_path, acc, "2023-01-01", "VTI", 10.0, 100.0, 0.0);
+    assert_eq!(holdings(&db_path).holdings[0].shares, 10.0);
+
+    // Written behind the service's back, so the cached lots are still served
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO transactions (account_id, date, payee, category, amount, ticker, shares, price_per_share, fee, action)
+         VALUES (?1, '2023-02-01', 'Buy VTI', 'Buy', -500.0, 'VTI', 5.0, 100.0, 0.0, 'buy')",
+        params![acc],
+    )
+    .unwrap();
+    assert_eq!(holdings(&db_path).holdings[0].shares, 10.0);
+    crate::invalidate_holdings_cache(&db_path);
+    assert_eq!(holdings(&db_path).holdings[0].shares, 15.0);
+
+    trade(&db_path, acc, "2023-03-01", "VTI", -3.0, 110.0, 0.0);
+    assert_eq!(holdings(&db_path).holdings[0].shares, 12.0);
+
+    let sale = crate::get_transactions_db(&db_path, acc)
+        .unwrap()
+        .into_iter()
+        .find(|t| t.shares == Some(-3.0))
+        .unwrap();
+    crate::delete_transaction_db(&db_path, sale.id, false).unwrap();
+    assert_eq!(holdings(&db_path).holdings[0].shares, 15.0);
+}
+
+#[test]
+fn test_cache_is_invalidated_by_account_changes() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    trade(&db_path, acc, "2023-01-02", "VTI", 10.0, 100.0, 0.0);
+    assert_eq!(holdings(&db_path).holdings[0].cost_basis, 1000.0);
+
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO daily_fx_rates (currency, date, rate) VALUES ('EUR', '2023-01-02', 1.1)",
+        [],
+    )
+    .unwrap();
+    crate::update_account_db(
+        &db_path,
+        acc,
+        "Broker".to_string(),
+        Some("EUR".to_string()),
+        None,
+    )
+    .unwrap();
+    let report = holdings(&db_path);
+    assert_eq!(report.holdings[0].currency, "EUR");
+    assert_eq!(report.holdings[0].cost_basis, 1100.0);
+}
+
+#[test]
+fn test_as_of_uses_closes_and_splits() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    trade(&db_path, acc, "2023-01-02", "NVDA", 10.0, 100.0, 0.0);
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            account_id: acc,
+            date: "2023-06-01".to_string(),
+            action: "split".to_string(),
+            ticker: Some("NVDA".to_string()),
+            numerator: Some(2.0),
+            denominator: Some(1.0),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    // Closes are stored in today's split basis
+    let conn = Connection::open(&db_path).unwrap();
+    for (date, price) in [
+        ("2023-05-30", 50.0),
+        ("2023-05-31", 55.0),
+        ("2023-06-30", 60.0),
+    ] {
+        conn.execute(
+            "INSERT INTO daily_stock_prices (ticker, date, price) VALUES ('NVDA', ?1, ?2)",
+            params![date, price],
+        )
+        .unwrap();
+    }
+
+    let before =
+        crate::get_holdings_db(&db_path, None, Some("2023-05-31".to_string()), None).unwrap();
+    let nvda = &before.holdings[0];
+    assert_eq!(nvda.shares, 10.0);
+    assert_eq!(nvda.price, 55.0);
+    assert_eq!(nvda.market_value, 1100.0);
+    assert_eq!(nvda.day_change, 100.0);
+    assert_eq!(nvda.cost_basis, 1000.0);
+
+    let after =
+        crate::get_holdings_db(&db_path, None, Some("2023-12-31".to_string()), None).unwrap();
+    assert_eq!(after.holdings[0].shares, 20.0);
+    assert_eq!(after.holdings[0].market_value, 1200.0);
+    assert_eq!(after.holdings[0].day_change, 100.0);
+}
+
+#[test]
+fn test_converts_to_base_currency() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Depot", Some("EUR"));
+    trade(&db_path, acc, "2023-01-02", "SAP", 10.0, 100.0, 0.0);
+    let conn = Connection::open(&db_path).unwrap();
+    for (date, rate) in [("2023-01-02", 1.10), ("2023-12-29", 1.20)] {
+        conn.execute(
+            "INSERT INTO daily_fx_rates (currency, date, rate) VALUES ('EUR', ?1, ?2)",
+            params![date, rate],
+        )
+        .unwrap();
+    }
+    conn.execute(
+        "INSERT INTO daily_stock_prices (ticker, date, price) VALUES ('SAP', '2023-12-29', 120.0)",
+        [],
+    )
+    .unwrap();
+
+    let usd = crate::get_holdings_db(&db_path, None, Some("2023-12-31".to_string()), None).unwrap();
+    let sap = &usd.holdings[0];
+    assert_eq!(sap.currency, "EUR");
+    assert_eq!(sap.price, 120.0);
+    assert_eq!(sap.cost_basis, 1100.0);
+    assert_eq!(sap.market_value, 1440.0);
+    assert_eq!(sap.unrealized_gain, 340.0);
+
+    let eur = crate::get_holdings_db(
+        &db_path,
+        None,
+        Some("2023-12-31".to_string()),
+        Some("eur".to_string()),
+    )
+    .unwrap();
+    assert_eq!(eur.base_currency, "EUR");
+    assert_eq!(eur.holdings[0].unrealized_gain, 200.0);
+
+    let missing = crate::get_holdings_db(
+        &db_path,
+        None,
+        Some("2023-12-31".to_string()),
+        Some("GBP".to_string()),
+    );
+    assert!(missing.unwrap_err().contains("No exchange rate"));
+}
@@ -0,0 +1,398 @@
+use super::common::{funded_account, setup_db};
+use rusqlite::{params, Connection};
+
+fn buy(db_path: &std::path::PathBuf, account_id: i32, ticker: &str, shares: f64, price: f64) {
+    crate::create_investment_transaction_db(
+        db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id,
+            date: "2023-01-02".to_string(),
+            ticker: ticker.to_string(),
+            shares,
+            price_per_share: price,
+            fee: 0.0,
+            is_buy: true,
+            currency: None,
+        },
+    )
+    .unwrap();
+}
+
+fn action(account_id: i32, date: &str, action: &str) -> crate::InvestmentActionArgs {
+    crate::InvestmentActionArgs {
+        account_id,
+        date: date.to_string(),
+        action: action.to_string(),
+        ..Default::default()
+    }
+}
+
+fn balance(db_path: &std::path::PathBuf, account_id: i32) -> f64 {
+    crate::get_accounts_db(db_path)
+        .unwrap()
+        .into_iter()
+        .find(|a| a.id == account_id)
+        .unwrap()
+        .balance
+}
+
+fn shares(db_path: &std::path::PathBuf, account_id: i32, ticker: &str) -> f64 {
+    let conn = Connection::open(db_path).unwrap();
+    conn.query_row(
+        "SELECT COALESCE(SUM(shares), 0) FROM transactions WHERE account_id = ?1 AND ticker = ?2",
+        params![account_id, ticker],
+        |row| row.get(0),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_cash_actions_move_balance() {
+    let (_dir, db_path) = setup_db();
+    let acc = funded_account(&db_path, "Broker", 1000.0);
+    buy(&db_path, acc, "AAPL", 10.0, 50.0);
+
+    let dividend = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("aapl".to_string()),
+            amount: Some(12.0),
+            ..action(acc, "2023-02-01", "dividend")
+        },
+    )
+    .unwrap();
+    assert_eq!(dividend.len(), 1);
+    assert_eq!(dividend[0].ticker.as_deref(), Some("AAPL"));
+    assert_eq!(dividend[0].shares, None);
+    assert_eq!(dividend[0].category.as_deref(), Some("Dividend"));
+    assert_eq!(dividend[0].action.as_deref(), Some("dividend"));
+
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("AAPL".to_string()),
+            amount: Some(1.8),
+            ..action(acc, "2023-02-01", "withholding_tax")
+        },
+    )
+    .unwrap();
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            amount: Some(3.0),
+            ..action(acc, "2023-02-28", "interest")
+        },
+    )
+    .unwrap();
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("AAPL".to_string()),
+            amount: Some(20.0),
+            fee: Some(1.0),
+            ..action(acc, "2023-03-01", "return_of_capital")
+        },
+    )
+    .unwrap();
+
+    // 1000 - 500 + 12 - 1.8 + 3 + (20 - 1)
+    assert!((balance(&db_path, acc) - 532.2).abs() < 1e-9);
+    assert_eq!(shares(&db_path, acc, "AAPL"), 10.0);
+}
+
+#[test]
+fn test_drip_records_income_and_reinvestment() {
+    let (_dir, db_path) = setup_db();
+    let acc = funded_account(&db_path, "Broker", 1000.0);
+    buy(&db_path, acc, "VTI", 10.0, 50.0);
+
+    let before = crate::get_transactions_db(&db_path, acc).unwrap().len();
+
+    let rows = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("VTI".to_string()),
+            amount: Some(25.0),
+            shares: Some(0.5),
+            ..action(acc, "2023-03-15", "drip")
+        },
+    )
+    .unwrap();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].action.as_deref(), Some("dividend"));
+    assert_eq!(rows[0].amount, 25.0);
+    assert_eq!(rows[1].action.as_deref(), Some("drip"));
+    assert_eq!(rows[1].amount, -25.0);
+    assert_eq!(rows[1].price_per_share, Some(50.0));
+    assert_eq!(balance(&db_path, acc), 500.0);
+    assert_eq!(shares(&db_path, acc, "VTI"), 10.5);
+
+    // Deleting the reinvestment keeps the dividend as cash
+    crate::delete_transaction_db(&db_path, rows[1].id, false).unwrap();
+    assert_eq!(shares(&db_path, acc, "VTI"), 10.0);
+    assert_eq!(balance(&db_path, acc), 525.0);
+    let remaining = crate::get_transactions_db(&db_path, acc).unwrap();
+    assert_eq!(remaining.len(), before + 1);
+}
+
+#[test]
+fn test_editing_drip_dividend_leaves_reinvestment() {
+    let (_dir, db_path) = setup_db();
+    let acc = funded_account(&db_path, "Broker", 1000.0);
+    buy(&db_path, acc, "VTI", 10.0, 50.0);
+    let rows = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("VTI".to_string()),
+            amount: Some(25.0),
+            shares: Some(0.5),
+            ..action(acc, "2023-03-15", "drip")
+        },
+    )
+    .unwrap();
+
+    crate::update_transaction_db(
+        &db_path,
+        crate::UpdateTransactionArgs {
+            id: rows[0].id,
+            account_id: acc,
+            date: "2023-03-16".to_string(),
+            payee: "VTI dividend".to_string(),
+            notes: Some("Corrected".to_string()),
+            category: Some("Dividend".to_string()),
+            amount: 26.0,
+            currency: None,
+            override_reconciled: false,
+        },
+    )
+    .unwrap();
+
+    let reinvested = crate::get_transactions_db(&db_path, acc)
+        .unwrap()
+        .into_iter()
+        .find(|t| t.id == rows[1].id)
+        .unwrap();
+    assert_eq!(reinvested.date, "2023-03-15");
+    assert_eq!(reinvested.payee, "Dividend Reinvestment");
+    assert_eq!(reinvested.category.as_deref(), Some("Investment"));
+    assert_eq!(reinvested.notes, rows[1].notes);
+    assert_eq!(reinvested.amount, -25.0);
+    assert_eq!(reinvested.shares, Some(0.5));
+    assert_eq!(balance(&db_path, acc), 501.0);
+
+    // The dividend can go on its own too
+    crate::delete_transaction_db(&db_path, rows[0].id, false).unwrap();
+    assert_eq!(shares(&db_path, acc, "VTI"), 10.5);
+}
+
+#[test]
+fn test_split_is_recorded_in_split_table() {
+    let (_dir, db_path) = setup_db();
+    let acc = funded_account(&db_path, "Broker", 1000.0);
+    buy(&db_path, acc, "NVDA", 5.0, 100.0);
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO daily_stock_prices (ticker, date, price) VALUES ('NVDA', '2023-05-31', 400.0)",
+        [],
+    )
+    .unwrap();
+    let close = || -> f64 {
+        conn.query_row(
+            "SELECT price FROM daily_stock_prices WHERE ticker = 'NVDA'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap()
+    };
+
+    let rows = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("NVDA".to_string()),
+            numerator: Some(4.0),
+            denominator: Some(1.0),
+            ..action(acc, "2023-06-01", "split")
+        },
+    )
+    .unwrap();
+    assert_eq!(rows[0].amount, 0.0);
+    assert_eq!(rows[0].notes.as_deref(), Some("4:1 split of NVDA"));
+
+    let splits = crate::get_stock_splits_db(&db_path, Some("NVDA".to_string())).unwrap();
+    assert_eq!(splits.len(), 1);
+    assert_eq!(splits[0].date, "2023-06-01");
+    assert_eq!(splits[0].numerator, 4.0);
+    assert_eq!(balance(&db_path, acc), 500.0);
+    assert_eq!(close(), 100.0);
+
+    // The same split reported a couple of days off is not counted twice
+    let err = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("NVDA".to_string()),
+            numerator: Some(4.0),
+            denominator: Some(1.0),
+            ..action(acc, "2023-06-03", "split")
+        },
+    )
+    .unwrap_err();
+    assert!(err.contains("already recorded on 2023-06-01"));
+    assert_eq!(close(), 100.0);
+
+    crate::delete_transaction_db(&db_path, rows[0].id, false).unwrap();
+    assert_eq!(close(), 400.0);
+    assert!(
+        crate::get_stock_splits_db(&db_path, Some("NVDA".to_string()))
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_spin_off_links_parent_and_child() {
+    let (_dir, db_path) = setup_db();
+    let acc = funded_account(&db_path, "Broker", 5000.0);
+    buy(&db_path, acc, "GE", 10.0, 100.0);
+
+    let rows = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("GE".to_string()),
+            new_ticker: Some("GEHC".to_string()),
+            shares: Some(3.0),
+            basis_fraction: Some(0.2),
+            ..action(acc, "2023-01-04", "spin_off")
+        },
+    )
+    .unwrap();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].ticker.as_deref(), Some("GE"));
+    assert_eq!(rows[0].shares, None);
+    assert_eq!(rows[1].ticker.as_deref(), Some("GEHC"));
+    assert_eq!(rows[1].shares, Some(3.0));
+    assert_eq!(shares(&db_path, acc, "GE"), 10.0);
+    assert_eq!(shares(&db_path, acc, "GEHC"), 3.0);
+    assert_eq!(balance(&db_path, acc), 4000.0);
+
+    let conn = Connection::open(&db_path).unwrap();
+    let (related, fraction): (i32, f64) = conn
+        .query_row(
+            "SELECT related_tx_id, basis_fraction FROM transactions WHERE id = ?1",
+            params![rows[1].id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(related, rows[0].id);
+    assert_eq!(fraction, 0.2);
+}
+
+#[test]
+fn test_transfer_out_moves_shares_between_accounts() {
+    let (_dir, db_path) = setup_db();
+    let from = funded_account(&db_path, "Old Broker", 1000.0);
+    let to = funded_account(&db_path, "New Broker", 0.0);
+    buy(&db_path, from, "MSFT", 8.0, 100.0);
+
+    let rows = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("MSFT".to_string()),
+            shares: Some(8.0),
+            fee: Some(25.0),
+            to_account_id: Some(to),
+            ..action(from, "2023-05-01", "transfer_out")
+        },
+    )
+    .unwrap();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].shares, Some(-8.0));
+    assert_eq!(rows[1].account_id, to);
+    assert_eq!(rows[1].action.as_deref(), Some("transfer_in"));
+    assert_eq!(shares(&db_path, from, "MSFT"), 0.0);
+    assert_eq!(shares(&db_path, to, "MSFT"), 8.0);
+    assert_eq!(balance(&db_path, from), 175.0);
+    assert_eq!(balance(&db_path, to), 0.0);
+}
+
+#[test]
+fn test_invalid_actions_are_rejected() {
+    let (_dir, db_path) = setup_db();
+    let acc = funded_account(&db_path, "Broker", 0.0);
+
+    let unknown = crate::record_investment_action_db(&db_path, action(acc, "2023-01-01", "gift"));
+    assert!(unknown.unwrap_err().contains("Unknown investment action"));
+
+    let no_ticker = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            amount: Some(5.0),
+            ..action(acc, "2023-01-01", "dividend")
+        },
+    );
+    assert!(no_ticker.unwrap_err().contains("ticker"));
+
+    let negative = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("AAPL".to_string()),
+            amount: Some(-5.0),
+            ..action(acc, "2023-01-01", "dividend")
+        },
+    );
+    assert!(negative.unwrap_err().contains("positive"));
+
+    let same_account = crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            ticker: Some("AAPL".to_string()),
+            shares: Some(1.0),
+            to_account_id: Some(acc),
+            ..action(acc, "2023-01-01", "transfer_out")
+        },
+    );
+    assert!(same_account.is_err());
+    assert!(crate::get_transactions_db(&db_path, acc)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_cash_actions_feed_income_reports() {
+    let (_dir, db_path) = setup_db();
+    let acc = funded_account(&db_path, "Broker", 1000.0);
+    buy(&db_path, acc, "AAPL", 10.0, 50.0);
+
+    let mut args = action(acc, "2023-03-01", "dividend");
+    args.ticker = Some("AAPL".to_string());
+    args.amount = Some(20.0);
+    crate::record_investment_action_db(&db_path, args).unwrap();
+    let mut args = action(acc, "2023-03-02", "interest");
+    args.amount = Some(5.0);
+    crate::record_investment_action_db(&db_path, args).unwrap();
+    let mut args = action(acc, "2023-03-03", "withholding_tax");
+    args.ticker = Some("AAPL".to_string());
+    args.amount = Some(3.0);
+    crate::record_investment_action_db(&db_path, args).unwrap();
+    let mut args = action(acc, "2023-03-15", "drip");
+    args.ticker = Some("AAPL".to_string());
+    args.amount = Some(30.0);
+    args.shares = Some(0.5);
+    crate::record_investment_action_db(&db_path, args).unwrap();
+
+    let report = crate::income_expense_report_db(
+        &db_path,
+        crate::ReportArgs {
+            from: Some("2023-03-01".to_string()),
+            to: Some("2023-03-31".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    // The reinvested dividend is income; buying shares with it is not spending
+    assert_eq!(report.income, 55.0);
+    assert_eq!(report.expense, 3.0);
+    assert!((report.months[0].savings_rate.unwrap() - 52.0 / 55.0 * 100.0).abs() < 1e-9);
+}
@@ -0,0 +1,255 @@
+use super::common::{account, setup_db, trade};
+
+// Two buys a year apart, then a sale of 15 shares spanning both lots
+fn two_lots_and_a_sale(db_path: &std::path::PathBuf, acc: i32) -> (i32, i32, i32) {
+    let first = trade(db_path, acc, "2022-01-10", "AAPL", 10.0, 100.0, 0.0);
+    let second = trade(db_path, acc, "2023-03-01", "AAPL", 10.0, 150.0, 0.0);
+    let sale = trade(db_path, acc, "2023-06-01", "AAPL", -15.0, 200.0, 3.0);
+    (first, second, sale)
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+#[test]
+fn test_fifo_sells_oldest_lot_first() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    let (first, second, sale) = two_lots_and_a_sale(&db_path, acc);
+
+    assert_eq!(crate::get_lot_method_db(&db_path, acc).unwrap(), "fifo");
+    let report = crate::get_tax_lots_db(&db_path, None, None).unwrap();
+
+    assert_eq!(report.realized.len(), 2);
+    let long = &report.realized[0];
+    assert_eq!(long.lot_id, Some(first));
+    assert_eq!(long.sell_tx_id, sale);
+    assert_eq!(long.term, "long");
+    assert!(close(long.shares, 10.0));
+    assert!(close(long.proceeds, 2000.0));
+    assert!(close(long.fees, 2.0));
+    assert!(close(long.gain, 998.0));
+    let short = &report.realized[1];
+    assert_eq!(short.lot_id, Some(second));
+    assert_eq!(short.term, "short");
+    assert!(close(short.cost_basis, 750.0));
+    assert!(close(short.gain, 249.0));
+
+    assert_eq!(report.open_lots.len(), 1);
+    assert_eq!(report.open_lots[0].lot_id, second);
+    assert_eq!(report.open_lots[0].acquired_date, "2023-03-01");
+    assert!(close(report.open_lots[0].shares, 5.0));
+    assert!(close(report.open_lots[0].cost_per_share, 150.0));
+}
+
+#[test]
+fn test_lifo_and_average_methods() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    let (first, second, _) = two_lots_and_a_sale(&db_path, acc);
+
+    crate::set_lot_method_db(&db_path, acc, "lifo".to_string()).unwrap();
+    let report = crate::get_tax_lots_db(&db_path, Some(vec![acc]), None).unwrap();
+    assert_eq!(report.realized[0].lot_id, Some(second));
+    assert_eq!(report.realized[0].term, "short");
+    assert!(close(report.realized[0].gain, 498.0));
+    assert_eq!(report.realized[1].lot_id, Some(first));
+    assert!(close(report.realized[1].gain, 499.0));
+    assert_eq!(report.open_lots[0].lot_id, first);
+    assert!(close(report.open_lots[0].cost_basis, 500.0));
+
+    crate::set_lot_method_db(&db_path, acc, "average".to_string()).unwrap();
+    let report = crate::get_tax_lots_db(&db_path, Some(vec![acc]), None).unwrap();
+    let basis: f64 = report.realized.iter().map(|r| r.cost_basis).sum();
+    assert!(close(basis, 15.0 * 125.0));
+    assert_eq!(report.realized[0].term, "long");
+    assert!(close(report.open_lots[0].cost_per_share, 125.0));
+}
+
+#[test]
+fn test_specific_identification() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    let (first, second, sale) = two_lots_and_a_sale(&db_path, acc);
+    crate::set_lot_method_db(&db_path, acc, "specific_id".to_string()).unwrap();
+
+    let selection = vec![crate::LotSelection {
+        lot_id: second,
+        shares: 10.0,
+    }];
+    crate::set_lot_selection_db(&db_path, sale, selection.clone()).unwrap();
+    assert_eq!(
+        crate::get_lot_selection_db(&db_path, sale).unwrap(),
+        selection
+    );
+
+    // The picked lot goes first, the rest of the sale falls back to FIFO
+    let report = crate::get_tax_lots_db(&db_path, None, None).unwrap();
+    assert_eq!(report.realized[0].lot_id, Some(second));
+    assert!(close(report.realized[0].shares, 10.0));
+    assert_eq!(report.realized[1].lot_id, Some(first));
+    assert!(close(report.realized[1].shares, 5.0));
+    assert_eq!(report.open_lots[0].lot_id, first);
+    assert!(close(report.open_lots[0].shares, 5.0));
+}
+
+#[test]
+fn test_invalid_method_and_selection_are_rejected() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    let (first, _, sale) = two_lots_and_a_sale(&db_path, acc);
+
+    assert!(crate::set_lot_method_db(&db_path, acc, "hifo".to_string()).is_err());
+    assert!(crate::set_lot_method_db(&db_path, 999, "lifo".to_string()).is_err());
+
+    let pick = |shares| {
+        vec![crate::LotSelection {
+            lot_id: first,
+            shares,
+        }]
+    };
+    assert!(crate::set_lot_selection_db(&db_path, first, pick(1.0)).is_err());
+    assert!(crate::set_lot_selection_db(&db_path, sale, pick(16.0)).is_err());
+    assert!(crate::set_lot_selection_db(&db_path, sale, pick(-1.0)).is_err());
+
+    // Lots of another ticker, another account or bought after the sale can't be picked
+    let other_ticker = trade(&db_path, acc, "2022-02-01", "MSFT", 10.0, 50.0, 0.0);
+    let other_account = account(&db_path, "Other", None);
+    let elsewhere = trade(
+        &db_path,
+        other_account,
+        "2022-02-01",
+        "AAPL",
+        10.0,
+        90.0,
+        0.0,
+    );
+    let later = trade(&db_path, acc, "2023-07-01", "AAPL", 10.0, 210.0, 0.0);
+    // Bought on the sale date, but recorded after the sale
+    let same_day = trade(&db_path, acc, "2023-06-01", "AAPL", 10.0, 205.0, 0.0);
+    for lot_id in [other_ticker, elsewhere, later, same_day, 9999] {
+        let selection = vec![crate::LotSelection {
+            lot_id,
+            shares: 1.0,
+        }];
+        assert!(crate::set_lot_selection_db(&db_path, sale, selection).is_err());
+    }
+    assert!(crate::get_lot_selection_db(&db_path, sale)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_lots_follow_splits() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    let buy = trade(&db_path, acc, "2023-01-02", "NVDA", 10.0, 100.0, 0.0);
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            account_id: acc,
+            date: "2023-06-01".to_string(),
+            action: "split".to_string(),
+            ticker: Some("NVDA".to_string()),
+            numerator: Some(2.0),
+            denominator: Some(1.0),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    trade(&db_path, acc, "2023-07-01", "NVDA", -5.0, 60.0, 0.0);
+
+    let report = crate::get_tax_lots_db(&db_path, None, None).unwrap();
+    assert!(close(report.realized[0].shares, 5.0));
+    assert!(close(report.realized[0].cost_basis, 250.0));
+    assert!(close(report.realized[0].gain, 50.0));
+    assert_eq!(report.open_lots[0].lot_id, buy);
+    assert!(close(report.open_lots[0].shares, 15.0));
+    assert!(close(report.open_lots[0].cost_basis, 750.0));
+
+    let before = crate::get_tax_lots_db(&db_path, None, Some("2023-05-01".to_string())).unwrap();
+    assert!(before.realized.is_empty());
+    assert!(close(before.open_lots[0].shares, 10.0));
+}
+
+#[test]
+fn test_transfer_keeps_acquisition_date() {
+    let (_dir, db_path) = setup_db();
+    let from = account(&db_path, "Old Broker", None);
+    let to = account(&db_path, "New Broker", None);
+    let buy = trade(&db_path, from, "2021-01-05", "MSFT", 10.0, 50.0, 0.0);
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            account_id: from,
+            date: "2023-01-01".to_string(),
+            action: "transfer_out".to_string(),
+            ticker: Some("MSFT".to_string()),
+            shares: Some(10.0),
+            to_account_id: Some(to),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    trade(&db_path, to, "2023-02-01", "MSFT", -10.0, 80.0, 0.0);
+
+    let report = crate::get_tax_lots_db(&db_path, Some(vec![to]), None).unwrap();
+    assert_eq!(report.realized.len(), 1);
+    assert_eq!(report.realized[0].lot_id, Some(buy));
+    assert_eq!(
+        report.realized[0].acquired_date.as_deref(),
+        Some("2021-01-05")
+    );
+    assert_eq!(report.realized[0].term, "long");
+    assert!(close(report.realized[0].gain, 300.0));
+    assert!(report.open_lots.is_empty());
+    assert!(crate::get_tax_lots_db(&db_path, Some(vec![from]), None)
+        .unwrap()
+        .realized
+        .is_empty());
+}
+
+#[test]
+fn test_spin_off_and_return_of_capital_adjust_basis() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    trade(&db_path, acc, "2022-05-02", "GE", 10.0, 100.0, 0.0);
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            account_id: acc,
+            date: "2023-01-04".to_string(),
+            action: "spin_off".to_string(),
+            ticker: Some("GE".to_string()),
+            new_ticker: Some("GEHC".to_string()),
+            shares: Some(3.0),
+            basis_fraction: Some(0.2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            account_id: acc,
+            date: "2023-02-01".to_string(),
+            action: "return_of_capital".to_string(),
+            ticker: Some("GE".to_string()),
+            amount: Some(100.0),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let report = crate::get_tax_lots_db(&db_path, None, None).unwrap();
+    assert_eq!(report.open_lots.len(), 2);
+    let ge = &report.open_lots[0];
+    assert_eq!(ge.ticker, "GE");
+    assert!(close(ge.cost_basis, 700.0));
+    let gehc = &report.open_lots[1];
+    assert_eq!(gehc.ticker, "GEHC");
+    assert_eq!(gehc.acquired_date, "2022-05-02");
+    assert!(close(gehc.shares, 3.0));
+    assert!(close(gehc.cost_basis, 200.0));
+}
@@ -1,6 +1,12 @@
 pub use super::common;
 
+pub mod allocation_tests;
 pub mod brokerage_transaction;
+pub mod capital_gains_tests;
+pub mod holdings_tests;
+pub mod investment_actions_tests;
+pub mod lots_tests;
+pub mod performance_tests;
 pub mod update_brokerage_counterpart_absent;
 pub mod update_brokerage_move;
 pub mod update_brokerage_transaction;
@@ -0,0 +1,238 @@
+use super::common::{account, setup_db, trade};
+use rusqlite::{params, Connection};
+
+fn prices(db_path: &std::path::PathBuf, ticker: &str, closes: &[(&str, f64)]) {
+    let conn = Connection::open(db_path).unwrap();
+    for (date, price) in closes {
+        conn.execute(
+            "INSERT INTO daily_stock_prices (ticker, date, price) VALUES (?1, ?2, ?3)",
+            params![ticker, date, price],
+        )
+        .unwrap();
+    }
+}
+
+fn report(db_path: &std::path::PathBuf, period: &str, as_of: &str) -> crate::PerformanceReport {
+    crate::get_performance_db(
+        db_path,
+        crate::PerformanceArgs {
+            period: period.to_string(),
+            as_of: Some(as_of.to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+fn close(a: Option<f64>, b: f64) -> bool {
+    a.is_some_and(|a| (a - b).abs() < 1e-4)
+}
+
+#[test]
+fn test_single_buy_returns() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    trade(&db_path, acc, "2023-01-02", "VTI", 10.0, 100.0, 0.0);
+    prices(
+        &db_path,
+        "VTI",
+        &[("2023-01-02", 100.0), ("2023-12-29", 120.0)],
+    );
+
+    let r = report(&db_path, "inception", "2023-12-31");
+    assert_eq!(r.start_date, "2023-01-01");
+    assert_eq!(r.end_date, "2023-12-31");
+    assert_eq!(r.portfolio.start_value, 0.0);
+    assert_eq!(r.portfolio.end_value, 1200.0);
+    assert_eq!(r.portfolio.net_contributions, 1000.0);
+    assert_eq!(r.portfolio.gain, 200.0);
+    assert!(close(r.portfolio.time_weighted_return, 0.2));
+    // Under a year, so not annualized; the IRR is a yearly rate over 363 days
+    assert_eq!(r.portfolio.annualized_return, None);
+    assert!(close(
+        r.portfolio.money_weighted_return,
+        1.2f64.powf(365.0 / 363.0) - 1.0
+    ));
+    assert_eq!(r.holdings.len(), 1);
+    assert_eq!(r.accounts[0].account_name, "Broker");
+}
+
+#[test]
+fn test_contribution_timing_separates_twr_from_mwr() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    prices(
+        &db_path,
+        "VTI",
+        &[
+            ("2023-01-02", 100.0),
+            ("2023-07-03", 50.0),
+            ("2023-12-29", 100.0),
+        ],
+    );
+    trade(&db_path, acc, "2023-01-02", "VTI", 10.0, 100.0, 0.0);
+    trade(&db_path, acc, "2023-07-03", "VTI", 10.0, 50.0, 0.0);
+
+    let r = report(&db_path, "inception", "2023-12-31");
+    // Halved, then doubled: flat over the year whatever was added
+    assert!(close(r.portfolio.time_weighted_return, 0.0));
+    assert!(r.portfolio.money_weighted_return.unwrap() > 0.2);
+    assert_eq!(r.portfolio.end_value, 2000.0);
+    assert_eq!(r.portfolio.gain, 500.0);
+}
+
+#[test]
+fn test_income_and_sales_count_as_returns() {
+    let (_dir, db_path) = setup_db();
+    let acc = account(&db_path, "Broker", None);
+    prices(&db_path, "KO", &[("2023-01-02", 100.0)]);
+    trade(&db_path, acc, "2023-01-02", "KO", 10.0, 100.0, 0.0);
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            account_id: acc,
+            date: "2023-04-01".to_string(),
+            action: "dividend".to_string(),
+            ticker: Some("KO".to_string()),
+            amount: Some(50.0),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    prices(&db_path, "KO", &[("2023-06-01", 110.0)]);
+    trade(&db_path, acc, "2023-06-01", "KO", -10.0, 110.0, 0.0);
+
+    let r = report(&db_path, "inception", "2023-12-31");
+    assert_eq!(r.portfolio.income, 50.0);
+    assert_eq!(r.portfolio.end_value, 0.0);
+    assert_eq!(r.portfolio.net_contributions, -100.0);
+    assert_eq!(r.portfolio.gain, 150.0);
+    assert!(close(r.portfolio.time_weighted_return, 1.05 * 1.1 - 1.0));
+}
+
+#[test]
+fn test_periods_accounts_and_benchmark() {
+    let (_dir, db_path) = setup_db();
+    let first = account(&db_path, "Taxable", None);
+    let second = account(&db_path, "IRA", None);
+    trade(&db_path, first, "2020-01-02", "VTI", 10.0, 100.0, 0.0);
+    trade(&db_path, second, "2023-06-30", "BND", 10.0, 50.0, 0.0);
+    prices(
+        &db_path,
+        "VTI",
+        &[
+            ("2020-01-02", 100.0),
+            ("2023-12-29", 150.0),
+            ("2024-06-28", 180.0),
+        ],
+    );
+    prices(
+        &db_path,
+        "BND",
+        &[("2023-06-30", 50.0), ("2024-06-28", 55.0)],
+    );
+    prices(
+        &db_path,
+        "SPY",
+        &[("2023-06-30", 400.0), ("2024-06-28", 440.0)],
+    );
+
+    let ytd = report(&db_path, "ytd", "2024-06-30");
+    assert_eq!(ytd.start_date, "2023-12-31");
+    assert_eq!(ytd.portfolio.start_value, 1500.0 + 500.0);
+
+    let year = crate::get_performance_db(
+        &db_path,
+        crate::PerformanceArgs {
+            period: "1y".to_string(),
+            benchmark: Some("spy".to_string()),
+            as_of: Some("2024-06-30".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(year.start_date, "2023-06-30");
+    assert_eq!(year.accounts.len(), 2);
+    let ira = year
+        .accounts
+        .iter()
+        .find(|a| a.account_id == second)
+        .unwrap();
+    assert!(close(ira.metrics.time_weighted_return, 0.1));
+    let benchmark = year.benchmark.unwrap();
+    assert_eq!(benchmark.ticker, "SPY");
+    assert!(close(benchmark.total_return, 0.1));
+    // 2024 is a leap year, so the year back is 366 days
+    assert!(close(
+        benchmark.annualized_return,
+        1.1f64.powf(365.0 / 366.0) - 1.0
+    ));
+    let twr = year.portfolio.time_weighted_return.unwrap();
+    assert!(close(benchmark.excess_return, twr - 0.1));
+
+    // Five years back reaches past the first trade, so the period starts at inception
+    let five = report(&db_path, "5y", "2024-06-30");
+    assert_eq!(five.start_date, "2020-01-01");
+    assert!(five.portfolio.annualized_return.is_some());
+
+    let only_ira = crate::get_performance_db(
+        &db_path,
+        crate::PerformanceArgs {
+            period: "inception".to_string(),
+            account_ids: Some(vec![second]),
+            as_of: Some("2024-06-30".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(only_ira.holdings.len(), 1);
+    assert_eq!(only_ira.holdings[0].ticker, "BND");
+
+    let bad = crate::get_performance_db(
+        &db_path,
+        crate::PerformanceArgs {
+            period: "2w".to_string(),
+            ..Default::default()
+        },
+    );
+    assert!(bad.is_err());
+}
+
+#[test]
+fn test_values_convert_from_the_quote_currency() {
+    let (_dir, db_path) = setup_db();
+    let depot = account(&db_path, "Depot", Some("EUR"));
+    // Bought in euros, but VTI closes in dollars
+    trade(&db_path, depot, "2023-01-02", "VTI", 10.0, 100.0, 0.0);
+    prices(
+        &db_path,
+        "VTI",
+        &[("2023-01-02", 110.0), ("2023-12-29", 132.0)],
+    );
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO stock_prices (ticker, price, currency, last_updated)
+         VALUES ('VTI', 132.0, 'USD', '2023-12-29')",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO daily_fx_rates (currency, date, rate) VALUES ('EUR', '2023-01-01', 1.10)",
+        [],
+    )
+    .unwrap();
+
+    let r = crate::get_performance_db(
+        &db_path,
+        crate::PerformanceArgs {
+            period: "inception".to_string(),
+            base_currency: Some("EUR".to_string()),
+            as_of: Some("2023-12-31".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(r.portfolio.net_contributions, 1000.0);
+    assert_eq!(r.portfolio.end_value, 1200.0);
+    assert!(close(r.portfolio.time_weighted_return, 0.2));
+}
@@ -3,8 +3,8 @@ use super::common::setup_db;
 #[test]
 fn test_delete_investment_transaction_updates_balance() {
     let (_dir, db_path) = setup_db();
-    let acc =
-        crate::create_account_db(&db_path, "Invest Delete".to_string(), 1000.0, None).unwrap();
+    let acc = crate::create_account_db(&db_path, "Invest Delete".to_string(), 1000.0, None, None)
+        .unwrap();
     // Create buy: cost 1005. Bal = -5.0.
     let args = crate::CreateInvestmentTransactionArgs {
         account_id: acc.id,
@@ -22,7 +22,7 @@ fn test_delete_investment_transaction_updates_balance() {
     assert_eq!(accounts[0].balance, -5.0);
 
     // Delete
-    crate::delete_transaction_db(&db_path, created.id).unwrap();
+    crate::delete_transaction_db(&db_path, created.id, false).unwrap();
 
     // Balance should revert to 1000.0.
     // -5.0 + 1005 = 1000.0.
@@ -3,8 +3,10 @@ use super::common::setup_db;
 #[test]
 fn test_update_investment_transaction_move_between_accounts() {
     let (_dir, db_path) = setup_db();
-    let acc_a = crate::create_account_db(&db_path, "AccountA".to_string(), 1000.0, None).unwrap();
-    let acc_b = crate::create_account_db(&db_path, "AccountB".to_string(), 1000.0, None).unwrap();
+    let acc_a =
+        crate::create_account_db(&db_path, "AccountA".to_string(), 1000.0, None, None).unwrap();
+    let acc_b =
+        crate::create_account_db(&db_path, "AccountB".to_string(), 1000.0, None, None).unwrap();
 
     // Create initial buy in A
     // Cost: 2*100 + 1 => 201.
@@ -40,6 +42,7 @@ fn test_update_investment_transaction_move_between_accounts() {
         is_buy: true,
         notes: None,
         currency: None,
+        override_reconciled: false,
     };
 
     crate::update_investment_transaction_db(&db_path, update_args).unwrap();
@@ -14,6 +14,7 @@ fn test_update_investment_transaction_missing_id_should_error() {
         is_buy: true,
         notes: None,
         currency: None,
+        override_reconciled: false,
     };
 
     let res = crate::update_investment_transaction_db(&db_path, args);
@@ -24,7 +25,7 @@ fn test_update_investment_transaction_missing_id_should_error() {
 fn test_update_investment_transaction_updates_balance() {
     let (_dir, db_path) = setup_db();
     // Start with 1000
-    let acc = crate::create_account_db(&db_path, "Invest".to_string(), 1000.0, None).unwrap();
+    let acc = crate::create_account_db(&db_path, "Invest".to_string(), 1000.0, None, None).unwrap();
 
     // Create initial buy: 10 * 100 + fee 2 = 1002 cost.
     // Balance: 1000 - 1002 = -2.0.
@@ -58,6 +59,7 @@ fn test_update_investment_transaction_updates_balance() {
         is_buy: true,
         notes: None,
         currency: None,
+        override_reconciled: false,
     };
 
     crate::update_investment_transaction_db(&db_path, update_args).unwrap();
@@ -73,7 +75,7 @@ fn test_update_investment_transaction_updates_balance() {
 #[test]
 fn test_update_investment_transaction_custom_notes() {
     let (_dir, db_path) = setup_db();
-    let acc = crate::create_account_db(&db_path, "Invest".to_string(), 1000.0, None).unwrap();
+    let acc = crate::create_account_db(&db_path, "Invest".to_string(), 1000.0, None, None).unwrap();
 
     let args = crate::CreateInvestmentTransactionArgs {
         account_id: acc.id,
@@ -101,6 +103,7 @@ fn test_update_investment_transaction_custom_notes() {
         is_buy: true,
         notes: Some(custom_note.clone()),
         currency: None,
+        override_reconciled: false,
     };
 
     crate::update_investment_transaction_db(&db_path, update_args).unwrap();
@@ -113,7 +116,7 @@ fn test_update_investment_transaction_custom_notes() {
 #[test]
 fn test_update_investment_transaction_sell_changes_amounts() {
     let (_dir, db_path) = setup_db();
-    let acc = crate::create_account_db(&db_path, "Invest".to_string(), 1000.0, None).unwrap();
+    let acc = crate::create_account_db(&db_path, "Invest".to_string(), 1000.0, None, None).unwrap();
 
     // Create initial buy: 10 * 100 + fee 2 = 1002 out.
     // Bal: -2.0.
@@ -144,6 +147,7 @@ fn test_update_investment_transaction_sell_changes_amounts() {
         is_buy: false,
         notes: None,
         currency: None,
+        override_reconciled: false,
     };
 
     crate::update_investment_transaction_db(&db_path, update_args).unwrap();
@@ -1,5 +1,5 @@
-use rusqlite::Connection;
-use std::path::PathBuf;
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
 use tempfile::tempdir;
 
 pub fn setup_db() -> (tempfile::TempDir, PathBuf) {
@@ -14,7 +14,11 @@ pub fn setup_db() -> (tempfile::TempDir, PathBuf) {
             name TEXT NOT NULL,
             balance REAL NOT NULL,
             currency TEXT,
-            kind TEXT DEFAULT 'cash'
+            kind TEXT DEFAULT 'cash',
+            archived INTEGER NOT NULL DEFAULT 0,
+            lot_method TEXT NOT NULL DEFAULT 'fifo',
+            portfolio TEXT,
+            balance_threshold REAL
         )",
         [],
     )
@@ -35,6 +39,11 @@ pub fn setup_db() -> (tempfile::TempDir, PathBuf) {
             fee REAL,
             currency TEXT,
             linked_tx_id INTEGER,
+            cleared TEXT NOT NULL DEFAULT 'uncleared',
+            transfer_id INTEGER,
+            action TEXT,
+            basis_fraction REAL,
+            related_tx_id INTEGER,
             FOREIGN KEY(account_id) REFERENCES accounts(id)
         )",
         [],
@@ -45,12 +54,72 @@ pub fn setup_db() -> (tempfile::TempDir, PathBuf) {
         "CREATE TABLE IF NOT EXISTS stock_prices (
             ticker TEXT PRIMARY KEY,
             price REAL NOT NULL,
+            change_percent REAL NOT NULL DEFAULT 0,
+            currency TEXT,
+            quote_type TEXT,
             last_updated TEXT NOT NULL
         )",
         [],
     )
     .unwrap();
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS daily_stock_prices (
+            ticker TEXT NOT NULL,
+            date TEXT NOT NULL,
+            price REAL NOT NULL,
+            open REAL,
+            high REAL,
+            low REAL,
+            adj_close REAL,
+            volume REAL,
+            PRIMARY KEY (ticker, date)
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stock_dividends (
+            ticker TEXT NOT NULL,
+            date TEXT NOT NULL,
+            amount REAL NOT NULL,
+            PRIMARY KEY (ticker, date)
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stock_splits (
+            ticker TEXT NOT NULL,
+            date TEXT NOT NULL,
+            numerator REAL NOT NULL,
+            denominator REAL NOT NULL,
+            PRIMARY KEY (ticker, date)
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stock_event_backfills (
+            ticker TEXT PRIMARY KEY,
+            date TEXT NOT NULL
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS custom_exchange_rates (
+            currency TEXT PRIMARY KEY,
+            rate REAL NOT NULL
+        )",
+        [],
+    )
+    .unwrap();
+
     conn.execute(
         "CREATE TABLE IF NOT EXISTS rules (
             id INTEGER PRIMARY KEY,
@@ -67,5 +136,221 @@ pub fn setup_db() -> (tempfile::TempDir, PathBuf) {
     )
     .unwrap();
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS account_valuations (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            value REAL NOT NULL,
+            UNIQUE (account_id, date),
+            FOREIGN KEY(account_id) REFERENCES accounts(id)
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reconciliations (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            statement_date TEXT NOT NULL,
+            statement_balance REAL NOT NULL,
+            transaction_count INTEGER NOT NULL,
+            reconciled_at TEXT NOT NULL,
+            FOREIGN KEY(account_id) REFERENCES accounts(id)
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS market_data_providers (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            kind TEXT NOT NULL,
+            base_url TEXT NOT NULL,
+            format TEXT NOT NULL DEFAULT 'json',
+            quote_path TEXT,
+            history_path TEXT,
+            search_path TEXT,
+            fx_ticker TEXT,
+            priority INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ticker_providers (
+            ticker TEXT PRIMARY KEY,
+            providers TEXT NOT NULL
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS fx_sources (
+            name TEXT PRIMARY KEY,
+            fetched_at TEXT NOT NULL,
+            latest_date TEXT
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS daily_fx_rates (
+            currency TEXT NOT NULL,
+            date TEXT NOT NULL,
+            rate REAL NOT NULL,
+            PRIMARY KEY (currency, date)
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS custom_exchange_rate_ranges (
+            id INTEGER PRIMARY KEY,
+            currency TEXT NOT NULL,
+            rate REAL NOT NULL,
+            valid_from TEXT,
+            valid_to TEXT
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transfers (
+            id INTEGER PRIMARY KEY,
+            from_tx_id INTEGER NOT NULL,
+            to_tx_id INTEGER NOT NULL,
+            fee REAL NOT NULL DEFAULT 0,
+            exchange_rate REAL NOT NULL DEFAULT 1,
+            rate_estimated INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS lot_selections (
+            sell_tx_id INTEGER NOT NULL,
+            lot_tx_id INTEGER NOT NULL,
+            shares REAL NOT NULL,
+            PRIMARY KEY (sell_tx_id, lot_tx_id)
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ticker_classifications (
+            ticker TEXT PRIMARY KEY,
+            asset_class TEXT,
+            region TEXT,
+            sector TEXT
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS allocation_targets (
+            portfolio TEXT NOT NULL,
+            asset_class TEXT NOT NULL,
+            target_percent REAL NOT NULL,
+            PRIMARY KEY (portfolio, asset_class)
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduled_transactions (
+            id INTEGER PRIMARY KEY,
+            account_id INTEGER NOT NULL,
+            payee TEXT NOT NULL,
+            category TEXT,
+            amount REAL NOT NULL,
+            frequency TEXT NOT NULL,
+            next_date TEXT NOT NULL,
+            end_date TEXT,
+            notes TEXT
+        )",
+        [],
+    )
+    .unwrap();
+
     (dir, db_path)
 }
+
+fn new_account(
+    db_path: &PathBuf,
+    name: &str,
+    balance: f64,
+    currency: Option<&str>,
+    kind: Option<&str>,
+) -> i32 {
+    crate::create_account_db(
+        db_path,
+        name.to_string(),
+        balance,
+        currency.map(String::from),
+        kind.map(String::from),
+    )
+    .unwrap()
+    .id
+}
+
+/// Empty cash account, in the base currency when `currency` is `None`.
+pub fn account(db_path: &PathBuf, name: &str, currency: Option<&str>) -> i32 {
+    new_account(db_path, name, 0.0, currency, None)
+}
+
+pub fn account_of_kind(db_path: &PathBuf, name: &str, currency: Option<&str>, kind: &str) -> i32 {
+    new_account(db_path, name, 0.0, currency, Some(kind))
+}
+
+pub fn funded_account(db_path: &PathBuf, name: &str, balance: f64) -> i32 {
+    new_account(db_path, name, balance, None, None)
+}
+
+/// Buys `shares` of `ticker`, or sells them when negative, returning the transaction id.
+pub fn trade(
+    db_path: &PathBuf,
+    account_id: i32,
+    date: &str,
+    ticker: &str,
+    shares: f64,
+    price: f64,
+    fee: f64,
+) -> i32 {
+    crate::create_investment_transaction_db(
+        db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id,
+            date: date.to_string(),
+            ticker: ticker.to_string(),
+            shares: shares.abs(),
+            price_per_share: price,
+            fee,
+            is_buy: shares > 0.0,
+            currency: None,
+        },
+    )
+    .unwrap()
+    .id
+}
+
+/// Stores a daily USD rate for `currency`, as the rate history would.
+pub fn set_daily_rate(db_path: &Path, currency: &str, date: &str, rate: f64) {
+    let conn = Connection::open(db_path).unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO daily_fx_rates (currency, date, rate) VALUES (?1, ?2, ?3)",
+        params![currency, date, rate],
+    )
+    .unwrap();
+}
@@ -14,6 +14,7 @@ fn test_write_settings_to_readonly_dir_errors() {
 
     let s = crate::AppSettings {
         db_path: Some("/tmp/some/path.db".to_string()),
+        ..Default::default()
     };
     let res = crate::write_settings_to_dir(&dir_path, &s);
 
@@ -48,6 +49,7 @@ fn test_set_db_path_parent_creation_permission_error() {
         &dir_path,
         &crate::AppSettings {
             db_path: Some(target.to_string_lossy().to_string()),
+            ..Default::default()
         },
     )
     .unwrap();
@@ -70,7 +72,7 @@ fn test_db_locked_write_fails() {
     conn.execute_batch("BEGIN EXCLUSIVE;").unwrap();
 
     // Attempts to create a new account should fail because DB is locked
-    let res = crate::create_account_db(&db_path, "LockTest".to_string(), 10.0, None);
+    let res = crate::create_account_db(&db_path, "LockTest".to_string(), 10.0, None, None);
     assert!(res.is_err());
 
     // End exclusive to unlock
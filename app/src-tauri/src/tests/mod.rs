@@ -9,6 +9,9 @@ pub mod errors;
 pub mod multicurrency;
 pub mod payees;
 pub mod property;
+pub mod reconciliation;
+pub mod reports;
 pub mod rules;
 pub mod stock;
 pub mod transactions;
+pub mod transfers;
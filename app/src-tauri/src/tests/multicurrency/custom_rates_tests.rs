@@ -8,7 +8,11 @@ fn test_custom_rate_application() {
         name: "TestAcc".to_string(),
         balance: 0.0,
         currency: Some("EUR".to_string()),
+        kind: "cash".to_string(),
         exchange_rate: 1.0,
+        valuation: None,
+        holdings_value: None,
+        archived: false,
     }];
 
     // Transaction: 100 EUR. Account: EUR. Target: USD.
@@ -47,7 +51,11 @@ fn test_cross_rate_conversion() {
         name: "GBP Acc".to_string(),
         balance: 0.0,
         currency: Some("GBP".to_string()),
+        kind: "cash".to_string(),
         exchange_rate: 1.0,
+        valuation: None,
+        holdings_value: None,
+        archived: false,
     }];
 
     let raw_data = vec![(1, "EUR".to_string(), 100.0)];
@@ -88,7 +96,11 @@ fn test_direct_rate_priority() {
         name: "GBP Acc".to_string(),
         balance: 0.0,
         currency: Some("GBP".to_string()),
+        kind: "cash".to_string(),
         exchange_rate: 1.0,
+        valuation: None,
+        holdings_value: None,
+        archived: false,
     }];
 
     let raw_data = vec![(1, "EUR".to_string(), 100.0)];
@@ -125,7 +137,11 @@ fn test_fallback_when_direct_rate_missing() {
         name: "GBP Acc".to_string(),
         balance: 0.0,
         currency: Some("GBP".to_string()),
+        kind: "cash".to_string(),
         exchange_rate: 1.0,
+        valuation: None,
+        holdings_value: None,
+        archived: false,
     }];
 
     let raw_data = vec![(1, "EUR".to_string(), 100.0)];
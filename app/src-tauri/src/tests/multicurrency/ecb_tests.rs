@@ -0,0 +1,128 @@
+use super::super::common::setup_db;
+use httpmock::Method::GET;
+use httpmock::MockServer;
+use tempfile::tempdir;
+
+const DAILY_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01" xmlns="http://www.ecb.int/vocabulary/2002-08-01/eurofxref">
+    <gesmes:subject>Reference rates</gesmes:subject>
+    <Cube>
+        <Cube time='2024-03-01'>
+            <Cube currency='USD' rate='1.0800'/>
+            <Cube currency='JPY' rate='162.00'/>
+            <Cube currency='GBP' rate='0.8550'/>
+        </Cube>
+    </Cube>
+</gesmes:Envelope>"#;
+
+const HISTORY_CSV: &str = "Date,USD,JPY,GBP,\n\
+2024-03-01,1.0800,162.00,0.8550,\n\
+29 February 2024,1.0813,N/A,0.8560,\n";
+
+#[test]
+fn test_parse_ecb_xml_and_csv() {
+    let xml = crate::parse_ecb_rates(DAILY_XML).unwrap();
+    assert_eq!(xml.len(), 1);
+    assert_eq!(xml[0].date, "2024-03-01");
+    assert_eq!(
+        xml[0].rates,
+        vec![
+            ("USD".to_string(), 1.08),
+            ("JPY".to_string(), 162.0),
+            ("GBP".to_string(), 0.855)
+        ]
+    );
+
+    let csv = crate::parse_ecb_rates(HISTORY_CSV).unwrap();
+    assert_eq!(csv.len(), 2);
+    assert_eq!(csv[0].rates, xml[0].rates);
+    // Long-form dates are normalized and N/A quotes are dropped
+    assert_eq!(csv[1].date, "2024-02-29");
+    assert_eq!(
+        csv[1].rates,
+        vec![("USD".to_string(), 1.0813), ("GBP".to_string(), 0.856)]
+    );
+}
+
+#[test]
+fn test_store_ecb_rates_pivots_to_usd() {
+    let (_dir, db_path) = setup_db();
+    let days = crate::parse_ecb_rates(HISTORY_CSV).unwrap();
+    let import = crate::store_ecb_rates_db(&db_path, &days).unwrap();
+
+    assert_eq!(import.days, 2);
+    assert_eq!(import.currencies, vec!["EUR", "GBP", "JPY"]);
+    assert_eq!(import.latest_date.as_deref(), Some("2024-03-01"));
+
+    let rate = |from: &str, to: &str, date: &str| {
+        crate::get_fx_rate_db(
+            &db_path,
+            from.to_string(),
+            to.to_string(),
+            Some(date.to_string()),
+        )
+        .unwrap()
+    };
+    assert!((rate("EUR", "USD", "2024-03-01") - 1.08).abs() < 1e-9);
+    assert!((rate("GBP", "USD", "2024-03-01") - 1.08 / 0.855).abs() < 1e-9);
+    assert!((rate("EUR", "JPY", "2024-03-01") - 162.0).abs() < 1e-6);
+    assert!((rate("EUR", "GBP", "2024-02-29") - 0.856).abs() < 1e-9);
+
+    let latest = crate::latest_ecb_rates_db(&db_path).unwrap();
+    assert_eq!(latest.len(), 3);
+    assert!((latest["JPY"] - 1.08 / 162.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_import_ecb_rates_from_file() {
+    let (_dir, db_path) = setup_db();
+    let files = tempdir().unwrap();
+    let path = files.path().join("eurofxref-daily.xml");
+    std::fs::write(&path, DAILY_XML).unwrap();
+
+    assert!(crate::ecb_rates_stale_db(&db_path).unwrap());
+    let import =
+        crate::import_ecb_rates_file_db(&db_path, path.to_string_lossy().to_string()).unwrap();
+    assert_eq!(import.days, 1);
+    assert!(!crate::ecb_rates_stale_db(&db_path).unwrap());
+
+    let missing = files.path().join("missing.xml");
+    assert!(
+        crate::import_ecb_rates_file_db(&db_path, missing.to_string_lossy().to_string()).is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_fetch_ecb_rates_keeps_cache_on_failure() {
+    let (_dir, db_path) = setup_db();
+    let server = MockServer::start();
+    let ok = server.mock(|when, then| {
+        when.method(GET).path("/eurofxref-daily.xml");
+        then.status(200).body(DAILY_XML);
+    });
+    let down = server.mock(|when, then| {
+        when.method(GET).path("/down.xml");
+        then.status(503);
+    });
+
+    let client = reqwest::Client::new();
+    crate::fetch_ecb_rates_with_client(
+        &db_path,
+        &client,
+        &format!("{}/eurofxref-daily.xml", server.base_url()),
+    )
+    .await
+    .unwrap();
+    ok.assert_calls(1);
+
+    let res = crate::fetch_ecb_rates_with_client(
+        &db_path,
+        &client,
+        &format!("{}/down.xml", server.base_url()),
+    )
+    .await;
+    // The outage is retried before giving up
+    down.assert_calls(4);
+    assert!(res.is_err());
+    assert_eq!(crate::latest_ecb_rates_db(&db_path).unwrap().len(), 3);
+}
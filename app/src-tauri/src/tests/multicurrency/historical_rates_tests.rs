@@ -0,0 +1,188 @@
+use super::super::common::{set_daily_rate, setup_db};
+use httpmock::Method::GET;
+use httpmock::MockServer;
+use rusqlite::Connection;
+
+#[tokio::test]
+async fn test_update_daily_fx_rates_backfills_history() {
+    let (_dir, db_path) = setup_db();
+    let server = MockServer::start();
+
+    let m = server.mock(|when, then| {
+        when.method(GET).path("/v8/finance/chart/EURUSD=X");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"chart":{"result":[{"meta":{"symbol":"EURUSD=X"},"timestamp":[1609459200,1609545600],"indicators":{"quote":[{"close":[1.22,null]}]}}]}}"#);
+    });
+
+    let client = reqwest::Client::builder().build().unwrap();
+    crate::update_daily_fx_rates_with_client_and_base(
+        &db_path,
+        &client,
+        &server.base_url(),
+        vec!["USD".to_string(), "EUR".to_string()],
+    )
+    .await
+    .unwrap();
+
+    // USD is the pivot and is never requested
+    m.assert_calls(1);
+    let conn = Connection::open(&db_path).unwrap();
+    let rows: Vec<(String, f64)> = conn
+        .prepare("SELECT date, rate FROM daily_fx_rates WHERE currency = 'EUR'")
+        .unwrap()
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(rows, vec![("2021-01-01".to_string(), 1.22)]);
+}
+
+#[test]
+fn test_fx_rate_uses_rate_on_or_before_date() {
+    let (_dir, db_path) = setup_db();
+    set_daily_rate(&db_path, "EUR", "2024-01-02", 1.10);
+    set_daily_rate(&db_path, "EUR", "2024-01-05", 1.20);
+    set_daily_rate(&db_path, "GBP", "2024-01-02", 1.25);
+
+    let rate = |from: &str, to: &str, date: &str| {
+        crate::get_fx_rate_db(
+            &db_path,
+            from.to_string(),
+            to.to_string(),
+            Some(date.to_string()),
+        )
+    };
+    assert_eq!(rate("EUR", "USD", "2024-01-04").unwrap(), 1.10);
+    assert_eq!(rate("EUR", "USD", "2024-01-05").unwrap(), 1.20);
+    assert!((rate("USD", "EUR", "2024-01-05").unwrap() - 1.0 / 1.2).abs() < 1e-9);
+    assert!((rate("GBP", "EUR", "2024-01-03").unwrap() - 1.25 / 1.10).abs() < 1e-9);
+    assert!(rate("EUR", "USD", "2023-12-31").is_err());
+
+    let converted = crate::convert_amount_db(
+        &db_path,
+        50.0,
+        "EUR".to_string(),
+        "USD".to_string(),
+        Some("2024-01-06".to_string()),
+    )
+    .unwrap();
+    assert!((converted - 60.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_custom_rate_ranges() {
+    let (_dir, db_path) = setup_db();
+    crate::set_custom_exchange_rate_db(&db_path, "XAU".to_string(), 2000.0).unwrap();
+    let range = crate::add_custom_exchange_rate_range_db(
+        &db_path,
+        "XAU".to_string(),
+        1800.0,
+        Some("2023-01-01".to_string()),
+        Some("2023-12-31".to_string()),
+    )
+    .unwrap();
+
+    let rate = |date: Option<&str>| {
+        crate::get_fx_rate_db(
+            &db_path,
+            "XAU".to_string(),
+            "USD".to_string(),
+            date.map(str::to_string),
+        )
+    };
+    assert_eq!(rate(Some("2023-06-30")), Ok(1800.0));
+    // The undated custom rate is today's, not a stand-in for past dates
+    assert!(rate(Some("2024-02-01")).is_err());
+    assert_eq!(rate(None), Ok(2000.0));
+
+    // Overlapping and inverted ranges are rejected
+    assert!(crate::add_custom_exchange_rate_range_db(
+        &db_path,
+        "XAU".to_string(),
+        1900.0,
+        Some("2023-12-01".to_string()),
+        None
+    )
+    .is_err());
+    assert!(crate::add_custom_exchange_rate_range_db(
+        &db_path,
+        "XAU".to_string(),
+        1900.0,
+        Some("2025-02-01".to_string()),
+        Some("2025-01-01".to_string())
+    )
+    .is_err());
+    crate::add_custom_exchange_rate_range_db(
+        &db_path,
+        "XAU".to_string(),
+        1900.0,
+        Some("2024-01-01".to_string()),
+        None,
+    )
+    .unwrap();
+    assert_eq!(rate(Some("2024-02-01")), Ok(1900.0));
+    // A range covering today also wins for current conversions
+    assert_eq!(rate(None), Ok(1900.0));
+    let custom_rates = crate::utils::get_custom_rates_map(&db_path).unwrap();
+    assert_eq!(custom_rates.get("XAU"), Some(&1900.0));
+
+    let ranges =
+        crate::get_custom_exchange_rate_ranges_db(&db_path, Some("XAU".to_string())).unwrap();
+    assert_eq!(ranges.len(), 2);
+    crate::delete_custom_exchange_rate_range_db(&db_path, range.id).unwrap();
+    assert!(rate(Some("2023-06-30")).is_err());
+}
+
+#[test]
+fn test_account_summary_at_transaction_dates() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(
+        &db_path,
+        "Main".to_string(),
+        0.0,
+        Some("USD".to_string()),
+        None,
+    )
+    .unwrap();
+    set_daily_rate(&db_path, "EUR", "2024-01-01", 1.0);
+    set_daily_rate(&db_path, "EUR", "2024-06-01", 1.5);
+
+    for (date, currency) in [
+        ("2024-01-10", "EUR"),
+        ("2024-06-10", "EUR"),
+        ("2024-06-10", "JPY"),
+    ] {
+        crate::create_transaction_db(
+            &db_path,
+            crate::CreateTransactionArgs {
+                account_id: acc.id,
+                date: date.to_string(),
+                payee: "Payee".to_string(),
+                notes: None,
+                category: None,
+                amount: 100.0,
+                ticker: None,
+                shares: None,
+                price_per_share: None,
+                fee: None,
+                currency: Some(currency.to_string()),
+            },
+        )
+        .unwrap();
+    }
+
+    let summary = crate::get_accounts_summary_at_transaction_dates_db(&db_path, "USD").unwrap();
+    let usd: f64 = summary
+        .raw_data
+        .iter()
+        .filter(|(id, c, _)| *id == acc.id && c == "USD")
+        .map(|(_, _, a)| a)
+        .sum();
+    assert!((usd - 250.0).abs() < 1e-9);
+    // Without any JPY rate the amount is left for conversion at the current rate
+    assert!(summary
+        .raw_data
+        .iter()
+        .any(|(_, c, a)| c == "JPY" && *a == 100.0));
+}
@@ -1,2 +1,4 @@
 pub mod custom_rates_tests;
+pub mod ecb_tests;
+pub mod historical_rates_tests;
 pub mod multicurrency_tests;
@@ -3,7 +3,7 @@ use super::common::setup_db;
 #[test]
 fn test_get_payees_and_categories() {
     let (_dir, db_path) = setup_db();
-    let acc = crate::create_account_db(&db_path, "A".to_string(), 100.0, None).unwrap();
+    let acc = crate::create_account_db(&db_path, "A".to_string(), 100.0, None, None).unwrap();
 
     crate::create_transaction_db(
         &db_path,
@@ -41,7 +41,7 @@ fn test_get_payees_and_categories() {
     .unwrap();
 
     // Add a transfer (should be categorized as Transfer and not show as category)
-    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None, None).unwrap();
     crate::create_transaction_db(
         &db_path,
         crate::CreateTransactionArgs {
@@ -49,7 +49,7 @@ fn test_get_payees_and_categories() {
             date: "2023-01-03".to_string(),
             payee: acc2.name.clone(),
             notes: Some("XFER".to_string()),
-            category: None,
+            category: Some("Transfer".to_string()),
             amount: -30.0,
             ticker: None,
             shares: None,
@@ -85,7 +85,7 @@ fn test_get_payees_and_categories_empty() {
 fn test_payees_and_categories_sorted() {
     let (_dir, db_path) = setup_db();
     // Use zero opening balance to avoid the "Opening Balance" payee
-    let acc = crate::create_account_db(&db_path, "A".to_string(), 0.0, None).unwrap();
+    let acc = crate::create_account_db(&db_path, "A".to_string(), 0.0, None, None).unwrap();
     crate::create_transaction_db(
         &db_path,
         crate::CreateTransactionArgs {
@@ -17,7 +17,7 @@ fn test_randomized_balance_invariants() {
         } else {
             rng.random_range(0..500) as f64
         };
-        let acc = crate::create_account_db(&db_path, format!("Acc{}", i), bal, None).unwrap();
+        let acc = crate::create_account_db(&db_path, format!("Acc{}", i), bal, None, None).unwrap();
         accounts.push(acc);
     }
 
@@ -109,6 +109,7 @@ fn test_randomized_balance_invariants() {
                         category: tx.category.clone(),
                         amount: new_amount,
                         currency: None,
+                        override_reconciled: false,
                     };
                     let _ = crate::update_transaction_db(&db_path, args);
                 }
@@ -118,7 +119,7 @@ fn test_randomized_balance_invariants() {
             if !tx_ids.is_empty() {
                 let idx = rng.random_range(0..tx_ids.len());
                 let tx_id = tx_ids.remove(idx);
-                let _ = crate::delete_transaction_db(&db_path, tx_id);
+                let _ = crate::delete_transaction_db(&db_path, tx_id, false);
             }
         }
     }
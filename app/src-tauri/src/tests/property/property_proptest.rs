@@ -14,7 +14,7 @@ proptest! {
         let mut accounts = Vec::new();
         for i in 0..3 {
             let bal = rng.random_range(0..500) as f64;
-            let acc = crate::create_account_db(&db_path, format!("Acc{}", i), bal, None).unwrap();
+            let acc = crate::create_account_db(&db_path, format!("Acc{}", i), bal, None, None).unwrap();
             accounts.push(acc);
         }
 
@@ -32,7 +32,7 @@ proptest! {
                         date: "2023-01-01".to_string(),
                         payee: accounts[b].name.clone(),
                         notes: Some("XFER".to_string()),
-                        category: None,
+                        category: Some("Transfer".to_string()),
                         amount: -rng.random_range(1..200) as f64,
                         ticker: None,
                         shares: None,
@@ -66,11 +66,11 @@ proptest! {
                 if !all.is_empty() {
                     if rng.random_bool(0.5) {
                         let tx = all[rng.random_range(0..all.len())].clone();
-                        let args = crate::UpdateTransactionArgs{ id: tx.id, account_id: tx.account_id, date: tx.date.clone(), payee: tx.payee.clone(), notes: tx.notes.clone(), category: tx.category.clone(), amount: tx.amount * (1.0 + rng.random_range(-50..50) as f64 / 100.0), currency: None};
+                        let args = crate::UpdateTransactionArgs{ id: tx.id, account_id: tx.account_id, date: tx.date.clone(), payee: tx.payee.clone(), notes: tx.notes.clone(), category: tx.category.clone(), amount: tx.amount * (1.0 + rng.random_range(-50..50) as f64 / 100.0), currency: None, override_reconciled: false};
                         let _ = crate::update_transaction_db(&db_path, args);
                     } else {
                         let tx = all[rng.random_range(0..all.len())].clone();
-                        let _ = crate::delete_transaction_db(&db_path, tx.id);
+                        let _ = crate::delete_transaction_db(&db_path, tx.id, false);
                     }
                 }
             }
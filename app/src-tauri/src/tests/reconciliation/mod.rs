@@ -0,0 +1,4 @@
+pub use super::common;
+
+pub mod reconcile_account;
+pub mod reconciled_lock;
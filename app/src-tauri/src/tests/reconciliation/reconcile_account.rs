@@ -0,0 +1,103 @@
+use super::common::setup_db;
+
+fn add_tx(db_path: &std::path::PathBuf, account_id: i32, date: &str, amount: f64) -> i32 {
+    crate::create_transaction_db(
+        db_path,
+        crate::CreateTransactionArgs {
+            account_id,
+            date: date.to_string(),
+            payee: "Shop".to_string(),
+            notes: None,
+            category: None,
+            amount,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap()
+    .id
+}
+
+#[test]
+fn test_start_reconciliation_reports_difference() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let t1 = add_tx(&db_path, acc.id, "2024-01-05", 500.0);
+    let t2 = add_tx(&db_path, acc.id, "2024-01-10", -120.0);
+    add_tx(&db_path, acc.id, "2024-01-12", -30.0); // not cleared yet
+
+    crate::set_transaction_cleared_db(&db_path, t1, "cleared".to_string(), false).unwrap();
+    crate::set_transaction_cleared_db(&db_path, t2, "cleared".to_string(), false).unwrap();
+
+    let status =
+        crate::start_reconciliation_db(&db_path, acc.id, "2024-01-31".to_string(), 350.0).unwrap();
+    assert_eq!(status.cleared_balance, 380.0);
+    assert_eq!(status.difference, -30.0);
+    assert_eq!(status.cleared_count, 2);
+}
+
+#[test]
+fn test_start_reconciliation_ignores_transactions_after_statement_date() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let t1 = add_tx(&db_path, acc.id, "2024-01-05", 100.0);
+    let t2 = add_tx(&db_path, acc.id, "2024-02-05", 50.0);
+    crate::set_transaction_cleared_db(&db_path, t1, "cleared".to_string(), false).unwrap();
+    crate::set_transaction_cleared_db(&db_path, t2, "cleared".to_string(), false).unwrap();
+
+    let status =
+        crate::start_reconciliation_db(&db_path, acc.id, "2024-01-31".to_string(), 100.0).unwrap();
+    assert_eq!(status.difference, 0.0);
+}
+
+#[test]
+fn test_finish_reconciliation_locks_and_records_history() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let t1 = add_tx(&db_path, acc.id, "2024-01-05", 200.0);
+    let t2 = add_tx(&db_path, acc.id, "2024-01-06", -50.0);
+    crate::set_transaction_cleared_db(&db_path, t1, "cleared".to_string(), false).unwrap();
+
+    let rec =
+        crate::finish_reconciliation_db(&db_path, acc.id, "2024-01-31".to_string(), 200.0).unwrap();
+    assert_eq!(rec.transaction_count, 1);
+
+    let txs = crate::get_transactions_db(&db_path, acc.id).unwrap();
+    let tx1 = txs.iter().find(|t| t.id == t1).unwrap();
+    let tx2 = txs.iter().find(|t| t.id == t2).unwrap();
+    assert_eq!(tx1.cleared, "reconciled");
+    assert_eq!(tx2.cleared, "uncleared");
+
+    let history = crate::get_reconciliations_db(&db_path, acc.id).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].statement_balance, 200.0);
+}
+
+#[test]
+fn test_finish_reconciliation_unbalanced_should_error() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let t1 = add_tx(&db_path, acc.id, "2024-01-05", 200.0);
+    crate::set_transaction_cleared_db(&db_path, t1, "cleared".to_string(), false).unwrap();
+
+    let res = crate::finish_reconciliation_db(&db_path, acc.id, "2024-01-31".to_string(), 150.0);
+    assert!(res.is_err());
+    assert!(crate::get_reconciliations_db(&db_path, acc.id)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_set_cleared_rejects_unknown_and_manual_reconciled_states() {
+    let (_dir, db_path) = setup_db();
+    let acc = crate::create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let t1 = add_tx(&db_path, acc.id, "2024-01-05", 10.0);
+
+    assert!(crate::set_transaction_cleared_db(&db_path, t1, "pending".to_string(), false).is_err());
+    assert!(
+        crate::set_transaction_cleared_db(&db_path, t1, "reconciled".to_string(), false).is_err()
+    );
+}
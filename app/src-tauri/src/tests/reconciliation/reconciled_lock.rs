@@ -0,0 +1,83 @@
+use super::common::setup_db;
+
+fn reconciled_tx(db_path: &std::path::PathBuf) -> (i32, i32) {
+    let acc = crate::create_account_db(db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let tx = crate::create_transaction_db(
+        db_path,
+        crate::CreateTransactionArgs {
+            account_id: acc.id,
+            date: "2024-01-05".to_string(),
+            payee: "Salary".to_string(),
+            notes: None,
+            category: None,
+            amount: 1000.0,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap();
+    crate::set_transaction_cleared_db(db_path, tx.id, "cleared".to_string(), false).unwrap();
+    crate::finish_reconciliation_db(db_path, acc.id, "2024-01-31".to_string(), 1000.0).unwrap();
+    (acc.id, tx.id)
+}
+
+fn update_args(
+    account_id: i32,
+    id: i32,
+    override_reconciled: bool,
+) -> crate::UpdateTransactionArgs {
+    crate::UpdateTransactionArgs {
+        id,
+        account_id,
+        date: "2024-01-05".to_string(),
+        payee: "Salary".to_string(),
+        notes: None,
+        category: None,
+        amount: 900.0,
+        currency: None,
+        override_reconciled,
+    }
+}
+
+#[test]
+fn test_update_reconciled_transaction_requires_override() {
+    let (_dir, db_path) = setup_db();
+    let (acc_id, tx_id) = reconciled_tx(&db_path);
+
+    assert!(crate::update_transaction_db(&db_path, update_args(acc_id, tx_id, false)).is_err());
+
+    let updated = crate::update_transaction_db(&db_path, update_args(acc_id, tx_id, true)).unwrap();
+    assert_eq!(updated.amount, 900.0);
+    assert_eq!(updated.cleared, "reconciled");
+}
+
+#[test]
+fn test_delete_reconciled_transaction_requires_override() {
+    let (_dir, db_path) = setup_db();
+    let (acc_id, tx_id) = reconciled_tx(&db_path);
+
+    assert!(crate::delete_transaction_db(&db_path, tx_id, false).is_err());
+    assert_eq!(
+        crate::get_transactions_db(&db_path, acc_id).unwrap().len(),
+        1
+    );
+
+    crate::delete_transaction_db(&db_path, tx_id, true).unwrap();
+    assert!(crate::get_transactions_db(&db_path, acc_id)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_unclearing_reconciled_transaction_requires_override() {
+    let (_dir, db_path) = setup_db();
+    let (_acc_id, tx_id) = reconciled_tx(&db_path);
+
+    assert!(
+        crate::set_transaction_cleared_db(&db_path, tx_id, "cleared".to_string(), false).is_err()
+    );
+    crate::set_transaction_cleared_db(&db_path, tx_id, "cleared".to_string(), true).unwrap();
+}
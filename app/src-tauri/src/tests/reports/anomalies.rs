@@ -0,0 +1,157 @@
+use super::common::{account, setup_db};
+use super::income_expense::{range, record};
+
+// Five months of steady groceries and dining before March
+fn history(db_path: &std::path::PathBuf) -> i32 {
+    let checking = account(db_path, "Checking", None);
+    for month in ["2023-10", "2023-11", "2023-12", "2024-01", "2024-02"] {
+        for day in ["03", "17"] {
+            let date = format!("{}-{}", month, day);
+            record(db_path, checking, &date, "Grocer", "Groceries", -50.0);
+        }
+        let date = format!("{}-10", month);
+        record(db_path, checking, &date, "Bistro", "Dining", -100.0);
+    }
+    checking
+}
+
+#[test]
+fn test_flags_outliers_new_payees_duplicates_and_spikes() {
+    let (_dir, db_path) = setup_db();
+    let checking = history(&db_path);
+    record(
+        &db_path,
+        checking,
+        "2024-03-05",
+        "Grocer",
+        "Groceries",
+        -400.0,
+    );
+    for day in ["08", "15", "22"] {
+        let date = format!("2024-03-{}", day);
+        record(&db_path, checking, &date, "Bistro", "Dining", -100.0);
+    }
+    record(
+        &db_path,
+        checking,
+        "2024-03-12",
+        "Jeweler",
+        "Shopping",
+        -900.0,
+    );
+    record(
+        &db_path,
+        checking,
+        "2024-03-20",
+        "Netflix",
+        "Streaming",
+        -15.49,
+    );
+    record(
+        &db_path,
+        checking,
+        "2024-03-20",
+        "NETFLIX ",
+        "Streaming",
+        -15.49,
+    );
+    // Income is never unusual spending
+    record(
+        &db_path,
+        checking,
+        "2024-03-25",
+        "Employer",
+        "Salary",
+        9000.0,
+    );
+
+    let report = crate::get_anomalies_db(&db_path, range("2024-03-01", "2024-03-31")).unwrap();
+    let found: Vec<(&str, &str, Option<&str>)> = report
+        .anomalies
+        .iter()
+        .map(|a| (a.kind.as_str(), a.date.as_str(), a.category.as_deref()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("category_spike", "2024-03", Some("Dining")),
+            ("category_spike", "2024-03", Some("Groceries")),
+            ("outlier", "2024-03-05", Some("Groceries")),
+            ("new_payee", "2024-03-12", Some("Shopping")),
+            ("duplicate", "2024-03-20", Some("Streaming")),
+        ]
+    );
+
+    let dining = &report.anomalies[0];
+    assert_eq!(dining.transaction_id, None);
+    assert_eq!((dining.amount, dining.baseline), (300.0, Some(100.0)));
+    assert_eq!(
+        dining.explanation,
+        "Spending on Dining in 2024-03 is 300.00 USD, 3.0 times the 100.00 monthly average of the previous 5 months"
+    );
+
+    let outlier = &report.anomalies[2];
+    assert_eq!(outlier.account_id, Some(checking));
+    assert_eq!(outlier.baseline, Some(50.0));
+    assert_eq!(
+        outlier.explanation,
+        "400.00 USD at Grocer is 140.0 deviations above the usual 50.00 there"
+    );
+
+    let jeweler = &report.anomalies[3];
+    assert_eq!(jeweler.amount, 900.0);
+    assert_eq!(
+        jeweler.explanation,
+        "First payment to Jeweler, 900.00 USD, is 18.0 times the typical 50.00 charge"
+    );
+
+    let duplicate = &report.anomalies[4];
+    assert_eq!(duplicate.payee.as_deref(), Some("NETFLIX"));
+    assert!(duplicate.explanation.contains("2 times on 2024-03-20"));
+}
+
+#[test]
+fn test_category_history_and_quiet_months() {
+    let (_dir, db_path) = setup_db();
+    let checking = history(&db_path);
+    // A new shop is judged by its category, and small wobbles are not outliers
+    record(
+        &db_path,
+        checking,
+        "2024-03-03",
+        "Grocer",
+        "Groceries",
+        -51.0,
+    );
+    record(
+        &db_path,
+        checking,
+        "2024-03-09",
+        "Market",
+        "Groceries",
+        -600.0,
+    );
+    record(&db_path, checking, "2024-03-10", "Bistro", "Dining", -100.0);
+
+    let report = crate::get_anomalies_db(&db_path, range("2024-03-01", "2024-03-31")).unwrap();
+    let kinds: Vec<&str> = report.anomalies.iter().map(|a| a.kind.as_str()).collect();
+    assert_eq!(kinds, vec!["category_spike", "outlier"]);
+    let market = &report.anomalies[1];
+    assert_eq!(market.payee.as_deref(), Some("Market"));
+    assert!(market.explanation.ends_with("for the category"));
+
+    // The history months themselves are unremarkable
+    let quiet = crate::get_anomalies_db(&db_path, range("2023-10-01", "2024-02-29")).unwrap();
+    assert!(quiet.anomalies.is_empty());
+
+    let other_account = account(&db_path, "Card", None);
+    let filtered = crate::get_anomalies_db(
+        &db_path,
+        crate::ReportArgs {
+            account_ids: Some(vec![other_account]),
+            ..range("2024-03-01", "2024-03-31")
+        },
+    )
+    .unwrap();
+    assert!(filtered.anomalies.is_empty());
+}
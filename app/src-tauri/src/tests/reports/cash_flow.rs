@@ -0,0 +1,270 @@
+use super::common::{account, setup_db};
+use super::income_expense::{range, record};
+
+fn sample(db_path: &std::path::PathBuf) -> i32 {
+    let checking = account(db_path, "Checking", None);
+    let card = crate::create_account_db(
+        db_path,
+        "Card".to_string(),
+        0.0,
+        None,
+        Some("credit_card".to_string()),
+    )
+    .unwrap()
+    .id;
+    crate::create_account_db(
+        db_path,
+        "Broker".to_string(),
+        0.0,
+        None,
+        Some("brokerage".to_string()),
+    )
+    .unwrap();
+    record(
+        db_path,
+        checking,
+        "2024-03-01",
+        "Employer",
+        "Income:Salary",
+        3000.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2024-03-15",
+        "employer",
+        "Income:Salary",
+        200.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2024-03-20",
+        "Freelance Co",
+        "Income:Side",
+        500.0,
+    );
+    record(db_path, checking, "2024-03-02", "Landlord", "Rent", -1000.0);
+    record(
+        db_path,
+        checking,
+        "2024-03-05",
+        "Grocer",
+        "Food:Groceries",
+        -300.0,
+    );
+    // The refund nets against groceries instead of counting as income
+    record(
+        db_path,
+        checking,
+        "2024-03-08",
+        "Grocer",
+        "Food:Groceries",
+        50.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2024-03-09",
+        "Bistro",
+        "Food:Dining",
+        -100.0,
+    );
+    record(db_path, card, "2024-03-10", "Bistro", "Food:Dining", -200.0);
+    // Paying the card moves money between budget accounts; the broker is investing
+    record(db_path, checking, "2024-03-25", "Card", "Transfer", -200.0);
+    record(
+        db_path,
+        checking,
+        "2024-03-26",
+        "Broker",
+        "Transfer",
+        -1000.0,
+    );
+    checking
+}
+
+fn links(graph: &crate::CashFlowGraph) -> Vec<(&str, &str, f64)> {
+    graph
+        .links
+        .iter()
+        .map(|l| (l.source.as_str(), l.target.as_str(), l.value))
+        .collect()
+}
+
+#[test]
+fn test_graph_follows_category_hierarchy() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+
+    let graph =
+        crate::cash_flow_graph_db(&db_path, range("2024-03-01", "2024-03-31"), None).unwrap();
+    assert_eq!(graph.depth, 2);
+    assert_eq!(
+        (
+            graph.income,
+            graph.expense,
+            graph.investments,
+            graph.savings
+        ),
+        (3700.0, 1550.0, 1000.0, 1150.0)
+    );
+    assert_eq!(
+        links(&graph),
+        vec![
+            ("income:Income:Salary", "income:Income", 3200.0),
+            ("income:Income", "pool", 3700.0),
+            ("income:Income:Side", "income:Income", 500.0),
+            ("source:employer", "income:Income:Salary", 3200.0),
+            ("source:freelance co", "income:Income:Side", 500.0),
+            ("pool", "expense:Rent", 1000.0),
+            ("pool", "expense:Food", 550.0),
+            ("expense:Food", "expense:Food:Dining", 300.0),
+            ("expense:Food", "expense:Food:Groceries", 250.0),
+            ("pool", "investments", 1000.0),
+            ("pool", "savings", 1150.0),
+        ]
+    );
+
+    let kinds: Vec<&str> = graph.nodes.iter().map(|n| n.kind.as_str()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            "source",
+            "source",
+            "income",
+            "income",
+            "income",
+            "pool",
+            "expense",
+            "expense",
+            "expense",
+            "expense",
+            "investments",
+            "savings",
+        ]
+    );
+    let employer = &graph.nodes[0];
+    assert_eq!(
+        (employer.label.as_str(), employer.value),
+        ("Employer", 3200.0)
+    );
+    let pool = graph.nodes.iter().find(|n| n.id == "pool").unwrap();
+    assert_eq!(pool.value, 3700.0);
+
+    // One level deep, subcategories fold into their parents
+    let shallow =
+        crate::cash_flow_graph_db(&db_path, range("2024-03-01", "2024-03-31"), Some(1)).unwrap();
+    assert!(shallow.nodes.iter().all(|n| n.id.matches(':').count() <= 1));
+    assert!(links(&shallow).contains(&("source:employer", "income:Income", 3200.0)));
+    assert!(
+        crate::cash_flow_graph_db(&db_path, range("2024-03-01", "2024-03-31"), Some(0)).is_err()
+    );
+}
+
+#[test]
+fn test_shortfalls_and_account_selection() {
+    let (_dir, db_path) = setup_db();
+    let checking = sample(&db_path);
+    record(
+        &db_path,
+        checking,
+        "2024-04-02",
+        "Landlord",
+        "Rent",
+        -1000.0,
+    );
+    let broker = crate::get_accounts_db(&db_path)
+        .unwrap()
+        .into_iter()
+        .find(|a| a.name == "Broker")
+        .unwrap()
+        .id;
+    record(
+        &db_path,
+        broker,
+        "2024-04-03",
+        "Checking",
+        "Transfer",
+        -300.0,
+    );
+
+    let april =
+        crate::cash_flow_graph_db(&db_path, range("2024-04-01", "2024-04-30"), None).unwrap();
+    assert_eq!((april.investments, april.savings), (-300.0, -700.0));
+    assert_eq!(
+        links(&april),
+        vec![
+            ("investments:in", "pool", 300.0),
+            ("balances", "pool", 700.0),
+            ("pool", "expense:Rent", 1000.0),
+        ]
+    );
+
+    // Without the card its spending drops out and paying it is just money leaving
+    let only_checking = crate::cash_flow_graph_db(
+        &db_path,
+        crate::ReportArgs {
+            account_ids: Some(vec![checking]),
+            ..range("2024-03-01", "2024-03-31")
+        },
+        None,
+    )
+    .unwrap();
+    assert_eq!(only_checking.expense, 1350.0);
+    assert_eq!(only_checking.savings, 1350.0);
+}
+
+#[test]
+fn test_investment_income_stays_invested() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+    let broker = crate::get_accounts_db(&db_path)
+        .unwrap()
+        .into_iter()
+        .find(|a| a.name == "Broker")
+        .unwrap()
+        .id;
+    crate::record_investment_action_db(
+        &db_path,
+        crate::InvestmentActionArgs {
+            account_id: broker,
+            date: "2024-03-28".to_string(),
+            action: "dividend".to_string(),
+            ticker: Some("VTI".to_string()),
+            amount: Some(40.0),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let graph =
+        crate::cash_flow_graph_db(&db_path, range("2024-03-01", "2024-03-31"), None).unwrap();
+    assert_eq!(
+        (graph.income, graph.investments, graph.savings),
+        (3740.0, 1040.0, 1150.0)
+    );
+    let links = links(&graph);
+    assert!(links.contains(&("source:dividend", "income:Dividend", 40.0)));
+    assert!(links.contains(&("pool", "investments", 1040.0)));
+}
+
+#[test]
+fn test_separator_only_categories_are_uncategorized() {
+    let (_dir, db_path) = setup_db();
+    let checking = account(&db_path, "Checking", None);
+    record(&db_path, checking, "2024-03-01", "Employer", ":", 1000.0);
+    record(&db_path, checking, "2024-03-02", "Shop", " : ", -100.0);
+    record(&db_path, checking, "2024-03-03", "Kiosk", "", -50.0);
+
+    let graph =
+        crate::cash_flow_graph_db(&db_path, range("2024-03-01", "2024-03-31"), None).unwrap();
+    assert_eq!(
+        links(&graph),
+        vec![
+            ("income:Uncategorized", "pool", 850.0),
+            ("source:employer", "income:Uncategorized", 850.0),
+            ("pool", "savings", 850.0),
+        ]
+    );
+}
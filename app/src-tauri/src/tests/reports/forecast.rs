@@ -0,0 +1,306 @@
+use super::common::{account, setup_db};
+use super::income_expense::record;
+
+fn schedule(
+    account_id: i32,
+    payee: &str,
+    category: &str,
+    amount: f64,
+    frequency: &str,
+    next_date: &str,
+) -> crate::ScheduledTransactionArgs {
+    crate::ScheduledTransactionArgs {
+        account_id,
+        payee: payee.to_string(),
+        category: Some(category.to_string()),
+        amount,
+        frequency: frequency.to_string(),
+        next_date: next_date.to_string(),
+        ..Default::default()
+    }
+}
+
+fn forecast(db_path: &std::path::PathBuf, days: u32) -> crate::BalanceForecast {
+    crate::forecast_balances_db(
+        db_path,
+        crate::ForecastArgs {
+            days,
+            as_of: Some("2024-03-31".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_scheduled_transaction_crud_and_validation() {
+    let (_dir, db_path) = setup_db();
+    let checking = account(&db_path, "Checking", None);
+
+    let rent = crate::create_scheduled_transaction_db(
+        &db_path,
+        schedule(
+            checking,
+            " Landlord ",
+            "Rent",
+            -1000.0,
+            "Monthly",
+            "2024-01-31",
+        ),
+    )
+    .unwrap();
+    assert_eq!(rent.payee, "Landlord");
+    assert_eq!(rent.frequency, "monthly");
+
+    let updated = crate::update_scheduled_transaction_db(
+        &db_path,
+        rent.id,
+        crate::ScheduledTransactionArgs {
+            end_date: Some("2024-12-31".to_string()),
+            ..schedule(
+                checking,
+                "Landlord",
+                "Rent",
+                -1100.0,
+                "monthly",
+                "2024-01-31",
+            )
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        crate::get_scheduled_transactions_db(&db_path, Some(vec![checking])).unwrap(),
+        vec![updated]
+    );
+
+    for bad in [
+        schedule(
+            checking,
+            "Landlord",
+            "Rent",
+            -1000.0,
+            "fortnightly",
+            "2024-01-31",
+        ),
+        schedule(checking, "", "Rent", -1000.0, "monthly", "2024-01-31"),
+        schedule(
+            checking + 10,
+            "Landlord",
+            "Rent",
+            -1000.0,
+            "monthly",
+            "2024-01-31",
+        ),
+        crate::ScheduledTransactionArgs {
+            end_date: Some("2024-01-01".to_string()),
+            ..schedule(
+                checking,
+                "Landlord",
+                "Rent",
+                -1000.0,
+                "monthly",
+                "2024-01-31",
+            )
+        },
+    ] {
+        assert!(crate::create_scheduled_transaction_db(&db_path, bad).is_err());
+    }
+
+    // Deleting the account takes its schedule with it
+    crate::delete_account_db(&db_path, checking, false).unwrap();
+    assert!(crate::get_scheduled_transactions_db(&db_path, None)
+        .unwrap()
+        .is_empty());
+    assert!(crate::delete_scheduled_transaction_db(&db_path, rent.id).is_err());
+}
+
+#[test]
+fn test_forecast_adds_schedule_and_average_spending() {
+    let (_dir, db_path) = setup_db();
+    let checking = account(&db_path, "Checking", None);
+    let savings = account(&db_path, "Savings", None);
+    record(
+        &db_path,
+        checking,
+        "2024-01-01",
+        "Employer",
+        "Salary",
+        5000.0,
+    );
+    record(
+        &db_path,
+        checking,
+        "2024-02-10",
+        "Grocer",
+        "Groceries",
+        -91.0,
+    );
+    record(
+        &db_path,
+        checking,
+        "2024-03-10",
+        "Grocer",
+        "Groceries",
+        -91.0,
+    );
+    // Scheduled rent and the transfer to savings are not part of the average
+    record(
+        &db_path,
+        checking,
+        "2024-03-01",
+        "Landlord",
+        "Rent",
+        -1000.0,
+    );
+    record(
+        &db_path,
+        checking,
+        "2024-03-15",
+        "Savings",
+        "Transfer",
+        -500.0,
+    );
+    crate::create_scheduled_transaction_db(
+        &db_path,
+        schedule(
+            checking,
+            "Landlord",
+            "Rent",
+            -1000.0,
+            "monthly",
+            "2024-03-01",
+        ),
+    )
+    .unwrap();
+    crate::set_balance_threshold_db(&db_path, checking, Some(2300.0)).unwrap();
+
+    let report = forecast(&db_path, 30);
+    assert_eq!(report.lookback_months, 3);
+    assert_eq!(report.accounts.len(), 2);
+    let checking_forecast = &report.accounts[0];
+    assert_eq!(checking_forecast.start_balance, 3318.0);
+    // 182 spent over the 91 days from January to March
+    assert_eq!(checking_forecast.daily_spending, 2.0);
+    assert_eq!(checking_forecast.points.len(), 30);
+    let first = &checking_forecast.points[0];
+    assert_eq!(first.date, "2024-04-01");
+    assert_eq!(first.balance, 2316.0);
+    assert_eq!(checking_forecast.points[29].balance, 2258.0);
+    assert_eq!(
+        checking_forecast.first_below_threshold.as_deref(),
+        Some("2024-04-10")
+    );
+    assert!(!checking_forecast.points[8].below_threshold);
+    assert!(checking_forecast.points[9].below_threshold);
+
+    // Two spending days in 91 give a daily spread of sqrt(178)
+    let band = 1.645 * 178.0_f64.sqrt();
+    assert!((first.high - first.balance - band).abs() < 0.01);
+    let last = &checking_forecast.points[29];
+    assert!((last.balance - last.low - band * 30.0_f64.sqrt()).abs() < 0.01);
+
+    let savings_forecast = &report.accounts[1];
+    assert_eq!(savings_forecast.account_id, savings);
+    assert_eq!(savings_forecast.threshold, None);
+    assert!(savings_forecast
+        .points
+        .iter()
+        .all(|p| p.balance == 500.0 && p.low == 500.0 && p.high == 500.0));
+}
+
+#[test]
+fn test_forecast_account_selection_and_errors() {
+    let (_dir, db_path) = setup_db();
+    let checking = account(&db_path, "Checking", None);
+    let closed = account(&db_path, "Closed", None);
+    crate::create_account_db(
+        &db_path,
+        "House".to_string(),
+        0.0,
+        None,
+        Some("asset".to_string()),
+    )
+    .unwrap();
+    crate::set_account_archived_db(&db_path, closed, true).unwrap();
+    // A bill past its end date no longer moves the balance
+    crate::create_scheduled_transaction_db(
+        &db_path,
+        crate::ScheduledTransactionArgs {
+            end_date: Some("2024-04-10".to_string()),
+            ..schedule(checking, "Gym", "Fitness", -50.0, "weekly", "2024-03-30")
+        },
+    )
+    .unwrap();
+
+    let report = forecast(&db_path, 14);
+    assert_eq!(report.accounts.len(), 1);
+    let balances: Vec<f64> = report.accounts[0]
+        .points
+        .iter()
+        .map(|p| p.balance)
+        .collect();
+    assert_eq!(balances[..6], [0.0, 0.0, 0.0, 0.0, 0.0, -50.0]);
+    assert_eq!(balances[13], -50.0);
+
+    let chosen = crate::forecast_balances_db(
+        &db_path,
+        crate::ForecastArgs {
+            days: 7,
+            account_ids: Some(vec![closed]),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(chosen.accounts[0].account_id, closed);
+
+    for args in [
+        crate::ForecastArgs {
+            days: 0,
+            ..Default::default()
+        },
+        crate::ForecastArgs {
+            days: 7,
+            lookback_months: Some(0),
+            ..Default::default()
+        },
+    ] {
+        assert!(crate::forecast_balances_db(&db_path, args).is_err());
+    }
+    assert!(crate::set_balance_threshold_db(&db_path, checking + 10, Some(1.0)).is_err());
+}
+
+#[test]
+fn test_forecast_currencies() {
+    let (_dir, db_path) = setup_db();
+    let checking = account(&db_path, "Checking", None);
+    record(
+        &db_path,
+        checking,
+        "2024-03-01",
+        "Employer",
+        "Salary",
+        1000.0,
+    );
+    let args = crate::ForecastArgs {
+        days: 1,
+        as_of: Some("2024-03-31".to_string()),
+        base_currency: Some("eur".to_string()),
+        ..Default::default()
+    };
+
+    // An account without a currency is in the base currency
+    let report = crate::forecast_balances_db(&db_path, args.clone()).unwrap();
+    assert_eq!(report.accounts[0].currency, "EUR");
+    assert_eq!(report.accounts[0].start_balance, 1000.0);
+
+    // An amount in another currency with no rate for its date is not taken at par
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO transactions (account_id, date, payee, amount, currency)
+         VALUES (?1, '2024-03-10', 'Hotel', -50.0, 'GBP')",
+        [checking],
+    )
+    .unwrap();
+    let err = crate::forecast_balances_db(&db_path, args).unwrap_err();
+    assert_eq!(err, "No exchange rate from GBP to EUR on 2024-03-10");
+}
@@ -0,0 +1,292 @@
+use super::common::{account, setup_db};
+
+pub(super) fn record(
+    db_path: &std::path::PathBuf,
+    account_id: i32,
+    date: &str,
+    payee: &str,
+    category: &str,
+    amount: f64,
+) {
+    crate::create_transaction_db(
+        db_path,
+        crate::CreateTransactionArgs {
+            account_id,
+            date: date.to_string(),
+            payee: payee.to_string(),
+            notes: None,
+            category: Some(category.to_string()),
+            amount,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap();
+}
+
+// A year of history before the report range, a transfer to savings and a share purchase
+pub(super) fn sample(db_path: &std::path::PathBuf) -> (i32, i32) {
+    let checking = account(db_path, "Checking", None);
+    let savings = account(db_path, "Savings", None);
+    let broker = account(db_path, "Broker", None);
+    record(
+        db_path,
+        checking,
+        "2023-01-15",
+        "Employer",
+        "Salary",
+        3000.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2023-01-20",
+        "Grocer",
+        "Groceries",
+        -100.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2024-01-15",
+        "Employer",
+        "Salary",
+        3300.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2024-01-20",
+        "Grocer",
+        "Groceries",
+        -150.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2024-01-25",
+        "Cinema",
+        "Entertainment",
+        -50.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2024-02-15",
+        "Employer",
+        "Salary",
+        3300.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2024-02-18",
+        " grocer",
+        "Groceries",
+        -200.0,
+    );
+    record(
+        db_path,
+        checking,
+        "2024-02-20",
+        "Savings",
+        "Transfer",
+        -1000.0,
+    );
+    crate::create_investment_transaction_db(
+        db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id: broker,
+            date: "2024-02-21".to_string(),
+            ticker: "VTI".to_string(),
+            shares: 5.0,
+            price_per_share: 200.0,
+            fee: 0.0,
+            is_buy: true,
+            currency: None,
+        },
+    )
+    .unwrap();
+    (checking, savings)
+}
+
+pub(super) fn range(from: &str, to: &str) -> crate::ReportArgs {
+    crate::ReportArgs {
+        from: Some(from.to_string()),
+        to: Some(to.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_monthly_income_expense_and_savings_rate() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+
+    let report =
+        crate::income_expense_report_db(&db_path, range("2024-01-01", "2024-02-29")).unwrap();
+    assert_eq!(report.base_currency, "USD");
+    assert_eq!(report.months.len(), 2);
+    let january = &report.months[0];
+    assert_eq!(january.month, "2024-01");
+    assert_eq!(january.income, 3300.0);
+    assert_eq!(january.expense, 200.0);
+    assert_eq!(january.net, 3100.0);
+    assert!((january.savings_rate.unwrap() - 3100.0 / 33.0).abs() < 1e-9);
+
+    // Transfers and share purchases are neither income nor spending
+    let february = &report.months[1];
+    assert_eq!(february.expense, 200.0);
+    assert_eq!(report.income, 6600.0);
+    assert_eq!(report.expense, 400.0);
+    assert_eq!(report.net, 6200.0);
+}
+
+#[test]
+fn test_month_and_year_comparisons() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+
+    let report =
+        crate::income_expense_report_db(&db_path, range("2024-01-01", "2024-02-29")).unwrap();
+    let january = &report.months[0];
+
+    // December had nothing, so there is no percentage to give
+    let mom = january.month_over_month.as_ref().unwrap();
+    assert_eq!(mom.compared_to, "2023-12");
+    assert_eq!(mom.income, 3300.0);
+    assert_eq!(mom.income_percent, None);
+
+    let yoy = january.year_over_year.as_ref().unwrap();
+    assert_eq!(yoy.compared_to, "2023-01");
+    assert_eq!(yoy.income, 300.0);
+    assert_eq!(yoy.expense, 100.0);
+    assert_eq!(yoy.net, 200.0);
+    assert!((yoy.income_percent.unwrap() - 10.0).abs() < 1e-9);
+    assert!((yoy.expense_percent.unwrap() - 100.0).abs() < 1e-9);
+
+    let february = report.months[1].month_over_month.as_ref().unwrap();
+    assert_eq!(february.compared_to, "2024-01");
+    assert_eq!(february.income_percent, Some(0.0));
+    assert_eq!(february.expense, 0.0);
+}
+
+#[test]
+fn test_transfers_and_account_filter() {
+    let (_dir, db_path) = setup_db();
+    let (checking, _) = sample(&db_path);
+
+    let with_transfers = crate::income_expense_report_db(
+        &db_path,
+        crate::ReportArgs {
+            include_transfers: true,
+            ..range("2024-02-01", "2024-02-29")
+        },
+    )
+    .unwrap();
+    // Both legs count: out of checking and into savings
+    assert_eq!(with_transfers.expense, 1200.0);
+    assert_eq!(with_transfers.income, 4300.0);
+
+    let only_checking = crate::income_expense_report_db(
+        &db_path,
+        crate::ReportArgs {
+            include_transfers: true,
+            account_ids: Some(vec![checking]),
+            ..range("2024-02-01", "2024-02-29")
+        },
+    )
+    .unwrap();
+    assert_eq!(only_checking.income, 3300.0);
+    assert_eq!(only_checking.expense, 1200.0);
+
+    // Without a start the report begins at the first transaction
+    let all = crate::income_expense_report_db(
+        &db_path,
+        crate::ReportArgs {
+            to: Some("2024-02-29".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(all.from, "2023-01-15");
+    assert_eq!(all.months.len(), 14);
+
+    assert!(crate::income_expense_report_db(&db_path, range("2024-03-01", "2024-02-01")).is_err());
+}
+
+#[test]
+fn test_investment_income_counts() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+    let broker = crate::get_accounts_db(&db_path)
+        .unwrap()
+        .into_iter()
+        .find(|a| a.name == "Broker")
+        .unwrap()
+        .id;
+    let action = |action: &str, amount: f64| crate::InvestmentActionArgs {
+        account_id: broker,
+        date: "2024-02-25".to_string(),
+        action: action.to_string(),
+        ticker: Some("VTI".to_string()),
+        amount: Some(amount),
+        ..Default::default()
+    };
+    crate::record_investment_action_db(&db_path, action("dividend", 40.0)).unwrap();
+    crate::record_investment_action_db(&db_path, action("withholding_tax", 6.0)).unwrap();
+    crate::record_investment_action_db(&db_path, action("return_of_capital", 25.0)).unwrap();
+
+    let report =
+        crate::income_expense_report_db(&db_path, range("2024-02-01", "2024-02-29")).unwrap();
+    // The share purchase still isn't spending, and the return of capital isn't income
+    assert_eq!(report.income, 3340.0);
+    assert_eq!(report.expense, 206.0);
+}
+
+#[test]
+fn test_rate_mode_picks_transaction_date_or_current_rate() {
+    let (_dir, db_path) = setup_db();
+    let euros = account(&db_path, "Girokonto", Some("EUR"));
+    record(&db_path, euros, "2024-01-10", "Employer", "Salary", 100.0);
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO daily_fx_rates (currency, date, rate) VALUES ('EUR', '2024-01-01', 1.1)",
+        [],
+    )
+    .unwrap();
+    crate::set_custom_exchange_rate_db(&db_path, "EUR".to_string(), 1.3).unwrap();
+
+    let income = |rate_mode: Option<&str>| {
+        crate::income_expense_report_db(
+            &db_path,
+            crate::ReportArgs {
+                from: Some("2024-01-01".to_string()),
+                to: Some("2024-01-31".to_string()),
+                rate_mode: rate_mode.map(String::from),
+                ..Default::default()
+            },
+        )
+        .map(|r| r.income)
+    };
+    assert_eq!(income(None), Ok(110.0));
+    assert_eq!(income(Some("transaction_date")), Ok(110.0));
+    assert_eq!(income(Some("current")), Ok(130.0));
+    assert!(income(Some("yesterday")).is_err());
+
+    // Without a rate for the day the report fails instead of using today's
+    record(&db_path, euros, "2023-12-20", "Employer", "Salary", 100.0);
+    let err = crate::income_expense_report_db(
+        &db_path,
+        crate::ReportArgs {
+            from: Some("2023-12-01".to_string()),
+            to: Some("2024-01-31".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, "No exchange rate from EUR to USD on 2023-12-20");
+}
@@ -0,0 +1,8 @@
+pub use super::common;
+
+pub mod anomalies;
+pub mod cash_flow;
+pub mod forecast;
+pub mod income_expense;
+pub mod spending;
+pub mod subscriptions;
@@ -0,0 +1,106 @@
+use super::common::{account, setup_db};
+use super::income_expense::{range, record, sample};
+use rusqlite::{params, Connection};
+
+#[test]
+fn test_category_trends_per_month() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+
+    let report = crate::category_trends_db(&db_path, range("2024-01-01", "2024-02-29")).unwrap();
+    assert_eq!(report.months, vec!["2024-01", "2024-02"]);
+    let names: Vec<&str> = report
+        .categories
+        .iter()
+        .map(|c| c.category.as_str())
+        .collect();
+    assert_eq!(names, vec!["Groceries", "Entertainment"]);
+    let groceries = &report.categories[0];
+    assert_eq!(groceries.monthly, vec![150.0, 200.0]);
+    assert_eq!(groceries.total, 350.0);
+    assert_eq!(groceries.average, 175.0);
+    assert_eq!(report.categories[1].monthly, vec![50.0, 0.0]);
+
+    let with_transfers = crate::category_trends_db(
+        &db_path,
+        crate::ReportArgs {
+            include_transfers: true,
+            ..range("2024-01-01", "2024-02-29")
+        },
+    )
+    .unwrap();
+    assert_eq!(with_transfers.categories[0].category, "Transfer");
+    assert_eq!(with_transfers.categories[0].monthly, vec![0.0, 1000.0]);
+}
+
+#[test]
+fn test_top_payees() {
+    let (_dir, db_path) = setup_db();
+    sample(&db_path);
+
+    let payees = crate::top_payees_db(&db_path, range("2024-01-01", "2024-02-29"), None).unwrap();
+    assert_eq!(payees.len(), 2);
+    // " grocer" and "Grocer" are the same payee
+    assert_eq!(payees[0].payee, "Grocer");
+    assert_eq!(payees[0].total, 350.0);
+    assert_eq!(payees[0].count, 2);
+    assert!((payees[0].share - 87.5).abs() < 1e-9);
+    assert_eq!(payees[1].payee, "Cinema");
+
+    let top = crate::top_payees_db(&db_path, range("2024-01-01", "2024-02-29"), Some(1)).unwrap();
+    assert_eq!(top.len(), 1);
+}
+
+#[test]
+fn test_amounts_convert_at_transaction_dates() {
+    let (_dir, db_path) = setup_db();
+    let euros = account(&db_path, "Girokonto", Some("EUR"));
+    record(
+        &db_path,
+        euros,
+        "2024-01-10",
+        "Bäckerei",
+        "Groceries",
+        -100.0,
+    );
+    record(
+        &db_path,
+        euros,
+        "2024-02-10",
+        "Bäckerei",
+        "Groceries",
+        -100.0,
+    );
+    let conn = Connection::open(&db_path).unwrap();
+    for (date, rate) in [("2024-01-10", 1.10), ("2024-02-10", 1.05)] {
+        conn.execute(
+            "INSERT INTO daily_fx_rates (currency, date, rate) VALUES ('EUR', ?1, ?2)",
+            params![date, rate],
+        )
+        .unwrap();
+    }
+
+    let usd = crate::category_trends_db(&db_path, range("2024-01-01", "2024-02-29")).unwrap();
+    assert_eq!(usd.categories[0].monthly, vec![110.0, 105.0]);
+
+    let eur = crate::category_trends_db(
+        &db_path,
+        crate::ReportArgs {
+            base_currency: Some("eur".to_string()),
+            ..range("2024-01-01", "2024-02-29")
+        },
+    )
+    .unwrap();
+    assert_eq!(eur.base_currency, "EUR");
+    assert_eq!(eur.categories[0].total, 200.0);
+
+    let missing = crate::top_payees_db(
+        &db_path,
+        crate::ReportArgs {
+            base_currency: Some("JPY".to_string()),
+            ..range("2024-01-01", "2024-02-29")
+        },
+        None,
+    );
+    assert!(missing.unwrap_err().contains("No exchange rate"));
+}
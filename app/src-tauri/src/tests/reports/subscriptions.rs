@@ -0,0 +1,162 @@
+use super::common::{account, setup_db};
+use super::income_expense::record;
+
+fn detect(db_path: &std::path::PathBuf, as_of: &str) -> Vec<crate::Subscription> {
+    crate::detect_subscriptions_db(
+        db_path,
+        crate::SubscriptionArgs {
+            as_of: Some(as_of.to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+fn sample(db_path: &std::path::PathBuf) -> (i32, i32) {
+    let checking = account(db_path, "Checking", None);
+    let card = account(db_path, "Card", None);
+    // A one-off purchase long before the subscription started
+    record(
+        db_path,
+        card,
+        "2023-06-01",
+        "Netflix.com",
+        "Streaming",
+        -30.0,
+    );
+    for (date, payee, amount) in [
+        ("2024-01-05", "NETFLIX.COM 8841", -15.49),
+        ("2024-02-05", "Netflix.com", -15.49),
+        ("2024-03-04", "netflix.com *2291", -15.49),
+        ("2024-04-05", "Netflix.com", -17.99),
+    ] {
+        record(db_path, card, date, payee, "Streaming", amount);
+    }
+    for date in ["2024-01-15", "2024-02-15", "2024-03-15"] {
+        record(db_path, checking, date, "Spotify", "Music", -10.99);
+    }
+    for date in ["2022-06-01", "2023-06-01"] {
+        record(
+            db_path,
+            checking,
+            date,
+            "Domain Registrar",
+            "Hosting",
+            -12.0,
+        );
+    }
+    // Irregular spending and monthly transfers are not subscriptions
+    for date in ["2024-01-03", "2024-01-20", "2024-03-02", "2024-03-09"] {
+        record(db_path, checking, date, "Grocer", "Groceries", -80.0);
+    }
+    for date in ["2024-01-01", "2024-02-01", "2024-03-01"] {
+        record(db_path, checking, date, "Card", "Transfer", -500.0);
+    }
+    (checking, card)
+}
+
+#[test]
+fn test_detects_cadence_next_charge_and_price_changes() {
+    let (_dir, db_path) = setup_db();
+    let (_, card) = sample(&db_path);
+
+    let subscriptions = detect(&db_path, "2024-04-10");
+    let payees: Vec<&str> = subscriptions.iter().map(|s| s.payee.as_str()).collect();
+    assert_eq!(payees, vec!["Netflix.com", "Spotify", "Domain Registrar"]);
+
+    let netflix = &subscriptions[0];
+    assert_eq!(netflix.normalized_payee, "netflix com");
+    assert_eq!(netflix.account_id, card);
+    assert_eq!(netflix.category.as_deref(), Some("Streaming"));
+    assert_eq!(netflix.cadence, "monthly");
+    assert_eq!(netflix.charges, 4);
+    assert_eq!(netflix.first_charge, "2024-01-05");
+    assert_eq!(netflix.last_amount, 17.99);
+    assert_eq!(netflix.next_charge, "2024-05-05");
+    assert_eq!(netflix.annualized_cost, 215.88);
+    assert_eq!(netflix.status, "changed");
+    assert_eq!(netflix.price_changes.len(), 1);
+    let change = &netflix.price_changes[0];
+    assert_eq!(change.date, "2024-04-05");
+    assert_eq!((change.old_amount, change.new_amount), (15.49, 17.99));
+    assert!((change.percent - 2.5 / 15.49 * 100.0).abs() < 1e-9);
+
+    let spotify = &subscriptions[1];
+    assert_eq!(spotify.status, "active");
+    assert_eq!(spotify.next_charge, "2024-04-15");
+    assert_eq!(spotify.annualized_cost, 131.88);
+
+    // Two yearly charges are enough, and the missed renewal shows as stopped
+    let domain = &subscriptions[2];
+    assert_eq!(domain.cadence, "yearly");
+    assert_eq!(domain.next_charge, "2024-06-01");
+    assert_eq!(domain.status, "active");
+    assert_eq!(detect(&db_path, "2024-07-15")[2].status, "stopped");
+}
+
+#[test]
+fn test_stopped_charges_and_account_filter() {
+    let (_dir, db_path) = setup_db();
+    let (checking, _) = sample(&db_path);
+
+    let later = detect(&db_path, "2024-04-30");
+    let spotify = later.iter().find(|s| s.payee == "Spotify").unwrap();
+    assert_eq!(spotify.status, "stopped");
+    // Charges after the as-of date are not seen yet
+    let earlier = detect(&db_path, "2024-03-20");
+    let netflix = earlier
+        .iter()
+        .find(|s| s.payee == "netflix.com *2291")
+        .unwrap();
+    assert_eq!(netflix.charges, 3);
+    assert_eq!(netflix.status, "active");
+    assert!(netflix.price_changes.is_empty());
+
+    let only_checking = crate::detect_subscriptions_db(
+        &db_path,
+        crate::SubscriptionArgs {
+            account_ids: Some(vec![checking]),
+            as_of: Some("2024-04-10".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(only_checking.len(), 2);
+    assert!(only_checking.iter().all(|s| s.account_id == checking));
+
+    assert!(crate::detect_subscriptions_db(
+        &db_path,
+        crate::SubscriptionArgs {
+            as_of: Some("April".to_string()),
+            ..Default::default()
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn test_varying_amounts_are_not_subscriptions() {
+    let (_dir, db_path) = setup_db();
+    let card = account(&db_path, "Card", None);
+    // A monthly habit whose bill swings too much to be a subscription
+    for (date, amount) in [
+        ("2024-01-10", -20.0),
+        ("2024-02-09", -35.0),
+        ("2024-03-11", -55.0),
+        ("2024-04-10", -28.0),
+    ] {
+        record(&db_path, card, date, "Pizza Place", "Dining", amount);
+    }
+    // A small rise on a steady charge stays in the run
+    for (date, amount) in [
+        ("2024-01-20", -9.99),
+        ("2024-02-20", -9.99),
+        ("2024-03-20", -11.49),
+    ] {
+        record(&db_path, card, date, "Cloud Storage", "Software", amount);
+    }
+
+    let found = detect(&db_path, "2024-04-15");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].payee, "Cloud Storage");
+    assert_eq!(found[0].charges, 3);
+}
@@ -0,0 +1,239 @@
+use crate::core::rules::{ApplyRulesArgs, CreateRuleDbParams};
+use crate::models::{RuleAction, RuleCondition, Transaction};
+use crate::tests::common::setup_db;
+use crate::{
+    apply_rules_db, create_account_db, create_rule_db, create_transaction_db,
+    finish_reconciliation_db, get_transactions_db, set_transaction_cleared_db,
+    update_transaction_db, CreateTransactionArgs, UpdateTransactionArgs,
+};
+use std::path::PathBuf;
+
+fn rule(db_path: &PathBuf, priority: i32, pattern: &str, actions: &[(&str, &str)]) -> i32 {
+    create_rule_db(
+        db_path,
+        CreateRuleDbParams {
+            priority,
+            match_field: "".to_string(),
+            match_pattern: "".to_string(),
+            action_field: "".to_string(),
+            action_value: "".to_string(),
+            logic: "and".to_string(),
+            conditions: vec![RuleCondition {
+                field: "payee".to_string(),
+                operator: "contains".to_string(),
+                value: pattern.to_string(),
+                negated: false,
+            }],
+            actions: actions
+                .iter()
+                .map(|(field, value)| RuleAction {
+                    field: field.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+        },
+    )
+    .unwrap()
+}
+
+fn record(db_path: &PathBuf, account_id: i32, date: &str, payee: &str) -> Transaction {
+    create_transaction_db(
+        db_path,
+        CreateTransactionArgs {
+            account_id,
+            date: date.to_string(),
+            payee: payee.to_string(),
+            notes: None,
+            category: None,
+            amount: -25.0,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap()
+}
+
+fn category(db_path: &PathBuf, account_id: i32, id: i32) -> Option<String> {
+    get_transactions_db(db_path, account_id)
+        .unwrap()
+        .into_iter()
+        .find(|t| t.id == id)
+        .unwrap()
+        .category
+}
+
+#[test]
+fn test_dry_run_reports_changes_without_saving() {
+    let (_dir, db_path) = setup_db();
+    let account = create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let coffee = record(&db_path, account.id, "2024-01-10", "Starbucks 123");
+    let other = record(&db_path, account.id, "2024-01-11", "Hardware store");
+    let low = rule(&db_path, 1, "starbucks", &[("category", "Food")]);
+    let high = rule(
+        &db_path,
+        2,
+        "starbucks",
+        &[("category", "Coffee"), ("payee", "Starbucks")],
+    );
+
+    let args = ApplyRulesArgs {
+        dry_run: true,
+        ..Default::default()
+    };
+    let result = apply_rules_db(&db_path, args.clone()).unwrap();
+    assert!(result.dry_run);
+    assert_eq!(result.examined, 2);
+    assert_eq!(result.transactions.len(), 1);
+    let diff = &result.transactions[0];
+    assert_eq!(diff.transaction_id, coffee.id);
+    assert_eq!(diff.payee, "Starbucks 123");
+    let fields: Vec<(&str, Option<&str>, i32)> = diff
+        .changes
+        .iter()
+        .map(|c| (c.field.as_str(), c.new_value.as_deref(), c.rule_id))
+        .collect();
+    // The higher priority rule wins and is credited with the change
+    assert_eq!(
+        fields,
+        vec![
+            ("payee", Some("Starbucks"), high),
+            ("category", Some("Coffee"), high)
+        ]
+    );
+    assert_ne!(low, high);
+    assert_eq!(category(&db_path, account.id, coffee.id), None);
+
+    let result = apply_rules_db(
+        &db_path,
+        ApplyRulesArgs {
+            dry_run: false,
+            ..args
+        },
+    )
+    .unwrap();
+    assert_eq!(result.transactions.len(), 1);
+    assert_eq!(
+        category(&db_path, account.id, coffee.id).as_deref(),
+        Some("Coffee")
+    );
+    assert_eq!(category(&db_path, account.id, other.id), None);
+
+    // Nothing is left to change on a second run
+    let again = apply_rules_db(&db_path, ApplyRulesArgs::default()).unwrap();
+    assert!(again.transactions.is_empty());
+}
+
+#[test]
+fn test_filters_by_range_account_and_rule() {
+    let (_dir, db_path) = setup_db();
+    let checking = create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let card = create_account_db(&db_path, "Card".to_string(), 0.0, None, None).unwrap();
+    let old = record(&db_path, checking.id, "2021-05-01", "Netflix");
+    let recent = record(&db_path, checking.id, "2024-05-01T10:00:00", "Netflix");
+    let on_card = record(&db_path, card.id, "2024-05-02", "Netflix");
+    let shop = record(&db_path, checking.id, "2024-05-03", "Grocer");
+    let streaming = rule(&db_path, 1, "netflix", &[("category", "Streaming")]);
+    rule(&db_path, 1, "grocer", &[("category", "Groceries")]);
+
+    let result = apply_rules_db(
+        &db_path,
+        ApplyRulesArgs {
+            from: Some("2023-01-01".to_string()),
+            to: Some("2024-12-31".to_string()),
+            account_ids: Some(vec![checking.id]),
+            rule_ids: Some(vec![streaming]),
+            dry_run: false,
+        },
+    )
+    .unwrap();
+    let ids: Vec<i32> = result
+        .transactions
+        .iter()
+        .map(|d| d.transaction_id)
+        .collect();
+    assert_eq!(ids, vec![recent.id]);
+    assert_eq!(category(&db_path, checking.id, old.id), None);
+    assert_eq!(category(&db_path, card.id, on_card.id), None);
+    assert_eq!(category(&db_path, checking.id, shop.id), None);
+
+    let err = apply_rules_db(
+        &db_path,
+        ApplyRulesArgs {
+            rule_ids: Some(vec![streaming + 100]),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert!(err.contains("not found"));
+    assert!(apply_rules_db(
+        &db_path,
+        ApplyRulesArgs {
+            from: Some("last year".to_string()),
+            ..Default::default()
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn test_skips_transfers_and_reconciled() {
+    let (_dir, db_path) = setup_db();
+    let checking = create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let savings = create_account_db(&db_path, "Savings".to_string(), 0.0, None, None).unwrap();
+    crate::create_transfer_db(
+        &db_path,
+        crate::CreateTransferArgs {
+            from_account_id: checking.id,
+            to_account_id: savings.id,
+            amount_from: 100.0,
+            amount_to: None,
+            date: "2024-03-01".to_string(),
+            fee: None,
+            notes: None,
+        },
+    )
+    .unwrap();
+    let wallet = create_account_db(&db_path, "Wallet".to_string(), 0.0, None, None).unwrap();
+    let locked = record(&db_path, wallet.id, "2024-03-02", "Savings bakery");
+    set_transaction_cleared_db(&db_path, locked.id, "cleared".to_string(), false).unwrap();
+    finish_reconciliation_db(&db_path, wallet.id, "2024-03-31".to_string(), -25.0).unwrap();
+    rule(&db_path, 1, "savings", &[("category", "Bakery")]);
+
+    let result = apply_rules_db(&db_path, ApplyRulesArgs::default()).unwrap();
+    assert!(result.transactions.is_empty());
+    assert_eq!(result.examined, 1);
+    assert_eq!(result.skipped_reconciled, 1);
+    assert_eq!(category(&db_path, wallet.id, locked.id), None);
+}
+
+#[test]
+fn test_update_applies_rules() {
+    let (_dir, db_path) = setup_db();
+    let account = create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let tx = record(&db_path, account.id, "2024-02-01", "Unknown shop");
+    rule(&db_path, 1, "uber", &[("category", "Transport")]);
+
+    let updated = update_transaction_db(
+        &db_path,
+        UpdateTransactionArgs {
+            id: tx.id,
+            account_id: account.id,
+            date: tx.date.clone(),
+            payee: "UBER *TRIP".to_string(),
+            notes: None,
+            category: None,
+            amount: -25.0,
+            currency: None,
+            override_reconciled: false,
+        },
+    )
+    .unwrap();
+    assert_eq!(updated.category.as_deref(), Some("Transport"));
+    assert_eq!(
+        category(&db_path, account.id, tx.id).as_deref(),
+        Some("Transport")
+    );
+}
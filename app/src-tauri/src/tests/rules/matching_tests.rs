@@ -15,6 +15,9 @@ fn create_base_transaction() -> Transaction {
         price_per_share: None,
         fee: None,
         currency: Some("USD".to_string()),
+        cleared: "uncleared".to_string(),
+        transfer_id: None,
+        action: None,
     }
 }
 
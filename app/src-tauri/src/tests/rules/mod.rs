@@ -1,6 +1,8 @@
+pub mod apply_rules_tests;
 pub mod create_rule;
 pub mod delete_rule;
 pub mod integration_tests;
 pub mod matching_tests;
+pub mod operator_tests;
 pub mod order_rules;
 pub mod update_rule;
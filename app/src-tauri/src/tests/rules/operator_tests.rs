@@ -0,0 +1,301 @@
+use crate::core::rules::{CreateRuleDbParams, UpdateRuleDbParams};
+use crate::models::{Rule, RuleAction, RuleCondition, Transaction};
+use crate::rules::apply_rules_to_transaction;
+use crate::tests::common::setup_db;
+use crate::{create_rule_db, create_transaction_db, update_rule_db, CreateTransactionArgs};
+
+fn transaction() -> Transaction {
+    Transaction {
+        id: 1,
+        account_id: 7,
+        date: "2024-03-15".to_string(),
+        payee: "AMZN Mktp US*2K4".to_string(),
+        notes: None,
+        category: None,
+        amount: -42.5,
+        ticker: None,
+        shares: None,
+        price_per_share: None,
+        fee: None,
+        currency: Some("EUR".to_string()),
+        cleared: "uncleared".to_string(),
+        transfer_id: None,
+        action: None,
+    }
+}
+
+fn condition(field: &str, operator: &str, value: &str) -> RuleCondition {
+    RuleCondition {
+        field: field.to_string(),
+        operator: operator.to_string(),
+        value: value.to_string(),
+        negated: false,
+    }
+}
+
+fn matches(condition: RuleCondition) -> bool {
+    let mut tx = transaction();
+    let rule = Rule {
+        id: 1,
+        priority: 1,
+        match_field: "".to_string(),
+        match_pattern: "".to_string(),
+        action_field: "".to_string(),
+        action_value: "".to_string(),
+        logic: "and".to_string(),
+        conditions: vec![condition],
+        actions: vec![RuleAction {
+            field: "category".to_string(),
+            value: "Matched".to_string(),
+        }],
+    };
+    apply_rules_to_transaction(&mut tx, &[rule]);
+    tx.category.is_some()
+}
+
+fn params(conditions: Vec<RuleCondition>) -> CreateRuleDbParams {
+    CreateRuleDbParams {
+        priority: 1,
+        match_field: "".to_string(),
+        match_pattern: "".to_string(),
+        action_field: "".to_string(),
+        action_value: "".to_string(),
+        logic: "and".to_string(),
+        conditions,
+        actions: vec![RuleAction {
+            field: "category".to_string(),
+            value: "Shopping".to_string(),
+        }],
+    }
+}
+
+#[test]
+fn test_text_and_list_operators() {
+    assert!(matches(condition("payee", "regex", r"^amzn mktp")));
+    assert!(!matches(condition("payee", "regex", r"^amazon")));
+    assert!(matches(condition(
+        "payee",
+        "in",
+        "Netflix, amzn mktp us*2k4"
+    )));
+    assert!(!matches(condition("payee", "in", "Netflix,Spotify")));
+    assert!(matches(condition("notes", "is_empty", "")));
+    assert!(!matches(condition("payee", "is_empty", "")));
+    assert!(matches(condition("payee", "not_contains", "ebay")));
+    assert!(matches(condition("currency", "equals", "eur")));
+    assert!(matches(condition("account", "equals", "7")));
+    assert!(matches(condition("account", "in", "3, 7")));
+    assert!(!matches(condition("account", "equals", "8")));
+    // Numbers compare as numbers, not text
+    assert!(matches(condition("amount", "equals", "-42.50")));
+}
+
+#[test]
+fn test_number_and_date_operators() {
+    assert!(matches(condition("amount", "between", "-50,-40")));
+    assert!(matches(condition("amount", "between", "-42.5, 0")));
+    assert!(!matches(condition("amount", "between", "0,100")));
+    assert!(matches(condition("amount", "sign", "negative")));
+    assert!(!matches(condition("amount", "sign", "positive")));
+    // A missing fee is empty, not zero
+    assert!(!matches(condition("fee", "less_than", "1")));
+    assert!(matches(condition("fee", "is_empty", "")));
+
+    assert!(matches(condition("date", "before", "2024-03-16")));
+    assert!(!matches(condition("date", "before", "2024-03-15")));
+    assert!(matches(condition("date", "after", "2024-03-01")));
+    assert!(matches(condition(
+        "date",
+        "between",
+        "2024-03-01,2024-03-31"
+    )));
+    assert!(matches(condition("date", "day_of_month", "1, 15")));
+    assert!(!matches(condition("date", "day_of_month", "14")));
+
+    let mut recent = transaction();
+    recent.date = chrono::Local::now().date_naive().to_string();
+    let rule = Rule {
+        id: 1,
+        priority: 1,
+        match_field: "".to_string(),
+        match_pattern: "".to_string(),
+        action_field: "".to_string(),
+        action_value: "".to_string(),
+        logic: "and".to_string(),
+        conditions: vec![condition("date", "within_days", "30")],
+        actions: vec![RuleAction {
+            field: "category".to_string(),
+            value: "Recent".to_string(),
+        }],
+    };
+    apply_rules_to_transaction(&mut recent, std::slice::from_ref(&rule));
+    assert_eq!(recent.category.as_deref(), Some("Recent"));
+    let mut old = transaction();
+    apply_rules_to_transaction(&mut old, &[rule]);
+    assert_eq!(old.category, None);
+}
+
+#[test]
+fn test_invalid_conditions_are_rejected() {
+    let (_dir, db_path) = setup_db();
+
+    for bad in [
+        condition("payee", "regex", "(unclosed"),
+        condition("amount", "greater_than", "lots"),
+        condition("amount", "equals", "ten"),
+        condition("amount", "between", "10"),
+        condition("amount", "between", "10,1"),
+        condition("amount", "in", "1,x"),
+        condition("amount", "sign", "up"),
+        condition("payee", "greater_than", "10"),
+        condition("payee", "before", "2024-01-01"),
+        condition("date", "after", "yesterday"),
+        condition("date", "within_days", "-3"),
+        condition("date", "day_of_month", "32"),
+        condition("account", "equals", "Checking"),
+        condition("payee", "sounds_like", "x"),
+        condition("memo", "equals", "x"),
+    ] {
+        let description = format!("{} {} {}", bad.field, bad.operator, bad.value);
+        assert!(
+            create_rule_db(&db_path, params(vec![bad])).is_err(),
+            "{} should be rejected",
+            description
+        );
+    }
+
+    let id = create_rule_db(
+        &db_path,
+        params(vec![condition("payee", "regex", r"amzn\s+mktp")]),
+    )
+    .unwrap();
+    let update = |conditions| {
+        let p = params(conditions);
+        update_rule_db(
+            &db_path,
+            UpdateRuleDbParams {
+                id,
+                priority: p.priority,
+                match_field: p.match_field,
+                match_pattern: p.match_pattern,
+                action_field: p.action_field,
+                action_value: p.action_value,
+                logic: p.logic,
+                conditions: p.conditions,
+                actions: p.actions,
+            },
+        )
+    };
+    assert!(update(vec![condition("payee", "regex", "[a-")]).is_err());
+    assert!(update(vec![condition("amount", "between", "-100, -10")]).is_ok());
+}
+
+#[test]
+fn test_currency_condition_uses_account_currency() {
+    let (_dir, db_path) = setup_db();
+    let account_id = crate::create_account_db(
+        &db_path,
+        "Girokonto".to_string(),
+        0.0,
+        Some("EUR".to_string()),
+        None,
+    )
+    .unwrap()
+    .id;
+    create_rule_db(
+        &db_path,
+        params(vec![
+            condition("currency", "equals", "EUR"),
+            condition("payee", "regex", r"^amzn"),
+        ]),
+    )
+    .unwrap();
+
+    let tx = create_transaction_db(
+        &db_path,
+        CreateTransactionArgs {
+            account_id,
+            date: "2024-03-15".to_string(),
+            payee: "AMZN Mktp DE".to_string(),
+            notes: None,
+            category: None,
+            amount: -20.0,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(tx.category.as_deref(), Some("Shopping"));
+    assert_eq!(tx.currency, None);
+}
+
+#[test]
+fn test_updated_regex_rule_uses_new_pattern() {
+    let (_dir, db_path) = setup_db();
+    let account_id = crate::create_account_db(&db_path, "Checking".to_string(), 0.0, None, None)
+        .unwrap()
+        .id;
+    let id = create_rule_db(
+        &db_path,
+        params(vec![condition("payee", "regex", r"^amzn")]),
+    )
+    .unwrap();
+    let record = |payee: &str| {
+        create_transaction_db(
+            &db_path,
+            CreateTransactionArgs {
+                account_id,
+                date: "2024-03-15".to_string(),
+                payee: payee.to_string(),
+                notes: None,
+                category: None,
+                amount: -20.0,
+                ticker: None,
+                shares: None,
+                price_per_share: None,
+                fee: None,
+                currency: None,
+            },
+        )
+        .unwrap()
+        .category
+    };
+    assert_eq!(record("AMZN Mktp").as_deref(), Some("Shopping"));
+
+    let p = params(vec![condition("payee", "regex", r"^ebay\b")]);
+    update_rule_db(
+        &db_path,
+        UpdateRuleDbParams {
+            id,
+            priority: p.priority,
+            match_field: p.match_field,
+            match_pattern: p.match_pattern,
+            action_field: p.action_field,
+            action_value: p.action_value,
+            logic: p.logic,
+            conditions: p.conditions,
+            actions: p.actions,
+        },
+    )
+    .unwrap();
+    assert_eq!(record("AMZN Mktp"), None);
+    assert_eq!(record("eBay order").as_deref(), Some("Shopping"));
+
+    crate::delete_rule_db(&db_path, id).unwrap();
+    assert_eq!(record("eBay order"), None);
+}
+
+#[test]
+fn test_regex_is_matched_as_stored() {
+    // The trailing space is part of the pattern, both when validated and when matched
+    assert!(matches(condition("payee", "regex", "^amzn ")));
+    assert!(!matches(condition("payee", "regex", "^amzn mktp  ")));
+    assert!(crate::rules::validate_rule_conditions(
+        "and",
+        &[condition("payee", "regex", "^amzn ")]
+    )
+    .is_ok());
+}
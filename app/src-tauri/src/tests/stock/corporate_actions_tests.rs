@@ -0,0 +1,259 @@
+use super::common::setup_db;
+use httpmock::Method::GET;
+use httpmock::MockServer;
+use rusqlite::{params, Connection};
+
+fn buy(db_path: &std::path::PathBuf, account_id: i32, date: &str, shares: f64, price: f64) {
+    crate::create_investment_transaction_db(
+        db_path,
+        crate::CreateInvestmentTransactionArgs {
+            account_id,
+            date: date.to_string(),
+            ticker: "AAPL".to_string(),
+            shares,
+            price_per_share: price,
+            fee: 0.0,
+            is_buy: true,
+            currency: None,
+        },
+    )
+    .unwrap();
+}
+
+fn brokerage(db_path: &std::path::PathBuf) -> i32 {
+    crate::create_account_db(
+        db_path,
+        "Broker".to_string(),
+        10000.0,
+        None,
+        Some("brokerage".to_string()),
+    )
+    .unwrap()
+    .id
+}
+
+#[tokio::test]
+async fn test_history_stores_bars_dividends_and_splits() {
+    let (_dir, db_path) = setup_db();
+    let server = MockServer::start();
+    let m = server.mock(|when, then| {
+        when.method(GET).path("/v8/finance/chart/AAPL");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"chart":{"result":[{
+                "meta":{"symbol":"AAPL"},
+                "timestamp":[1609459200,1609545600],
+                "events":{
+                    "dividends":{"1609459200":{"amount":0.205,"date":1609459200}},
+                    "splits":{"1609545600":{"date":1609545600,"numerator":4,"denominator":1,"splitRatio":"4:1"}}
+                },
+                "indicators":{
+                    "quote":[{"open":[99.0,101.0],"high":[102.0,112.0],"low":[98.0,100.5],"close":[100.0,110.0],"volume":[1000,null]}],
+                    "adjclose":[{"adjclose":[99.5,109.4]}]
+                }}]}}"#);
+    });
+
+    let results = crate::update_daily_stock_prices_with_client_and_base(
+        &db_path,
+        &reqwest::Client::new(),
+        &server.base_url(),
+        vec!["AAPL".to_string()],
+    )
+    .await
+    .unwrap();
+    m.assert_calls(1);
+    assert_eq!(results[0].rows, 2);
+
+    let bars = crate::get_daily_stock_bars_db(&db_path, "AAPL".to_string()).unwrap();
+    assert_eq!(
+        bars[0],
+        crate::DailyBar {
+            date: "2021-01-01".to_string(),
+            open: Some(99.0),
+            high: Some(102.0),
+            low: Some(98.0),
+            close: 100.0,
+            adj_close: Some(99.5),
+            volume: Some(1000.0),
+        }
+    );
+    assert_eq!(bars[1].volume, None);
+
+    // The close-only view is unchanged
+    let prices = crate::get_daily_stock_prices_from_path(&db_path, "AAPL".to_string()).unwrap();
+    assert_eq!(prices[1].price, 110.0);
+
+    let dividends = crate::get_stock_dividends_db(&db_path, Some("aapl".to_string())).unwrap();
+    assert_eq!(dividends.len(), 1);
+    assert_eq!(
+        (dividends[0].date.as_str(), dividends[0].amount),
+        ("2021-01-01", 0.205)
+    );
+
+    let splits = crate::get_stock_splits_db(&db_path, None).unwrap();
+    assert_eq!(
+        splits,
+        vec![crate::StockSplit {
+            ticker: "AAPL".to_string(),
+            date: "2021-01-02".to_string(),
+            numerator: 4.0,
+            denominator: 1.0,
+        }]
+    );
+}
+
+fn chart_with_split(server: &MockServer) -> httpmock::Mock<'_> {
+    server.mock(|when, then| {
+        when.method(GET).path("/v8/finance/chart/AAPL");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"chart":{"result":[{
+                "meta":{"symbol":"AAPL"},
+                "timestamp":[1609459200,1609545600],
+                "events":{
+                    "splits":{"1609545600":{"date":1609545600,"numerator":4,"denominator":1,"splitRatio":"4:1"}}
+                },
+                "indicators":{
+                    "quote":[{"open":[99.0,101.0],"high":[102.0,112.0],"low":[98.0,100.5],"close":[100.0,110.0],"volume":[1000,2000]}],
+                    "adjclose":[{"adjclose":[99.5,109.4]}]
+                }}]}}"#);
+    })
+}
+
+async fn update_aapl(db_path: &std::path::Path, server: &MockServer) {
+    crate::update_daily_stock_prices_with_client_and_base(
+        db_path,
+        &reqwest::Client::new(),
+        &server.base_url(),
+        vec!["AAPL".to_string()],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_new_split_rescales_stored_history() {
+    let (_dir, db_path) = setup_db();
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO daily_stock_prices (ticker, date, price, volume) VALUES ('AAPL', '2020-12-31', 400.0, 500)",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO stock_dividends (ticker, date, amount) VALUES ('AAPL', '2020-12-30', 0.82)",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO stock_event_backfills (ticker, date) VALUES ('AAPL', '2020-12-31')",
+        [],
+    )
+    .unwrap();
+
+    let server = MockServer::start();
+    let m = chart_with_split(&server);
+    update_aapl(&db_path, &server).await;
+    // The events are known now, so a second update leaves the history alone
+    update_aapl(&db_path, &server).await;
+    m.assert_calls(2);
+
+    let bars = crate::get_daily_stock_bars_db(&db_path, "AAPL".to_string()).unwrap();
+    assert_eq!(bars[0].date, "2020-12-31");
+    assert_eq!((bars[0].close, bars[0].volume), (100.0, Some(2000.0)));
+    assert_eq!(bars[1].close, 100.0);
+    let dividends = crate::get_stock_dividends_db(&db_path, Some("AAPL".to_string())).unwrap();
+    assert_eq!(dividends[0].amount, 0.205);
+}
+
+#[tokio::test]
+async fn test_history_without_events_is_backfilled() {
+    let (_dir, db_path) = setup_db();
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO daily_stock_prices (ticker, date, price) VALUES ('AAPL', '2021-01-01', 400.0)",
+        [],
+    )
+    .unwrap();
+
+    let server = MockServer::start();
+    chart_with_split(&server);
+    update_aapl(&db_path, &server).await;
+
+    // The split before the stored history's end is picked up, and the refetched closes
+    // replace the stored ones rather than being rescaled
+    let splits = crate::get_stock_splits_db(&db_path, Some("AAPL".to_string())).unwrap();
+    assert_eq!(splits.len(), 1);
+    let bars = crate::get_daily_stock_bars_db(&db_path, "AAPL".to_string()).unwrap();
+    assert_eq!(
+        (bars[0].date.as_str(), bars[0].close),
+        ("2021-01-01", 100.0)
+    );
+    let backfilled: i64 = conn
+        .query_row("SELECT COUNT(*) FROM stock_event_backfills", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(backfilled, 1);
+}
+
+#[test]
+fn test_holdings_are_split_adjusted() {
+    let (_dir, db_path) = setup_db();
+    let acc = brokerage(&db_path);
+    buy(&db_path, acc, "2024-01-02", 2.0, 400.0);
+    // Bought on the ex-date, so already in post-split shares
+    buy(&db_path, acc, "2024-06-10", 1.0, 100.0);
+
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO stock_splits (ticker, date, numerator, denominator) VALUES ('AAPL', '2024-06-10', 4, 1)",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO stock_prices (ticker, price, last_updated) VALUES (?1, ?2, datetime('now'))",
+        params!["AAPL", 100.0],
+    )
+    .unwrap();
+
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    let a = accounts.iter().find(|a| a.id == acc).unwrap();
+    assert_eq!(a.holdings_value, Some(900.0));
+}
+
+#[test]
+fn test_dividend_suggestions_skip_recorded_income() {
+    let (_dir, db_path) = setup_db();
+    let acc = brokerage(&db_path);
+    buy(&db_path, acc, "2024-01-02", 10.0, 100.0);
+
+    let conn = Connection::open(&db_path).unwrap();
+    for (date, amount) in [
+        ("2023-11-10", 0.24),
+        ("2024-02-09", 0.24),
+        ("2024-05-10", 0.25),
+    ] {
+        conn.execute(
+            "INSERT INTO stock_dividends (ticker, date, amount) VALUES ('AAPL', ?1, ?2)",
+            params![date, amount],
+        )
+        .unwrap();
+    }
+    conn.execute(
+        "INSERT INTO transactions (account_id, date, payee, amount, ticker) VALUES (?1, '2024-02-15', 'Dividend', 2.4, 'AAPL')",
+        params![acc],
+    )
+    .unwrap();
+
+    let suggestions = crate::get_dividend_suggestions_db(&db_path, Some(acc)).unwrap();
+    assert_eq!(suggestions.len(), 1);
+    let s = &suggestions[0];
+    assert_eq!((s.account_id, s.ticker.as_str()), (acc, "AAPL"));
+    assert_eq!(s.date, "2024-05-10");
+    assert_eq!((s.shares, s.amount), (10.0, 2.5));
+
+    assert!(crate::get_dividend_suggestions_db(&db_path, Some(acc + 1))
+        .unwrap()
+        .is_empty());
+}
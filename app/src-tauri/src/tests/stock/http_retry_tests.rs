@@ -0,0 +1,86 @@
+use super::common::setup_db;
+use httpmock::Method::GET;
+use httpmock::MockServer;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_batch_update_reports_each_ticker() {
+    let (_dir, db_path) = setup_db();
+    let server = MockServer::start();
+    let ok = server.mock(|when, then| {
+        when.method(GET).path("/v8/finance/chart/FOO");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"chart":{"result":[{"meta":{"symbol":"FOO"},"timestamp":[1609459200,1609545600],"indicators":{"quote":[{"close":[100.0,110.0]}]}}]}}"#);
+    });
+    let busy = server.mock(|when, then| {
+        when.method(GET).path("/v8/finance/chart/BUSY");
+        then.status(503);
+    });
+    let missing = server.mock(|when, then| {
+        when.method(GET).path("/v8/finance/chart/GONE");
+        then.status(404);
+    });
+
+    let client = reqwest::Client::new();
+    let results = crate::update_daily_stock_prices_with_client_and_base(
+        &db_path,
+        &client,
+        &server.base_url(),
+        vec!["BUSY".to_string(), "FOO".to_string(), "GONE".to_string()],
+    )
+    .await
+    .unwrap();
+
+    // 5xx is retried with backoff, 404 is final
+    busy.assert_calls(4);
+    missing.assert_calls(1);
+    ok.assert_calls(1);
+
+    let tickers: Vec<&str> = results.iter().map(|r| r.ticker.as_str()).collect();
+    assert_eq!(tickers, vec!["BUSY", "FOO", "GONE"]);
+    assert!(results[0].error.as_deref().unwrap().contains("503"));
+    assert_eq!(results[0].rows, 0);
+    assert_eq!((results[1].rows, results[1].error.is_none()), (2, true));
+    assert_eq!((results[2].rows, results[2].error.is_none()), (0, true));
+}
+
+#[tokio::test]
+async fn test_rate_limited_request_is_retried() {
+    let server = MockServer::start();
+    let limited = server.mock(|when, then| {
+        when.method(GET).path("/limited");
+        then.status(429).header("Retry-After", "0");
+    });
+
+    let client = crate::http::client().unwrap();
+    let resp = crate::http::send_with_retry(client.get(format!("{}/limited", server.base_url())))
+        .await
+        .unwrap();
+    limited.assert_calls(4);
+    assert_eq!(resp.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_run_bounded_limits_concurrency_and_keeps_order() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let results = crate::http::run_bounded((0..20).collect(), |i: usize| {
+        let running = running.clone();
+        let peak = peak.clone();
+        async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            i * 2
+        }
+    })
+    .await;
+
+    let values: Vec<usize> = results.into_iter().map(|r| r.unwrap()).collect();
+    assert_eq!(values, (0..20).map(|i| i * 2).collect::<Vec<_>>());
+    assert!(peak.load(Ordering::SeqCst) <= crate::http::MAX_CONCURRENT_REQUESTS);
+}
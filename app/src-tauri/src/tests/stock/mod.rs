@@ -2,7 +2,11 @@ pub use super::common;
 
 pub mod all_network_fail_no_db;
 pub mod concurrency_stress;
+pub mod corporate_actions_tests;
 pub mod daily_prices_tests;
+pub mod http_retry_tests;
+pub mod providers_tests;
+pub mod quote_cache_tests;
 pub mod stock_http_mock_tests;
 pub mod stock_http_tests;
 pub mod stock_parsing_tests;
@@ -0,0 +1,225 @@
+use super::common::setup_db;
+use httpmock::Method::GET;
+use httpmock::MockServer;
+use std::sync::Arc;
+
+fn http_config(name: &str, base_url: &str, format: &str) -> crate::MarketDataProviderConfig {
+    crate::MarketDataProviderConfig {
+        id: 0,
+        name: name.to_string(),
+        kind: "http".to_string(),
+        base_url: base_url.to_string(),
+        format: format.to_string(),
+        quote_path: Some("/quote/{ticker}".to_string()),
+        history_path: Some("/history/{ticker}?from={from}&to={to}".to_string()),
+        search_path: Some("/search?q={query}".to_string()),
+        fx_ticker: Some("{currency}USD".to_string()),
+        priority: 0,
+    }
+}
+
+fn http_provider(name: &str, base_url: &str, format: &str) -> Arc<dyn crate::MarketDataProvider> {
+    Arc::new(crate::HttpProvider::new(
+        reqwest::Client::new(),
+        http_config(name, base_url, format),
+    ))
+}
+
+#[tokio::test]
+async fn test_http_provider_reads_json_and_csv() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/quote/FOO");
+        then.status(200)
+            .body(r#"{"symbol":"FOO","price":12.5,"currency":"EUR","change_percent":1.5}"#);
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/quote/BAR");
+        then.status(200)
+            .body("symbol,price,currency\nBAR,7.25,USD\n");
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/history/FOO");
+        then.status(200)
+            .body("date,close\n2021-01-04,11.0\n2021-01-01,10.0\n2020-12-01,9.0\n");
+    });
+
+    let json = http_provider("local", &server.base_url(), "json");
+    let quote = json.quote("FOO").await.unwrap().unwrap();
+    assert_eq!(quote.price, 12.5);
+    assert_eq!(quote.currency.as_deref(), Some("EUR"));
+    assert_eq!(quote.change_percent, 1.5);
+
+    let csv = http_provider("local", &server.base_url(), "csv");
+    let quote = csv.quote("BAR").await.unwrap().unwrap();
+    assert_eq!(quote.symbol, "BAR");
+    assert_eq!(quote.price, 7.25);
+
+    // 2021-01-01 .. 2021-01-05, rows outside the range are dropped and the rest sorted
+    let closes = csv
+        .history("FOO", 1609459200, 1609804800)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        closes,
+        vec![
+            ("2021-01-01".to_string(), 10.0),
+            ("2021-01-04".to_string(), 11.0)
+        ]
+    );
+    assert_eq!(csv.fx_ticker("EUR"), "EURUSD");
+}
+
+#[tokio::test]
+async fn test_registry_falls_back_when_yahoo_fails() {
+    let (_dir, db_path) = setup_db();
+    let yahoo = MockServer::start();
+    let local = MockServer::start();
+    yahoo.mock(|when, then| {
+        when.method(GET).path("/v8/finance/chart/FOO");
+        then.status(500);
+    });
+    local.mock(|when, then| {
+        when.method(GET).path("/quote/FOO");
+        then.status(200).body(r#"{"symbol":"FOO","price":42.0}"#);
+    });
+
+    let registry = Arc::new(crate::ProviderRegistry::new(vec![
+        Arc::new(crate::YahooProvider::new(
+            reqwest::Client::new(),
+            &yahoo.base_url(),
+        )),
+        http_provider("local", &local.base_url(), "json"),
+    ]));
+    let quotes = crate::get_stock_quotes_with_registry(registry, &db_path, vec!["FOO".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(quotes.len(), 1);
+    assert_eq!(quotes[0].price, 42.0);
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let stored: f64 = conn
+        .query_row(
+            "SELECT price FROM stock_prices WHERE ticker = 'FOO'",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(stored, 42.0);
+}
+
+#[tokio::test]
+async fn test_ticker_preference_is_tried_first() {
+    let (_dir, db_path) = setup_db();
+    let yahoo = MockServer::start();
+    let local = MockServer::start();
+    let yahoo_bar = yahoo.mock(|when, then| {
+        when.method(GET).path("/v8/finance/chart/BAR");
+        then.status(200)
+            .body(r#"{"chart":{"result":[{"meta":{"symbol":"BAR","regularMarketPrice":1.0}}]}}"#);
+    });
+    local.mock(|when, then| {
+        when.method(GET).path("/quote/BAR");
+        then.status(200).body(r#"{"symbol":"BAR","price":2.0}"#);
+    });
+
+    crate::save_market_data_provider_db(&db_path, http_config("local", &local.base_url(), "json"))
+        .unwrap();
+    crate::set_ticker_providers_db(&db_path, "bar".to_string(), vec!["local".to_string()]).unwrap();
+
+    let mut registry = crate::ProviderRegistry::new(vec![
+        Arc::new(crate::YahooProvider::new(
+            reqwest::Client::new(),
+            &yahoo.base_url(),
+        )),
+        http_provider("local", &local.base_url(), "json"),
+    ]);
+    let preferred = crate::get_ticker_providers_db(&db_path, "BAR".to_string()).unwrap();
+    registry.set_ticker_providers("BAR", preferred);
+    let chain: Vec<String> = registry
+        .chain_for("BAR")
+        .iter()
+        .map(|p| p.name().to_string())
+        .collect();
+    assert_eq!(chain, vec!["local", "yahoo"]);
+
+    let quotes = crate::get_stock_quotes_with_registry(
+        Arc::new(registry),
+        &db_path,
+        vec!["BAR".to_string()],
+    )
+    .await
+    .unwrap();
+    assert_eq!(quotes[0].price, 2.0);
+    yahoo_bar.assert_calls(0);
+}
+
+#[test]
+fn test_provider_configuration_is_validated_and_loaded_in_order() {
+    let (_dir, db_path) = setup_db();
+
+    let mut bad = http_config("broken", "ftp://example.com", "json");
+    assert!(crate::save_market_data_provider_db(&db_path, bad.clone()).is_err());
+    bad.base_url = "http://localhost:9".to_string();
+    bad.format = "xml".to_string();
+    assert!(crate::save_market_data_provider_db(&db_path, bad).is_err());
+    assert!(crate::save_market_data_provider_db(
+        &db_path,
+        http_config("yahoo", "http://localhost:9", "json")
+    )
+    .is_err());
+
+    let mut second = http_config("second", "http://localhost:9", "csv");
+    second.priority = 2;
+    crate::save_market_data_provider_db(&db_path, second).unwrap();
+    let mut first = http_config("first", "http://localhost:9", "json");
+    first.priority = 1;
+    let saved = crate::save_market_data_provider_db(&db_path, first.clone()).unwrap();
+    // Saving again under the same name updates in place
+    let again = crate::save_market_data_provider_db(&db_path, first).unwrap();
+    assert_eq!(saved.id, again.id);
+
+    assert!(
+        crate::set_ticker_providers_db(&db_path, "X".to_string(), vec!["nope".to_string()])
+            .is_err()
+    );
+
+    let registry = crate::ProviderRegistry::load(&db_path, reqwest::Client::new()).unwrap();
+    let names: Vec<&str> = registry.providers().iter().map(|p| p.name()).collect();
+    // Yahoo stays available after the configured providers
+    assert_eq!(names, vec!["first", "second", "yahoo"]);
+
+    crate::delete_market_data_provider_db(&db_path, "second".to_string()).unwrap();
+    assert_eq!(
+        crate::get_market_data_providers_db(&db_path).unwrap().len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_fx_history_uses_provider_symbol() {
+    let (_dir, db_path) = setup_db();
+    let local = MockServer::start();
+    let m = local.mock(|when, then| {
+        when.method(GET).path("/history/EURUSD");
+        then.status(200)
+            .body(r#"[{"date":"2021-01-04","close":1.23}]"#);
+    });
+
+    let registry =
+        crate::ProviderRegistry::new(vec![http_provider("local", &local.base_url(), "json")]);
+    crate::update_daily_fx_rates_with_registry(&db_path, &registry, vec!["EUR".to_string()])
+        .await
+        .unwrap();
+    m.assert_calls(1);
+
+    let rate = crate::get_fx_rate_db(
+        &db_path,
+        "EUR".to_string(),
+        "USD".to_string(),
+        Some("2021-01-05".to_string()),
+    )
+    .unwrap();
+    assert_eq!(rate, 1.23);
+}
@@ -0,0 +1,162 @@
+use super::common::setup_db;
+use httpmock::Method::GET;
+use httpmock::MockServer;
+use std::sync::Arc;
+
+fn mock_chart<'a>(server: &'a MockServer, ticker: &str, price: f64) -> httpmock::Mock<'a> {
+    server.mock(|when, then| {
+        when.method(GET).path(format!("/v8/finance/chart/{}", ticker));
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(format!(
+                r#"{{"chart":{{"result":[{{"meta":{{"symbol":"{}","regularMarketPrice":{},"chartPreviousClose":100.0,"currency":"USD","instrumentType":"EQUITY"}}}}]}}}}"#,
+                ticker, price
+            ));
+    })
+}
+
+fn settings(ttl_seconds: u64, offline: bool) -> crate::QuoteCacheSettings {
+    crate::QuoteCacheSettings {
+        ttl_seconds,
+        offline,
+    }
+}
+
+#[tokio::test]
+async fn test_fresh_quotes_are_served_without_request() {
+    let (_dir, db_path) = setup_db();
+    let server = MockServer::start();
+    let m = mock_chart(&server, "FOO", 110.0);
+    let registry = Arc::new(crate::ProviderRegistry::yahoo(
+        reqwest::Client::new(),
+        &server.base_url(),
+    ));
+
+    let first = crate::get_stock_quotes_with_policy(
+        registry.clone(),
+        &db_path,
+        vec!["FOO".to_string()],
+        &settings(900, false),
+    )
+    .await
+    .unwrap();
+    assert!(first[0].last_updated.is_some());
+
+    let second = crate::get_stock_quotes_with_policy(
+        registry.clone(),
+        &db_path,
+        vec!["foo".to_string()],
+        &settings(900, false),
+    )
+    .await
+    .unwrap();
+    m.assert_calls(1);
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].symbol, "FOO");
+    assert!((second[0].change_percent - 10.0).abs() < 1e-9);
+    assert_eq!(second[0].currency.as_deref(), Some("USD"));
+    assert_eq!(second[0].quote_type.as_deref(), Some("EQUITY"));
+    assert_eq!(second[0].last_updated, first[0].last_updated);
+
+    // A zero TTL always refetches
+    crate::get_stock_quotes_with_policy(
+        registry,
+        &db_path,
+        vec!["FOO".to_string()],
+        &settings(0, false),
+    )
+    .await
+    .unwrap();
+    m.assert_calls(2);
+}
+
+#[tokio::test]
+async fn test_expired_quotes_are_refetched() {
+    let (_dir, db_path) = setup_db();
+    let server = MockServer::start();
+    let m = mock_chart(&server, "FOO", 120.0);
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO stock_prices (ticker, price, last_updated) VALUES ('FOO', 42.0, datetime('now', '-2 hours'))",
+        [],
+    )
+    .unwrap();
+
+    let quotes = crate::get_stock_quotes_with_policy(
+        Arc::new(crate::ProviderRegistry::yahoo(
+            reqwest::Client::new(),
+            &server.base_url(),
+        )),
+        &db_path,
+        vec!["FOO".to_string()],
+        &settings(3600, false),
+    )
+    .await
+    .unwrap();
+    m.assert_calls(1);
+    assert_eq!(quotes.len(), 1);
+    assert!((quotes[0].price - 120.0).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_offline_mode_never_requests() {
+    let (_dir, db_path) = setup_db();
+    let server = MockServer::start();
+    let m = mock_chart(&server, "FOO", 120.0);
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO stock_prices (ticker, price, last_updated) VALUES ('FOO', 42.0, '2020-01-01 00:00:00')",
+        [],
+    )
+    .unwrap();
+
+    let quotes = crate::get_stock_quotes_with_policy(
+        Arc::new(crate::ProviderRegistry::yahoo(
+            reqwest::Client::new(),
+            &server.base_url(),
+        )),
+        &db_path,
+        vec!["FOO".to_string(), "BAR".to_string()],
+        &settings(0, true),
+    )
+    .await
+    .unwrap();
+    m.assert_calls(0);
+    assert_eq!(quotes.len(), 1);
+    assert!((quotes[0].price - 42.0).abs() < 1e-9);
+    assert_eq!(
+        quotes[0].last_updated.as_deref(),
+        Some("2020-01-01 00:00:00")
+    );
+}
+
+#[test]
+fn test_search_known_tickers_offline() {
+    let (_dir, db_path) = setup_db();
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO stock_prices (ticker, price, currency, last_updated) VALUES ('AAPL', 1.0, 'USD', datetime('now'))",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO accounts (id, name, balance) VALUES (1, 'Brokerage', 0.0)",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO transactions (account_id, date, payee, amount, ticker, shares) VALUES (1, '2024-01-01', 'Buy', -10.0, 'aapl', 1.0), (1, '2024-01-02', 'Buy', -10.0, 'MSFT', 1.0)",
+        [],
+    )
+    .unwrap();
+
+    let results = crate::search_known_tickers_db(&db_path, "a").unwrap();
+    let symbols: Vec<&str> = results.iter().map(|r| r.symbol.as_str()).collect();
+    assert_eq!(symbols, vec!["AAPL"]);
+    assert_eq!(results[0].currency.as_deref(), Some("USD"));
+
+    let all = crate::search_known_tickers_db(&db_path, "").unwrap();
+    assert_eq!(all.len(), 2);
+}
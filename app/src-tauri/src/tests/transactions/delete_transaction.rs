@@ -4,7 +4,8 @@ use rusqlite::{params, Connection};
 #[test]
 fn test_delete_transaction() {
     let (_dir, db_path) = setup_db();
-    let account = crate::create_account_db(&db_path, "Test".to_string(), 100.0, None).unwrap();
+    let account =
+        crate::create_account_db(&db_path, "Test".to_string(), 100.0, None, None).unwrap();
     let tx = crate::create_transaction_db(
         &db_path,
         crate::CreateTransactionArgs {
@@ -23,7 +24,7 @@ fn test_delete_transaction() {
     )
     .unwrap();
 
-    crate::delete_transaction_db(&db_path, tx.id).unwrap();
+    crate::delete_transaction_db(&db_path, tx.id, false).unwrap();
 
     let accounts = crate::get_accounts_db(&db_path).unwrap();
     assert_eq!(accounts[0].balance, 100.0);
@@ -32,8 +33,8 @@ fn test_delete_transaction() {
 #[test]
 fn test_delete_transaction_deletes_linked_counterpart() {
     let (_dir, db_path) = setup_db();
-    let acc1 = crate::create_account_db(&db_path, "A1".to_string(), 100.0, None).unwrap();
-    let acc2 = crate::create_account_db(&db_path, "A2".to_string(), 0.0, None).unwrap();
+    let acc1 = crate::create_account_db(&db_path, "A1".to_string(), 100.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "A2".to_string(), 0.0, None, None).unwrap();
 
     // Create a transfer via API which should link txs
     let tx = crate::create_transaction_db(
@@ -43,7 +44,7 @@ fn test_delete_transaction_deletes_linked_counterpart() {
             date: "2023-01-01".to_string(),
             payee: acc2.name.clone(),
             notes: None,
-            category: None,
+            category: Some("Transfer".to_string()),
             amount: -30.0,
             ticker: None,
             shares: None,
@@ -74,7 +75,7 @@ fn test_delete_transaction_deletes_linked_counterpart() {
     assert_eq!(a2_before, 30.0);
 
     // Delete the first transaction
-    crate::delete_transaction_db(&db_path, tx.id).unwrap();
+    crate::delete_transaction_db(&db_path, tx.id, false).unwrap();
 
     // After delete, both transactions should be gone and balances restored
     let txs1 = crate::get_transactions_db(&db_path, acc1.id).unwrap();
@@ -104,10 +105,10 @@ fn test_delete_transaction_deletes_linked_counterpart() {
 }
 
 #[test]
-fn test_delete_transaction_fallback_by_notes() {
+fn test_delete_transaction_does_not_match_counterpart_by_notes() {
     let (_dir, db_path) = setup_db();
-    let acc1 = crate::create_account_db(&db_path, "Acc1".to_string(), 100.0, None).unwrap();
-    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None).unwrap();
+    let acc1 = crate::create_account_db(&db_path, "Acc1".to_string(), 100.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None, None).unwrap();
 
     // Insert two transactions manually with matching notes but no linked_tx_id
     let conn = Connection::open(&db_path).unwrap();
@@ -135,14 +136,14 @@ fn test_delete_transaction_fallback_by_notes() {
     )
     .unwrap();
 
-    // Now delete tx1 (it has no linked_tx_id but notes match), delete should remove both
-    crate::delete_transaction_db(&db_path, tx1_id).unwrap();
+    // tx1 has no linked_tx_id; matching notes alone must not delete tx2
+    crate::delete_transaction_db(&db_path, tx1_id, false).unwrap();
 
     let txs1 = crate::get_transactions_db(&db_path, acc1.id).unwrap();
     let txs2 = crate::get_transactions_db(&db_path, acc2.id).unwrap();
 
     assert!(txs1.iter().all(|t| t.id != tx1_id));
-    assert!(txs2.iter().all(|t| t.id != tx2_id));
+    assert!(txs2.iter().any(|t| t.id == tx2_id));
 
     let accounts_after = crate::get_accounts_db(&db_path).unwrap();
     let a1_after = accounts_after
@@ -157,12 +158,12 @@ fn test_delete_transaction_fallback_by_notes() {
         .balance;
 
     assert_eq!(a1_after, 100.0);
-    assert_eq!(a2_after, 0.0);
+    assert_eq!(a2_after, 20.0);
 }
 
 #[test]
 fn test_delete_transaction_missing_id_should_error() {
     let (_dir, db_path) = setup_db();
-    let res = crate::delete_transaction_db(&db_path, -999);
+    let res = crate::delete_transaction_db(&db_path, -999, false);
     assert!(res.is_err());
 }
@@ -3,8 +3,8 @@ use super::common::setup_db;
 #[test]
 fn test_update_transaction_move_between_accounts() {
     let (_dir, db_path) = setup_db();
-    let acc1 = crate::create_account_db(&db_path, "From".to_string(), 100.0, None).unwrap();
-    let acc2 = crate::create_account_db(&db_path, "To".to_string(), 50.0, None).unwrap();
+    let acc1 = crate::create_account_db(&db_path, "From".to_string(), 100.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "To".to_string(), 50.0, None, None).unwrap();
 
     // Create a simple non-transfer transaction in acc1
     let tx = crate::create_transaction_db(
@@ -35,6 +35,7 @@ fn test_update_transaction_move_between_accounts() {
         category: Some("Misc".to_string()),
         amount: -20.0,
         currency: None,
+        override_reconciled: false,
     };
 
     crate::update_transaction_db(&db_path, args).unwrap();
@@ -58,7 +59,7 @@ fn test_update_transaction_move_between_accounts() {
 #[test]
 fn test_create_transaction_payee_same_account_name_no_transfer_created() {
     let (_dir, db_path) = setup_db();
-    let acc = crate::create_account_db(&db_path, "SelfAcc".to_string(), 100.0, None).unwrap();
+    let acc = crate::create_account_db(&db_path, "SelfAcc".to_string(), 100.0, None, None).unwrap();
 
     // Create transaction where payee equals the same account name - should NOT create transfer
     let tx = crate::create_transaction_db(
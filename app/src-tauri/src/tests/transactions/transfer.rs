@@ -3,8 +3,8 @@ use super::common::setup_db;
 #[test]
 fn test_transfer() {
     let (_dir, db_path) = setup_db();
-    let acc1 = crate::create_account_db(&db_path, "Acc1".to_string(), 100.0, None).unwrap();
-    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None).unwrap();
+    let acc1 = crate::create_account_db(&db_path, "Acc1".to_string(), 100.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None, None).unwrap();
 
     // Transfer 50 from Acc1 to Acc2
     // Payee should be "Acc2"
@@ -15,7 +15,7 @@ fn test_transfer() {
             date: "2023-01-01".to_string(),
             payee: "Acc2".to_string(),
             notes: None,
-            category: None,
+            category: Some("Transfer".to_string()),
             amount: -50.0,
             ticker: None,
             shares: None,
@@ -4,7 +4,8 @@ use rusqlite::{params, Connection};
 #[test]
 fn test_update_transaction() {
     let (_dir, db_path) = setup_db();
-    let account = crate::create_account_db(&db_path, "Test".to_string(), 100.0, None).unwrap();
+    let account =
+        crate::create_account_db(&db_path, "Test".to_string(), 100.0, None, None).unwrap();
     let tx = crate::create_transaction_db(
         &db_path,
         crate::CreateTransactionArgs {
@@ -32,6 +33,7 @@ fn test_update_transaction() {
         category: Some("Food".to_string()),
         amount: -20.0,
         currency: None,
+        override_reconciled: false,
     };
 
     crate::update_transaction_db(&db_path, args).unwrap();
@@ -53,6 +55,7 @@ fn test_update_transaction_missing_id_should_error() {
         category: None,
         amount: 10.0,
         currency: None,
+        override_reconciled: false,
     };
 
     let res = crate::update_transaction_db(&db_path, args);
@@ -60,10 +63,10 @@ fn test_update_transaction_missing_id_should_error() {
 }
 
 #[test]
-fn test_update_transaction_finds_counterpart_by_notes() {
+fn test_update_transaction_does_not_match_counterpart_by_notes() {
     let (_dir, db_path) = setup_db();
-    let acc1 = crate::create_account_db(&db_path, "Acc1".to_string(), 100.0, None).unwrap();
-    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None).unwrap();
+    let acc1 = crate::create_account_db(&db_path, "Acc1".to_string(), 100.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None, None).unwrap();
 
     // Insert two transactions manually without linked_tx_id but with matching notes
     let conn = Connection::open(&db_path).unwrap();
@@ -77,7 +80,6 @@ fn test_update_transaction_finds_counterpart_by_notes() {
         "INSERT INTO transactions (account_id, date, payee, notes, category, amount) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         params![acc2.id, "2023-01-01", acc1.name, "XFER", "Transfer", 50.0],
     ).unwrap();
-    let _tx2_id = conn.last_insert_rowid() as i32;
 
     // Adjust account balances to reflect those transactions
     conn.execute(
@@ -91,7 +93,7 @@ fn test_update_transaction_finds_counterpart_by_notes() {
     )
     .unwrap();
 
-    // Now update tx1 amount to -60.0 using update_transaction_db which should find counterpart by notes
+    // Identical notes alone must not tie the two rows together
     let args = crate::UpdateTransactionArgs {
         id: tx1_id,
         account_id: acc1.id,
@@ -101,30 +103,28 @@ fn test_update_transaction_finds_counterpart_by_notes() {
         category: Some("Transfer".to_string()),
         amount: -60.0,
         currency: None,
+        override_reconciled: false,
     };
 
     crate::update_transaction_db(&db_path, args).unwrap();
 
-    // Verify balances updated: acc1: 100 - 60 = 40, acc2: 0 + 60 = 60
     let accounts = crate::get_accounts_db(&db_path).unwrap();
     let a1 = accounts.iter().find(|a| a.id == acc1.id).unwrap();
     let a2 = accounts.iter().find(|a| a.id == acc2.id).unwrap();
 
     assert_eq!(a1.balance, 40.0);
-    assert_eq!(a2.balance, 60.0);
+    assert_eq!(a2.balance, 50.0);
 
-    // Verify counterpart transaction amount updated to 60.0 and payee set to source account name
     let txs2 = crate::get_transactions_db(&db_path, acc2.id).unwrap();
     assert_eq!(txs2.len(), 1);
-    assert_eq!(txs2[0].amount, 60.0);
-    assert_eq!(txs2[0].payee, acc1.name);
+    assert_eq!(txs2[0].amount, 50.0);
 }
 
 #[test]
 fn test_update_transaction_updates_counterpart_when_linked() {
     let (_dir, db_path) = setup_db();
-    let acc1 = crate::create_account_db(&db_path, "Acc1".to_string(), 100.0, None).unwrap();
-    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None).unwrap();
+    let acc1 = crate::create_account_db(&db_path, "Acc1".to_string(), 100.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "Acc2".to_string(), 0.0, None, None).unwrap();
 
     // Create transfer via API which should link txs
     let tx = crate::create_transaction_db(
@@ -134,7 +134,7 @@ fn test_update_transaction_updates_counterpart_when_linked() {
             date: "2023-01-01".to_string(),
             payee: acc2.name.clone(),
             notes: Some("X".to_string()),
-            category: None,
+            category: Some("Transfer".to_string()),
             amount: -40.0,
             ticker: None,
             shares: None,
@@ -159,6 +159,7 @@ fn test_update_transaction_updates_counterpart_when_linked() {
         category: Some("Transfer".to_string()),
         amount: -50.0,
         currency: None,
+        override_reconciled: false,
     };
 
     crate::update_transaction_db(&db_path, args).unwrap();
@@ -179,7 +180,7 @@ fn test_update_transaction_updates_counterpart_when_linked() {
 #[test]
 fn test_update_transaction_no_amount_change_doesnt_alter_balances() {
     let (_dir, db_path) = setup_db();
-    let account = crate::create_account_db(&db_path, "T".to_string(), 100.0, None).unwrap();
+    let account = crate::create_account_db(&db_path, "T".to_string(), 100.0, None, None).unwrap();
     let tx = crate::create_transaction_db(
         &db_path,
         crate::CreateTransactionArgs {
@@ -207,6 +208,7 @@ fn test_update_transaction_no_amount_change_doesnt_alter_balances() {
         category: Some("Misc".to_string()),
         amount: -20.0,
         currency: None,
+        override_reconciled: false,
     };
 
     crate::update_transaction_db(&db_path, args).unwrap();
@@ -0,0 +1,133 @@
+use super::common::setup_db;
+
+fn args(from: i32, to: i32, amount_from: f64) -> crate::CreateTransferArgs {
+    crate::CreateTransferArgs {
+        from_account_id: from,
+        to_account_id: to,
+        amount_from,
+        amount_to: None,
+        date: "2024-03-01".to_string(),
+        fee: None,
+        notes: None,
+    }
+}
+
+fn balance(db_path: &std::path::PathBuf, id: i32) -> f64 {
+    crate::get_accounts_db(db_path)
+        .unwrap()
+        .into_iter()
+        .find(|a| a.id == id)
+        .unwrap()
+        .balance
+}
+
+#[test]
+fn test_create_transfer_links_both_legs() {
+    let (_dir, db_path) = setup_db();
+    let acc1 =
+        crate::create_account_db(&db_path, "Checking".to_string(), 500.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "Savings".to_string(), 0.0, None, None).unwrap();
+
+    let transfer = crate::create_transfer_db(&db_path, args(acc1.id, acc2.id, 200.0)).unwrap();
+
+    assert_eq!(transfer.from.amount, -200.0);
+    assert_eq!(transfer.to.amount, 200.0);
+    assert_eq!(transfer.from.transfer_id, Some(transfer.id));
+    assert_eq!(transfer.to.transfer_id, Some(transfer.id));
+    assert_eq!(transfer.from.category.as_deref(), Some("Transfer"));
+    assert_eq!(transfer.to.payee, "Checking");
+    assert_eq!(balance(&db_path, acc1.id), 300.0);
+    assert_eq!(balance(&db_path, acc2.id), 200.0);
+
+    let loaded = crate::get_transfer_db(&db_path, transfer.id).unwrap();
+    assert_eq!(loaded.from.id, transfer.from.id);
+    assert_eq!(loaded.to.id, transfer.to.id);
+}
+
+#[test]
+fn test_create_transfer_with_fee_and_different_amounts() {
+    let (_dir, db_path) = setup_db();
+    let usd = crate::create_account_db(
+        &db_path,
+        "USD".to_string(),
+        1000.0,
+        Some("USD".to_string()),
+        None,
+    )
+    .unwrap();
+    let eur = crate::create_account_db(
+        &db_path,
+        "EUR".to_string(),
+        0.0,
+        Some("EUR".to_string()),
+        None,
+    )
+    .unwrap();
+
+    let mut a = args(usd.id, eur.id, 100.0);
+    a.amount_to = Some(92.0);
+    a.fee = Some(2.5);
+    let transfer = crate::create_transfer_db(&db_path, a).unwrap();
+
+    assert_eq!(transfer.fee, 2.5);
+    assert_eq!(transfer.from.amount, -102.5);
+    assert_eq!(transfer.from.fee, Some(2.5));
+    assert_eq!(transfer.from.currency.as_deref(), Some("USD"));
+    assert_eq!(transfer.to.amount, 92.0);
+    assert_eq!(transfer.to.currency.as_deref(), Some("EUR"));
+    assert_eq!(balance(&db_path, usd.id), 897.5);
+    assert_eq!(balance(&db_path, eur.id), 92.0);
+}
+
+#[test]
+fn test_create_transfer_rejects_invalid_input() {
+    let (_dir, db_path) = setup_db();
+    let acc1 = crate::create_account_db(&db_path, "A".to_string(), 100.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "B".to_string(), 0.0, None, None).unwrap();
+
+    assert!(crate::create_transfer_db(&db_path, args(acc1.id, acc1.id, 10.0)).is_err());
+    assert!(crate::create_transfer_db(&db_path, args(acc1.id, acc2.id, -10.0)).is_err());
+    assert!(crate::create_transfer_db(&db_path, args(acc1.id, 9999, 10.0)).is_err());
+    let mut negative_fee = args(acc1.id, acc2.id, 10.0);
+    negative_fee.fee = Some(-1.0);
+    assert!(crate::create_transfer_db(&db_path, negative_fee).is_err());
+
+    // Nothing was written
+    assert!(crate::get_all_transactions_db(&db_path)
+        .unwrap()
+        .iter()
+        .all(|t| t.category.as_deref() != Some("Transfer")));
+    assert_eq!(balance(&db_path, acc1.id), 100.0);
+}
+
+#[test]
+fn test_payee_matching_account_name_is_not_a_transfer() {
+    let (_dir, db_path) = setup_db();
+    let acc1 = crate::create_account_db(&db_path, "A".to_string(), 100.0, None, None).unwrap();
+    // A merchant that happens to share its name with an account
+    let acc2 = crate::create_account_db(&db_path, "Amazon".to_string(), 0.0, None, None).unwrap();
+
+    let tx = crate::create_transaction_db(
+        &db_path,
+        crate::CreateTransactionArgs {
+            account_id: acc1.id,
+            date: "2024-03-01".to_string(),
+            payee: "Amazon".to_string(),
+            notes: None,
+            category: Some("Shopping".to_string()),
+            amount: -25.0,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(tx.transfer_id, None);
+    assert!(crate::get_transactions_db(&db_path, acc2.id)
+        .unwrap()
+        .is_empty());
+    assert_eq!(balance(&db_path, acc2.id), 0.0);
+}
@@ -0,0 +1,259 @@
+use super::common::{set_daily_rate, setup_db};
+
+fn transfer_tx(
+    db_path: &std::path::PathBuf,
+    account_id: i32,
+    payee: &str,
+    amount: f64,
+) -> Result<crate::Transaction, String> {
+    crate::create_transaction_db(
+        db_path,
+        crate::CreateTransactionArgs {
+            account_id,
+            date: "2024-03-01".to_string(),
+            payee: payee.to_string(),
+            notes: None,
+            category: Some("Transfer".to_string()),
+            amount,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+}
+
+#[test]
+fn test_transfer_records_each_leg_in_account_currency() {
+    let (_dir, db_path) = setup_db();
+    let eur = crate::create_account_db(
+        &db_path,
+        "Euro".to_string(),
+        0.0,
+        Some("EUR".to_string()),
+        None,
+    )
+    .unwrap();
+    let usd = crate::create_account_db(
+        &db_path,
+        "Dollar".to_string(),
+        0.0,
+        Some("USD".to_string()),
+        None,
+    )
+    .unwrap();
+    set_daily_rate(&db_path, "EUR", "2024-02-29", 1.1);
+
+    let tx = transfer_tx(&db_path, eur.id, "Dollar", -100.0).unwrap();
+    assert_eq!(tx.currency.as_deref(), Some("EUR"));
+
+    let transfer = crate::get_transfer_db(&db_path, tx.transfer_id.unwrap()).unwrap();
+    assert_eq!(transfer.to.amount, 110.0);
+    assert_eq!(transfer.to.currency.as_deref(), Some("USD"));
+    assert!((transfer.exchange_rate - 1.1).abs() < 1e-9);
+
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    assert_eq!(
+        accounts.iter().find(|a| a.id == eur.id).unwrap().balance,
+        -100.0
+    );
+    assert_eq!(
+        accounts.iter().find(|a| a.id == usd.id).unwrap().balance,
+        110.0
+    );
+
+    // Editing the source leg converts the counterpart at the recorded rate
+    crate::update_transaction_db(
+        &db_path,
+        crate::UpdateTransactionArgs {
+            id: tx.id,
+            account_id: eur.id,
+            date: "2024-03-01".to_string(),
+            payee: "Dollar".to_string(),
+            notes: None,
+            category: Some("Transfer".to_string()),
+            amount: -200.0,
+            currency: Some("EUR".to_string()),
+            override_reconciled: false,
+        },
+    )
+    .unwrap();
+    let to = crate::get_transactions_db(&db_path, usd.id).unwrap();
+    assert_eq!(to[0].amount, 220.0);
+    assert_eq!(to[0].currency.as_deref(), Some("USD"));
+}
+
+#[test]
+fn test_incoming_cross_currency_transfer_stores_destination_rate() {
+    let (_dir, db_path) = setup_db();
+    let eur = crate::create_account_db(
+        &db_path,
+        "Euro".to_string(),
+        0.0,
+        Some("EUR".to_string()),
+        None,
+    )
+    .unwrap();
+    crate::create_account_db(
+        &db_path,
+        "Dollar".to_string(),
+        0.0,
+        Some("USD".to_string()),
+        None,
+    )
+    .unwrap();
+    set_daily_rate(&db_path, "EUR", "2024-03-01", 1.25);
+
+    // Money arriving in the EUR account from the USD account
+    let tx = transfer_tx(&db_path, eur.id, "Dollar", 80.0).unwrap();
+    let transfer = crate::get_transfer_db(&db_path, tx.transfer_id.unwrap()).unwrap();
+
+    assert_eq!(transfer.from.amount, -100.0);
+    assert_eq!(transfer.from.currency.as_deref(), Some("USD"));
+    assert_eq!(transfer.to.id, tx.id);
+    assert!((transfer.exchange_rate - 0.8).abs() < 1e-9);
+}
+
+#[test]
+fn test_cross_currency_transfer_without_rate_is_flagged() {
+    let (_dir, db_path) = setup_db();
+    let eur = crate::create_account_db(
+        &db_path,
+        "Euro".to_string(),
+        0.0,
+        Some("EUR".to_string()),
+        None,
+    )
+    .unwrap();
+    let yen = crate::create_account_db(
+        &db_path,
+        "Yen".to_string(),
+        0.0,
+        Some("JPY".to_string()),
+        None,
+    )
+    .unwrap();
+
+    // Offline the transfer is still recorded, at par and flagged
+    let tx = transfer_tx(&db_path, eur.id, "Yen", -100.0).unwrap();
+    let estimate_id = tx.transfer_id.unwrap();
+    let transfer = crate::get_transfer_db(&db_path, estimate_id).unwrap();
+    assert!(transfer.rate_estimated);
+    assert_eq!(transfer.exchange_rate, 1.0);
+    assert_eq!(transfer.to.account_id, yen.id);
+    assert_eq!(transfer.to.amount, 100.0);
+
+    set_daily_rate(&db_path, "EUR", "2024-03-01", 1.1);
+    set_daily_rate(&db_path, "JPY", "2024-03-01", 0.0067);
+    let tx = transfer_tx(&db_path, eur.id, "Yen", -10.0).unwrap();
+    let transfer = crate::get_transfer_db(&db_path, tx.transfer_id.unwrap()).unwrap();
+    assert!(!transfer.rate_estimated);
+
+    // The estimate has no exchange result until the real rate is entered
+    let report = crate::get_transfer_fx_report_db(&db_path, None, None).unwrap();
+    let estimated = report
+        .iter()
+        .find(|r| r.transfer_id == estimate_id)
+        .unwrap();
+    assert!(estimated.rate_estimated);
+    assert!(estimated.market_rate.is_some());
+    assert_eq!(estimated.fx_gain_loss, None);
+
+    let yen_before = crate::get_accounts_db(&db_path)
+        .unwrap()
+        .into_iter()
+        .find(|a| a.id == yen.id)
+        .unwrap()
+        .balance;
+    let corrected = crate::set_transfer_rate_db(&db_path, estimate_id, 160.0).unwrap();
+    assert!(!corrected.rate_estimated);
+    assert_eq!(corrected.to.amount, 16000.0);
+    let yen_after = crate::get_accounts_db(&db_path)
+        .unwrap()
+        .into_iter()
+        .find(|a| a.id == yen.id)
+        .unwrap()
+        .balance;
+    assert_eq!(yen_after - yen_before, 15900.0);
+    let report = crate::get_transfer_fx_report_db(&db_path, None, None).unwrap();
+    let corrected = report
+        .iter()
+        .find(|r| r.transfer_id == estimate_id)
+        .unwrap();
+    assert!(corrected.fx_gain_loss.is_some());
+    assert!(crate::set_transfer_rate_db(&db_path, estimate_id, 0.0).is_err());
+}
+
+#[test]
+fn test_transfer_fx_report_compares_with_market_rate() {
+    let (_dir, db_path) = setup_db();
+    let eur = crate::create_account_db(
+        &db_path,
+        "Euro".to_string(),
+        1000.0,
+        Some("EUR".to_string()),
+        None,
+    )
+    .unwrap();
+    let usd = crate::create_account_db(
+        &db_path,
+        "Dollar".to_string(),
+        0.0,
+        Some("USD".to_string()),
+        None,
+    )
+    .unwrap();
+    let usd2 = crate::create_account_db(
+        &db_path,
+        "Dollar 2".to_string(),
+        0.0,
+        Some("USD".to_string()),
+        None,
+    )
+    .unwrap();
+    set_daily_rate(&db_path, "EUR", "2024-03-01", 1.1);
+
+    let transfer = |from: i32, to: i32, date: &str, amount_from: f64, amount_to: f64, fee: f64| {
+        crate::create_transfer_db(
+            &db_path,
+            crate::CreateTransferArgs {
+                from_account_id: from,
+                to_account_id: to,
+                amount_from,
+                amount_to: Some(amount_to),
+                date: date.to_string(),
+                fee: Some(fee),
+                notes: None,
+            },
+        )
+        .unwrap()
+    };
+    // Bank gave 1.08 while the market was at 1.10, plus a 2 EUR fee
+    let t1 = transfer(eur.id, usd.id, "2024-03-04", 100.0, 108.0, 2.0);
+    // Before any stored quote
+    transfer(eur.id, usd.id, "2024-01-15", 50.0, 55.0, 0.0);
+    // Same currency transfers have no exchange result
+    transfer(usd.id, usd2.id, "2024-03-05", 10.0, 10.0, 0.0);
+
+    let report = crate::get_transfer_fx_report_db(&db_path, None, None).unwrap();
+    assert_eq!(report.len(), 2);
+    assert!(report[0].market_rate.is_none());
+    assert!(report[0].fx_gain_loss.is_none());
+
+    let r = report.iter().find(|r| r.transfer_id == t1.id).unwrap();
+    assert_eq!(r.from_currency, "EUR");
+    assert_eq!(r.to_currency, "USD");
+    assert_eq!(r.amount_from, 100.0);
+    assert!((r.effective_rate - 1.08).abs() < 1e-9);
+    assert!((r.market_rate.unwrap() - 1.1).abs() < 1e-9);
+    assert_eq!(r.fx_gain_loss, Some(-2.0));
+
+    let march = crate::get_transfer_fx_report_db(
+        &db_path,
+        Some("2024-03-01".to_string()),
+        Some("2024-03-31".to_string()),
+    )
+    .unwrap();
+    assert_eq!(march.len(), 1);
+}
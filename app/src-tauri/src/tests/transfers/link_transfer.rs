@@ -0,0 +1,99 @@
+use super::common::setup_db;
+
+fn add_tx(db_path: &std::path::PathBuf, account_id: i32, payee: &str, amount: f64) -> i32 {
+    crate::create_transaction_db(
+        db_path,
+        crate::CreateTransactionArgs {
+            account_id,
+            date: "2024-03-01".to_string(),
+            payee: payee.to_string(),
+            notes: None,
+            category: None,
+            amount,
+            ticker: None,
+            shares: None,
+            price_per_share: None,
+            fee: None,
+            currency: None,
+        },
+    )
+    .unwrap()
+    .id
+}
+
+#[test]
+fn test_link_existing_pair_then_update_syncs_counterpart() {
+    let (_dir, db_path) = setup_db();
+    let acc1 = crate::create_account_db(&db_path, "Checking".to_string(), 0.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "Savings".to_string(), 0.0, None, None).unwrap();
+    // Imported separately from two statements
+    let incoming = add_tx(&db_path, acc2.id, "From checking", 75.0);
+    let outgoing = add_tx(&db_path, acc1.id, "To savings", -75.0);
+
+    let transfer = crate::link_transfer_db(&db_path, incoming, outgoing).unwrap();
+    assert_eq!(transfer.from.id, outgoing);
+    assert_eq!(transfer.to.id, incoming);
+    assert_eq!(transfer.to.transfer_id, Some(transfer.id));
+    assert_eq!(transfer.to.category.as_deref(), Some("Transfer"));
+
+    crate::update_transaction_db(
+        &db_path,
+        crate::UpdateTransactionArgs {
+            id: outgoing,
+            account_id: acc1.id,
+            date: "2024-03-02".to_string(),
+            payee: "To savings".to_string(),
+            notes: None,
+            category: Some("Transfer".to_string()),
+            amount: -80.0,
+            currency: None,
+            override_reconciled: false,
+        },
+    )
+    .unwrap();
+
+    let txs2 = crate::get_transactions_db(&db_path, acc2.id).unwrap();
+    assert_eq!(txs2.len(), 1);
+    assert_eq!(txs2[0].amount, 80.0);
+    let accounts = crate::get_accounts_db(&db_path).unwrap();
+    assert_eq!(
+        accounts.iter().find(|a| a.id == acc2.id).unwrap().balance,
+        80.0
+    );
+}
+
+#[test]
+fn test_link_transfer_rejects_invalid_pairs() {
+    let (_dir, db_path) = setup_db();
+    let acc1 = crate::create_account_db(&db_path, "A".to_string(), 0.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "B".to_string(), 0.0, None, None).unwrap();
+    let out1 = add_tx(&db_path, acc1.id, "X", -10.0);
+    let in1 = add_tx(&db_path, acc1.id, "X", 10.0);
+    let out2 = add_tx(&db_path, acc2.id, "X", -10.0);
+    let in2 = add_tx(&db_path, acc2.id, "X", 10.0);
+
+    // Same account
+    assert!(crate::link_transfer_db(&db_path, out1, in1).is_err());
+    // Same direction
+    assert!(crate::link_transfer_db(&db_path, out1, out2).is_err());
+
+    crate::link_transfer_db(&db_path, out1, in2).unwrap();
+    // Already linked
+    assert!(crate::link_transfer_db(&db_path, out1, in1).is_err());
+    assert!(crate::link_transfer_db(&db_path, out2, in2).is_err());
+}
+
+#[test]
+fn test_delete_linked_transfer_removes_transfer_record() {
+    let (_dir, db_path) = setup_db();
+    let acc1 = crate::create_account_db(&db_path, "A".to_string(), 0.0, None, None).unwrap();
+    let acc2 = crate::create_account_db(&db_path, "B".to_string(), 0.0, None, None).unwrap();
+    let out = add_tx(&db_path, acc1.id, "X", -10.0);
+    let inc = add_tx(&db_path, acc2.id, "X", 10.0);
+    let transfer = crate::link_transfer_db(&db_path, out, inc).unwrap();
+
+    crate::delete_transaction_db(&db_path, inc, false).unwrap();
+
+    assert!(crate::get_transfer_db(&db_path, transfer.id).is_err());
+    assert!(crate::get_all_transactions_db(&db_path).unwrap().is_empty());
+}
@@ -0,0 +1,5 @@
+pub use super::common;
+
+pub mod create_transfer;
+pub mod cross_currency;
+pub mod link_transfer;